@@ -14,17 +14,86 @@
 
 //! Foundation bits exposing the Block Storage API.
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 
-use osauth::services::BLOCK_STORAGE;
+use osauth::services::{ServiceType, VersionedService, BLOCK_STORAGE};
 use osauth::ErrorKind;
+use reqwest::header::{HeaderName, HeaderValue};
 use serde::Serialize;
+use serde_json::Value;
 
+use super::super::common::ApiVersion;
 use super::super::session::Session;
-use super::super::utils;
-use super::super::Result;
+use super::super::utils::{self, FetchJson};
+use super::super::{Error, Result};
 use super::protocol::*;
 
+/// Block Storage service with microversion support.
+#[derive(Copy, Clone, Debug)]
+pub struct VersionedBlockStorageService;
+
+/// Block Storage service type with microversion support.
+pub const VERSIONED_BLOCK_STORAGE: VersionedBlockStorageService = VersionedBlockStorageService;
+
+impl ServiceType for VersionedBlockStorageService {
+    fn catalog_type(&self) -> &'static str {
+        BLOCK_STORAGE.catalog_type()
+    }
+
+    fn major_version_supported(&self, version: ApiVersion) -> bool {
+        BLOCK_STORAGE.major_version_supported(version)
+    }
+}
+
+impl VersionedService for VersionedBlockStorageService {
+    fn get_version_header(&self, version: ApiVersion) -> (HeaderName, HeaderValue) {
+        let value = format!("volume {version}")
+            .parse()
+            .expect("API version is always a valid header value");
+        (HeaderName::from_static("openstack-api-version"), value)
+    }
+}
+
+/// Generic volume groups.
+const API_VERSION_GROUPS: ApiVersion = ApiVersion(3, 13);
+/// Volume group snapshots.
+const API_VERSION_GROUP_SNAPSHOTS: ApiVersion = ApiVersion(3, 14);
+/// Listing volumes of a volume group.
+const API_VERSION_GROUP_VOLUMES: ApiVersion = ApiVersion(3, 25);
+
+async fn require_api_version(session: &Session, version: ApiVersion) -> Result<()> {
+    if session
+        .supports_api_version(VERSIONED_BLOCK_STORAGE, version)
+        .await?
+    {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::IncompatibleApiVersion,
+            format!("Block Storage API version {version} is required for this request"),
+        ))
+    }
+}
+
+/// Pick the API version for fetching volume groups.
+async fn volume_group_api_version(session: &Session) -> Result<ApiVersion> {
+    session
+        .pick_api_version(
+            VERSIONED_BLOCK_STORAGE,
+            vec![API_VERSION_GROUPS, API_VERSION_GROUP_VOLUMES],
+        )
+        .await?
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::IncompatibleApiVersion,
+                format!(
+                    "Block Storage API version {API_VERSION_GROUPS} is required for volume groups"
+                ),
+            )
+        })
+}
+
 /// Delete a volume.
 pub async fn delete_volume<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
     trace!("Deleting volume {}", id.as_ref());
@@ -53,7 +122,7 @@ pub async fn get_volume_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result
     trace!("Fetching volume {}", id.as_ref());
     let root: VolumeRoot = session
         .get(BLOCK_STORAGE, &["volumes", id.as_ref()])
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received {:?}", root.volume);
     Ok(root.volume)
@@ -64,8 +133,8 @@ pub async fn get_volume_by_name<S: AsRef<str>>(session: &Session, name: S) -> Re
     trace!("Get volume by name {}", name.as_ref());
     let root: VolumesRoot = session
         .get(BLOCK_STORAGE, &["volumes"])
-        .query(&[("name", name.as_ref())])
-        .fetch()
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
         .await?;
     let result = utils::one(
         root.volumes,
@@ -76,6 +145,83 @@ pub async fn get_volume_by_name<S: AsRef<str>>(session: &Session, name: S) -> Re
     Ok(result)
 }
 
+/// Get a volume type.
+pub async fn get_volume_type<S: AsRef<str>>(
+    session: &Session,
+    id_or_name: S,
+) -> Result<VolumeType> {
+    let s = id_or_name.as_ref();
+    match get_volume_type_by_id(session, s).await {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            get_volume_type_by_name(session, s).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a volume type by its ID.
+pub async fn get_volume_type_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result<VolumeType> {
+    trace!("Fetching volume type {}", id.as_ref());
+    let root: VolumeTypeRoot = session
+        .get(BLOCK_STORAGE, &["types", id.as_ref()])
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", root.volume_type);
+    Ok(root.volume_type)
+}
+
+/// Get a volume type by its name.
+pub async fn get_volume_type_by_name<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+) -> Result<VolumeType> {
+    trace!("Get volume type by name {}", name.as_ref());
+    // The volume types API does not support filtering by name.
+    let result = utils::one(
+        list_volume_types(session)
+            .await?
+            .into_iter()
+            .filter(|item| item.name == name.as_ref()),
+        "Volume type with given name or ID not found",
+        "Too many volume types found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get the encryption specification of a volume type.
+pub async fn get_volume_type_encryption<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<Option<VolumeTypeEncryption>> {
+    trace!("Fetching encryption of volume type {}", id.as_ref());
+    let value: Value = session
+        .get(BLOCK_STORAGE, &["types", id.as_ref(), "encryption"])
+        .fetch_json()
+        .await?;
+    // An empty object is returned for unencrypted types.
+    let result = match value {
+        Value::Object(ref map) if map.is_empty() => None,
+        other => Some(serde_json::from_value(other).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidResponse,
+                format!("Cannot parse volume type encryption: {err}"),
+            )
+        })?),
+    };
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// List volume types.
+pub async fn list_volume_types(session: &Session) -> Result<Vec<VolumeType>> {
+    trace!("Listing volume types");
+    let root: VolumeTypesRoot = session.get(BLOCK_STORAGE, &["types"]).fetch_json().await?;
+    trace!("Received volume types: {:?}", root.volume_types);
+    Ok(root.volume_types)
+}
+
 /// List volumes.
 pub async fn list_volumes<Q: Serialize + Sync + Debug>(
     session: &Session,
@@ -85,7 +231,7 @@ pub async fn list_volumes<Q: Serialize + Sync + Debug>(
     let root: VolumesRoot = session
         .get(BLOCK_STORAGE, &["volumes", "detail"])
         .query(query)
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received volumes: {:?}", root.volumes);
     Ok(root.volumes)
@@ -94,12 +240,373 @@ pub async fn list_volumes<Q: Serialize + Sync + Debug>(
 /// Create a volume.
 pub async fn create_volume(session: &Session, request: VolumeCreate) -> Result<Volume> {
     debug!("Creating a volume with {:?}", request);
+    let in_group = request.group_id.is_some();
     let body = VolumeCreateRoot { volume: request };
+    let root: VolumeRoot = if in_group {
+        require_api_version(session, API_VERSION_GROUPS).await?;
+        let mut builder = session
+            .post(VERSIONED_BLOCK_STORAGE, &["volumes"])
+            .json(&body);
+        builder.set_api_version(API_VERSION_GROUPS);
+        builder.fetch_json().await?
+    } else {
+        session
+            .post(BLOCK_STORAGE, &["volumes"])
+            .json(&body)
+            .fetch_json()
+            .await?
+    };
+    trace!("Requested creation of volume {:?}", root.volume);
+    Ok(root.volume)
+}
+
+/// List volumes on a backend that are not managed by the Block Storage service.
+pub async fn list_manageable_volumes<S: AsRef<str>>(
+    session: &Session,
+    host: S,
+) -> Result<Vec<ManageableVolume>> {
+    trace!("Listing manageable volumes on {}", host.as_ref());
+    let root: ManageableVolumesRoot = session
+        .get(BLOCK_STORAGE, &["os-volume-manage", "detail"])
+        .query(&[("host", host.as_ref())])
+        .fetch_json()
+        .await?;
+    trace!("Received manageable volumes: {:?}", root.manageable_volumes);
+    Ok(root.manageable_volumes)
+}
+
+/// Take an existing backend volume under management.
+pub async fn manage_volume(session: &Session, request: VolumeManage) -> Result<Volume> {
+    debug!("Managing an existing volume with {:?}", request);
+    let body = VolumeManageRoot { volume: request };
     let root: VolumeRoot = session
-        .post(BLOCK_STORAGE, &["volumes"])
+        .post(BLOCK_STORAGE, &["os-volume-manage"])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
-    trace!("Requested creation of volume {:?}", root.volume);
+    debug!("Requested management of volume {:?}", root.volume);
     Ok(root.volume)
 }
+
+/// Migrate a volume to another backend.
+pub async fn migrate_volume<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    request: VolumeMigrate,
+) -> Result<()> {
+    debug!("Migrating volume {} with {:?}", id.as_ref(), request);
+    let body = VolumeMigrateRoot {
+        migrate_volume: request,
+    };
+    let _ = session
+        .post(BLOCK_STORAGE, &["volumes", id.as_ref(), "action"])
+        .json(&body)
+        .send()
+        .await?;
+    debug!("Requested migration of volume {}", id.as_ref());
+    Ok(())
+}
+
+/// Remove a volume from the Block Storage service without deleting it.
+pub async fn unmanage_volume<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Unmanaging volume {}", id.as_ref());
+    let body = VolumeUnmanageRoot {
+        unmanage: HashMap::new(),
+    };
+    let _ = session
+        .post(BLOCK_STORAGE, &["volumes", id.as_ref(), "action"])
+        .json(&body)
+        .send()
+        .await?;
+    debug!("Requested unmanaging of volume {}", id.as_ref());
+    Ok(())
+}
+
+/// Upload a volume to the Image service.
+///
+/// Returns the ID of the new image.
+#[cfg(feature = "image")]
+pub async fn upload_volume_to_image<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    request: VolumeUploadImage,
+) -> Result<String> {
+    debug!(
+        "Uploading volume {} to image with {:?}",
+        id.as_ref(),
+        request
+    );
+    let body = VolumeUploadImageRoot {
+        upload_image: request,
+    };
+    let root: VolumeUploadedImageRoot = session
+        .post(BLOCK_STORAGE, &["volumes", id.as_ref(), "action"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!(
+        "Requested upload of volume {} to image {}",
+        id.as_ref(),
+        root.upload_image.image_id
+    );
+    Ok(root.upload_image.image_id)
+}
+
+/// Create a volume group.
+///
+/// Returns the ID of the new group.
+pub async fn create_volume_group(session: &Session, request: VolumeGroupCreate) -> Result<String> {
+    debug!("Creating a volume group with {:?}", request);
+    require_api_version(session, API_VERSION_GROUPS).await?;
+    let body = VolumeGroupCreateRoot { group: request };
+    let mut builder = session
+        .post(VERSIONED_BLOCK_STORAGE, &["groups"])
+        .json(&body);
+    builder.set_api_version(API_VERSION_GROUPS);
+    let root: CreatedVolumeGroupRoot = builder.fetch_json().await?;
+    debug!("Requested creation of volume group {}", root.group.id);
+    Ok(root.group.id)
+}
+
+/// Create a snapshot of a volume group.
+///
+/// Returns the ID of the new snapshot.
+pub async fn create_volume_group_snapshot(
+    session: &Session,
+    request: VolumeGroupSnapshotCreate,
+) -> Result<String> {
+    debug!("Creating a volume group snapshot with {:?}", request);
+    require_api_version(session, API_VERSION_GROUP_SNAPSHOTS).await?;
+    let body = VolumeGroupSnapshotCreateRoot {
+        group_snapshot: request,
+    };
+    let mut builder = session
+        .post(VERSIONED_BLOCK_STORAGE, &["group_snapshots"])
+        .json(&body);
+    builder.set_api_version(API_VERSION_GROUP_SNAPSHOTS);
+    let root: CreatedVolumeGroupSnapshotRoot = builder.fetch_json().await?;
+    debug!(
+        "Requested creation of volume group snapshot {}",
+        root.group_snapshot.id
+    );
+    Ok(root.group_snapshot.id)
+}
+
+/// Delete a volume group, optionally with its volumes.
+pub async fn delete_volume_group<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    delete_volumes: bool,
+) -> Result<()> {
+    debug!(
+        "Deleting volume group {} (with volumes: {})",
+        id.as_ref(),
+        delete_volumes
+    );
+    require_api_version(session, API_VERSION_GROUPS).await?;
+    let body = VolumeGroupDeleteRoot {
+        delete: VolumeGroupDelete { delete_volumes },
+    };
+    let mut builder = session
+        .post(VERSIONED_BLOCK_STORAGE, &["groups", id.as_ref(), "action"])
+        .json(&body);
+    builder.set_api_version(API_VERSION_GROUPS);
+    let _ = builder.send().await?;
+    debug!(
+        "Successfully requested deletion of volume group {}",
+        id.as_ref()
+    );
+    Ok(())
+}
+
+/// Delete a volume group snapshot.
+pub async fn delete_volume_group_snapshot<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Deleting volume group snapshot {}", id.as_ref());
+    require_api_version(session, API_VERSION_GROUP_SNAPSHOTS).await?;
+    let mut builder = session.delete(VERSIONED_BLOCK_STORAGE, &["group_snapshots", id.as_ref()]);
+    builder.set_api_version(API_VERSION_GROUP_SNAPSHOTS);
+    let _ = builder.send().await?;
+    debug!(
+        "Successfully requested deletion of volume group snapshot {}",
+        id.as_ref()
+    );
+    Ok(())
+}
+
+/// Get a group type by its ID or name.
+pub async fn get_group_type<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result<GroupType> {
+    let s = id_or_name.as_ref();
+    require_api_version(session, API_VERSION_GROUPS).await?;
+    let mut builder = session.get(VERSIONED_BLOCK_STORAGE, &["group_types", s]);
+    builder.set_api_version(API_VERSION_GROUPS);
+    match builder.fetch_json::<GroupTypeRoot>().await {
+        Ok(root) => Ok(root.group_type),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            trace!("Get group type by name {}", s);
+            let mut builder = session.get(VERSIONED_BLOCK_STORAGE, &["group_types"]);
+            builder.set_api_version(API_VERSION_GROUPS);
+            let root: GroupTypesRoot = builder.fetch_json().await?;
+            utils::one(
+                root.group_types.into_iter().filter(|item| item.name == s),
+                "Group type with given name or ID not found",
+                "Too many group types found with given name",
+            )
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a volume group.
+pub async fn get_volume_group<S: AsRef<str>>(
+    session: &Session,
+    id_or_name: S,
+) -> Result<VolumeGroup> {
+    let s = id_or_name.as_ref();
+    match get_volume_group_by_id(session, s).await {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            get_volume_group_by_name(session, s).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a volume group by its ID.
+pub async fn get_volume_group_by_id<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<VolumeGroup> {
+    trace!("Fetching volume group {}", id.as_ref());
+    let version = volume_group_api_version(session).await?;
+    let mut builder = session.get(VERSIONED_BLOCK_STORAGE, &["groups", id.as_ref()]);
+    builder.set_api_version(version);
+    if version >= API_VERSION_GROUP_VOLUMES {
+        builder = builder.query(&[("list_volume", "True")]);
+    }
+    let root: VolumeGroupRoot = builder.fetch_json().await?;
+    trace!("Received {:?}", root.group);
+    Ok(root.group)
+}
+
+/// Get a volume group by its name.
+pub async fn get_volume_group_by_name<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+) -> Result<VolumeGroup> {
+    trace!("Get volume group by name {}", name.as_ref());
+    // Not all versions support filtering by name, so filter locally as well.
+    let groups = list_volume_groups(session, &[("name", name.as_ref())]).await?;
+    let result = utils::one(
+        groups
+            .into_iter()
+            .filter(|item| item.name.as_deref() == Some(name.as_ref())),
+        "Volume group with given name or ID not found",
+        "Too many volume groups found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get a volume group snapshot.
+pub async fn get_volume_group_snapshot<S: AsRef<str>>(
+    session: &Session,
+    id_or_name: S,
+) -> Result<VolumeGroupSnapshot> {
+    let s = id_or_name.as_ref();
+    match get_volume_group_snapshot_by_id(session, s).await {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            get_volume_group_snapshot_by_name(session, s).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a volume group snapshot by its ID.
+pub async fn get_volume_group_snapshot_by_id<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<VolumeGroupSnapshot> {
+    trace!("Fetching volume group snapshot {}", id.as_ref());
+    require_api_version(session, API_VERSION_GROUP_SNAPSHOTS).await?;
+    let mut builder = session.get(VERSIONED_BLOCK_STORAGE, &["group_snapshots", id.as_ref()]);
+    builder.set_api_version(API_VERSION_GROUP_SNAPSHOTS);
+    let root: VolumeGroupSnapshotRoot = builder.fetch_json().await?;
+    trace!("Received {:?}", root.group_snapshot);
+    Ok(root.group_snapshot)
+}
+
+/// Get a volume group snapshot by its name.
+pub async fn get_volume_group_snapshot_by_name<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+) -> Result<VolumeGroupSnapshot> {
+    trace!("Get volume group snapshot by name {}", name.as_ref());
+    // Not all versions support filtering by name, so filter locally as well.
+    let snapshots = list_volume_group_snapshots(session, &[("name", name.as_ref())]).await?;
+    let result = utils::one(
+        snapshots
+            .into_iter()
+            .filter(|item| item.name.as_deref() == Some(name.as_ref())),
+        "Volume group snapshot with given name or ID not found",
+        "Too many volume group snapshots found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// List volume groups.
+pub async fn list_volume_groups<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    query: &Q,
+) -> Result<Vec<VolumeGroup>> {
+    trace!("Listing volume groups with {:?}", query);
+    let version = volume_group_api_version(session).await?;
+    let mut builder = session
+        .get(VERSIONED_BLOCK_STORAGE, &["groups", "detail"])
+        .query(query);
+    builder.set_api_version(version);
+    if version >= API_VERSION_GROUP_VOLUMES {
+        builder = builder.query(&[("list_volume", "True")]);
+    }
+    let root: VolumeGroupsRoot = builder.fetch_json().await?;
+    trace!("Received volume groups: {:?}", root.groups);
+    Ok(root.groups)
+}
+
+/// List volume group snapshots.
+pub async fn list_volume_group_snapshots<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    query: &Q,
+) -> Result<Vec<VolumeGroupSnapshot>> {
+    trace!("Listing volume group snapshots with {:?}", query);
+    require_api_version(session, API_VERSION_GROUP_SNAPSHOTS).await?;
+    let mut builder = session
+        .get(VERSIONED_BLOCK_STORAGE, &["group_snapshots", "detail"])
+        .query(query);
+    builder.set_api_version(API_VERSION_GROUP_SNAPSHOTS);
+    let root: VolumeGroupSnapshotsRoot = builder.fetch_json().await?;
+    trace!(
+        "Received volume group snapshots: {:?}",
+        root.group_snapshots
+    );
+    Ok(root.group_snapshots)
+}
+
+/// Update a volume group.
+pub async fn update_volume_group<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    update: VolumeGroupUpdate,
+) -> Result<()> {
+    debug!("Updating volume group {} with {:?}", id.as_ref(), update);
+    require_api_version(session, API_VERSION_GROUPS).await?;
+    let body = VolumeGroupUpdateRoot { group: update };
+    let mut builder = session
+        .put(VERSIONED_BLOCK_STORAGE, &["groups", id.as_ref()])
+        .json(&body);
+    builder.set_api_version(API_VERSION_GROUPS);
+    let _ = builder.send().await?;
+    debug!("Requested update of volume group {}", id.as_ref());
+    Ok(())
+}
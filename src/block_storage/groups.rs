@@ -0,0 +1,1075 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generic volume groups and their snapshots via Block Storage API.
+//!
+//! Requires Block Storage API version 3.13 (3.14 for snapshots).
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use futures::stream::{Stream, TryStreamExt};
+
+use super::super::common::{
+    Refresh, ResourceIterator, ResourceQuery, ValidationReport, VolumeGroupRef, VolumeRef,
+    VolumeTypeRef,
+};
+use super::super::session::Session;
+use super::super::utils::{NamePattern, Query};
+use super::super::waiter::{refresh_with_reauth, DeletionWaiter, Waiter};
+use super::super::{Error, ErrorKind, Result};
+use super::{api, protocol, Volume};
+
+/// A query to volume group list.
+#[derive(Clone, Debug)]
+pub struct VolumeGroupQuery {
+    session: Session,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    name_pattern: Option<NamePattern>,
+}
+
+/// Structure representing a generic volume group.
+///
+/// Volumes in a group can be snapshotted together, which gives
+/// crash-consistent snapshots of multi-volume workloads.
+#[derive(Clone, Debug)]
+pub struct VolumeGroup {
+    session: Session,
+    inner: protocol::VolumeGroup,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a volume group.
+#[derive(Clone, Debug)]
+pub struct NewVolumeGroup {
+    session: Session,
+    inner: protocol::VolumeGroupCreate,
+    volume_types: Vec<VolumeTypeRef>,
+}
+
+/// A query to volume group snapshot list.
+#[derive(Clone, Debug)]
+pub struct VolumeGroupSnapshotQuery {
+    session: Session,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    name_pattern: Option<NamePattern>,
+}
+
+/// Structure representing a snapshot of a volume group.
+#[derive(Clone, Debug)]
+pub struct VolumeGroupSnapshot {
+    session: Session,
+    inner: protocol::VolumeGroupSnapshot,
+}
+
+/// Waiter for a volume group to become available.
+#[derive(Debug)]
+pub struct VolumeGroupStatusWaiter<'group> {
+    group: &'group mut VolumeGroup,
+}
+
+/// Waiter for a volume group snapshot to become available.
+#[derive(Debug)]
+pub struct VolumeGroupSnapshotStatusWaiter<'snapshot> {
+    snapshot: &'snapshot mut VolumeGroupSnapshot,
+}
+
+impl VolumeGroup {
+    /// Create a volume group object.
+    fn new(session: Session, inner: protocol::VolumeGroup) -> VolumeGroup {
+        VolumeGroup {
+            session,
+            inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a VolumeGroup object.
+    pub(crate) async fn load<Id: AsRef<str>>(session: Session, id: Id) -> Result<VolumeGroup> {
+        let inner = api::get_volume_group(&session, id).await?;
+        Ok(VolumeGroup::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Name of the availability zone."]
+        availability_zone: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "When the volume group was created."]
+        created_at: Option<DateTime<FixedOffset>>
+    }
+
+    transparent_property! {
+        #[doc = "Description of the volume group."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the snapshot the volume group was created from (if any)."]
+        group_snapshot_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the group type."]
+        group_type_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Name of the volume group."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project (if available)."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the volume group this one was cloned from (if any)."]
+        source_group_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Status of the volume group."]
+        status: clone protocol::VolumeGroupStatus
+    }
+
+    transparent_property! {
+        #[doc = "IDs of the volume types supported by the volume group."]
+        volume_types: ref Vec<String>
+    }
+
+    /// IDs of the volumes in the volume group.
+    ///
+    /// Requires Block Storage API version 3.25, empty on older versions.
+    #[inline]
+    pub fn volume_ids(&self) -> &Vec<String> {
+        &self.inner.volumes
+    }
+
+    /// Load the volumes in the volume group.
+    ///
+    /// Requires Block Storage API version 3.25, empty on older versions.
+    pub async fn volumes(&self) -> Result<Vec<Volume>> {
+        let mut result = Vec::with_capacity(self.inner.volumes.len());
+        for id in &self.inner.volumes {
+            result.push(Volume::new(self.session.clone(), id).await?);
+        }
+        Ok(result)
+    }
+
+    /// Add existing volumes to the volume group.
+    ///
+    /// The volumes must have one of the volume types of the group. The change
+    /// is applied immediately, use
+    /// [wait_until_available](#method.wait_until_available) to wait for it
+    /// to finish.
+    pub async fn add_volumes<I, V>(&mut self, volumes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<VolumeRef>,
+    {
+        let ids = self.verify_volumes(volumes).await?;
+        let update = protocol::VolumeGroupUpdate {
+            add_volumes: Some(ids),
+            ..Default::default()
+        };
+        self.update(update).await
+    }
+
+    /// Remove volumes from the volume group.
+    ///
+    /// The volumes are not deleted. The change is applied immediately, use
+    /// [wait_until_available](#method.wait_until_available) to wait for it
+    /// to finish.
+    pub async fn remove_volumes<I, V>(&mut self, volumes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<VolumeRef>,
+    {
+        let ids = self.verify_volumes(volumes).await?;
+        let update = protocol::VolumeGroupUpdate {
+            remove_volumes: Some(ids),
+            ..Default::default()
+        };
+        self.update(update).await
+    }
+
+    async fn verify_volumes<I, V>(&self, volumes: I) -> Result<String>
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<VolumeRef>,
+    {
+        let mut ids = Vec::new();
+        for volume in volumes {
+            ids.push(String::from(
+                volume.into().into_verified(&self.session).await?,
+            ));
+        }
+        if ids.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "At least one volume is required",
+            ));
+        }
+        Ok(ids.join(","))
+    }
+
+    async fn update(&mut self, update: protocol::VolumeGroupUpdate) -> Result<()> {
+        if self.is_dirty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The volume group has unsaved changes",
+            ));
+        }
+        api::update_volume_group(&self.session, &self.inner.id, update).await?;
+        self.refresh().await
+    }
+
+    /// Create a snapshot of all volumes in the volume group.
+    ///
+    /// Use [VolumeGroupSnapshot::wait_until_available](struct.VolumeGroupSnapshot.html#method.wait_until_available)
+    /// to wait for the snapshot to be ready.
+    pub async fn create_snapshot<S: Into<String>>(&self, name: S) -> Result<VolumeGroupSnapshot> {
+        let request = protocol::VolumeGroupSnapshotCreate {
+            description: None,
+            group_id: self.inner.id.clone(),
+            name: Some(name.into()),
+        };
+        let id = api::create_volume_group_snapshot(&self.session, request).await?;
+        VolumeGroupSnapshot::load(self.session.clone(), id).await
+    }
+
+    /// Wait for the volume group to become available.
+    ///
+    /// Useful after creating a volume group or changing its volumes. The
+    /// waiter fails if the volume group gets into an error status.
+    pub fn wait_until_available(&mut self) -> VolumeGroupStatusWaiter<'_> {
+        VolumeGroupStatusWaiter { group: self }
+    }
+
+    /// Delete the volume group.
+    ///
+    /// Fails if the volume group is not empty, see
+    /// [delete_with_volumes](#method.delete_with_volumes).
+    pub async fn delete(self) -> Result<DeletionWaiter<VolumeGroup>> {
+        self.delete_impl(false).await
+    }
+
+    /// Delete the volume group together with all its volumes.
+    pub async fn delete_with_volumes(self) -> Result<DeletionWaiter<VolumeGroup>> {
+        self.delete_impl(true).await
+    }
+
+    async fn delete_impl(self, delete_volumes: bool) -> Result<DeletionWaiter<VolumeGroup>> {
+        api::delete_volume_group(&self.session, &self.inner.id, delete_volumes).await?;
+        Ok(DeletionWaiter::new(
+            self,
+            Duration::new(300, 0),
+            Duration::new(2, 0),
+        ))
+    }
+
+    /// Whether the volume group is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the volume group.
+    pub async fn save(&mut self) -> Result<()> {
+        if !self.is_dirty() {
+            return Ok(());
+        }
+        let mut update = protocol::VolumeGroupUpdate::default();
+        save_option_fields! {
+            self -> update: description name
+        };
+        api::update_volume_group(&self.session, &self.inner.id, update).await?;
+        self.refresh().await
+    }
+}
+
+#[async_trait]
+impl Refresh for VolumeGroup {
+    /// Refresh the volume group.
+    async fn refresh(&mut self) -> Result<()> {
+        self.inner = api::get_volume_group_by_id(&self.session, &self.inner.id).await?;
+        self.dirty.clear();
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+}
+
+impl VolumeGroupQuery {
+    pub(crate) fn new(session: Session) -> VolumeGroupQuery {
+        VolumeGroupQuery {
+            session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            name_pattern: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    query_name_pattern!("volume groups");
+
+    /// Convert this query into a stream executing the request.
+    ///
+    /// Returns a `TryStream`, which is a stream with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream(self) -> impl Stream<Item = Result<VolumeGroup>> {
+        debug!("Fetching volume groups with {:?}", self.query);
+        ResourceIterator::new(self.session.clone(), self).into_stream()
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_stream().try_collect().await`.
+    pub async fn all(self) -> Result<Vec<VolumeGroup>> {
+        self.into_stream().try_collect().await
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub async fn one(mut self) -> Result<VolumeGroup> {
+        debug!("Fetching one volume group with {:?}", self.query);
+        if self.can_paginate && self.name_pattern.is_none() {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yields more than one result.
+            self.query.push("limit", 2);
+        }
+
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
+    }
+}
+
+#[async_trait]
+impl ResourceQuery for VolumeGroupQuery {
+    type Item = VolumeGroup;
+
+    const DEFAULT_LIMIT: usize = 50;
+
+    async fn can_paginate(&self) -> Result<bool> {
+        Ok(self.can_paginate)
+    }
+
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
+    fn extract_marker(&self, resource: &Self::Item) -> String {
+        resource.id().clone()
+    }
+
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
+    async fn fetch_chunk(
+        &self,
+        limit: Option<usize>,
+        marker: Option<String>,
+    ) -> Result<Vec<Self::Item>> {
+        let query = self.query.with_marker_and_limit(limit, marker);
+        Ok(api::list_volume_groups(&self.session, &query)
+            .await?
+            .into_iter()
+            .map(|item| VolumeGroup::new(self.session.clone(), item))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<'group> Waiter<(), Error> for VolumeGroupStatusWaiter<'group> {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(Duration::new(600, 0))
+    }
+
+    fn default_delay(&self) -> Duration {
+        Duration::new(2, 0)
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(
+            ErrorKind::OperationTimedOut,
+            format!(
+                "Timeout waiting for volume group {} to become available",
+                self.group.id()
+            ),
+        )
+    }
+
+    async fn poll(&mut self) -> Result<Option<()>> {
+        refresh_with_reauth(self.group).await?;
+        match self.group.status() {
+            protocol::VolumeGroupStatus::Available => {
+                debug!("Volume group {} is available", self.group.id());
+                Ok(Some(()))
+            }
+            protocol::VolumeGroupStatus::Error | protocol::VolumeGroupStatus::ErrorDeleting => {
+                debug!(
+                    "Volume group {} failed to become available - status is {}",
+                    self.group.id(),
+                    self.group.status()
+                );
+                Err(Error::new(
+                    ErrorKind::OperationFailed,
+                    format!(
+                        "Volume group {} got into {} state",
+                        self.group.id(),
+                        self.group.status()
+                    ),
+                ))
+            }
+            other => {
+                trace!(
+                    "Still waiting for volume group {} to become available, current is {}",
+                    self.group.id(),
+                    other
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<'group> VolumeGroupStatusWaiter<'group> {
+    /// Current state of the volume group.
+    pub fn current_state(&self) -> &VolumeGroup {
+        self.group
+    }
+}
+
+impl NewVolumeGroup {
+    /// Start creating a volume group.
+    pub(crate) fn new(session: Session, group_type: String) -> NewVolumeGroup {
+        NewVolumeGroup {
+            session,
+            inner: protocol::VolumeGroupCreate {
+                availability_zone: None,
+                description: None,
+                group_type,
+                name: None,
+                volume_types: Vec::new(),
+            },
+            volume_types: Vec::new(),
+        }
+    }
+
+    /// Request creation of the volume group.
+    ///
+    /// Fails with all problems found by [validate](#method.validate) before
+    /// sending any requests. Use
+    /// [VolumeGroup::wait_until_available](struct.VolumeGroup.html#method.wait_until_available)
+    /// to wait for the volume group to be ready.
+    pub async fn create(mut self) -> Result<VolumeGroup> {
+        self.validate().into_result()?;
+        self.inner.group_type = api::get_group_type(&self.session, &self.inner.group_type)
+            .await?
+            .id;
+        for volume_type in self.volume_types {
+            self.inner
+                .volume_types
+                .push(volume_type.into_verified(&self.session).await?.into());
+        }
+        let id = api::create_volume_group(&self.session, self.inner).await?;
+        VolumeGroup::load(self.session, id).await
+    }
+
+    /// Check the request for problems that can be detected locally.
+    ///
+    /// Verifies that at least one volume type is set.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        if self.volume_types.is_empty() {
+            report.add("at least one volume type is required");
+        }
+        report
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the availability zone."]
+        set_availability_zone, with_availability_zone -> availability_zone: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    /// Add a volume type that volumes in the group can use.
+    pub fn add_volume_type<T: Into<VolumeTypeRef>>(&mut self, value: T) {
+        self.volume_types.push(value.into());
+    }
+
+    /// Add a volume type that volumes in the group can use.
+    #[inline]
+    pub fn with_volume_type<T: Into<VolumeTypeRef>>(mut self, value: T) -> Self {
+        self.add_volume_type(value);
+        self
+    }
+}
+
+impl From<VolumeGroup> for VolumeGroupRef {
+    fn from(value: VolumeGroup) -> VolumeGroupRef {
+        VolumeGroupRef::new_verified(value.inner.id)
+    }
+}
+
+#[cfg(feature = "block-storage")]
+impl VolumeGroupRef {
+    /// Verify this reference and convert to an ID, if possible.
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<VolumeGroupRef> {
+        Ok(if self.verified {
+            self
+        } else {
+            VolumeGroupRef::new_verified(api::get_volume_group(session, &self.value).await?.id)
+        })
+    }
+}
+
+impl VolumeGroupSnapshot {
+    /// Load a VolumeGroupSnapshot object.
+    pub(crate) async fn load<Id: AsRef<str>>(
+        session: Session,
+        id: Id,
+    ) -> Result<VolumeGroupSnapshot> {
+        let inner = api::get_volume_group_snapshot(&session, id).await?;
+        Ok(VolumeGroupSnapshot { session, inner })
+    }
+
+    transparent_property! {
+        #[doc = "When the snapshot was created."]
+        created_at: Option<DateTime<FixedOffset>>
+    }
+
+    transparent_property! {
+        #[doc = "Description of the snapshot."]
+        description: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the snapshotted volume group."]
+        group_id: ref String
+    }
+
+    /// Load the snapshotted volume group.
+    pub async fn group(&self) -> Result<VolumeGroup> {
+        VolumeGroup::load(self.session.clone(), &self.inner.group_id).await
+    }
+
+    transparent_property! {
+        #[doc = "ID of the group type."]
+        group_type_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Name of the snapshot."]
+        name: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project (if available)."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Status of the snapshot."]
+        status: clone protocol::VolumeGroupSnapshotStatus
+    }
+
+    /// Wait for the snapshot to become available.
+    ///
+    /// The waiter fails if the snapshot gets into an error status.
+    pub fn wait_until_available(&mut self) -> VolumeGroupSnapshotStatusWaiter<'_> {
+        VolumeGroupSnapshotStatusWaiter { snapshot: self }
+    }
+
+    /// Delete the snapshot.
+    pub async fn delete(self) -> Result<DeletionWaiter<VolumeGroupSnapshot>> {
+        api::delete_volume_group_snapshot(&self.session, &self.inner.id).await?;
+        Ok(DeletionWaiter::new(
+            self,
+            Duration::new(300, 0),
+            Duration::new(2, 0),
+        ))
+    }
+}
+
+#[async_trait]
+impl Refresh for VolumeGroupSnapshot {
+    /// Refresh the snapshot.
+    async fn refresh(&mut self) -> Result<()> {
+        self.inner = api::get_volume_group_snapshot_by_id(&self.session, &self.inner.id).await?;
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+}
+
+impl VolumeGroupSnapshotQuery {
+    pub(crate) fn new(session: Session) -> VolumeGroupSnapshotQuery {
+        VolumeGroupSnapshotQuery {
+            session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            name_pattern: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by the ID of the snapshotted volume group."]
+        with_group_id -> group_id
+    }
+
+    query_name_pattern!("volume group snapshots");
+
+    /// Convert this query into a stream executing the request.
+    ///
+    /// Returns a `TryStream`, which is a stream with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream(self) -> impl Stream<Item = Result<VolumeGroupSnapshot>> {
+        debug!("Fetching volume group snapshots with {:?}", self.query);
+        ResourceIterator::new(self.session.clone(), self).into_stream()
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_stream().try_collect().await`.
+    pub async fn all(self) -> Result<Vec<VolumeGroupSnapshot>> {
+        self.into_stream().try_collect().await
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub async fn one(mut self) -> Result<VolumeGroupSnapshot> {
+        debug!("Fetching one volume group snapshot with {:?}", self.query);
+        if self.can_paginate && self.name_pattern.is_none() {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yields more than one result.
+            self.query.push("limit", 2);
+        }
+
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
+    }
+}
+
+#[async_trait]
+impl ResourceQuery for VolumeGroupSnapshotQuery {
+    type Item = VolumeGroupSnapshot;
+
+    const DEFAULT_LIMIT: usize = 50;
+
+    async fn can_paginate(&self) -> Result<bool> {
+        Ok(self.can_paginate)
+    }
+
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
+    fn extract_marker(&self, resource: &Self::Item) -> String {
+        resource.id().clone()
+    }
+
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
+    async fn fetch_chunk(
+        &self,
+        limit: Option<usize>,
+        marker: Option<String>,
+    ) -> Result<Vec<Self::Item>> {
+        let query = self.query.with_marker_and_limit(limit, marker);
+        Ok(api::list_volume_group_snapshots(&self.session, &query)
+            .await?
+            .into_iter()
+            .map(|item| VolumeGroupSnapshot {
+                session: self.session.clone(),
+                inner: item,
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl<'snapshot> Waiter<(), Error> for VolumeGroupSnapshotStatusWaiter<'snapshot> {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(Duration::new(1800, 0))
+    }
+
+    fn default_delay(&self) -> Duration {
+        Duration::new(2, 0)
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(
+            ErrorKind::OperationTimedOut,
+            format!(
+                "Timeout waiting for volume group snapshot {} to become available",
+                self.snapshot.id()
+            ),
+        )
+    }
+
+    async fn poll(&mut self) -> Result<Option<()>> {
+        refresh_with_reauth(self.snapshot).await?;
+        match self.snapshot.status() {
+            protocol::VolumeGroupSnapshotStatus::Available => {
+                debug!("Volume group snapshot {} is available", self.snapshot.id());
+                Ok(Some(()))
+            }
+            protocol::VolumeGroupSnapshotStatus::Error
+            | protocol::VolumeGroupSnapshotStatus::ErrorDeleting => {
+                debug!(
+                    "Volume group snapshot {} failed to become available - status is {}",
+                    self.snapshot.id(),
+                    self.snapshot.status()
+                );
+                Err(Error::new(
+                    ErrorKind::OperationFailed,
+                    format!(
+                        "Volume group snapshot {} got into {} state",
+                        self.snapshot.id(),
+                        self.snapshot.status()
+                    ),
+                ))
+            }
+            other => {
+                trace!(
+                    "Still waiting for volume group snapshot {} to become available, current is {}",
+                    self.snapshot.id(),
+                    other
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<'snapshot> VolumeGroupSnapshotStatusWaiter<'snapshot> {
+    /// Current state of the volume group snapshot.
+    pub fn current_state(&self) -> &VolumeGroupSnapshot {
+        self.snapshot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use osauth::NoAuth;
+
+    use super::*;
+
+    async fn session() -> Session {
+        Session::new(NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn group_json() -> serde_json::Value {
+        serde_json::json!({
+            "availability_zone": "az1",
+            "created_at": "2015-09-16T09:28:52.000000",
+            "description": "first group",
+            "group_snapshot_id": null,
+            "group_type": "29514915-5208-46ab-9ece-1cc4688ad0c1",
+            "id": "6f519a48-3183-46cf-a32f-41815f813986",
+            "name": "first_group",
+            "project_id": "7ccf4863071f44aeb8f141f65780c51b",
+            "source_group_id": null,
+            "status": "available",
+            "volume_types": ["c4daaf47-c530-4901-b28e-f5f0a359c4e6"],
+            "volumes": ["a2cdf1ad-5497-4e57-bd7d-f573768f3d03"]
+        })
+    }
+
+    #[test]
+    fn test_group_from_json() {
+        let group: protocol::VolumeGroup = serde_json::from_value(group_json()).unwrap();
+        assert_eq!(group.id, "6f519a48-3183-46cf-a32f-41815f813986");
+        assert_eq!(group.name.as_deref(), Some("first_group"));
+        assert_eq!(
+            group.group_type_id.as_deref(),
+            Some("29514915-5208-46ab-9ece-1cc4688ad0c1")
+        );
+        assert_eq!(group.status, protocol::VolumeGroupStatus::Available);
+        assert!(group.created_at.is_some());
+        assert!(group.group_snapshot_id.is_none());
+        assert_eq!(group.volumes, vec!["a2cdf1ad-5497-4e57-bd7d-f573768f3d03"]);
+    }
+
+    #[test]
+    fn test_group_from_minimal_json() {
+        // Volumes are only returned with API version 3.25 and newer.
+        let group: protocol::VolumeGroup = serde_json::from_value(serde_json::json!({
+            "id": "6f519a48-3183-46cf-a32f-41815f813986",
+            "status": "reserved"
+        }))
+        .unwrap();
+        assert_eq!(
+            group.status,
+            protocol::VolumeGroupStatus::Unknown("reserved".into())
+        );
+        assert!(group.name.is_none());
+        assert!(group.created_at.is_none());
+        assert!(group.volume_types.is_empty());
+        assert!(group.volumes.is_empty());
+    }
+
+    #[test]
+    fn test_group_snapshot_from_json() {
+        let root: protocol::VolumeGroupSnapshotsRoot = serde_json::from_value(serde_json::json!({
+            "group_snapshots": [{
+                "created_at": "2015-09-16T09:28:52.000000",
+                "description": "first group snapshot",
+                "group_id": "6f519a48-3183-46cf-a32f-41815f813986",
+                "group_type_id": "0ef094a2-d9fd-4c79-acfd-ac60a0506b7d",
+                "id": "6f519a48-3183-46cf-a32f-41815f816666",
+                "name": "first_group_snapshot",
+                "project_id": "7ccf4863071f44aeb8f141f65780c51b",
+                "status": "creating"
+            }]
+        }))
+        .unwrap();
+        assert_eq!(root.group_snapshots.len(), 1);
+        let snapshot = &root.group_snapshots[0];
+        assert_eq!(snapshot.group_id, "6f519a48-3183-46cf-a32f-41815f813986");
+        assert_eq!(snapshot.name.as_deref(), Some("first_group_snapshot"));
+        assert_eq!(
+            snapshot.status,
+            protocol::VolumeGroupSnapshotStatus::Creating
+        );
+        assert!(snapshot.created_at.is_some());
+    }
+
+    #[test]
+    fn test_group_type_from_json() {
+        let root: protocol::GroupTypesRoot = serde_json::from_value(serde_json::json!({
+            "group_types": [{
+                "id": "6685584b-1eac-4da6-b5c3-555430cf68ff",
+                "name": "grp-type-001",
+                "description": "group type 0001",
+                "is_public": true,
+                "group_specs": {"consistent_group_snapshot_enabled": "<is> False"}
+            }]
+        }))
+        .unwrap();
+        assert_eq!(root.group_types.len(), 1);
+        assert_eq!(root.group_types[0].name, "grp-type-001");
+    }
+
+    #[tokio::test]
+    async fn test_new_group_builder() {
+        let group = NewVolumeGroup::new(session().await, "grp-type-001".into())
+            .with_name("group1")
+            .with_description("a group")
+            .with_availability_zone("az1")
+            .with_volume_type("lvm")
+            .with_volume_type(VolumeTypeRef::new_verified("ssd-id".into()));
+        assert!(group.validate().is_ok());
+        assert_eq!(group.volume_types.len(), 2);
+        assert_eq!(
+            serde_json::to_value(&group.inner).unwrap(),
+            serde_json::json!({
+                "availability_zone": "az1",
+                "description": "a group",
+                "group_type": "grp-type-001",
+                "name": "group1",
+                // Filled in on creation from the verified volume types.
+                "volume_types": []
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_group_validate() {
+        let group = NewVolumeGroup::new(session().await, "grp-type-001".into());
+        let report = group.validate();
+        assert_eq!(report.problems.len(), 1);
+        let err = group.create().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_group_verify_volumes() {
+        let group = VolumeGroup::new(
+            session().await,
+            serde_json::from_value(group_json()).unwrap(),
+        );
+        let ids = group
+            .verify_volumes(vec![
+                VolumeRef::new_verified("vol1".into()),
+                VolumeRef::new_verified("vol2".into()),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(ids, "vol1,vol2");
+
+        let err = group
+            .verify_volumes(Vec::<VolumeRef>::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_group_update_requires_saved_changes() {
+        let mut group = VolumeGroup::new(
+            session().await,
+            serde_json::from_value(group_json()).unwrap(),
+        )
+        .with_name("renamed");
+        assert!(group.is_dirty());
+        let err = group
+            .add_volumes(vec![VolumeRef::new_verified("vol1".into())])
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(group.name().as_deref(), Some("renamed"));
+    }
+
+    #[tokio::test]
+    async fn test_group_save_without_changes() {
+        let mut group = VolumeGroup::new(
+            session().await,
+            serde_json::from_value(group_json()).unwrap(),
+        );
+        assert!(!group.is_dirty());
+        // Nothing listens on the session endpoint, so any request would fail.
+        group.save().await.unwrap();
+    }
+}
@@ -15,8 +15,20 @@
 //! Block Storage API implementation bits.
 
 mod api;
+mod groups;
 mod protocol;
+mod volume_types;
 mod volumes;
 
-pub use self::protocol::{VolumeAttachment, VolumeSortKey, VolumeStatus};
-pub use self::volumes::{NewVolume, Volume, VolumeQuery};
+pub use self::groups::{
+    NewVolumeGroup, VolumeGroup, VolumeGroupQuery, VolumeGroupSnapshot, VolumeGroupSnapshotQuery,
+    VolumeGroupSnapshotStatusWaiter, VolumeGroupStatusWaiter,
+};
+pub use self::protocol::{
+    ManageableVolume, VolumeAttachment, VolumeGroupSnapshotStatus, VolumeGroupStatus,
+    VolumeSortKey, VolumeStatus, VolumeTypeEncryption,
+};
+pub use self::volume_types::VolumeType;
+pub use self::volumes::{
+    NewVolume, Volume, VolumeMigrationWaiter, VolumeQuery, VolumeStatusWaiter,
+};
@@ -16,13 +16,18 @@
 
 #![allow(missing_docs)]
 
-use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use chrono::{DateTime, FixedOffset};
 use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
+use super::super::common::protocol::{
+    deserialize_openstack_datetime, deserialize_optional_openstack_datetime,
+};
+
 protocol_enum! {
     #[doc = "Possible volume statuses."]
-    enum VolumeStatus {
+    enum VolumeStatus: Unknown(String) {
         Creating = "creating",
         Available = "available",
         Reserved = "reserved",
@@ -46,9 +51,37 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "Possible volume group statuses."]
+    enum VolumeGroupStatus: Unknown(String) {
+        Creating = "creating",
+        Available = "available",
+        Updating = "updating",
+        InUse = "in-use",
+        Deleting = "deleting",
+        Deleted = "deleted",
+        Error = "error",
+        ErrorDeleting = "error_deleting"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Possible volume group snapshot statuses."]
+    enum VolumeGroupSnapshotStatus: Unknown(String) {
+        Creating = "creating",
+        Available = "available",
+        Deleting = "deleting",
+        Deleted = "deleted",
+        Error = "error",
+        ErrorDeleting = "error_deleting"
+    }
+}
+
 protocol_enum! {
     #[doc = "Available sort keys."]
+    #[derive(Default)]
     enum VolumeSortKey {
+        #[default]
         CreatedAt = "created_at",
         Id = "id",
         Name = "name",
@@ -56,22 +89,24 @@ protocol_enum! {
     }
 }
 
-impl Default for VolumeSortKey {
-    fn default() -> VolumeSortKey {
-        VolumeSortKey::CreatedAt
-    }
-}
-
 /// A volume attachment.
 #[derive(Debug, Clone, Deserialize)]
 #[non_exhaustive]
 pub struct VolumeAttachment {
-    pub server_id: String, // this should be a reference to a server
+    /// ID of the server the volume is attached to.
+    pub server_id: String,
+    /// ID of the attachment.
     pub attachment_id: String,
-    pub attached_at: String,
+    /// Date and time of the attachment, if it has been completed.
+    #[serde(default, deserialize_with = "deserialize_optional_openstack_datetime")]
+    pub attached_at: Option<DateTime<FixedOffset>>,
+    /// Name of the host the volume is attached to.
     pub host_name: Option<String>,
-    pub volume_id: String, // this should be a reference to a volume
+    /// ID of the attached volume.
+    pub volume_id: String,
+    /// Device name inside the server, e.g. `/dev/vdb`.
     pub device: String,
+    /// ID of the attached volume (legacy field).
     pub id: String,
 }
 
@@ -95,43 +130,6 @@ where
     }
 }
 
-fn parse_openstack_datetime(s: &str) -> Result<DateTime<FixedOffset>, String> {
-    match DateTime::parse_from_rfc3339(s) {
-        Ok(dt) => Ok(dt),
-        Err(_) => match NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S.%f") {
-            Ok(dt) => Ok(DateTime::from_naive_utc_and_offset(
-                dt,
-                FixedOffset::east_opt(0).unwrap(),
-            )),
-            Err(_) => Err("invalid date format".to_string()),
-        },
-    }
-}
-
-fn deserialize_openstack_datetime<'de, D>(
-    deserializer: D,
-) -> Result<DateTime<FixedOffset>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    parse_openstack_datetime(&s).map_err(serde::de::Error::custom)
-}
-
-fn deserialize_optional_openstack_datetime<'de, D>(
-    deserializer: D,
-) -> Result<Option<DateTime<FixedOffset>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    match Option::<String>::deserialize(deserializer)? {
-        Some(s) => Ok(Some(
-            parse_openstack_datetime(&s).map_err(serde::de::Error::custom)?,
-        )),
-        None => Ok(None),
-    }
-}
-
 /// A volume.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Volume {
@@ -192,6 +190,8 @@ pub struct Volume {
     pub cluster_name: Option<String>,
     pub consumes_quota: Option<bool>,
     pub count: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 /// A volume root.
@@ -232,6 +232,10 @@ pub struct VolumeCreate {
         rename = "consistency_group_id"
     )]
     pub consistency_group_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiattach: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
 }
 
 /// A volume create request.
@@ -255,6 +259,310 @@ impl VolumeCreate {
             volume_type: None,
             metadata: None,
             consistency_group_id: None,
+            multiattach: None,
+            group_id: None,
         }
     }
 }
+
+/// An action to upload a volume to the Image service.
+#[cfg(feature = "image")]
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeUploadImage {
+    pub image_name: String,
+    pub force: bool,
+}
+
+#[cfg(feature = "image")]
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeUploadImageRoot {
+    #[serde(rename = "os-volume_upload_image")]
+    pub upload_image: VolumeUploadImage,
+}
+
+/// A result of uploading a volume to the Image service.
+#[cfg(feature = "image")]
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeUploadedImage {
+    pub image_id: String,
+}
+
+#[cfg(feature = "image")]
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeUploadedImageRoot {
+    #[serde(rename = "os-volume_upload_image")]
+    pub upload_image: VolumeUploadedImage,
+}
+
+/// Arguments for migrating a volume to another backend.
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeMigrate {
+    pub host: String,
+    pub force_host_copy: bool,
+    pub lock_volume: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeMigrateRoot {
+    #[serde(rename = "os-migrate_volume")]
+    pub migrate_volume: VolumeMigrate,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeUnmanageRoot {
+    #[serde(rename = "os-unmanage")]
+    pub unmanage: HashMap<String, Value>,
+}
+
+/// A volume on a backend that is not managed by the Block Storage service.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct ManageableVolume {
+    /// ID of the volume in the Block Storage service if it is already managed.
+    #[serde(default)]
+    pub cinder_id: Option<String>,
+    /// Backend-specific information about the volume.
+    #[serde(default)]
+    pub extra_info: Option<Value>,
+    /// Why the volume cannot be managed (if it cannot).
+    #[serde(default)]
+    pub reason_not_safe: Option<String>,
+    /// Backend-specific reference to pass to `manage_existing_volume`.
+    pub reference: HashMap<String, Value>,
+    /// Whether the volume can be safely taken under management.
+    pub safe_to_manage: bool,
+    /// Size of the volume in GiB.
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ManageableVolumesRoot {
+    #[serde(rename = "manageable-volumes")]
+    pub manageable_volumes: Vec<ManageableVolume>,
+}
+
+/// A request to take an existing backend volume under management.
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeManage {
+    pub host: String,
+    #[serde(rename = "ref")]
+    pub reference: HashMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeManageRoot {
+    pub volume: VolumeManage,
+}
+
+/// A volume type.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VolumeType {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub extra_specs: HashMap<String, String>,
+    pub id: String,
+    #[serde(
+        rename = "os-volume-type-access:is_public",
+        default = "default_volume_type_is_public"
+    )]
+    pub is_public: bool,
+    pub name: String,
+    #[serde(default)]
+    pub qos_specs_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[inline]
+fn default_volume_type_is_public() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeTypeRoot {
+    pub volume_type: VolumeType,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeTypesRoot {
+    pub volume_types: Vec<VolumeType>,
+}
+
+/// Encryption specification of a volume type.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct VolumeTypeEncryption {
+    /// Encryption algorithm or mode, e.g. `aes-xts-plain64`.
+    #[serde(default)]
+    pub cipher: Option<String>,
+    /// Where encryption is performed: `front-end` (Compute) or `back-end`.
+    pub control_location: String,
+    /// Size of the encryption key in bits.
+    #[serde(default)]
+    pub key_size: Option<u32>,
+    /// Encryption provider, e.g. `luks`.
+    pub provider: String,
+}
+
+/// An ID of a newly created resource.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreatedResource {
+    pub id: String,
+}
+
+/// A group type.
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupType {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupTypeRoot {
+    pub group_type: GroupType,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupTypesRoot {
+    pub group_types: Vec<GroupType>,
+}
+
+/// A generic volume group.
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeGroup {
+    #[serde(default)]
+    pub availability_zone: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_openstack_datetime")]
+    pub created_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub group_snapshot_id: Option<String>,
+    #[serde(default, rename = "group_type")]
+    pub group_type_id: Option<String>,
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub source_group_id: Option<String>,
+    pub status: VolumeGroupStatus,
+    #[serde(default)]
+    pub volume_types: Vec<String>,
+    #[serde(default)]
+    pub volumes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeGroupRoot {
+    pub group: VolumeGroup,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeGroupsRoot {
+    pub groups: Vec<VolumeGroup>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreatedVolumeGroupRoot {
+    pub group: CreatedResource,
+}
+
+/// Volume group arguments for a create request.
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeGroupCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub availability_zone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub group_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub volume_types: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeGroupCreateRoot {
+    pub group: VolumeGroupCreate,
+}
+
+/// An update to a volume group.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct VolumeGroupUpdate {
+    /// Comma-separated IDs of volumes to add.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub add_volumes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Comma-separated IDs of volumes to remove.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove_volumes: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeGroupUpdateRoot {
+    pub group: VolumeGroupUpdate,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeGroupDelete {
+    #[serde(rename = "delete-volumes")]
+    pub delete_volumes: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeGroupDeleteRoot {
+    pub delete: VolumeGroupDelete,
+}
+
+/// A snapshot of a generic volume group.
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeGroupSnapshot {
+    #[serde(default, deserialize_with = "deserialize_optional_openstack_datetime")]
+    pub created_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub group_id: String,
+    #[serde(default)]
+    pub group_type_id: Option<String>,
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    pub status: VolumeGroupSnapshotStatus,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeGroupSnapshotRoot {
+    pub group_snapshot: VolumeGroupSnapshot,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeGroupSnapshotsRoot {
+    pub group_snapshots: Vec<VolumeGroupSnapshot>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreatedVolumeGroupSnapshotRoot {
+    pub group_snapshot: CreatedResource,
+}
+
+/// Volume group snapshot arguments for a create request.
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeGroupSnapshotCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub group_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeGroupSnapshotCreateRoot {
+    pub group_snapshot: VolumeGroupSnapshotCreate,
+}
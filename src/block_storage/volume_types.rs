@@ -0,0 +1,128 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Volume type management via Block Storage API.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::super::common::{Refresh, VolumeTypeRef};
+use super::super::session::Session;
+use super::super::Result;
+use super::{api, protocol};
+
+/// Structure representing a volume type.
+#[derive(Clone, Debug)]
+pub struct VolumeType {
+    session: Session,
+    inner: protocol::VolumeType,
+}
+
+impl VolumeType {
+    /// Load a VolumeType object.
+    pub(crate) async fn load<Id: AsRef<str>>(
+        session: Session,
+        id_or_name: Id,
+    ) -> Result<VolumeType> {
+        let inner = api::get_volume_type(&session, id_or_name).await?;
+        Ok(VolumeType { session, inner })
+    }
+
+    /// List all volume types.
+    pub(crate) async fn list(session: Session) -> Result<Vec<VolumeType>> {
+        Ok(api::list_volume_types(&session)
+            .await?
+            .into_iter()
+            .map(|inner| VolumeType {
+                session: session.clone(),
+                inner,
+            })
+            .collect())
+    }
+
+    transparent_property! {
+        #[doc = "Volume type description."]
+        description: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
+    transparent_property! {
+        #[doc = "Extra specs of the volume type (usually visible only to administrators)."]
+        extra_specs: ref HashMap<String, String>
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Whether the volume type is available to all projects."]
+        is_public: bool
+    }
+
+    transparent_property! {
+        #[doc = "Volume type name."]
+        name: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the QoS specification associated with the volume type."]
+        qos_specs_id: ref Option<String>
+    }
+
+    /// Fetch the encryption specification of the volume type.
+    ///
+    /// Returns `None` if volumes of this type are not encrypted.
+    pub async fn encryption(&self) -> Result<Option<protocol::VolumeTypeEncryption>> {
+        api::get_volume_type_encryption(&self.session, &self.inner.id).await
+    }
+}
+
+#[async_trait]
+impl Refresh for VolumeType {
+    /// Refresh the volume type.
+    async fn refresh(&mut self) -> Result<()> {
+        self.inner = api::get_volume_type_by_id(&self.session, &self.inner.id).await?;
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+}
+
+impl From<VolumeType> for VolumeTypeRef {
+    fn from(value: VolumeType) -> VolumeTypeRef {
+        VolumeTypeRef::new_verified(value.inner.id)
+    }
+}
+
+#[cfg(feature = "block-storage")]
+impl VolumeTypeRef {
+    /// Verify this reference and convert to an ID, if possible.
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<VolumeTypeRef> {
+        Ok(if self.verified {
+            self
+        } else {
+            VolumeTypeRef::new_verified(api::get_volume_type(session, &self.value).await?.id)
+        })
+    }
+}
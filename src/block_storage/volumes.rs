@@ -17,15 +17,25 @@
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use futures::stream::{Stream, TryStreamExt};
+use reqwest::Url;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
 use std::time::Duration;
 
-use super::super::common::{Refresh, ResourceIterator, ResourceQuery, VolumeRef};
+#[cfg(feature = "image")]
+use super::super::common::ImageRef;
+use super::super::common::{
+    Refresh, ResourceIterator, ResourceQuery, SnapshotRef, VolumeGroupRef, VolumeRef, VolumeTypeRef,
+};
+#[cfg(feature = "compute")]
+use super::super::compute::Server;
+#[cfg(feature = "image")]
+use super::super::image::{Image, ImageWaiter};
 use super::super::session::Session;
-use super::super::utils::Query;
-use super::super::waiter::DeletionWaiter;
-use super::super::{Result, Sort};
+use super::super::utils::{url, NamePattern, Query};
+use super::super::waiter::{refresh_with_reauth, DeletionWaiter, Waiter};
+use super::super::{Error, ErrorKind, Result, Sort};
 use super::{api, protocol};
 
 /// A query to volume list.
@@ -34,7 +44,9 @@ pub struct VolumeQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
     sort: Vec<String>,
+    name_pattern: Option<NamePattern>,
 }
 
 /// Structure representing a summary of a single volume.
@@ -49,6 +61,24 @@ pub struct Volume {
 pub struct NewVolume {
     session: Session,
     inner: protocol::VolumeCreate,
+    group: Option<VolumeGroupRef>,
+    #[cfg(feature = "image")]
+    image: Option<ImageRef>,
+    source_snapshot: Option<SnapshotRef>,
+    source_volume: Option<VolumeRef>,
+    volume_type: Option<VolumeTypeRef>,
+}
+
+/// Waiter for a volume to become available.
+#[derive(Debug)]
+pub struct VolumeStatusWaiter<'volume> {
+    volume: &'volume mut Volume,
+}
+
+/// Waiter for a volume migration to finish.
+#[derive(Debug)]
+pub struct VolumeMigrationWaiter<'volume> {
+    volume: &'volume mut Volume,
 }
 
 impl Display for Volume {
@@ -64,6 +94,39 @@ impl Volume {
         Ok(Volume { session, inner })
     }
 
+    /// Create a Volume object from its JSON representation.
+    #[cfg(test)]
+    pub(crate) fn from_json(session: Session, value: Value) -> Volume {
+        Volume {
+            session,
+            inner: serde_json::from_value(value).unwrap(),
+        }
+    }
+
+    /// Take an existing backend volume under management.
+    pub(crate) async fn manage_existing(
+        session: Session,
+        host: String,
+        reference: HashMap<String, Value>,
+    ) -> Result<Volume> {
+        let request = protocol::VolumeManage { host, reference };
+        let inner = api::manage_volume(&session, request).await?;
+        Ok(Volume { session, inner })
+    }
+
+    /// List volumes on a backend that can be taken under management.
+    pub(crate) async fn list_manageable<S: AsRef<str>>(
+        session: &Session,
+        host: S,
+    ) -> Result<Vec<protocol::ManageableVolume>> {
+        api::list_manageable_volumes(session, host).await
+    }
+
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "Migration status."]
         migration_status: ref Option<String>
@@ -74,6 +137,18 @@ impl Volume {
         attachments: ref Vec<protocol::VolumeAttachment>
     }
 
+    /// Load the servers this volume is attached to.
+    ///
+    /// Usually returns at most one server unless the volume is multi-attachable.
+    #[cfg(feature = "compute")]
+    pub async fn servers(&self) -> Result<Vec<Server>> {
+        let mut result = Vec::with_capacity(self.inner.attachments.len());
+        for attachment in &self.inner.attachments {
+            result.push(Server::load(self.session.clone(), &attachment.server_id).await?);
+        }
+        Ok(result)
+    }
+
     transparent_property! {
         #[doc = "Name of the availability zone."]
         availability_zone: ref Option<String>
@@ -136,7 +211,7 @@ impl Volume {
 
     transparent_property! {
         #[doc = "Status of the volume."]
-        status: protocol::VolumeStatus
+        status: clone protocol::VolumeStatus
     }
 
     transparent_property! {
@@ -234,6 +309,74 @@ impl Volume {
         count: Option<u64>
     }
 
+    /// Upload the volume to the Image service.
+    ///
+    /// The ID of the new image is available from the returned waiter right
+    /// away, use the waiter to wait for the image to become active. The volume
+    /// itself stays in the `uploading` status until the upload is finished.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::waiter::Waiter;
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let volume = os.get_volume("appliance").await?;
+    /// let image = volume.upload_to_image("appliance-image").await?.wait().await?;
+    /// println!("Created image {}", image.id());
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "image")]
+    pub async fn upload_to_image<S: Into<String>>(&self, name: S) -> Result<ImageWaiter> {
+        let request = protocol::VolumeUploadImage {
+            image_name: name.into(),
+            force: false,
+        };
+        let image_id = api::upload_volume_to_image(&self.session, &self.inner.id, request).await?;
+        Ok(ImageWaiter::new(self.session.clone(), image_id))
+    }
+
+    /// Wait for the volume to become available.
+    ///
+    /// Useful after creating a volume or uploading it to an image. The waiter
+    /// fails if the volume gets into one of the error statuses.
+    pub fn wait_until_available(&mut self) -> VolumeStatusWaiter<'_> {
+        VolumeStatusWaiter { volume: self }
+    }
+
+    /// Migrate the volume to another backend (admin-only).
+    ///
+    /// The `host` is the target backend in the `host@backend#pool` format.
+    /// Use the returned waiter to wait for the migration to finish, the
+    /// progress is reflected in the `migration_status` field.
+    pub async fn migrate<S: Into<String>>(&mut self, host: S) -> Result<VolumeMigrationWaiter<'_>> {
+        let request = protocol::VolumeMigrate {
+            host: host.into(),
+            force_host_copy: false,
+            lock_volume: false,
+        };
+        api::migrate_volume(&self.session, &self.inner.id, request).await?;
+        Ok(VolumeMigrationWaiter { volume: self })
+    }
+
+    /// Remove the volume from the Block Storage service (admin-only).
+    ///
+    /// Unlike `delete`, the volume is kept on the backend and can be brought
+    /// back using
+    /// [Cloud::manage_existing_volume](../struct.Cloud.html#method.manage_existing_volume).
+    pub async fn unmanage(self) -> Result<()> {
+        api::unmanage_volume(&self.session, &self.inner.id).await
+    }
+
+    /// Link to the volume in the dashboard (Horizon) at `base`.
+    ///
+    /// See [Cloud::dashboard_url](../struct.Cloud.html#method.dashboard_url)
+    /// for where to get the base URL from.
+    pub fn dashboard_url(&self, base: &Url) -> Result<Url> {
+        url::dashboard(base, ["project", "volumes", &self.inner.id, ""])
+    }
+
     /// Delete the volume.
     pub async fn delete(self) -> Result<DeletionWaiter<Volume>> {
         api::delete_volume(&self.session, &self.inner.id).await?;
@@ -252,6 +395,10 @@ impl Refresh for Volume {
         self.inner = api::get_volume_by_id(&self.session, &self.inner.id).await?;
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl VolumeQuery {
@@ -260,7 +407,9 @@ impl VolumeQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
             sort: Vec::new(),
+            name_pattern: None,
         }
     }
 
@@ -289,11 +438,22 @@ impl VolumeQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by volume name."]
         with_name -> name
     }
 
+    query_name_pattern!("volumes");
+
     query_filter! {
         #[doc = "Filter by volume status."]
         with_status -> status: protocol::VolumeStatus
@@ -312,7 +472,7 @@ impl VolumeQuery {
             self.query.push_str("sort", self.sort.join(","));
         }
         debug!("Fetching volumes with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -328,13 +488,15 @@ impl VolumeQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<Volume> {
         debug!("Fetching one volume with {:?}", self.query);
-        if self.can_paginate {
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yields more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -348,10 +510,27 @@ impl ResourceQuery for VolumeQuery {
         Ok(self.can_paginate)
     }
 
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
     fn extract_marker(&self, resource: &Self::Item) -> String {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern
+            .as_ref()
+            .map_or(true, |pattern| pattern.matches(resource.name()))
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -369,17 +548,187 @@ impl ResourceQuery for VolumeQuery {
     }
 }
 
+#[async_trait]
+impl<'volume> Waiter<(), Error> for VolumeStatusWaiter<'volume> {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        // Creating from an image may involve a lengthy download.
+        Some(Duration::new(1800, 0))
+    }
+
+    fn default_delay(&self) -> Duration {
+        Duration::new(2, 0)
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(
+            ErrorKind::OperationTimedOut,
+            format!(
+                "Timeout waiting for volume {} to become available",
+                self.volume.id()
+            ),
+        )
+    }
+
+    async fn poll(&mut self) -> Result<Option<()>> {
+        refresh_with_reauth(self.volume).await?;
+        match self.volume.status() {
+            protocol::VolumeStatus::Available => {
+                debug!("Volume {} is available", self.volume.id());
+                Ok(Some(()))
+            }
+            protocol::VolumeStatus::Error
+            | protocol::VolumeStatus::ErrorBackingUp
+            | protocol::VolumeStatus::ErrorDeleting
+            | protocol::VolumeStatus::ErrorExtending
+            | protocol::VolumeStatus::ErrorRestoring => {
+                debug!(
+                    "Volume {} failed to become available - status is {}",
+                    self.volume.id(),
+                    self.volume.status()
+                );
+                Err(Error::new(
+                    ErrorKind::OperationFailed,
+                    format!(
+                        "Volume {} got into {} state",
+                        self.volume.id(),
+                        self.volume.status()
+                    ),
+                ))
+            }
+            other => {
+                trace!(
+                    "Still waiting for volume {} to become available, current is {}",
+                    self.volume.id(),
+                    other
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<'volume> VolumeStatusWaiter<'volume> {
+    /// Current state of the volume.
+    pub fn current_state(&self) -> &Volume {
+        self.volume
+    }
+}
+
+#[async_trait]
+impl<'volume> Waiter<(), Error> for VolumeMigrationWaiter<'volume> {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        // Migration may involve copying the whole volume.
+        Some(Duration::new(3600, 0))
+    }
+
+    fn default_delay(&self) -> Duration {
+        Duration::new(5, 0)
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(
+            ErrorKind::OperationTimedOut,
+            format!(
+                "Timeout waiting for volume {} to be migrated",
+                self.volume.id()
+            ),
+        )
+    }
+
+    async fn poll(&mut self) -> Result<Option<()>> {
+        refresh_with_reauth(self.volume).await?;
+        match self.volume.migration_status().as_deref() {
+            Some("success") => {
+                debug!("Volume {} has been migrated", self.volume.id());
+                Ok(Some(()))
+            }
+            Some("error") => {
+                debug!("Migration of volume {} failed", self.volume.id());
+                Err(Error::new(
+                    ErrorKind::OperationFailed,
+                    format!("Migration of volume {} failed", self.volume.id()),
+                ))
+            }
+            other => {
+                trace!(
+                    "Still waiting for volume {} to be migrated, current is {:?}",
+                    self.volume.id(),
+                    other
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<'volume> VolumeMigrationWaiter<'volume> {
+    /// Current state of the volume.
+    pub fn current_state(&self) -> &Volume {
+        self.volume
+    }
+}
+
 impl NewVolume {
     /// Start creating a volume.
     pub(crate) fn new(session: Session, size: u64) -> NewVolume {
         NewVolume {
             session,
             inner: protocol::VolumeCreate::new(size),
+            group: None,
+            #[cfg(feature = "image")]
+            image: None,
+            source_snapshot: None,
+            source_volume: None,
+            volume_type: None,
+        }
+    }
+
+    /// Start creating a volume from an image.
+    ///
+    /// Unless set explicitly, the size is inferred from the image.
+    #[cfg(feature = "image")]
+    pub(crate) fn from_image(session: Session, image: ImageRef) -> NewVolume {
+        NewVolume {
+            session,
+            inner: protocol::VolumeCreate::new(0),
+            group: None,
+            image: Some(image),
+            source_snapshot: None,
+            source_volume: None,
+            volume_type: None,
         }
     }
 
     /// Request creation of the volume.
-    pub async fn create(self) -> Result<Volume> {
+    ///
+    /// Use [Volume::wait_until_available](struct.Volume.html#method.wait_until_available)
+    /// to wait for the volume to be ready.
+    pub async fn create(mut self) -> Result<Volume> {
+        #[cfg(feature = "image")]
+        if let Some(image_ref) = self.image.take() {
+            let image = Image::new(self.session.clone(), image_ref).await?;
+            if self.inner.size == 0 {
+                self.inner.size = size_from_image(&image);
+                debug!(
+                    "Using size {} GiB for a volume from image {}",
+                    self.inner.size,
+                    image.id()
+                );
+            }
+            self.inner.image_id = Some(image.id().clone());
+        }
+        if let Some(snapshot) = self.source_snapshot {
+            self.inner.snapshot_id = Some(snapshot.into_verified(&self.session).await?.into());
+        }
+        if let Some(volume) = self.source_volume {
+            self.inner.source_volume_id = Some(volume.into_verified(&self.session).await?.into());
+        }
+        if let Some(volume_type) = self.volume_type {
+            self.inner.volume_type = Some(volume_type.into_verified(&self.session).await?.into());
+        }
+        if let Some(group) = self.group {
+            self.inner.group_id = Some(group.into_verified(&self.session).await?.into());
+        }
         let inner = api::create_volume(&self.session, self.inner).await?;
         Ok(Volume {
             session: self.session,
@@ -387,6 +736,11 @@ impl NewVolume {
         })
     }
 
+    creation_inner_field! {
+        #[doc = "Set the size in GiB."]
+        set_size, with_size -> size: u64
+    }
+
     creation_inner_field! {
         #[doc = "Set the availability zone."]
         set_availability_zone, with_availability_zone -> availability_zone: optional String
@@ -422,9 +776,91 @@ impl NewVolume {
         set_image_id, with_image_id -> image_id: optional String
     }
 
+    /// Set the volume type.
+    pub fn set_volume_type<T>(&mut self, value: T)
+    where
+        T: Into<VolumeTypeRef>,
+    {
+        self.volume_type = Some(value.into());
+    }
+
+    /// Set the volume type.
+    #[inline]
+    pub fn with_volume_type<T>(mut self, value: T) -> Self
+    where
+        T: Into<VolumeTypeRef>,
+    {
+        self.set_volume_type(value);
+        self
+    }
+
+    /// Create the volume in a volume group.
+    ///
+    /// Requires Block Storage API version 3.13. The volume type must be one
+    /// of the volume types of the group.
+    pub fn set_group<G>(&mut self, value: G)
+    where
+        G: Into<VolumeGroupRef>,
+    {
+        self.group = Some(value.into());
+    }
+
+    /// Create the volume in a volume group.
+    ///
+    /// Requires Block Storage API version 3.13. The volume type must be one
+    /// of the volume types of the group.
+    #[inline]
+    pub fn with_group<G>(mut self, value: G) -> Self
+    where
+        G: Into<VolumeGroupRef>,
+    {
+        self.set_group(value);
+        self
+    }
+
+    /// Create the volume from a snapshot.
+    pub fn set_source_snapshot<S>(&mut self, value: S)
+    where
+        S: Into<SnapshotRef>,
+    {
+        self.source_snapshot = Some(value.into());
+    }
+
+    /// Create the volume from a snapshot.
+    #[inline]
+    pub fn with_source_snapshot<S>(mut self, value: S) -> Self
+    where
+        S: Into<SnapshotRef>,
+    {
+        self.set_source_snapshot(value);
+        self
+    }
+
+    /// Create the volume as a clone of another volume.
+    ///
+    /// The source volume can be given by its ID or name.
+    pub fn set_source_volume<V>(&mut self, value: V)
+    where
+        V: Into<VolumeRef>,
+    {
+        self.source_volume = Some(value.into());
+    }
+
+    /// Create the volume as a clone of another volume.
+    ///
+    /// The source volume can be given by its ID or name.
+    #[inline]
+    pub fn with_source_volume<V>(mut self, value: V) -> Self
+    where
+        V: Into<VolumeRef>,
+    {
+        self.set_source_volume(value);
+        self
+    }
+
     creation_inner_field! {
-        #[doc = "Set the volume type."]
-        set_volume_type, with_volume_type -> volume_type: optional String
+        #[doc = "Allow attaching the volume to several servers at once."]
+        set_multiattach, with_multiattach -> multiattach: optional bool
     }
 
     creation_inner_field! {
@@ -438,6 +874,18 @@ impl NewVolume {
     }
 }
 
+/// Minimum volume size in GiB that fits the image.
+#[cfg(feature = "image")]
+fn size_from_image(image: &Image) -> u64 {
+    const GIB: u64 = 1024 * 1024 * 1024;
+    let data_size = image
+        .virtual_size()
+        .or_else(|| image.size())
+        .map(|size| (size + GIB - 1) / GIB)
+        .unwrap_or(0);
+    data_size.max(image.minimum_required_disk().into()).max(1)
+}
+
 impl From<Volume> for VolumeRef {
     fn from(value: Volume) -> VolumeRef {
         VolumeRef::new_verified(value.inner.id)
@@ -16,30 +16,67 @@
 
 #[allow(unused_imports)]
 use futures::io::AsyncRead;
+use futures::FutureExt;
+use std::collections::HashMap;
 #[allow(unused_imports)]
 use std::io;
+#[cfg(feature = "object-storage")]
+use std::path::Path;
+
+use osauth::client::AuthenticatedClient;
+use osauth::identity::ApplicationCredential;
+use osauth::{BasicAuth, CloudConfig};
+use reqwest::{Client, Url};
 
-use super::auth::AuthType;
+#[cfg(feature = "identity")]
+use super::auth::Scope;
+use super::auth::{AuthType, NoAuth, Password, Token};
 #[cfg(feature = "block-storage")]
-use super::block_storage::{NewVolume, Volume, VolumeQuery};
+use super::block_storage::{
+    ManageableVolume, NewVolume, NewVolumeGroup, Volume, VolumeGroup, VolumeGroupQuery,
+    VolumeGroupSnapshot, VolumeGroupSnapshotQuery, VolumeQuery, VolumeType,
+};
+use super::common::ApiVersion;
 #[allow(unused_imports)]
-use super::common::{ContainerRef, FlavorRef, NetworkRef};
+use super::common::{
+    ContainerRef, FlavorRef, FloatingIpRef, ImageRef, NetworkRef, ProjectRef, ServerRef, UserRef,
+};
 #[cfg(feature = "compute")]
 use super::compute::{
-    Flavor, FlavorQuery, FlavorSummary, KeyPair, KeyPairQuery, NewKeyPair, NewServer, Server,
-    ServerQuery, ServerSummary,
+    self, Flavor, FlavorQuery, FlavorSummary, KeyPair, KeyPairQuery, NewKeyPair, NewServer, Server,
+    ServerExternalEvent, ServerQuery, ServerSummary,
 };
+#[cfg(feature = "identity")]
+use super::identity::{self, Ec2Credential, ServiceProbe, TokenInfo};
 #[cfg(feature = "image")]
-use super::image::{Image, ImageQuery};
+use super::image::{Image, ImageCache, ImageQuery, NewImage};
+#[cfg(all(
+    feature = "block-storage",
+    feature = "compute",
+    feature = "image",
+    feature = "network"
+))]
+use super::inventory::Inventory;
+#[cfg(feature = "metric")]
+use super::metric::MeasureQuery;
 #[cfg(feature = "network")]
 use super::network::{
-    FloatingIp, FloatingIpQuery, Network, NetworkQuery, NewFloatingIp, NewNetwork, NewPort,
-    NewRouter, NewSubnet, Port, PortQuery, Router, RouterQuery, Subnet, SubnetQuery,
+    self, FirewallGroup, FirewallGroupQuery, FirewallPolicy, FirewallPolicyQuery, FirewallRule,
+    FirewallRuleQuery, FloatingIp, FloatingIpQuery, Network, NetworkPurgeReport, NetworkQuery,
+    NetworkQuotaUsage, NewFirewallGroup, NewFirewallPolicy, NewFirewallRule, NewFloatingIp,
+    NewNetwork, NewPort, NewRouter, NewSubnet, Port, PortQuery, Router, RouterQuery, SecurityGroup,
+    Subnet, SubnetQuery,
 };
 #[cfg(feature = "object-storage")]
 use super::object_storage::{Container, ContainerQuery, NewObject, Object, ObjectQuery};
-use super::session::Session;
-use super::{EndpointFilters, InterfaceType, Result};
+#[cfg(feature = "placement")]
+use super::placement::{AllocationCandidateQuery, ResourceProvider, ResourceProviderQuery};
+use super::ratelimit::RateLimitedAuth;
+use super::reauth::RenewableAuth;
+use super::session::{ServiceType, Session};
+use super::tls::{self, TlsConfig};
+use super::utils;
+use super::{EndpointFilters, Error, ErrorKind, InterfaceType, Result};
 
 /// OpenStack cloud API.
 ///
@@ -47,6 +84,8 @@ use super::{EndpointFilters, InterfaceType, Result};
 #[derive(Debug, Clone)]
 pub struct Cloud {
     session: Session,
+    dashboard_url: Option<Url>,
+    rate_limit: Option<u32>,
 }
 
 impl Cloud {
@@ -76,14 +115,39 @@ impl Cloud {
     ///
     /// * [from_config](#method.from_config) to create a Cloud from clouds.yaml
     /// * [from_env](#method.from_env) to create a Cloud from environment variables
+    /// * [new_with_tls](#method.new_with_tls) to use custom TLS options
     pub async fn new<Auth: AuthType + 'static>(auth_type: Auth) -> Result<Cloud> {
         Ok(Cloud {
             session: Session::new(auth_type).await?,
+            dashboard_url: None,
+            rate_limit: None,
+        })
+    }
+
+    /// Create a new cloud object with a given authentication plugin and TLS options.
+    ///
+    /// See [TlsConfig](struct.TlsConfig.html) for an example.
+    pub async fn new_with_tls<Auth: AuthType + 'static>(
+        auth_type: Auth,
+        tls: &TlsConfig,
+    ) -> Result<Cloud> {
+        Ok(Cloud {
+            session: Session::new_with_client(tls.build_client()?, auth_type).await?,
+            dashboard_url: None,
+            rate_limit: None,
         })
     }
 
     /// Create a new cloud object from a configuration file
     ///
+    /// The URL of the dashboard is read from the `dashboard_url` key of the
+    /// cloud entry, see [dashboard_url](#method.dashboard_url).
+    ///
+    /// TLS options are read from the `cacert`, `cert`, `key` and `verify` keys.
+    ///
+    /// If a service rejects the token, a new one is requested using the same
+    /// configuration.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -92,13 +156,28 @@ impl Cloud {
     /// # Ok(()) }
     /// ```
     pub async fn from_config<S: AsRef<str>>(cloud_name: S) -> Result<Cloud> {
+        let config = CloudConfig::from_config(cloud_name)?;
+        let dashboard_url = dashboard_url_from_config(&config)?;
+        let value = tls::config_to_value(&config)?;
+        let tls = TlsConfig::from_config(&value)?;
         Ok(Cloud {
-            session: Session::from_config(cloud_name).await?,
+            session: create_session(config, &tls).await?,
+            dashboard_url,
+            rate_limit: None,
         })
     }
 
     /// Create a new cloud object from environment variables.
     ///
+    /// Endpoint overrides are read from `OS_<SERVICE TYPE>_ENDPOINT_OVERRIDE`
+    /// variables, e.g. `OS_BAREMETAL_ENDPOINT_OVERRIDE`.
+    ///
+    /// TLS options are read from `OS_CACERT`, `OS_CERT`, `OS_KEY` and
+    /// `OS_INSECURE` variables.
+    ///
+    /// If a service rejects the token, a new one is requested using the same
+    /// settings.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -107,8 +186,18 @@ impl Cloud {
     /// # Ok(()) }
     /// ```
     pub async fn from_env() -> Result<Cloud> {
+        let overrides = endpoint_overrides_from_env(utils::env_vars())?;
+        let config = CloudConfig::from_env()?;
+        let value = tls::config_to_value(&config)?;
+        let tls = TlsConfig::from_config(&value)?.with_vars(utils::env_vars());
+        let mut session = create_session(config, &tls).await?;
+        if !overrides.is_empty() {
+            session.endpoint_overrides_mut().extend(overrides);
+        }
         Ok(Cloud {
-            session: Session::from_env().await?,
+            session,
+            dashboard_url: None,
+            rate_limit: None,
         })
     }
 
@@ -169,11 +258,361 @@ impl Cloud {
         self
     }
 
+    /// Convert this cloud into one using the given endpoint for a service.
+    ///
+    /// Useful for services that are not in the catalog or are accessed
+    /// through a proxy.
+    ///
+    /// Removes cached endpoint information and detaches this object from a shared `Session`.
+    #[inline]
+    pub fn with_endpoint_override<Srv: ServiceType>(mut self, service: Srv, url: Url) -> Cloud {
+        self.session.set_endpoint_override(service, url);
+        self
+    }
+
+    /// Convert this cloud into one using the given endpoint overrides.
+    ///
+    /// The keys are service types as they appear in the catalog, e.g.
+    /// `baremetal` or `volumev3`. Existing overrides are replaced.
+    ///
+    /// Removes cached endpoint information and detaches this object from a shared `Session`.
+    #[inline]
+    pub fn with_endpoint_overrides(mut self, endpoint_overrides: HashMap<String, Url>) -> Cloud {
+        *self.endpoint_overrides_mut() = endpoint_overrides;
+        self
+    }
+
+    /// Endpoint overrides for this cloud.
+    ///
+    /// Overrides from `clouds.yaml` (`<service type>_endpoint_override`) are
+    /// loaded automatically by [from_config](#method.from_config).
+    #[inline]
+    pub fn endpoint_overrides(&self) -> &HashMap<String, Url> {
+        self.session.endpoint_overrides()
+    }
+
+    /// Modify endpoint overrides for this cloud.
+    ///
+    /// Removes cached endpoint information and detaches this object from a shared `Session`.
+    #[inline]
+    pub fn endpoint_overrides_mut(&mut self) -> &mut HashMap<String, Url> {
+        self.session.endpoint_overrides_mut()
+    }
+
+    /// URL of the dashboard (Horizon) for this cloud, if known.
+    ///
+    /// Use it with the `dashboard_url` calls of resources to build links
+    /// to them, e.g. [Server::dashboard_url](compute/struct.Server.html#method.dashboard_url).
+    /// These calls fail with `InvalidInput` if the URL cannot be a base,
+    /// e.g. `mailto:admin@example.com`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_config("cloud-1").await?;
+    /// let server = os.get_server("my-server").await?;
+    /// if let Some(base) = os.dashboard_url() {
+    ///     println!("See {}", server.dashboard_url(base)?);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[inline]
+    pub fn dashboard_url(&self) -> Option<&Url> {
+        self.dashboard_url.as_ref()
+    }
+
+    /// Convert this cloud into one using the given dashboard URL.
+    #[inline]
+    pub fn with_dashboard_url(mut self, dashboard_url: Url) -> Cloud {
+        self.dashboard_url = Some(dashboard_url);
+        self
+    }
+
+    /// Convert this cloud into one sending at most `per_second` requests per second.
+    ///
+    /// The limit is shared by all requests of this object and of resources
+    /// and clones created from it afterwards, extra requests wait for their
+    /// turn. Setting another limit adds to the existing one, so the lowest
+    /// limit takes effect. A value of zero is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// async fn cloud_from_env() -> openstack::Result<openstack::Cloud> {
+    ///     openstack::Cloud::from_env().await.map(|os| os.with_rate_limit(10))
+    /// }
+    /// ```
+    ///
+    /// Removes cached endpoint information and detaches this object from a shared `Session`.
+    pub fn with_rate_limit(mut self, per_second: u32) -> Cloud {
+        if per_second > 0 {
+            self.apply_rate_limit(per_second);
+            self.rate_limit = Some(self.rate_limit.map_or(per_second, |x| x.min(per_second)));
+        }
+        self
+    }
+
+    /// Maximum number of requests per second (if limited).
+    #[inline]
+    pub fn rate_limit(&self) -> Option<u32> {
+        self.rate_limit
+    }
+
+    fn apply_rate_limit(&mut self, per_second: u32) {
+        let auth = RateLimitedAuth::new(self.session.client().clone(), per_second);
+        self.session.set_auth_type(auth);
+    }
+
+    /// Session used by this cloud.
+    ///
+    /// The session can be used to access services that are not covered by
+    /// this crate, including private extensions with custom service types.
+    /// Clone it to get an owned session sharing the same authentication, or
+    /// use `Session::from(cloud)` to consume the `Cloud`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::session::{GenericService, VersionSelector};
+    ///
+    /// const CUSTOM_METRICS: GenericService =
+    ///     GenericService::new("custom-metrics", VersionSelector::Major(1));
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let metrics: serde_json::Value = os
+    ///     .session()
+    ///     .get_json(CUSTOM_METRICS, &["metrics"])
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    #[inline]
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
     /// Refresh this `Cloud` object (renew token, refetch service catalog, etc).
     pub async fn refresh(&mut self) -> Result<()> {
         self.session.refresh().await
     }
 
+    /// Concurrently fetch servers, volumes, images and network resources.
+    ///
+    /// The resulting `Inventory` resolves references between the resources,
+    /// e.g. from a floating IP to its port and server.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let inventory = os.snapshot().await?;
+    /// for fip in inventory.floating_ips() {
+    ///     if let Some(server) = inventory.server_of_floating_ip(fip) {
+    ///         println!("{} -> {}", fip.floating_ip_address(), server.name());
+    ///     }
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[cfg(all(
+        feature = "block-storage",
+        feature = "compute",
+        feature = "image",
+        feature = "network"
+    ))]
+    pub async fn snapshot(&self) -> Result<Inventory> {
+        Inventory::fetch(&self.session).await
+    }
+
+    /// Check whether the service supports the given API (micro)version.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::common::ApiVersion;
+    /// use openstack::session::COMPUTE;
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// if os.supports(COMPUTE, ApiVersion(2, 90)).await? {
+    ///     println!("Server host names can be set");
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub async fn supports<Srv>(&self, service: Srv, version: ApiVersion) -> Result<bool>
+    where
+        Srv: ServiceType + Send,
+    {
+        self.session.supports_api_version(service, version).await
+    }
+
+    /// Minimum and maximum API (micro)versions supported by the service.
+    ///
+    /// Returns `None` if the service does not support microversions.
+    pub async fn supported_microversions<Srv>(
+        &self,
+        service: Srv,
+    ) -> Result<Option<(ApiVersion, ApiVersion)>>
+    where
+        Srv: ServiceType + Send,
+    {
+        self.session.get_api_versions(service).await
+    }
+
+    /// Switch this `Cloud` to a different scope, e.g. another project.
+    ///
+    /// The current token is exchanged for a token with the new scope, no
+    /// credentials are required. Since the new token is derived from the
+    /// current one, it cannot be renewed after the original token expires.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// async fn other_project() -> openstack::Result<openstack::Cloud> {
+    ///     let mut cloud = openstack::Cloud::from_env().await?;
+    ///     cloud
+    ///         .rescope(openstack::auth::Scope::Project {
+    ///             project: openstack::IdOrName::from_name("project2"),
+    ///             domain: Some(openstack::IdOrName::from_id("default")),
+    ///         })
+    ///         .await?;
+    ///     Ok(cloud)
+    /// }
+    /// ```
+    ///
+    /// Removes cached endpoint information and detaches this object from a shared `Session`.
+    #[cfg(feature = "identity")]
+    pub async fn rescope(&mut self, scope: Scope) -> Result<()> {
+        identity::rescope(&mut self.session, scope).await?;
+        // The new authentication replaces the rate limiting wrapper.
+        if let Some(per_second) = self.rate_limit {
+            self.apply_rate_limit(per_second);
+        }
+        Ok(())
+    }
+
+    /// Get details of the token used by this `Cloud`.
+    ///
+    /// Includes the expiration time, the scope, the roles and the catalog.
+    #[cfg(feature = "identity")]
+    pub async fn token_info(&self) -> Result<TokenInfo> {
+        identity::get_token_info(&self.session).await
+    }
+
+    /// Probe all services from the catalog.
+    ///
+    /// Concurrently requests the endpoint of each service (respecting the
+    /// endpoint filters and overrides) and reports whether it is reachable,
+    /// how long it took to respond and which version it reports.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// for probe in os.probe_services().await? {
+    ///     if !probe.is_healthy() {
+    ///         println!("Service {} is unhealthy: {:?}", probe.service_type, probe.error);
+    ///     }
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "identity")]
+    pub async fn probe_services(&self) -> Result<Vec<ServiceProbe>> {
+        identity::probe_services(&self.session).await
+    }
+
+    /// Create EC2-style credentials for a user in a project.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let token = os.token_info().await?;
+    /// let project = token.project.expect("Token is not project scoped");
+    /// let creds = os.create_ec2_credential(token.user.id, project.id).await?;
+    /// println!("Access key {}", creds.access());
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "identity")]
+    pub async fn create_ec2_credential<U, P>(&self, user: U, project: P) -> Result<Ec2Credential>
+    where
+        U: Into<UserRef>,
+        P: Into<ProjectRef>,
+    {
+        Ec2Credential::create(self.session.clone(), user.into(), project.into()).await
+    }
+
+    /// Get EC2-style credentials of a user by the access key.
+    #[cfg(feature = "identity")]
+    pub async fn get_ec2_credential<U, A>(&self, user: U, access: A) -> Result<Ec2Credential>
+    where
+        U: Into<UserRef>,
+        A: AsRef<str>,
+    {
+        Ec2Credential::load(self.session.clone(), user.into(), access).await
+    }
+
+    /// List EC2-style credentials of a user.
+    #[cfg(feature = "identity")]
+    pub async fn list_ec2_credentials<U>(&self, user: U) -> Result<Vec<Ec2Credential>>
+    where
+        U: Into<UserRef>,
+    {
+        Ec2Credential::list(self.session.clone(), user.into()).await
+    }
+
+    /// Create several networks in one request.
+    ///
+    /// The Networking service creates either all networks or none of them,
+    /// so an error means that no networks were created.
+    #[cfg(feature = "network")]
+    pub async fn bulk_create_networks<I>(&self, networks: I) -> Result<Vec<Network>>
+    where
+        I: IntoIterator<Item = NewNetwork>,
+    {
+        NewNetwork::create_many(self.session.clone(), networks.into_iter().collect()).await
+    }
+
+    /// Create several ports in one request.
+    ///
+    /// The ports are validated and their references are resolved before
+    /// sending the request. The Networking service creates either all ports
+    /// or none of them, so an error means that no ports were created.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let ports = (0..200).map(|idx| {
+    ///     os.new_port("cluster-net").with_name(format!("node-{}", idx))
+    /// });
+    /// let created = os.bulk_create_ports(ports).await?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "network")]
+    pub async fn bulk_create_ports<I>(&self, ports: I) -> Result<Vec<Port>>
+    where
+        I: IntoIterator<Item = NewPort>,
+    {
+        NewPort::create_many(self.session.clone(), ports.into_iter().collect()).await
+    }
+
+    /// Create several subnets in one request.
+    ///
+    /// The Networking service creates either all subnets or none of them,
+    /// so an error means that no subnets were created.
+    #[cfg(feature = "network")]
+    pub async fn bulk_create_subnets<I>(&self, subnets: I) -> Result<Vec<Subnet>>
+    where
+        I: IntoIterator<Item = NewSubnet>,
+    {
+        NewSubnet::create_many(self.session.clone(), subnets.into_iter().collect()).await
+    }
+
     /// Create a new container.
     ///
     /// If the container already exists, this call returns successfully.
@@ -193,6 +632,55 @@ impl Cloud {
         Object::create(self.session.clone(), container, name, body).await
     }
 
+    /// Associate a floating IP with a server.
+    ///
+    /// The server can be given by its name or ID, the floating IP - by its ID
+    /// or address. The server's port is discovered automatically, see
+    /// [FloatingIp::associate_with_server](network/struct.FloatingIp.html#method.associate_with_server).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let fip = os.associate_floating_ip("my-server", "172.24.4.10")
+    ///     .await
+    ///     .expect("Unable to associate a floating IP");
+    /// # }
+    /// ```
+    #[cfg(feature = "network")]
+    pub async fn associate_floating_ip<S, F>(&self, server: S, floating_ip: F) -> Result<FloatingIp>
+    where
+        S: Into<ServerRef>,
+        F: Into<FloatingIpRef>,
+    {
+        let mut fip = FloatingIp::load_by_ref(self.session.clone(), floating_ip.into()).await?;
+        fip.associate_with_server(server).await?;
+        Ok(fip)
+    }
+
+    /// Dissociate a floating IP from a server.
+    ///
+    /// The server can be given by its name or ID, the floating IP - by its ID
+    /// or address. Fails with `InvalidInput` if the floating IP is not
+    /// associated with this server.
+    #[cfg(feature = "network")]
+    pub async fn dissociate_floating_ip<S, F>(
+        &self,
+        server: S,
+        floating_ip: F,
+    ) -> Result<FloatingIp>
+    where
+        S: Into<ServerRef>,
+        F: Into<FloatingIpRef>,
+    {
+        let mut fip = FloatingIp::load_by_ref(self.session.clone(), floating_ip.into()).await?;
+        fip.dissociate_from_server(server).await?;
+        Ok(fip)
+    }
+
     /// Build a query against container list.
     ///
     /// The returned object is a builder that should be used to construct
@@ -224,6 +712,59 @@ impl Cloud {
         FlavorQuery::new(self.session.clone())
     }
 
+    /// Find the smallest flavor with at least the given VCPU count and RAM.
+    ///
+    /// RAM size is in MiB. Among suitable flavors the one with the fewest
+    /// VCPUs is returned, then the one with the least RAM and root disk.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let flavor = os
+    ///     .find_smallest_flavor(2, 4096)
+    ///     .await
+    ///     .expect("No suitable flavor");
+    /// # }
+    /// ```
+    #[cfg(feature = "compute")]
+    pub async fn find_smallest_flavor(&self, vcpu_count: u32, ram_size: u64) -> Result<Flavor> {
+        self.find_flavors()
+            .detailed()
+            .smallest(vcpu_count, ram_size)
+            .await
+    }
+
+    /// Build a query against firewall group list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "network")]
+    pub fn find_firewall_groups(&self) -> FirewallGroupQuery {
+        FirewallGroupQuery::new(self.session.clone())
+    }
+
+    /// Build a query against firewall policy list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "network")]
+    pub fn find_firewall_policies(&self) -> FirewallPolicyQuery {
+        FirewallPolicyQuery::new(self.session.clone())
+    }
+
+    /// Build a query against firewall rule list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "network")]
+    pub fn find_firewall_rules(&self) -> FirewallRuleQuery {
+        FirewallRuleQuery::new(self.session.clone())
+    }
+
     /// Build a query against floating IP list.
     ///
     /// The returned object is a builder that should be used to construct
@@ -251,6 +792,37 @@ impl Cloud {
         KeyPairQuery::new(self.session.clone())
     }
 
+    /// Build a query for measures of a resource metric.
+    ///
+    /// Requires the Metric service (Gnocchi) to be deployed.
+    ///
+    /// # Example
+    ///
+    /// Average CPU time consumed by a server per 5 minutes during the last hour:
+    ///
+    /// ```rust,no_run
+    /// use chrono::{Duration, Utc};
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let measures = os.find_measures("8a1c355b-2e1e-440a-8aa8-f272df72bc32", "cpu")
+    ///     .with_resource_type("instance")
+    ///     .with_aggregation("rate:mean")
+    ///     .with_granularity(300)
+    ///     .with_start(Utc::now() - Duration::hours(1))
+    ///     .all().await.expect("Unable to fetch measures");
+    /// # }
+    /// ```
+    #[cfg(feature = "metric")]
+    pub fn find_measures<R, M>(&self, resource_id: R, metric: M) -> MeasureQuery
+    where
+        R: Into<String>,
+        M: Into<String>,
+    {
+        MeasureQuery::new(self.session.clone(), resource_id.into(), metric.into())
+    }
+
     /// Build a query against network list.
     ///
     /// The returned object is a builder that should be used to construct
@@ -269,6 +841,39 @@ impl Cloud {
         PortQuery::new(self.session.clone())
     }
 
+    /// Build a query for allocation candidates.
+    ///
+    /// The returned object is a builder that should be used to specify the
+    /// requested resources.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let candidates = os.find_allocation_candidates()
+    ///     .with_resource("VCPU", 8)
+    ///     .with_resource("VGPU", 1)
+    ///     .fetch().await.expect("Unable to fetch allocation candidates");
+    /// println!("{} possible allocations", candidates.allocation_requests.len());
+    /// # }
+    /// ```
+    #[cfg(feature = "placement")]
+    pub fn find_allocation_candidates(&self) -> AllocationCandidateQuery {
+        AllocationCandidateQuery::new(self.session.clone())
+    }
+
+    /// Build a query against resource provider list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "placement")]
+    pub fn find_resource_providers(&self) -> ResourceProviderQuery {
+        ResourceProviderQuery::new(self.session.clone())
+    }
+
     /// Build a query against router list.
     ///
     /// The returned object is a builder that should be used to construct
@@ -321,6 +926,24 @@ impl Cloud {
         VolumeQuery::new(self.session.clone())
     }
 
+    /// Build a query against volume group list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "block-storage")]
+    pub fn find_volume_groups(&self) -> VolumeGroupQuery {
+        VolumeGroupQuery::new(self.session.clone())
+    }
+
+    /// Build a query against volume group snapshot list.
+    ///
+    /// The returned object is a builder that should be used to construct
+    /// the query.
+    #[cfg(feature = "block-storage")]
+    pub fn find_volume_group_snapshots(&self) -> VolumeGroupSnapshotQuery {
+        VolumeGroupSnapshotQuery::new(self.session.clone())
+    }
+
     /// Get object container metadata by its name.
     ///
     /// # Example
@@ -359,7 +982,24 @@ impl Cloud {
         Object::load(self.session.clone(), container, name).await
     }
 
-    /// Find a flavor by its name or ID.
+    /// Find a flavor by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let server = os.get_flavor("m1.medium").await.expect("Unable to get a flavor");
+    /// # }
+    /// ```
+    #[cfg(feature = "compute")]
+    pub async fn get_flavor<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Flavor> {
+        Flavor::load(self.session.clone(), id_or_name).await
+    }
+
+    /// Find a firewall group by its name or ID.
     ///
     /// # Example
     ///
@@ -368,12 +1008,33 @@ impl Cloud {
     ///
     /// # async fn async_wrapper() {
     /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
-    /// let server = os.get_flavor("m1.medium").await.expect("Unable to get a flavor");
+    /// let group = os
+    ///     .get_firewall_group("default")
+    ///     .await
+    ///     .expect("Unable to get a firewall group");
     /// # }
     /// ```
-    #[cfg(feature = "compute")]
-    pub async fn get_flavor<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<Flavor> {
-        Flavor::load(self.session.clone(), id_or_name).await
+    #[cfg(feature = "network")]
+    pub async fn get_firewall_group<Id: AsRef<str>>(
+        &self,
+        id_or_name: Id,
+    ) -> Result<FirewallGroup> {
+        FirewallGroup::load(self.session.clone(), id_or_name).await
+    }
+
+    /// Find a firewall policy by its name or ID.
+    #[cfg(feature = "network")]
+    pub async fn get_firewall_policy<Id: AsRef<str>>(
+        &self,
+        id_or_name: Id,
+    ) -> Result<FirewallPolicy> {
+        FirewallPolicy::load(self.session.clone(), id_or_name).await
+    }
+
+    /// Find a firewall rule by its name or ID.
+    #[cfg(feature = "network")]
+    pub async fn get_firewall_rule<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<FirewallRule> {
+        FirewallRule::load(self.session.clone(), id_or_name).await
     }
 
     /// Find a floating IP by its ID.
@@ -412,6 +1073,15 @@ impl Cloud {
         Image::new(self.session.clone(), id_or_name).await
     }
 
+    /// Get the cached and queued images of the Image service.
+    ///
+    /// Requires Image API 2.14 and usually administrator privileges. Note
+    /// that each Image API node has its own cache.
+    #[cfg(feature = "image")]
+    pub async fn get_image_cache(&self) -> Result<ImageCache> {
+        Image::cache(&self.session).await
+    }
+
     /// Find a key pair by its name or ID.
     ///
     /// # Example
@@ -446,6 +1116,74 @@ impl Cloud {
         Network::load(self.session.clone(), id_or_name).await
     }
 
+    /// Get network quotas of a project together with their current usage.
+    ///
+    /// Uses a single request if the `quota_details` extension is available,
+    /// otherwise counts the resources of the project.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let usage = os
+    ///     .network_quota_usage("demo")
+    ///     .await
+    ///     .expect("Unable to get quota usage");
+    /// println!("Floating IPs left: {:?}", usage.floating_ips.available());
+    /// # }
+    /// ```
+    #[cfg(feature = "network")]
+    pub async fn network_quota_usage<P>(&self, project: P) -> Result<NetworkQuotaUsage>
+    where
+        P: Into<ProjectRef>,
+    {
+        let project = project.into().into_verified(&self.session).await?;
+        network::get_quota_usage(&self.session, project).await
+    }
+
+    /// Get the default security group of a project.
+    ///
+    /// The project can be given by its ID or name.
+    #[cfg(feature = "network")]
+    pub async fn get_default_security_group<P>(&self, project: P) -> Result<SecurityGroup>
+    where
+        P: Into<ProjectRef>,
+    {
+        let project = project.into().into_verified(&self.session).await?;
+        network::get_default_security_group(&self.session, project).await
+    }
+
+    /// Delete network resources of a project (administrators only).
+    ///
+    /// Deletes floating IPs, routers (after removing their interfaces), ports
+    /// and networks owned by the project, similarly to `neutron purge`.
+    /// Ports managed by the Networking service itself are removed together
+    /// with their owners. Deletion continues after failures, which are
+    /// collected in the report.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let report = os.purge_network_resources("demo").await?;
+    /// for failure in &report.failures {
+    ///     println!("{} {}: {}", failure.resource_type, failure.id, failure.error);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "network")]
+    pub async fn purge_network_resources<P>(&self, project: P) -> Result<NetworkPurgeReport>
+    where
+        P: Into<ProjectRef>,
+    {
+        let project = project.into().into_verified(&self.session).await?;
+        network::purge_network_resources(&self.session, project).await
+    }
+
     /// Find an port by its name or ID.
     ///
     /// # Example
@@ -482,6 +1220,29 @@ impl Cloud {
         Router::load(self.session.clone(), id_or_name).await
     }
 
+    /// Find a resource provider by its name or UUID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let provider = os.get_resource_provider("compute-1.example.com")
+    ///     .await
+    ///     .expect("Unable to get a resource provider");
+    /// let usages = provider.usages().await.expect("Unable to get usages");
+    /// # }
+    /// ```
+    #[cfg(feature = "placement")]
+    pub async fn get_resource_provider<Id: AsRef<str>>(
+        &self,
+        id_or_name: Id,
+    ) -> Result<ResourceProvider> {
+        ResourceProvider::load(self.session.clone(), id_or_name).await
+    }
+
     /// Find a server by its name or ID.
     ///
     /// # Example
@@ -537,6 +1298,42 @@ impl Cloud {
         Volume::new(self.session.clone(), id_or_name).await
     }
 
+    /// Find a volume group by its name or ID.
+    ///
+    /// Requires Block Storage API version 3.13.
+    #[cfg(feature = "block-storage")]
+    pub async fn get_volume_group<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<VolumeGroup> {
+        VolumeGroup::load(self.session.clone(), id_or_name).await
+    }
+
+    /// Find a volume group snapshot by its name or ID.
+    ///
+    /// Requires Block Storage API version 3.14.
+    #[cfg(feature = "block-storage")]
+    pub async fn get_volume_group_snapshot<Id: AsRef<str>>(
+        &self,
+        id_or_name: Id,
+    ) -> Result<VolumeGroupSnapshot> {
+        VolumeGroupSnapshot::load(self.session.clone(), id_or_name).await
+    }
+
+    /// Find a volume type by its name or ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let volume_type = os.get_volume_type("ssd").await.expect("Unable to get a volume type");
+    /// # }
+    /// ```
+    #[cfg(feature = "block-storage")]
+    pub async fn get_volume_type<Id: AsRef<str>>(&self, id_or_name: Id) -> Result<VolumeType> {
+        VolumeType::load(self.session.clone(), id_or_name).await
+    }
+
     /// List all containers.
     ///
     /// This call can yield a lot of results, use the
@@ -603,6 +1400,36 @@ impl Cloud {
         self.find_flavors().all().await
     }
 
+    /// List all firewall groups.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_firewall_groups](#method.find_firewall_groups) call to limit the
+    /// number of firewall groups to receive.
+    #[cfg(feature = "network")]
+    pub async fn list_firewall_groups(&self) -> Result<Vec<FirewallGroup>> {
+        self.find_firewall_groups().all().await
+    }
+
+    /// List all firewall policies.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_firewall_policies](#method.find_firewall_policies) call to limit
+    /// the number of firewall policies to receive.
+    #[cfg(feature = "network")]
+    pub async fn list_firewall_policies(&self) -> Result<Vec<FirewallPolicy>> {
+        self.find_firewall_policies().all().await
+    }
+
+    /// List all firewall rules.
+    ///
+    /// This call can yield a lot of results, use the
+    /// [find_firewall_rules](#method.find_firewall_rules) call to limit the
+    /// number of firewall rules to receive.
+    #[cfg(feature = "network")]
+    pub async fn list_firewall_rules(&self) -> Result<Vec<FirewallRule>> {
+        self.find_firewall_rules().all().await
+    }
+
     /// List all floating IPs
     ///
     /// This call can yield a lot of results, use the
@@ -662,6 +1489,19 @@ impl Cloud {
         self.find_keypairs().all().await
     }
 
+    /// List volumes on a backend that are not managed yet (admin-only).
+    ///
+    /// The `host` is the backend in the `host@backend#pool` format. Use the
+    /// `reference` of a volume that is safe to manage with
+    /// [manage_existing_volume](#method.manage_existing_volume).
+    #[cfg(feature = "block-storage")]
+    pub async fn list_manageable_volumes<S: AsRef<str>>(
+        &self,
+        host: S,
+    ) -> Result<Vec<ManageableVolume>> {
+        Volume::list_manageable(&self.session, host).await
+    }
+
     /// List all networks.
     ///
     /// This call can yield a lot of results, use the
@@ -725,6 +1565,12 @@ impl Cloud {
         self.find_routers().all().await
     }
 
+    /// List all resource providers.
+    #[cfg(feature = "placement")]
+    pub async fn list_resource_providers(&self) -> Result<Vec<ResourceProvider>> {
+        self.find_resource_providers().all().await
+    }
+
     /// List all servers.
     ///
     /// This call can yield a lot of results, use the
@@ -773,6 +1619,53 @@ impl Cloud {
         self.find_volumes().all().await
     }
 
+    /// List all volume groups.
+    #[cfg(feature = "block-storage")]
+    pub async fn list_volume_groups(&self) -> Result<Vec<VolumeGroup>> {
+        self.find_volume_groups().all().await
+    }
+
+    /// List all volume group snapshots.
+    #[cfg(feature = "block-storage")]
+    pub async fn list_volume_group_snapshots(&self) -> Result<Vec<VolumeGroupSnapshot>> {
+        self.find_volume_group_snapshots().all().await
+    }
+
+    /// List all volume types.
+    #[cfg(feature = "block-storage")]
+    pub async fn list_volume_types(&self) -> Result<Vec<VolumeType>> {
+        VolumeType::list(self.session.clone()).await
+    }
+
+    /// Take an existing backend volume under management (admin-only).
+    ///
+    /// The `reference` identifies the volume on the backend, usually with
+    /// a `source-name` or a `source-id` key.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// for candidate in os.list_manageable_volumes("cinder@lvm#lvm").await? {
+    ///     if candidate.safe_to_manage {
+    ///         let volume = os
+    ///             .manage_existing_volume("cinder@lvm#lvm", candidate.reference)
+    ///             .await?;
+    ///         println!("Managed volume {}", volume.id());
+    ///     }
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "block-storage")]
+    pub async fn manage_existing_volume<S: Into<String>>(
+        &self,
+        host: S,
+        reference: HashMap<String, serde_json::Value>,
+    ) -> Result<Volume> {
+        Volume::manage_existing(self.session.clone(), host.into(), reference).await
+    }
+
     /// Prepare a new object for creation.
     ///
     /// This call returns a `NewObject` object, which is a builder
@@ -787,6 +1680,69 @@ impl Cloud {
         NewObject::new(self.session.clone(), container.into(), object.into(), body)
     }
 
+    /// Prepare a new object for creation from a local file.
+    ///
+    /// The file is streamed during the upload. Its MD5 checksum is
+    /// calculated beforehand and verified by the object storage.
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let object = os
+    ///     .new_object_from_path("backups", "db.tar.gz", "/var/backups/db.tar.gz")
+    ///     .await?
+    ///     .create()
+    ///     .await?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "object-storage")]
+    pub async fn new_object_from_path<C, O, P>(
+        &self,
+        container: C,
+        object: O,
+        path: P,
+    ) -> Result<NewObject<impl AsyncRead + Sync + Send + 'static>>
+    where
+        C: Into<ContainerRef>,
+        O: Into<String>,
+        P: AsRef<Path>,
+    {
+        NewObject::from_path(
+            self.session.clone(),
+            container.into(),
+            object.into(),
+            path.as_ref(),
+        )
+        .await
+    }
+
+    /// Prepare a new firewall group for creation.
+    ///
+    /// This call returns a `NewFirewallGroup` object, which is a builder
+    /// to populate firewall group fields.
+    #[cfg(feature = "network")]
+    pub fn new_firewall_group(&self) -> NewFirewallGroup {
+        NewFirewallGroup::new(self.session.clone())
+    }
+
+    /// Prepare a new firewall policy for creation.
+    ///
+    /// This call returns a `NewFirewallPolicy` object, which is a builder
+    /// to populate firewall policy fields.
+    #[cfg(feature = "network")]
+    pub fn new_firewall_policy(&self) -> NewFirewallPolicy {
+        NewFirewallPolicy::new(self.session.clone())
+    }
+
+    /// Prepare a new firewall rule for creation.
+    ///
+    /// This call returns a `NewFirewallRule` object, which is a builder
+    /// to populate firewall rule fields.
+    #[cfg(feature = "network")]
+    pub fn new_firewall_rule(&self, action: network::FirewallAction) -> NewFirewallRule {
+        NewFirewallRule::new(self.session.clone(), action)
+    }
+
     /// Prepare a new floating IP for creation.
     ///
     /// This call returns a `NewFloatingIp` object, which is a builder
@@ -799,6 +1755,36 @@ impl Cloud {
         NewFloatingIp::new(self.session.clone(), floating_network.into())
     }
 
+    /// Prepare a new image for creation from its data.
+    ///
+    /// This call returns a `NewImage` object, which is a builder to populate
+    /// image fields. The disk format is detected from the data unless set
+    /// explicitly, and the formats are validated against the image schema
+    /// of the cloud before the upload starts.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let data = futures::io::Cursor::new(std::fs::read("cirros.qcow2").unwrap());
+    /// let image = os
+    ///     .new_image("cirros", data)
+    ///     .with_visibility(openstack::image::ImageVisibility::Private)
+    ///     .create()
+    ///     .await?;
+    /// println!("Created image {} in {} format", image.id(), image.disk_format().unwrap());
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn new_image<S, B>(&self, name: S, body: B) -> NewImage<B>
+    where
+        S: Into<String>,
+        B: AsyncRead + Send + 'static,
+    {
+        NewImage::new(self.session.clone(), name.into(), body)
+    }
+
     /// Prepare a new key pair for creation.
     ///
     /// This call returns a `NewKeyPair` object, which is a builder to populate
@@ -866,6 +1852,60 @@ impl Cloud {
         NewVolume::new(self.session.clone(), size.into())
     }
 
+    /// Prepare a new volume group for creation.
+    ///
+    /// The group type can be given by its ID or name. At least one volume
+    /// type must be added to the returned builder.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::waiter::Waiter;
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let mut group = os
+    ///     .new_volume_group("default")
+    ///     .with_name("database")
+    ///     .with_volume_type("ssd")
+    ///     .create()
+    ///     .await?;
+    /// group.wait_until_available().wait().await?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "block-storage")]
+    pub fn new_volume_group<S: Into<String>>(&self, group_type: S) -> NewVolumeGroup {
+        NewVolumeGroup::new(self.session.clone(), group_type.into())
+    }
+
+    /// Prepare a new volume for creation from an image.
+    ///
+    /// Unless set explicitly, the size is inferred from the image's size and
+    /// minimum disk requirement.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::waiter::Waiter;
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let mut volume = os
+    ///     .new_volume_from_image("cirros")
+    ///     .with_name("cirros-volume".to_string())
+    ///     .create()
+    ///     .await?;
+    /// volume.wait_until_available().wait().await?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(all(feature = "block-storage", feature = "image"))]
+    pub fn new_volume_from_image<I>(&self, image: I) -> NewVolume
+    where
+        I: Into<ImageRef>,
+    {
+        NewVolume::from_image(self.session.clone(), image.into())
+    }
+
     /// Prepare a new subnet for creation.
     ///
     /// This call returns a `NewSubnet` object, which is a builder to populate
@@ -894,10 +1934,350 @@ impl Cloud {
     {
         NewSubnet::new(self.session.clone(), network.into(), cidr)
     }
+
+    /// Send external events to servers.
+    ///
+    /// This is used by services that integrate with the Compute service, e.g.
+    /// to notify it that a port has been bound when plugging virtual
+    /// interfaces manually. Requires administrator privileges.
+    ///
+    /// Returns the events with their processing results, see
+    /// [ServerExternalEvent::is_accepted](compute/struct.ServerExternalEvent.html#method.is_accepted).
+    /// Fails with `ResourceNotFound` if none of the events could be delivered.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::compute::{ServerExternalEvent, ServerExternalEventName};
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let event = ServerExternalEvent::new(ServerExternalEventName::NetworkVifPlugged, "8c1b...")
+    ///     .with_tag("5e2f...");
+    /// let result = os.send_server_external_events(vec![event]).await?;
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "compute")]
+    pub async fn send_server_external_events<I>(
+        &self,
+        events: I,
+    ) -> Result<Vec<ServerExternalEvent>>
+    where
+        I: IntoIterator<Item = ServerExternalEvent>,
+    {
+        compute::create_server_external_events(&self.session, events.into_iter().collect()).await
+    }
 }
 
 impl From<Session> for Cloud {
     fn from(value: Session) -> Cloud {
-        Cloud { session: value }
+        Cloud {
+            session: value,
+            dashboard_url: None,
+            rate_limit: None,
+        }
+    }
+}
+
+impl From<Cloud> for Session {
+    fn from(value: Cloud) -> Session {
+        value.session
+    }
+}
+
+impl AsRef<Session> for Cloud {
+    fn as_ref(&self) -> &Session {
+        &self.session
+    }
+}
+
+/// Create a session from the configuration, using the TLS options.
+///
+/// The authentication is done through the same HTTP client as all further
+/// requests. Refreshing the session authenticates from the configuration
+/// again with this client, so that a token rejected by a service is really
+/// replaced.
+async fn create_session(config: CloudConfig, tls: &TlsConfig) -> Result<Session> {
+    let client = tls.build_client()?;
+    let settings = endpoint_settings(&config).await?;
+    let authenticated = authenticate(config.clone(), client.clone()).await?;
+    let auth = RenewableAuth::new(authenticated.clone(), move || {
+        authenticate(config.clone(), client.clone()).boxed()
+    });
+    let mut session = Session::new_with_authenticated_client(authenticated)
+        .with_endpoint_filters(settings.endpoint_filters().clone())
+        .with_endpoint_overrides(settings.endpoint_overrides().clone());
+    session.set_auth_type(auth);
+    Ok(session)
+}
+
+/// Authenticate with the credentials from the configuration.
+async fn authenticate(config: CloudConfig, client: Client) -> Result<AuthenticatedClient> {
+    let value = tls::config_to_value(&config)?;
+    let auth_type = match value.get("auth_type") {
+        Some(serde_yaml::Value::String(auth_type)) => auth_type.clone(),
+        _ if value.get("auth").and_then(|a| a.get("token")).is_some() => "v3token".into(),
+        _ => "password".into(),
+    };
+    match auth_type.as_str() {
+        "password" => AuthenticatedClient::new(client, Password::try_from(config)?).await,
+        "v3token" => AuthenticatedClient::new(client, Token::try_from(config)?).await,
+        "v3applicationcredential" => {
+            let auth = value.get("auth").cloned().unwrap_or_default();
+            AuthenticatedClient::new(client, application_credential(&auth)?).await
+        }
+        "http_basic" => AuthenticatedClient::new(client, BasicAuth::try_from(config)?).await,
+        "none" => AuthenticatedClient::new(client, NoAuth::try_from(config)?).await,
+        other => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unsupported authentication type: {}", other),
+        )),
+    }
+}
+
+/// Create an application credential from the `auth` section of the configuration.
+fn application_credential(auth: &serde_yaml::Value) -> Result<ApplicationCredential> {
+    let get = |key: &str| auth.get(key).and_then(|v| v.as_str());
+    let require = |key: &str, message: &str| {
+        get(key).ok_or_else(|| Error::new(ErrorKind::InvalidConfig, message.to_string()))
+    };
+    let auth_url = require(
+        "auth_url",
+        "Application credential requires an authentication URL",
+    )?;
+    let secret = require(
+        "application_credential_secret",
+        "Application credential requires a secret",
+    )?;
+    if let Some(id) = get("application_credential_id") {
+        ApplicationCredential::new(auth_url, id, secret)
+    } else if let Some(name) = get("application_credential_name") {
+        let user_id = require(
+            "user_id",
+            "Application credential authentication by name requires the user ID",
+        )?;
+        ApplicationCredential::with_user_id(auth_url, name, secret, user_id)
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidConfig,
+            "Application credential requires an id or a name",
+        ))
+    }
+}
+
+/// Load endpoint overrides, region and interface from the configuration.
+///
+/// Lets `osauth` parse them with authentication disabled, so that no requests
+/// are made.
+async fn endpoint_settings(config: &CloudConfig) -> Result<Session> {
+    let mut value = tls::config_to_value(config)?;
+    if let Some(mapping) = value.as_mapping_mut() {
+        let _ = mapping.remove("auth");
+        let _ = mapping.remove("cacert");
+        let _ = mapping.insert("auth_type".into(), "none".into());
+    }
+    let config: CloudConfig = serde_yaml::from_value(value).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Cannot load the cloud configuration: {}", err),
+        )
+    })?;
+    config.create_session().await
+}
+
+/// Extract the dashboard URL from the cloud configuration.
+fn dashboard_url_from_config(config: &CloudConfig) -> Result<Option<Url>> {
+    let value = tls::config_to_value(config)?;
+    match value.get("dashboard_url") {
+        Some(serde_yaml::Value::String(url)) => match Url::parse(url) {
+            Ok(result) if result.cannot_be_a_base() => Err(Error::new(
+                ErrorKind::InvalidConfig,
+                format!("Invalid dashboard_url `{}`: cannot be a base URL", url),
+            )),
+            Ok(result) => Ok(Some(result)),
+            Err(err) => Err(Error::new(
+                ErrorKind::InvalidConfig,
+                format!("Invalid dashboard_url `{}`: {}", url, err),
+            )),
+        },
+        Some(other) => Err(Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Invalid dashboard_url {:?}: expected a string", other),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Collect endpoint overrides from `OS_*_ENDPOINT_OVERRIDE` variables.
+fn endpoint_overrides_from_env<I>(vars: I) -> Result<HashMap<String, Url>>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut result = HashMap::new();
+    for (key, value) in vars {
+        let service_type = match key
+            .strip_prefix("OS_")
+            .and_then(|rest| rest.strip_suffix("_ENDPOINT_OVERRIDE"))
+        {
+            Some(service_type) if !service_type.is_empty() => service_type.to_lowercase(),
+            _ => continue,
+        };
+        let url = Url::parse(&value).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidConfig,
+                format!("Invalid {} `{}`: {}", key, value, err),
+            )
+        })?;
+        // Handle types like baremetal-introspection
+        let with_dashes = service_type.replace('_', "-");
+        let _ = result.insert(with_dashes, url.clone());
+        let _ = result.insert(service_type, url);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+    use std::net::TcpListener;
+
+    use osauth::{CloudConfig, ErrorKind};
+
+    use super::super::tls::TlsConfig;
+    use super::{
+        application_credential, create_session, dashboard_url_from_config,
+        endpoint_overrides_from_env,
+    };
+
+    #[test]
+    fn test_endpoint_overrides_from_env() {
+        let vars = vec![
+            ("OS_AUTH_URL".to_string(), "http://keystone".to_string()),
+            (
+                "OS_BAREMETAL_ENDPOINT_OVERRIDE".to_string(),
+                "http://ironic/v1".to_string(),
+            ),
+            (
+                "OS_BAREMETAL_INTROSPECTION_ENDPOINT_OVERRIDE".to_string(),
+                "http://inspector/".to_string(),
+            ),
+        ];
+        let result = endpoint_overrides_from_env(vars).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result["baremetal"].as_str(), "http://ironic/v1");
+        assert_eq!(
+            result["baremetal-introspection"].as_str(),
+            "http://inspector/"
+        );
+        assert_eq!(
+            result["baremetal_introspection"].as_str(),
+            "http://inspector/"
+        );
+    }
+
+    #[test]
+    fn test_endpoint_overrides_from_env_invalid() {
+        let vars = vec![(
+            "OS_COMPUTE_ENDPOINT_OVERRIDE".to_string(),
+            "?! banana".to_string(),
+        )];
+        assert!(endpoint_overrides_from_env(vars).is_err());
+    }
+
+    #[test]
+    fn test_dashboard_url_from_config() {
+        let config: CloudConfig = serde_yaml::from_str(
+            "auth:\n  auth_url: http://keystone\ndashboard_url: https://horizon/dashboard/",
+        )
+        .unwrap();
+        let result = dashboard_url_from_config(&config).unwrap().unwrap();
+        assert_eq!(result.as_str(), "https://horizon/dashboard/");
+    }
+
+    #[test]
+    fn test_dashboard_url_from_config_missing() {
+        let config: CloudConfig = serde_yaml::from_str("region_name: RegionOne").unwrap();
+        assert!(dashboard_url_from_config(&config).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dashboard_url_from_config_invalid() {
+        for text in [
+            "dashboard_url: 42",
+            "dashboard_url: 'mailto:admin@example.com'",
+        ] {
+            let config: CloudConfig = serde_yaml::from_str(text).unwrap();
+            assert!(dashboard_url_from_config(&config).is_err(), "{}", text);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_session_tls_used_for_authentication() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let config: CloudConfig = serde_yaml::from_str(&format!(
+            "auth:\n  auth_url: http://{}/v3\n  username: u\n  password: p",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        let tls = TlsConfig::new().with_vars(vec![
+            ("OS_CERT".to_string(), "/I/do/not/exist.pem".to_string()),
+            ("OS_KEY".to_string(), "/I/do/not/exist.key".to_string()),
+            ("OS_INSECURE".to_string(), "true".to_string()),
+        ]);
+        let err = create_session(config, &tls).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidConfig);
+        // The client certificate is loaded before any authentication request.
+        assert_eq!(
+            listener.accept().unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_session_endpoint_settings() {
+        let config: CloudConfig = serde_yaml::from_str(
+            "auth_type: none\nauth:\n  endpoint: http://127.0.0.1:5000/\n\
+             region_name: r1\ncompute_endpoint_override: http://nova/",
+        )
+        .unwrap();
+        let tls = TlsConfig::new().with_insecure(true);
+        let session = create_session(config, &tls).await.unwrap();
+        assert_eq!(session.endpoint_filters().region.as_deref(), Some("r1"));
+        assert_eq!(
+            session.endpoint_overrides()["compute"].as_str(),
+            "http://nova/"
+        );
+        assert!(format!("{:?}", session.client().auth_type()).starts_with("RenewableAuth"));
+        let endpoint = session
+            .client()
+            .get_endpoint("image", &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(endpoint.as_str(), "http://127.0.0.1:5000/");
+    }
+
+    #[test]
+    fn test_application_credential() {
+        for text in [
+            "auth_url: http://keystone/v3\napplication_credential_id: id\n\
+             application_credential_secret: s",
+            "auth_url: http://keystone/v3\napplication_credential_name: name\n\
+             application_credential_secret: s\nuser_id: u",
+        ] {
+            let auth: serde_yaml::Value = serde_yaml::from_str(text).unwrap();
+            assert!(application_credential(&auth).is_ok(), "{}", text);
+        }
+        for text in [
+            "auth_url: http://keystone/v3\napplication_credential_id: id",
+            "auth_url: http://keystone/v3\napplication_credential_secret: s",
+            "application_credential_name: name\napplication_credential_secret: s",
+            "auth_url: http://keystone/v3\napplication_credential_name: name\n\
+             application_credential_secret: s",
+        ] {
+            let auth: serde_yaml::Value = serde_yaml::from_str(text).unwrap();
+            let err = application_credential(&auth).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidConfig, "{}", text);
+        }
     }
 }
@@ -15,13 +15,19 @@
 //! Types and traits shared by all API parts.
 
 pub(crate) mod protocol;
+mod rawquery;
 mod resourceiterator;
 mod types;
+mod validation;
 
 pub use osauth::ApiVersion;
 
+pub use self::rawquery::RawQuery;
 pub use self::resourceiterator::{ResourceIterator, ResourceQuery};
 pub use self::types::{
-    ContainerRef, FlavorRef, ImageRef, KeyPairRef, NetworkRef, ObjectRef, PortRef, ProjectRef,
-    Refresh, RouterRef, SecurityGroupRef, SnapshotRef, SubnetRef, UserRef, VolumeRef,
+    ContainerRef, FirewallGroupRef, FirewallPolicyRef, FirewallRuleRef, FlavorRef, FloatingIpRef,
+    ImageRef, KeyPairRef, NetworkRef, ObjectRef, PortRef, ProjectRef, Refresh, RouterRef,
+    SecurityGroupRef, ServerRef, SnapshotRef, SubnetRef, UserRef, VolumeGroupRef, VolumeRef,
+    VolumeTypeRef,
 };
+pub use self::validation::ValidationReport;
@@ -19,6 +19,7 @@
 
 use std::collections::HashMap;
 
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use reqwest::header::{HeaderMap, HeaderName};
 use reqwest::Url;
 use serde::de::Error as DeserError;
@@ -26,6 +27,46 @@ use serde::{Deserialize, Deserializer};
 
 use super::super::{Error, ErrorKind};
 
+/// Parse a date and time in RFC 3339 or in the OpenStack format without time zone.
+fn parse_openstack_datetime(s: &str) -> Result<DateTime<FixedOffset>, String> {
+    match DateTime::parse_from_rfc3339(s) {
+        Ok(dt) => Ok(dt),
+        Err(_) => match NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S.%f") {
+            Ok(dt) => Ok(DateTime::from_naive_utc_and_offset(
+                dt,
+                FixedOffset::east_opt(0).unwrap(),
+            )),
+            Err(_) => Err("invalid date format".to_string()),
+        },
+    }
+}
+
+/// Deserialize a date and time in RFC 3339 or OpenStack format.
+pub fn deserialize_openstack_datetime<'de, D>(
+    deserializer: D,
+) -> Result<DateTime<FixedOffset>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse_openstack_datetime(&s).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize an optional date and time in RFC 3339 or OpenStack format.
+pub fn deserialize_optional_openstack_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(s) => Ok(Some(
+            parse_openstack_datetime(&s).map_err(serde::de::Error::custom)?,
+        )),
+        None => Ok(None),
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct KeyValue {
     pub key: String,
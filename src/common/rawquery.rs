@@ -0,0 +1,255 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Paginated listing of resources not modelled by this crate.
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::super::session::{ServiceType, Session};
+use super::super::utils::{FetchJson, Query};
+use super::super::{Error, ErrorKind, Result};
+use super::{ResourceIterator, ResourceQuery};
+
+/// A query to an arbitrary collection using marker-based pagination.
+///
+/// Use it for endpoints that this crate does not support yet. The response is
+/// expected to be a JSON object with the list of items under the root key,
+/// e.g. `{"things": [...]}`, where each item has a field used as a marker
+/// (`id` by default).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use openstack::common::RawQuery;
+/// use osauth::services::NETWORK;
+///
+/// # async fn async_wrapper() {
+/// let session = osauth::Session::from_env()
+///     .await
+///     .expect("Unable to authenticate");
+/// let groups: Vec<serde_json::Value> =
+///     RawQuery::new(session, NETWORK, &["address-groups"], "address_groups")
+///         .with_query("name", "group1")
+///         .all()
+///         .await
+///         .expect("Unable to list address groups");
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RawQuery<Srv> {
+    session: Session,
+    service: Srv,
+    path: Vec<String>,
+    root: String,
+    marker_field: String,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+}
+
+impl<Srv> RawQuery<Srv>
+where
+    Srv: ServiceType + Debug + Clone + Send + Sync,
+{
+    /// Create a query for the given service, path and root key.
+    pub fn new<I, S>(session: Session, service: Srv, path: I, root: S) -> RawQuery<Srv>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        S: Into<String>,
+    {
+        RawQuery {
+            session,
+            service,
+            path: path.into_iter().map(|s| s.as_ref().to_string()).collect(),
+            root: root.into(),
+            marker_field: "id".into(),
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+        }
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Use a different item field as a pagination marker.
+    ///
+    /// The field must be a string or a number.
+    pub fn with_marker_field<T: Into<String>>(mut self, field: T) -> Self {
+        self.marker_field = field.into();
+        self
+    }
+
+    /// Add an arbitrary query parameter to the request.
+    pub fn with_query<K, V>(mut self, param: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.query.push_str(param, value);
+        self
+    }
+
+    /// Convert this query into a stream executing the request.
+    ///
+    /// Each item is deserialized into `T`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream<T>(self) -> impl Stream<Item = Result<T>>
+    where
+        T: DeserializeOwned,
+    {
+        debug!(
+            "Fetching {} from {:?} with {:?}",
+            self.root, self.service, self.query
+        );
+        ResourceIterator::new(self.session.clone(), self)
+            .into_stream()
+            .map(|item| item.and_then(deserialize_item))
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_stream().try_collect().await`.
+    pub async fn all<T>(self) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.into_stream().try_collect().await
+    }
+}
+
+#[async_trait]
+impl<Srv> ResourceQuery for RawQuery<Srv>
+where
+    Srv: ServiceType + Debug + Clone + Send + Sync,
+{
+    type Item = Value;
+
+    const DEFAULT_LIMIT: usize = 50;
+
+    async fn can_paginate(&self) -> Result<bool> {
+        Ok(self.can_paginate)
+    }
+
+    fn extract_marker(&self, resource: &Self::Item) -> String {
+        match resource.get(&self.marker_field) {
+            Some(Value::String(value)) => value.clone(),
+            Some(other) => other.to_string(),
+            None => String::new(),
+        }
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
+    async fn fetch_chunk(
+        &self,
+        limit: Option<usize>,
+        marker: Option<String>,
+    ) -> Result<Vec<Self::Item>> {
+        let query = self.query.with_marker_and_limit(limit, marker);
+        trace!("Listing {} with {:?}", self.root, query);
+        let mut root: Value = self
+            .session
+            .get(self.service.clone(), &self.path)
+            .query(&query)
+            .fetch_json()
+            .await?;
+        let items = match root.get_mut(&self.root).map(Value::take) {
+            Some(Value::Array(items)) => items,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidResponse,
+                    format!("Expected a list under the {} key", self.root),
+                ))
+            }
+        };
+        if self.can_paginate
+            && items
+                .iter()
+                .any(|item| item.get(&self.marker_field).is_none())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidResponse,
+                format!(
+                    "Marker field {} is missing in {}",
+                    self.marker_field, self.root
+                ),
+            ));
+        }
+        trace!("Received {}: {:?}", self.root, items);
+        Ok(items)
+    }
+}
+
+fn deserialize_item<T: DeserializeOwned>(item: Value) -> Result<T> {
+    serde_json::from_value(item).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidResponse,
+            format!("Cannot deserialize an item: {err}"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::super::super::session::Session;
+    use super::super::ResourceQuery;
+    use super::RawQuery;
+
+    #[tokio::test]
+    async fn test_extract_marker() {
+        let session = Session::new(osauth::NoAuth::new("http://127.0.0.1").unwrap())
+            .await
+            .unwrap();
+        let query = RawQuery::new(session, osauth::services::NETWORK, &["things"], "things");
+        assert_eq!(query.extract_marker(&json!({"id": "abcd"})), "abcd");
+        let query = query.with_marker_field("number");
+        assert_eq!(query.extract_marker(&json!({"number": 42})), "42");
+    }
+}
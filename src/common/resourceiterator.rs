@@ -21,6 +21,8 @@ use async_trait::async_trait;
 use futures::pin_mut;
 use futures::stream::{Stream, TryStreamExt};
 
+use super::super::session::Session;
+use super::super::utils;
 use super::super::{Error, ErrorKind, Result};
 
 /// A query for resources.
@@ -40,6 +42,21 @@ pub trait ResourceQuery {
     /// Extract a marker from a resource.
     fn extract_marker(&self, resource: &Self::Item) -> String;
 
+    /// Whether a resource passes the client-side filters of this query.
+    ///
+    /// Resources that do not match are still used for pagination.
+    fn matches(&self, _resource: &Self::Item) -> bool {
+        true
+    }
+
+    /// Number of items per page explicitly requested by the caller.
+    ///
+    /// If `None`, `DEFAULT_LIMIT` is used and may be reduced automatically
+    /// when the service rejects it.
+    fn page_size(&self) -> Option<usize> {
+        None
+    }
+
     /// Get a chunk of resources.
     async fn fetch_chunk(
         &self,
@@ -56,14 +73,32 @@ pub trait ResourceQuery {
     }
 }
 
+/// How many times the default page size can be halved.
+const MAX_PAGE_SIZE_REDUCTIONS: usize = 4;
+
+/// Whether the error means that the requested page size is too large.
+///
+/// The HTTP status is not exposed, so rely on the message: 413 responses
+/// mention the request being too large, 400 responses from services with
+/// a maximum page size mention the limit.
+fn is_page_size_error(err: &Error) -> bool {
+    if err.kind() != ErrorKind::InvalidInput {
+        return false;
+    }
+    let message = err.to_string().to_lowercase();
+    message.contains("too large") || message.contains("limit")
+}
+
 /// Generic iterator over resources.
 #[derive(Debug, Clone)]
 pub struct ResourceIterator<Q: ResourceQuery> {
+    session: Session,
     query: Q,
     cache: Option<vec::IntoIter<Q::Item>>,
     marker: Option<String>,
     can_paginate: Option<bool>,
-    validated: bool,
+    page_size: Option<usize>,
+    page_size_reductions: usize,
 }
 
 impl<Q> ResourceIterator<Q>
@@ -71,13 +106,15 @@ where
     Q: ResourceQuery,
 {
     #[allow(dead_code)] // unused with --no-default-features
-    pub(crate) fn new(query: Q) -> ResourceIterator<Q> {
+    pub(crate) fn new(session: Session, query: Q) -> ResourceIterator<Q> {
         ResourceIterator {
+            session,
             query,
             cache: None,
             marker: None,
             can_paginate: None, // ask the service later
-            validated: false,
+            page_size: None,
+            page_size_reductions: 0,
         }
     }
 }
@@ -119,37 +156,75 @@ where
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(mut self) -> impl Stream<Item = Result<Q::Item>> {
         try_stream! {
-            if !self.validated {
-                self.query.validate().await?;
-                self.validated = true;
-            }
+            self.query.validate().await?;
 
             if self.can_paginate.is_none() {
                 self.can_paginate = Some(self.query.can_paginate().await?);
             }
 
+            let mut reauthenticated = false;
             loop {
                 let maybe_next = self.cache.as_mut().and_then(|cache| cache.next());
                 if let Some(next) = maybe_next {
                     self.marker = Some(self.query.extract_marker(&next));
-                    yield next;
+                    if self.query.matches(&next) {
+                        yield next;
+                    }
                 } else if self.cache.is_some() && self.can_paginate == Some(false) {
                     // We have exhausted the results and pagination is not possible
                     break;
                 } else {
                     let (marker, limit) = if self.can_paginate == Some(true) {
                         // can_paginate=true implies no limit was provided
-                        (self.marker.clone(), Some(Q::DEFAULT_LIMIT))
+                        let page_size = *self.page_size.get_or_insert_with(|| {
+                            self.query.page_size().unwrap_or(Q::DEFAULT_LIMIT)
+                        });
+                        (self.marker.clone(), Some(page_size))
                     } else {
                         (None, None)
                     };
 
-                    let mut iter = self.query.fetch_chunk(limit, marker).await?.into_iter();
+                    let chunk = match self.query.fetch_chunk(limit, marker).await {
+                        // Some services reject page sizes above their own
+                        // maximum. Retry with a smaller page, but only until
+                        // the first page has been received and only if the
+                        // caller has not requested a specific page size.
+                        Err(err)
+                            if is_page_size_error(&err)
+                                && self.cache.is_none()
+                                && self.query.page_size().is_none()
+                                && self.page_size_reductions < MAX_PAGE_SIZE_REDUCTIONS
+                                && limit.unwrap_or(0) > 1 =>
+                        {
+                            let reduced = limit.unwrap_or(0) / 2;
+                            self.page_size_reductions += 1;
+                            warn!(
+                                "Listing with page size {:?} failed ({}), retrying with {}",
+                                limit, err, reduced
+                            );
+                            self.page_size = Some(reduced);
+                            continue;
+                        }
+                        // The token may expire during a long iteration.
+                        // Re-authenticate and retry, but only once in a row.
+                        Err(err) if !reauthenticated => {
+                            if utils::reauthenticate(Some(&self.session), &err).await {
+                                reauthenticated = true;
+                                continue;
+                            }
+                            Err(err)?
+                        }
+                        other => other?,
+                    };
+                    reauthenticated = false;
+                    let mut iter = chunk.into_iter();
                     let maybe_next = iter.next();
                     self.cache = Some(iter);
                     if let Some(next) = maybe_next {
                         self.marker = Some(self.query.extract_marker(&next));
-                        yield next;
+                        if self.query.matches(&next) {
+                            yield next;
+                        }
                     } else {
                         break;
                     }
@@ -161,12 +236,23 @@ where
 
 #[cfg(test)]
 mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
     use async_trait::async_trait;
     use futures::stream::TryStreamExt;
+    use osauth::NoAuth;
 
-    use super::super::super::Result;
+    use super::super::super::session::Session;
+    use super::super::super::{Error, ErrorKind, Result};
     use super::{ResourceIterator, ResourceQuery};
 
+    async fn session() -> Session {
+        Session::new(NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap()
+    }
+
     #[derive(Debug, PartialEq, Eq)]
     struct Test(u8);
 
@@ -202,6 +288,36 @@ mod test {
         }
     }
 
+    #[derive(Debug)]
+    struct Filtered;
+
+    #[async_trait]
+    impl ResourceQuery for Filtered {
+        type Item = Test;
+
+        const DEFAULT_LIMIT: usize = 2;
+
+        async fn can_paginate(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn extract_marker(&self, resource: &Test) -> String {
+            resource.0.to_string()
+        }
+
+        fn matches(&self, resource: &Test) -> bool {
+            resource.0 == 3
+        }
+
+        async fn fetch_chunk(
+            &self,
+            limit: Option<usize>,
+            marker: Option<String>,
+        ) -> Result<Vec<Self::Item>> {
+            TestQuery.fetch_chunk(limit, marker).await
+        }
+    }
+
     #[derive(Debug)]
     struct NoPagination;
 
@@ -230,21 +346,195 @@ mod test {
         }
     }
 
+    #[derive(Debug)]
+    struct LimitedPageSize;
+
+    #[async_trait]
+    impl ResourceQuery for LimitedPageSize {
+        type Item = Test;
+
+        const DEFAULT_LIMIT: usize = 10;
+
+        async fn can_paginate(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn extract_marker(&self, resource: &Test) -> String {
+            resource.0.to_string()
+        }
+
+        async fn fetch_chunk(
+            &self,
+            limit: Option<usize>,
+            marker: Option<String>,
+        ) -> Result<Vec<Self::Item>> {
+            match (limit, marker.map(|s| s.parse::<u8>().unwrap())) {
+                (Some(10), None) => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Invalid input: limit must be at most 5",
+                )),
+                (Some(5), None) => Ok((0..5).map(Test).collect()),
+                (Some(5), Some(4)) => Ok(vec![Test(5)]),
+                (Some(5), Some(5)) => Ok(Vec::new()),
+                (l, m) => panic!("unexpected limit {:?} and marker {:?}", l, m),
+            }
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Rejected {
+        page_size: Option<usize>,
+        message: &'static str,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ResourceQuery for Rejected {
+        type Item = Test;
+
+        const DEFAULT_LIMIT: usize = 1000;
+
+        async fn can_paginate(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn extract_marker(&self, resource: &Test) -> String {
+            resource.0.to_string()
+        }
+
+        fn page_size(&self) -> Option<usize> {
+            self.page_size
+        }
+
+        async fn fetch_chunk(
+            &self,
+            _limit: Option<usize>,
+            _marker: Option<String>,
+        ) -> Result<Vec<Self::Item>> {
+            let _ = self.attempts.fetch_add(1, Ordering::SeqCst);
+            Err(Error::new(ErrorKind::InvalidInput, self.message))
+        }
+    }
+
+    async fn rejected_attempts(query: Rejected) -> usize {
+        let attempts = query.attempts.clone();
+        let it = ResourceIterator::new(session().await, query);
+        let err = it
+            .into_stream()
+            .try_collect::<Vec<Test>>()
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        attempts.load(Ordering::SeqCst)
+    }
+
+    #[derive(Debug, Default)]
+    struct Unauthorized {
+        attempts: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ResourceQuery for Unauthorized {
+        type Item = Test;
+
+        const DEFAULT_LIMIT: usize = 2;
+
+        async fn can_paginate(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn extract_marker(&self, resource: &Test) -> String {
+            resource.0.to_string()
+        }
+
+        async fn fetch_chunk(
+            &self,
+            _limit: Option<usize>,
+            _marker: Option<String>,
+        ) -> Result<Vec<Self::Item>> {
+            let _ = self.attempts.fetch_add(1, Ordering::SeqCst);
+            Err(Error::new(ErrorKind::AuthenticationFailed, "expired"))
+        }
+    }
+
     #[tokio::test]
     async fn test_resource_iterator() {
-        let it: ResourceIterator<TestQuery> = ResourceIterator::new(TestQuery);
+        let it: ResourceIterator<TestQuery> = ResourceIterator::new(session().await, TestQuery);
         assert_eq!(
             it.into_stream().try_collect::<Vec<Test>>().await.unwrap(),
             vec![Test(0), Test(1), Test(2), Test(3)]
         );
     }
 
+    #[tokio::test]
+    async fn test_resource_iterator_filtered() {
+        let it: ResourceIterator<Filtered> = ResourceIterator::new(session().await, Filtered);
+        assert_eq!(
+            it.into_stream().try_collect::<Vec<Test>>().await.unwrap(),
+            vec![Test(3)]
+        );
+    }
+
     #[tokio::test]
     async fn test_resource_iterator_no_pagination() {
-        let it: ResourceIterator<NoPagination> = ResourceIterator::new(NoPagination);
+        let it: ResourceIterator<NoPagination> =
+            ResourceIterator::new(session().await, NoPagination);
         assert_eq!(
             it.into_stream().try_collect::<Vec<Test>>().await.unwrap(),
             vec![Test(0), Test(1), Test(2)]
         );
     }
+
+    #[tokio::test]
+    async fn test_resource_iterator_reduces_page_size() {
+        let it: ResourceIterator<LimitedPageSize> =
+            ResourceIterator::new(session().await, LimitedPageSize);
+        assert_eq!(
+            it.into_stream().try_collect::<Vec<Test>>().await.unwrap(),
+            (0..6).map(Test).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resource_iterator_unrelated_error_not_retried() {
+        let query = Rejected {
+            message: "Invalid status value",
+            ..Rejected::default()
+        };
+        assert_eq!(rejected_attempts(query).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_resource_iterator_page_size_reductions_capped() {
+        let query = Rejected {
+            message: "Request Entity Too Large",
+            ..Rejected::default()
+        };
+        assert_eq!(rejected_attempts(query).await, 5);
+    }
+
+    #[tokio::test]
+    async fn test_resource_iterator_explicit_page_size_not_reduced() {
+        let query = Rejected {
+            page_size: Some(1000),
+            message: "limit must be at most 500",
+            ..Rejected::default()
+        };
+        assert_eq!(rejected_attempts(query).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_resource_iterator_auth_failure_retried_once() {
+        let query = Unauthorized::default();
+        let attempts = query.attempts.clone();
+        let it = ResourceIterator::new(session().await, query);
+        let err = it
+            .into_stream()
+            .try_collect::<Vec<Test>>()
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AuthenticationFailed);
+        // NoAuth is refreshed successfully, but the request is only retried once.
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
 }
@@ -16,13 +16,21 @@
 
 use async_trait::async_trait;
 
-use super::super::Result;
+use super::super::session::Session;
+use super::super::{ErrorKind, Result};
 
 /// Trait representing something that can be refreshed.
 #[async_trait]
 pub trait Refresh {
     /// Refresh the resource representation.
     async fn refresh(&mut self) -> Result<()>;
+
+    /// Session used to refresh the resource (if any).
+    ///
+    /// Waiters use it to re-authenticate when the token expires.
+    fn session(&self) -> Option<&Session> {
+        None
+    }
 }
 
 macro_rules! opaque_resource_type {
@@ -118,8 +126,16 @@ macro_rules! opaque_resource_type {
 
 opaque_resource_type!(#[doc = "An ID of a `Container`"] ContainerRef ? "object-storage");
 
+opaque_resource_type!(#[doc = "An ID of a `FirewallGroup`"] FirewallGroupRef ? "network");
+
+opaque_resource_type!(#[doc = "An ID of a `FirewallPolicy`"] FirewallPolicyRef ? "network");
+
+opaque_resource_type!(#[doc = "An ID of a `FirewallRule`"] FirewallRuleRef ? "network");
+
 opaque_resource_type!(#[doc = "An ID of a `Flavor`"] FlavorRef ? "compute");
 
+opaque_resource_type!(#[doc = "An ID or an address of a `FloatingIp`"] FloatingIpRef ? "network");
+
 opaque_resource_type!(#[doc = "An ID of an `Image`"] ImageRef ? "image");
 
 opaque_resource_type!(#[doc = "An ID of a `KeyPair`"] KeyPairRef ? "compute");
@@ -139,12 +155,40 @@ opaque_resource_type!(#[doc = "An ID of a `SecurityGroup`"] SecurityGroupRef ? "
 // TODO: change the feature to `block-storage, when the snapshot API is implemented.
 opaque_resource_type!(#[doc = "An ID of a `Snapshot`"] SnapshotRef ? "block-storage-snapshot");
 
+opaque_resource_type!(#[doc = "An ID of a `Server`"] ServerRef ? "compute");
+
 opaque_resource_type!(#[doc = "An ID of a `Subnet`"] SubnetRef ? "network");
 
 opaque_resource_type!(#[doc = "An ID of a `User`"] UserRef ? "identity");
 
 opaque_resource_type!(#[doc = "An ID of a `Volume`"] VolumeRef ? "block-storage");
 
+opaque_resource_type!(#[doc = "An ID of a `VolumeGroup`"] VolumeGroupRef ? "block-storage");
+
+opaque_resource_type!(#[doc = "An ID of a `VolumeType`"] VolumeTypeRef ? "block-storage");
+
+macro_rules! filter_reference {
+    ($name:ident) => {
+        #[allow(dead_code)]
+        impl $name {
+            /// Verify this reference for use in a query filter.
+            ///
+            /// Unlike `into_verified`, passes the reference as it is if it
+            /// is not found: the service may still know it.
+            pub(crate) async fn into_filter(self, session: &Session) -> Result<Self> {
+                match self.clone().into_verified(session).await {
+                    Err(err) if err.kind() == ErrorKind::ResourceNotFound => Ok(self),
+                    other => other,
+                }
+            }
+        }
+    };
+}
+
+filter_reference!(ProjectRef);
+
+filter_reference!(UserRef);
+
 #[cfg(test)]
 mod test {
     opaque_resource_type!(TestId ? "test");
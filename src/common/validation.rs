@@ -0,0 +1,102 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of creation requests.
+
+use std::fmt;
+
+use super::super::{Error, ErrorKind, Result};
+
+/// Problems found when validating a creation request.
+///
+/// Returned by the `validate` and `verify` calls of the `New*` builders.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct ValidationReport {
+    /// Human-readable descriptions of the problems.
+    pub problems: Vec<String>,
+}
+
+impl ValidationReport {
+    /// Whether no problems were found.
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Convert the report into an `InvalidInput` error listing all problems.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_ok() {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidInput, self.to_string()))
+        }
+    }
+
+    /// Record a problem.
+    #[inline]
+    #[allow(unused)]
+    pub(crate) fn add<S: Into<String>>(&mut self, problem: S) {
+        self.problems.push(problem.into());
+    }
+
+    /// Record a problem if the result of a look up is `ResourceNotFound`.
+    ///
+    /// Other errors are returned as they are.
+    #[allow(unused)]
+    pub(crate) fn check_found<T, D: fmt::Display>(
+        &mut self,
+        result: Result<T>,
+        what: D,
+    ) -> Result<()> {
+        match result {
+            Ok(..) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+                self.add(format!("{what} not found"));
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            f.write_str("no problems found")
+        } else {
+            f.write_str(&self.problems.join("; "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::ErrorKind;
+    use super::ValidationReport;
+
+    #[test]
+    fn test_validation_report() {
+        let mut report = ValidationReport::default();
+        assert!(report.is_ok());
+        report.add("name is empty");
+        report.add("network net1 not found");
+        assert!(!report.is_ok());
+        let err = report.into_result().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err
+            .to_string()
+            .contains("name is empty; network net1 not found"));
+    }
+}
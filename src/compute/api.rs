@@ -20,29 +20,69 @@ use std::fmt::Debug;
 use osauth::common::{IdAndName, Ref};
 use osauth::services::COMPUTE;
 use osauth::ErrorKind;
+#[cfg(feature = "image")]
+use reqwest::header::LOCATION;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
 
 use super::super::common::ApiVersion;
 use super::super::session::Session;
-use super::super::utils;
-use super::super::Result;
+use super::super::utils::{self, FetchJson};
+use super::super::{Error, Result};
 use super::protocol::*;
 
 const API_VERSION_KEYPAIR_TYPE: ApiVersion = ApiVersion(2, 2);
+const API_VERSION_KEYPAIR_USER: ApiVersion = ApiVersion(2, 10);
 const API_VERSION_SERVER_DESCRIPTION: ApiVersion = ApiVersion(2, 19);
 const API_VERSION_KEYPAIR_PAGINATION: ApiVersion = ApiVersion(2, 35);
+const API_VERSION_DEVICE_TAGS: ApiVersion = ApiVersion(2, 42);
+const API_VERSION_LEGACY_ACTIONS_REMOVED: ApiVersion = ApiVersion(2, 44);
 const API_VERSION_SERVER_FLAVOR: ApiVersion = ApiVersion(2, 47);
+const API_VERSION_SERVER_DIAGNOSTICS: ApiVersion = ApiVersion(2, 48);
+const API_VERSION_EVENT_VOLUME_EXTENDED: ApiVersion = ApiVersion(2, 51);
+const API_VERSION_ACTION_EVENTS: ApiVersion = ApiVersion(2, 51);
 const API_VERSION_FLAVOR_DESCRIPTION: ApiVersion = ApiVersion(2, 55);
+const API_VERSION_ACTION_UPDATED_AT: ApiVersion = ApiVersion(2, 58);
 const API_VERSION_FLAVOR_EXTRA_SPECS: ApiVersion = ApiVersion(2, 61);
+const API_VERSION_TRUSTED_CERTS: ApiVersion = ApiVersion(2, 63);
+pub const API_VERSION_CHANGES_BEFORE: ApiVersion = ApiVersion(2, 66);
+pub const API_VERSION_SERVER_LOCKED_REASON: ApiVersion = ApiVersion(2, 73);
+const API_VERSION_EVENT_POWER_UPDATE: ApiVersion = ApiVersion(2, 76);
+const API_VERSION_SERVER_TOPOLOGY: ApiVersion = ApiVersion(2, 78);
+const API_VERSION_ATTACHMENT_DELETE_FLAG: ApiVersion = ApiVersion(2, 79);
+const API_VERSION_EVENT_ACCELERATOR_REQUEST: ApiVersion = ApiVersion(2, 82);
+const API_VERSION_ATTACHMENT_UPDATE: ApiVersion = ApiVersion(2, 85);
+const API_VERSION_SERVER_HOSTNAME: ApiVersion = ApiVersion(2, 90);
+const API_VERSION_EVENT_VOLUME_REIMAGED: ApiVersion = ApiVersion(2, 93);
 
-async fn server_api_version(session: &Session) -> Result<Option<ApiVersion>> {
-    session
-        .pick_api_version(
-            COMPUTE,
-            vec![API_VERSION_SERVER_DESCRIPTION, API_VERSION_SERVER_FLAVOR],
-        )
-        .await
+const X_OPENSTACK_REQUEST_ID: &str = "x-openstack-request-id";
+const X_COMPUTE_REQUEST_ID: &str = "x-compute-request-id";
+
+const FLAVOR_PAGE_SIZE: usize = 100;
+
+fn escape_regex(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Pick the API version for fetching servers.
+///
+/// Versions above 2.47 are only used if `required` asks for them: starting
+/// with 2.69 servers in down cells are returned as incomplete records.
+async fn server_api_version(
+    session: &Session,
+    required: Option<ApiVersion>,
+) -> Result<Option<ApiVersion>> {
+    let mut versions = vec![API_VERSION_SERVER_DESCRIPTION, API_VERSION_SERVER_FLAVOR];
+    versions.extend(required);
+    session.pick_api_version(COMPUTE, versions).await
 }
 
 async fn flavor_api_version(session: &Session) -> Result<Option<ApiVersion>> {
@@ -73,35 +113,197 @@ pub async fn create_keypair(session: &Session, request: KeyPairCreate) -> Result
         builder = builder.api_version(version)
     }
 
-    let root: KeyPairRoot = builder.fetch().await?;
+    let root: KeyPairRoot = builder.fetch_json().await?;
     debug!("Created key pair {:?}", root.keypair);
     Ok(root.keypair)
 }
 
+/// A field of a server creation request that requires a newer API version.
+struct ServerCreateFeature {
+    name: &'static str,
+    version: ApiVersion,
+    is_used: fn(&ServerCreate) -> bool,
+    remove: fn(&mut ServerCreate),
+}
+
+const SERVER_CREATE_FEATURES: &[ServerCreateFeature] = &[
+    ServerCreateFeature {
+        name: "description",
+        version: API_VERSION_SERVER_DESCRIPTION,
+        is_used: |request| request.description.is_some(),
+        remove: |request| request.description = None,
+    },
+    // Tags were introduced in 2.32, but accidentally dropped in 2.33-2.41.
+    ServerCreateFeature {
+        name: "device tags",
+        version: API_VERSION_DEVICE_TAGS,
+        is_used: |request| {
+            request.networks.iter().any(|n| n.tag().is_some())
+                || request.block_devices.iter().any(|bd| bd.tag.is_some())
+        },
+        remove: |request| {
+            for network in &mut request.networks {
+                network.clear_tag();
+            }
+            for block_device in &mut request.block_devices {
+                block_device.tag = None;
+            }
+        },
+    },
+    ServerCreateFeature {
+        name: "trusted image certificates",
+        version: API_VERSION_TRUSTED_CERTS,
+        is_used: |request| !request.trusted_image_certificates.is_empty(),
+        remove: |request| request.trusted_image_certificates.clear(),
+    },
+    ServerCreateFeature {
+        name: "hostname",
+        version: API_VERSION_SERVER_HOSTNAME,
+        is_used: |request| request.hostname.is_some(),
+        remove: |request| request.hostname = None,
+    },
+];
+
+/// Make a server creation request compatible with the supported API versions.
+///
+/// Unsupported fields cause an `IncompatibleApiVersion` error if `strict` is
+/// true and are removed otherwise. Returns the API version to use (if any).
+fn adjust_server_create<F>(
+    request: &mut ServerCreate,
+    strict: bool,
+    supports: F,
+) -> Result<Option<ApiVersion>>
+where
+    F: Fn(ApiVersion) -> bool,
+{
+    let mut required_version = None;
+    let mut unsupported = Vec::new();
+    for feature in SERVER_CREATE_FEATURES {
+        if !(feature.is_used)(request) {
+            continue;
+        }
+        if supports(feature.version) {
+            required_version = required_version.max(Some(feature.version));
+        } else {
+            unsupported.push(feature);
+        }
+    }
+
+    if strict && !unsupported.is_empty() {
+        let details = unsupported
+            .iter()
+            .map(|feature| format!("{} (requires {})", feature.name, feature.version))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(Error::new(
+            ErrorKind::IncompatibleApiVersion,
+            format!("Compute API does not support the following server fields: {details}"),
+        ));
+    }
+
+    for feature in unsupported {
+        warn!(
+            "Ignoring server {} since Compute API {} is not supported",
+            feature.name, feature.version
+        );
+        (feature.remove)(request);
+    }
+    Ok(required_version)
+}
+
 /// Create a server.
-pub async fn create_server(session: &Session, request: ServerCreate) -> Result<Ref> {
-    debug!("Creating a server with {:?}", request);
-    let body = ServerCreateRoot { server: request };
-    let root: CreatedServerRoot = session
-        .post(COMPUTE, &["servers"])
-        .json(&body)
-        .fetch()
-        .await?;
+///
+/// Fields not supported by the Compute API are removed unless `strict` is set.
+pub async fn create_server(
+    session: &Session,
+    mut request: ServerCreate,
+    scheduler_hints: HashMap<String, Value>,
+    strict: bool,
+) -> Result<Ref> {
+    debug!(
+        "Creating a server with {:?}, scheduler hints {:?}",
+        request, scheduler_hints
+    );
+    let versions = session.get_api_versions(COMPUTE).await?;
+    let required_version = adjust_server_create(&mut request, strict, |version| {
+        versions.is_some_and(|(min, max)| min <= version && version <= max)
+    })?;
+    let body = ServerCreateRoot {
+        server: request,
+        scheduler_hints,
+    };
+    let mut builder = session.post(COMPUTE, &["servers"]).json(&body);
+    if let Some(version) = required_version {
+        builder.set_api_version(version);
+    }
+    let root: CreatedServerRoot = builder.fetch_json().await?;
     trace!("Requested creation of server {:?}", root.server);
     Ok(root.server)
 }
 
-/// Delete a key pair.
-pub async fn delete_keypair<S: AsRef<str>>(session: &Session, name: S) -> Result<()> {
-    debug!("Deleting key pair {}", name.as_ref());
-    let _ = session
-        .delete(COMPUTE, &["os-keypairs", name.as_ref()])
-        .send()
+/// Send external events to servers.
+pub async fn create_server_external_events(
+    session: &Session,
+    events: Vec<ServerExternalEvent>,
+) -> Result<Vec<ServerExternalEvent>> {
+    debug!("Sending external server events {:?}", events);
+    // Newer event types are only accepted with newer API versions.
+    let maybe_version = session
+        .pick_api_version(
+            COMPUTE,
+            vec![
+                API_VERSION_EVENT_VOLUME_EXTENDED,
+                API_VERSION_EVENT_POWER_UPDATE,
+                API_VERSION_EVENT_ACCELERATOR_REQUEST,
+                API_VERSION_EVENT_VOLUME_REIMAGED,
+            ],
+        )
         .await?;
+    let body = ServerExternalEventsRoot { events };
+    let mut builder = session
+        .post(COMPUTE, &["os-server-external-events"])
+        .json(&body);
+    if let Some(version) = maybe_version {
+        builder.set_api_version(version);
+    }
+    let root: ServerExternalEventsRoot = builder.fetch_json().await?;
+    debug!("Sent external server events {:?}", root.events);
+    Ok(root.events)
+}
+
+/// Delete a key pair, optionally owned by another user.
+pub async fn delete_keypair<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+    user_id: Option<&str>,
+) -> Result<()> {
+    debug!("Deleting key pair {}", name.as_ref());
+    let mut builder = session.delete(COMPUTE, &["os-keypairs", name.as_ref()]);
+    if let Some(user_id) = user_id {
+        ensure_keypair_user_supported(session).await?;
+        builder = builder
+            .api_version(API_VERSION_KEYPAIR_USER)
+            .query(&[("user_id", user_id)]);
+    }
+    let _ = builder.send().await?;
     debug!("Key pair {} was deleted", name.as_ref());
     Ok(())
 }
 
+async fn ensure_keypair_user_supported(session: &Session) -> Result<()> {
+    if supports_keypair_user(session).await? {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::IncompatibleApiVersion,
+            format!(
+                "Managing key pairs of other users requires compute API {}",
+                API_VERSION_KEYPAIR_USER
+            ),
+        ))
+    }
+}
+
 /// Delete a server.
 pub async fn delete_server<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
     trace!("Deleting server {}", id.as_ref());
@@ -120,12 +322,43 @@ pub async fn get_extra_specs_by_flavor_id<S: AsRef<str>>(
 ) -> Result<HashMap<String, String>> {
     trace!("Get compute extra specs by ID {}", id.as_ref());
     let root: ExtraSpecsRoot = session
-        .get_json(COMPUTE, &["flavors", id.as_ref(), "os-extra_specs"])
+        .get(COMPUTE, &["flavors", id.as_ref(), "os-extra_specs"])
+        .fetch_json()
         .await?;
     trace!("Received {:?}", root.extra_specs);
     Ok(root.extra_specs)
 }
 
+/// Run an action on a flavor, returning the resulting access list.
+pub async fn flavor_action<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    action: FlavorAction,
+) -> Result<Vec<FlavorAccess>> {
+    trace!("Running {:?} on flavor {}", action, id.as_ref());
+    let root: FlavorAccessRoot = session
+        .post(COMPUTE, &["flavors", id.as_ref(), "action"])
+        .json(&action)
+        .fetch_json()
+        .await?;
+    debug!("Successfully ran {:?} on flavor {}", action, id.as_ref());
+    Ok(root.flavor_access)
+}
+
+/// Get projects with access to a private flavor.
+pub async fn get_flavor_access<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<Vec<FlavorAccess>> {
+    trace!("Get access list of flavor {}", id.as_ref());
+    let root: FlavorAccessRoot = session
+        .get(COMPUTE, &["flavors", id.as_ref(), "os-flavor-access"])
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", root.flavor_access);
+    Ok(root.flavor_access)
+}
+
 /// Get a flavor.
 pub async fn get_flavor<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result<Flavor> {
     let s = id_or_name.as_ref();
@@ -146,7 +379,7 @@ pub async fn get_flavor_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result
     if let Some(version) = maybe_version {
         builder.set_api_version(version);
     }
-    let root: FlavorRoot = builder.fetch().await?;
+    let root: FlavorRoot = builder.fetch_json().await?;
     trace!("Received {:?}", root.flavor);
     Ok(root.flavor)
 }
@@ -154,28 +387,58 @@ pub async fn get_flavor_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result
 /// Get a flavor by its name.
 pub async fn get_flavor_by_name<S: AsRef<str>>(session: &Session, name: S) -> Result<Flavor> {
     trace!("Get compute flavor by name {}", name.as_ref());
-    let root: FlavorsRoot = session.get_json(COMPUTE, &["flavors"]).await?;
+    // Flavors cannot be filtered by name, so go through all pages.
+    let mut base_query = utils::Query::new();
+    // Make private flavors visible to administrators as well.
+    base_query.push_str("is_public", "None");
+    let mut found = Vec::new();
+    let mut marker = None;
+    loop {
+        let query = base_query.with_marker_and_limit(Some(FLAVOR_PAGE_SIZE), marker);
+        let root: FlavorsRoot = session
+            .get(COMPUTE, &["flavors"])
+            .query(&query)
+            .fetch_json()
+            .await?;
+        let page_size = root.flavors.len();
+        marker = root.flavors.last().map(|item| item.id.clone());
+        found.extend(
+            root.flavors
+                .into_iter()
+                .filter(|item| item.name == name.as_ref()),
+        );
+        if found.len() > 1 || page_size < FLAVOR_PAGE_SIZE {
+            break;
+        }
+    }
     let item = utils::one(
-        root.flavors
-            .into_iter()
-            .filter(|item| item.name == name.as_ref()),
+        found,
         "Flavor with given name or ID not found",
         "Too many flavors found with given name",
     )?;
     get_flavor_by_id(session, item.id).await
 }
 
-/// Get a key pair by its name.
-pub async fn get_keypair<S: AsRef<str>>(session: &Session, name: S) -> Result<KeyPair> {
+/// Get a key pair by its name, optionally owned by another user.
+pub async fn get_keypair<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+    user_id: Option<&str>,
+) -> Result<KeyPair> {
     trace!("Get compute key pair by name {}", name.as_ref());
-    let maybe_version = session
-        .pick_api_version(COMPUTE, Some(API_VERSION_KEYPAIR_TYPE))
-        .await?;
     let mut builder = session.get(COMPUTE, &["os-keypairs", name.as_ref()]);
-    if let Some(version) = maybe_version {
+    if let Some(user_id) = user_id {
+        ensure_keypair_user_supported(session).await?;
+        builder = builder
+            .api_version(API_VERSION_KEYPAIR_USER)
+            .query(&[("user_id", user_id)]);
+    } else if let Some(version) = session
+        .pick_api_version(COMPUTE, Some(API_VERSION_KEYPAIR_TYPE))
+        .await?
+    {
         builder.set_api_version(version);
     }
-    let root: KeyPairRoot = builder.fetch().await?;
+    let root: KeyPairRoot = builder.fetch_json().await?;
     trace!("Received {:?}", root.keypair);
     Ok(root.keypair)
 }
@@ -195,23 +458,119 @@ pub async fn get_server<S: AsRef<str>>(session: &Session, id_or_name: S) -> Resu
 /// Get a server by its ID.
 pub async fn get_server_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result<Server> {
     trace!("Get compute server with ID {}", id.as_ref());
-    let maybe_version = server_api_version(session).await?;
+    let maybe_version = server_api_version(session, None).await?;
     let mut builder = session.get(COMPUTE, &["servers", id.as_ref()]);
     if let Some(version) = maybe_version {
         builder.set_api_version(version);
     }
-    let root: ServerRoot = builder.fetch().await?;
+    let root: ServerRoot = builder.fetch_json().await?;
     trace!("Received {:?}", root.server);
     Ok(root.server)
 }
 
+/// Get diagnostics of a server.
+pub async fn get_server_diagnostics<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<ServerDiagnostics> {
+    trace!("Fetching diagnostics of server {}", id.as_ref());
+    let builder = session.get(COMPUTE, &["servers", id.as_ref(), "diagnostics"]);
+    let result = if session
+        .supports_api_version(COMPUTE, API_VERSION_SERVER_DIAGNOSTICS)
+        .await?
+    {
+        ServerDiagnostics::Detailed(
+            builder
+                .api_version(API_VERSION_SERVER_DIAGNOSTICS)
+                .fetch_json()
+                .await?,
+        )
+    } else {
+        ServerDiagnostics::Legacy(builder.fetch_json().await?)
+    };
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get NUMA topology of a server.
+pub async fn get_server_topology<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<ServerTopology> {
+    trace!("Fetching topology of server {}", id.as_ref());
+    if !session
+        .supports_api_version(COMPUTE, API_VERSION_SERVER_TOPOLOGY)
+        .await?
+    {
+        return Err(Error::new(
+            ErrorKind::IncompatibleApiVersion,
+            format!("Server topology requires compute API {API_VERSION_SERVER_TOPOLOGY}"),
+        ));
+    }
+    let result: ServerTopology = session
+        .get(COMPUTE, &["servers", id.as_ref(), "topology"])
+        .api_version(API_VERSION_SERVER_TOPOLOGY)
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get a record of an action performed on a server.
+pub async fn get_instance_action<S1, S2>(
+    session: &Session,
+    id: S1,
+    request_id: S2,
+) -> Result<InstanceAction>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    trace!(
+        "Fetching action {} of server {}",
+        request_id.as_ref(),
+        id.as_ref()
+    );
+    let mut builder = session.get(
+        COMPUTE,
+        &[
+            "servers",
+            id.as_ref(),
+            "os-instance-actions",
+            request_id.as_ref(),
+        ],
+    );
+    if let Some(version) = session
+        .pick_api_version(
+            COMPUTE,
+            vec![API_VERSION_ACTION_EVENTS, API_VERSION_ACTION_UPDATED_AT],
+        )
+        .await?
+    {
+        builder.set_api_version(version);
+    }
+    let root: InstanceActionRoot = builder.fetch_json().await?;
+    trace!("Received {:?}", root.instance_action);
+    Ok(root.instance_action)
+}
+
+/// Get absolute limits of the current project.
+pub async fn get_limits(session: &Session) -> Result<AbsoluteLimits> {
+    trace!("Fetching compute limits");
+    let root: LimitsRoot = session.get(COMPUTE, &["limits"]).fetch_json().await?;
+    trace!("Received {:?}", root.limits.absolute);
+    Ok(root.limits.absolute)
+}
+
 /// Get a server by its name.
 pub async fn get_server_by_name<S: AsRef<str>>(session: &Session, name: S) -> Result<Server> {
     trace!("Get compute server with name {}", name.as_ref());
+    // The name filter is a regular expression, make it match exactly.
+    let pattern = format!("^{}$", escape_regex(name.as_ref()));
     let root: ServersRoot = session
         .get(COMPUTE, &["servers"])
-        .query(&[("name", name.as_ref())])
-        .fetch()
+        .query(&[("name", pattern.as_str()), ("limit", "2")])
+        .fetch_json()
         .await?;
     let item = utils::one(
         root.servers
@@ -232,7 +591,7 @@ pub async fn list_flavors<Q: Serialize + Sync + Debug>(
     let root: FlavorsRoot = session
         .get(COMPUTE, &["flavors"])
         .query(query)
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received flavors: {:?}", root.flavors);
     Ok(root.flavors)
@@ -251,7 +610,7 @@ pub async fn list_flavors_detail<Q: Serialize + Sync + Debug>(
     if let Some(version) = maybe_version {
         builder.set_api_version(version);
     }
-    let root: FlavorsDetailRoot = builder.fetch().await?;
+    let root: FlavorsDetailRoot = builder.fetch_json().await?;
     trace!("Received flavors: {:?}", root.flavors);
     Ok(root.flavors)
 }
@@ -265,14 +624,18 @@ pub async fn list_keypairs<Q: Serialize + Sync + Debug>(
     let maybe_version = session
         .pick_api_version(
             COMPUTE,
-            vec![API_VERSION_KEYPAIR_TYPE, API_VERSION_KEYPAIR_PAGINATION],
+            vec![
+                API_VERSION_KEYPAIR_TYPE,
+                API_VERSION_KEYPAIR_USER,
+                API_VERSION_KEYPAIR_PAGINATION,
+            ],
         )
         .await?;
     let mut builder = session.get(COMPUTE, &["os-keypairs"]).query(query);
     if let Some(version) = maybe_version {
         builder.set_api_version(version);
     }
-    let root: KeyPairsRoot = builder.fetch().await?;
+    let root: KeyPairsRoot = builder.fetch_json().await?;
     let result = root
         .keypairs
         .into_iter()
@@ -286,51 +649,165 @@ pub async fn list_keypairs<Q: Serialize + Sync + Debug>(
 pub async fn list_servers<Q: Serialize + Sync + Debug>(
     session: &Session,
     query: &Q,
+    required_version: Option<ApiVersion>,
 ) -> Result<Vec<IdAndName>> {
     trace!("Listing compute servers with {:?}", query);
-    let root: ServersRoot = session
-        .get(COMPUTE, &["servers"])
-        .query(query)
-        .fetch()
-        .await?;
+    let maybe_version = server_api_version(session, required_version).await?;
+    let mut builder = session.get(COMPUTE, &["servers"]).query(query);
+    if let Some(version) = maybe_version {
+        builder.set_api_version(version);
+    }
+    let root: ServersRoot = builder.fetch_json().await?;
     trace!("Received servers: {:?}", root.servers);
     Ok(root.servers)
 }
 
+/// List security groups of a server.
+pub async fn list_server_security_groups<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<Vec<ServerSecurityGroup>> {
+    trace!("Listing security groups of server {}", id.as_ref());
+    let root: ServerSecurityGroupsRoot = session
+        .get(COMPUTE, &["servers", id.as_ref(), "os-security-groups"])
+        .fetch_json()
+        .await?;
+    trace!("Received security groups: {:?}", root.security_groups);
+    Ok(root.security_groups)
+}
+
 /// List servers with details.
 pub async fn list_servers_detail<Q: Serialize + Sync + Debug>(
     session: &Session,
     query: &Q,
+    required_version: Option<ApiVersion>,
 ) -> Result<Vec<Server>> {
     trace!("Listing compute servers with {:?}", query);
-    let maybe_version = session
-        .pick_api_version(COMPUTE, Some(API_VERSION_SERVER_DESCRIPTION))
-        .await?;
+    let maybe_version = server_api_version(session, required_version).await?;
     let mut builder = session.get(COMPUTE, &["servers", "detail"]).query(query);
     if let Some(version) = maybe_version {
         builder.set_api_version(version);
     }
-    let root: ServersDetailRoot = builder.fetch().await?;
+    let root: ServersDetailRoot = builder.fetch_json().await?;
     trace!("Received servers: {:?}", root.servers);
     Ok(root.servers)
 }
 
-/// Run an action on a server.
-pub async fn server_action<S1, Q>(session: &Session, id: S1, action: Q) -> Result<()>
+/// List volumes attached to a server.
+pub async fn list_volume_attachments<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<Vec<VolumeAttachment>> {
+    trace!("Listing volume attachments of server {}", id.as_ref());
+    let maybe_version = session
+        .pick_api_version(COMPUTE, Some(API_VERSION_ATTACHMENT_DELETE_FLAG))
+        .await?;
+    let mut builder = session.get(COMPUTE, &["servers", id.as_ref(), "os-volume_attachments"]);
+    if let Some(version) = maybe_version {
+        builder.set_api_version(version);
+    }
+    let root: VolumeAttachmentsRoot = builder.fetch_json().await?;
+    trace!("Received volume attachments: {:?}", root.volume_attachments);
+    Ok(root.volume_attachments)
+}
+
+/// Create an image from a server and return its ID.
+#[cfg(feature = "image")]
+pub async fn create_server_image<S1, Q>(session: &Session, id: S1, action: Q) -> Result<String>
 where
     S1: AsRef<str>,
     Q: Serialize + Send + Debug,
 {
-    trace!("Running {:?} on server {}", action, id.as_ref(),);
-    let _ = session
+    trace!("Running {:?} on server {}", action, id.as_ref());
+    let response = session
         .post(COMPUTE, &["servers", id.as_ref(), "action"])
         .json(&action)
         .send()
         .await?;
-    debug!("Successfully ran {:?} on server {}", action, id.as_ref());
+    // Before API 2.45 the image URL is returned in the Location header.
+    let from_header = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim_end_matches('/').rsplit('/').next())
+        .map(String::from);
+    let image_id = match from_header {
+        Some(image_id) => image_id,
+        None => response.json::<CreatedImage>().await?.image_id,
+    };
+    debug!("Created image {} from server {}", image_id, id.as_ref());
+    Ok(image_id)
+}
+
+/// Run an action on a server that was removed in API 2.44.
+pub async fn server_legacy_action<S1, Q>(session: &Session, id: S1, action: Q) -> Result<()>
+where
+    S1: AsRef<str>,
+    Q: Serialize + Send + Debug,
+{
+    if !supports_legacy_actions(session).await? {
+        return Err(Error::new(
+            ErrorKind::IncompatibleApiVersion,
+            format!(
+                "{:?} requires compute API older than {}",
+                action, API_VERSION_LEGACY_ACTIONS_REMOVED
+            ),
+        ));
+    }
+    let _ = server_action(session, id, action).await?;
     Ok(())
 }
 
+/// Run an action on a server, return its request ID if known.
+pub async fn server_action<S1, Q>(session: &Session, id: S1, action: Q) -> Result<Option<String>>
+where
+    S1: AsRef<str>,
+    Q: Serialize + Send + Debug,
+{
+    server_action_with_version(session, id, action, None).await
+}
+
+/// Run an action on a server using the given API version.
+///
+/// Fails if the API version is not supported.
+pub async fn server_action_with_version<S1, Q>(
+    session: &Session,
+    id: S1,
+    action: Q,
+    version: Option<ApiVersion>,
+) -> Result<Option<String>>
+where
+    S1: AsRef<str>,
+    Q: Serialize + Send + Debug,
+{
+    trace!("Running {:?} on server {}", action, id.as_ref(),);
+    let mut builder = session
+        .post(COMPUTE, &["servers", id.as_ref(), "action"])
+        .json(&action);
+    if let Some(version) = version {
+        if !session.supports_api_version(COMPUTE, version).await? {
+            return Err(Error::new(
+                ErrorKind::IncompatibleApiVersion,
+                format!("Compute API version {version} is required for {action:?}"),
+            ));
+        }
+        builder.set_api_version(version);
+    }
+    let response = builder.send().await?;
+    let request_id = [X_OPENSTACK_REQUEST_ID, X_COMPUTE_REQUEST_ID]
+        .iter()
+        .filter_map(|name| response.headers().get(*name))
+        .find_map(|value| value.to_str().ok())
+        .map(String::from);
+    debug!(
+        "Successfully ran {:?} on server {}, request ID {:?}",
+        action,
+        id.as_ref(),
+        request_id
+    );
+    Ok(request_id)
+}
+
 /// Run an action on a server and return result.
 pub async fn server_action_with_result<S1, Q, R>(session: &Session, id: S1, action: Q) -> Result<R>
 where
@@ -342,7 +819,7 @@ where
     let response = session
         .post(COMPUTE, &["servers", id.as_ref(), "action"])
         .json(&action)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Successfully ran {:?} on server {}", action, id.as_ref());
     Ok(response)
@@ -355,3 +832,155 @@ pub async fn supports_keypair_pagination(session: &Session) -> Result<bool> {
         .supports_api_version(COMPUTE, API_VERSION_KEYPAIR_PAGINATION)
         .await
 }
+
+/// Whether key pairs of other users can be accessed.
+#[inline]
+pub async fn supports_keypair_user(session: &Session) -> Result<bool> {
+    session
+        .supports_api_version(COMPUTE, API_VERSION_KEYPAIR_USER)
+        .await
+}
+
+/// Whether filtering servers by the changes-before time is supported.
+#[inline]
+pub async fn supports_server_changes_before_filter(session: &Session) -> Result<bool> {
+    session
+        .supports_api_version(COMPUTE, API_VERSION_CHANGES_BEFORE)
+        .await
+}
+
+/// Whether filtering servers by the lock state is supported.
+#[inline]
+pub async fn supports_server_locked_filter(session: &Session) -> Result<bool> {
+    session
+        .supports_api_version(COMPUTE, API_VERSION_SERVER_LOCKED_REASON)
+        .await
+}
+
+/// Whether actions removed in API 2.44 (e.g. fixed IP management) are available.
+pub async fn supports_legacy_actions(session: &Session) -> Result<bool> {
+    Ok(match session.get_api_versions(COMPUTE).await? {
+        Some((min, _)) => min < API_VERSION_LEGACY_ACTIONS_REMOVED,
+        // No microversions means the legacy API.
+        None => true,
+    })
+}
+
+/// Change whether an attached volume is deleted together with the server.
+pub async fn update_volume_attachment<S1, S2>(
+    session: &Session,
+    id: S1,
+    volume_id: S2,
+    delete_on_termination: bool,
+) -> Result<()>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    if !session
+        .supports_api_version(COMPUTE, API_VERSION_ATTACHMENT_UPDATE)
+        .await?
+    {
+        return Err(Error::new(
+            ErrorKind::IncompatibleApiVersion,
+            format!(
+                "Updating volume attachments requires compute API {}",
+                API_VERSION_ATTACHMENT_UPDATE
+            ),
+        ));
+    }
+    let body = VolumeAttachmentUpdateRoot {
+        volume_attachment: VolumeAttachmentUpdate {
+            volume_id: volume_id.as_ref().into(),
+            delete_on_termination,
+        },
+    };
+    debug!(
+        "Setting delete_on_termination={} for volume {} on server {}",
+        delete_on_termination,
+        volume_id.as_ref(),
+        id.as_ref()
+    );
+    let _ = session
+        .put(
+            COMPUTE,
+            &[
+                "servers",
+                id.as_ref(),
+                "os-volume_attachments",
+                volume_id.as_ref(),
+            ],
+        )
+        .api_version(API_VERSION_ATTACHMENT_UPDATE)
+        .json(&body)
+        .send()
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::super::super::common::ApiVersion;
+    use super::super::super::ErrorKind;
+    use super::super::protocol::{ServerCreate, ServerNetwork};
+    use super::{adjust_server_create, escape_regex};
+
+    fn server_create() -> ServerCreate {
+        ServerCreate {
+            adminPass: None,
+            block_devices: Vec::new(),
+            config_drive: None,
+            description: Some("description".into()),
+            flavorRef: "flavor".into(),
+            hostname: Some("host".into()),
+            imageRef: Some("image".into()),
+            key_name: None,
+            metadata: HashMap::new(),
+            name: "server".into(),
+            networks: vec![ServerNetwork::Network {
+                uuid: "network".into(),
+                tag: Some("data".into()),
+            }],
+            trusted_image_certificates: Vec::new(),
+            user_data: None,
+            availability_zone: None,
+        }
+    }
+
+    #[test]
+    fn test_escape_regex() {
+        assert_eq!(escape_regex("server-1"), "server-1");
+        assert_eq!(escape_regex("web.1 (old)"), "web\\.1 \\(old\\)");
+        assert_eq!(escape_regex("a*b+c?"), "a\\*b\\+c\\?");
+    }
+
+    #[test]
+    fn test_adjust_server_create_supported() {
+        let mut request = server_create();
+        let version = adjust_server_create(&mut request, true, |_| true).unwrap();
+        assert_eq!(version, Some(ApiVersion(2, 90)));
+        assert!(request.description.is_some());
+        assert!(request.hostname.is_some());
+    }
+
+    #[test]
+    fn test_adjust_server_create_strict() {
+        let mut request = server_create();
+        let err = adjust_server_create(&mut request, true, |v| v < ApiVersion(2, 42)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IncompatibleApiVersion);
+        assert!(err.to_string().contains("device tags (requires 2.42)"));
+        assert!(err.to_string().contains("hostname (requires 2.90)"));
+    }
+
+    #[test]
+    fn test_adjust_server_create_lenient() {
+        let mut request = server_create();
+        let version = adjust_server_create(&mut request, false, |v| v < ApiVersion(2, 42)).unwrap();
+        assert_eq!(version, Some(ApiVersion(2, 19)));
+        assert!(request.description.is_some());
+        assert!(request.hostname.is_none());
+        assert!(request.networks[0].tag().is_none());
+    }
+}
@@ -107,6 +107,13 @@ pub struct BlockDevice {
 
     /// A source for this block device (if any).
     pub source: Option<BlockDeviceSource>,
+
+    /// A device tag exposed to the guest via the metadata service.
+    ///
+    /// # Note
+    ///
+    /// Requires Compute API version 2.42.
+    pub tag: Option<String>,
 }
 
 impl BlockDevice {
@@ -122,6 +129,7 @@ impl BlockDevice {
             guest_format: None,
             size_gib: None,
             source: Some(source),
+            tag: None,
         }
     }
 
@@ -134,6 +142,7 @@ impl BlockDevice {
             guest_format: Some("swap".into()),
             size_gib: Some(size_gib),
             source: None,
+            tag: None,
         }
     }
 
@@ -154,6 +163,7 @@ impl BlockDevice {
             guest_format: None,
             size_gib: None,
             source: Some(BlockDeviceSource::Image(image.into())),
+            tag: None,
         }
     }
 
@@ -171,6 +181,7 @@ impl BlockDevice {
             guest_format: None,
             size_gib: None,
             source: Some(BlockDeviceSource::Volume(volume.into())),
+            tag: None,
         }
     }
 
@@ -183,6 +194,7 @@ impl BlockDevice {
             guest_format: None,
             size_gib: Some(size_gib),
             source: None,
+            tag: None,
         }
     }
 
@@ -200,9 +212,18 @@ impl BlockDevice {
             guest_format: None,
             size_gib: Some(size_gib),
             source: Some(BlockDeviceSource::Image(image.into())),
+            tag: None,
         }
     }
 
+    /// Set a device tag exposed to the guest via the metadata service.
+    ///
+    /// Requires Compute API version 2.42.
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> BlockDevice {
+        self.tag = Some(tag.into());
+        self
+    }
+
     #[inline]
     fn non_null_field_count(&self) -> usize {
         let mut count = 4;
@@ -215,6 +236,9 @@ impl BlockDevice {
         if self.size_gib.is_some() {
             count += 1
         }
+        if self.tag.is_some() {
+            count += 1
+        }
         count
     }
 
@@ -252,6 +276,9 @@ impl Serialize for BlockDevice {
         if let Some(volume_size) = self.size_gib {
             bd.serialize_field("volume_size", &volume_size)?;
         }
+        if let Some(ref tag) = self.tag {
+            bd.serialize_field("tag", tag)?;
+        }
         bd.end()
     }
 }
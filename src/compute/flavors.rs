@@ -19,11 +19,12 @@ use std::collections::HashMap;
 use async_trait::async_trait;
 use futures::stream::{Stream, TryStreamExt};
 use osauth::common::IdAndName;
+use serde_json::Value;
 
-use super::super::common::{FlavorRef, Refresh, ResourceIterator, ResourceQuery};
+use super::super::common::{FlavorRef, ProjectRef, Refresh, ResourceIterator, ResourceQuery};
 use super::super::session::Session;
 use super::super::utils::Query;
-use super::super::Result;
+use super::super::{Error, ErrorKind, Result, Sort};
 use super::{api, protocol};
 
 /// Structure representing a flavor.
@@ -47,12 +48,14 @@ pub struct FlavorQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
 }
 
 /// A detailed query to flavor list.
 #[derive(Clone, Debug)]
 pub struct DetailedFlavorQuery {
     inner: FlavorQuery,
+    extra_specs: Vec<(String, String)>,
 }
 
 impl Flavor {
@@ -88,6 +91,11 @@ impl Flavor {
         self.inner.ephemeral
     }
 
+    /// Fields returned by the service that are not known to this crate.
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.inner.extra
+    }
+
     /// Extra specs of the flavor.
     pub fn extra_specs(&self) -> &HashMap<String, String> {
         &self.extra_specs
@@ -134,6 +142,45 @@ impl Flavor {
     pub fn vcpu_count(&self) -> u32 {
         self.inner.vcpus
     }
+
+    /// List projects that have access to this private flavor.
+    ///
+    /// Fails for public flavors.
+    pub async fn access_list(&self) -> Result<Vec<protocol::FlavorAccess>> {
+        api::get_flavor_access(&self.session, &self.inner.id).await
+    }
+
+    /// Give a project access to this private flavor.
+    ///
+    /// Returns the updated access list. Usually requires administrator privileges.
+    pub async fn add_project_access<P>(&self, project: P) -> Result<Vec<protocol::FlavorAccess>>
+    where
+        P: Into<ProjectRef>,
+    {
+        let tenant = project.into().into_verified(&self.session).await?.into();
+        api::flavor_action(
+            &self.session,
+            &self.inner.id,
+            protocol::FlavorAction::AddTenantAccess { tenant },
+        )
+        .await
+    }
+
+    /// Revoke access of a project to this private flavor.
+    ///
+    /// Returns the updated access list. Usually requires administrator privileges.
+    pub async fn remove_project_access<P>(&self, project: P) -> Result<Vec<protocol::FlavorAccess>>
+    where
+        P: Into<ProjectRef>,
+    {
+        let tenant = project.into().into_verified(&self.session).await?.into();
+        api::flavor_action(
+            &self.session,
+            &self.inner.id,
+            protocol::FlavorAction::RemoveTenantAccess { tenant },
+        )
+        .await
+    }
 }
 
 #[async_trait]
@@ -143,6 +190,10 @@ impl Refresh for Flavor {
         self.inner = api::get_flavor_by_id(&self.session, &self.inner.id).await?;
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl FlavorSummary {
@@ -168,6 +219,7 @@ impl FlavorQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
         }
     }
 
@@ -189,9 +241,54 @@ impl FlavorQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Add sorting to the request.
+    pub fn sort_by(mut self, sort: Sort<protocol::FlavorSortKey>) -> Self {
+        let (field, direction) = sort.into();
+        self.query.push_str("sort_key", field);
+        self.query.push("sort_dir", direction);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by public or private flavors."]
+        set_is_public, with_is_public -> is_public: bool
+    }
+
+    /// List both public and private flavors.
+    ///
+    /// Only has effect for administrators, who otherwise only see public
+    /// flavors. Other users always see public flavors and private flavors
+    /// their project has access to.
+    pub fn with_any_visibility(mut self) -> Self {
+        self.query.push_str("is_public", "None");
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by minimum root disk size in GiB."]
+        set_min_disk, with_min_disk -> minDisk: u64
+    }
+
+    query_filter! {
+        #[doc = "Filter by minimum RAM size in MiB."]
+        set_min_ram, with_min_ram -> minRam: u64
+    }
+
     /// Convert this query into a detailed query.
     pub fn detailed(self) -> DetailedFlavorQuery {
-        DetailedFlavorQuery { inner: self }
+        DetailedFlavorQuery {
+            inner: self,
+            extra_specs: Vec::new(),
+        }
     }
 
     /// Convert this query into an stream executing the request.
@@ -205,7 +302,7 @@ impl FlavorQuery {
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<FlavorSummary>> {
         debug!("Fetching flavors with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -227,7 +324,9 @@ impl FlavorQuery {
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -245,6 +344,10 @@ impl ResourceQuery for FlavorQuery {
         resource.id().clone()
     }
 
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -263,6 +366,19 @@ impl ResourceQuery for FlavorQuery {
 }
 
 impl DetailedFlavorQuery {
+    /// Only return flavors that have the given extra spec set to the value.
+    ///
+    /// The Compute API cannot filter on extra specs, so this filter is
+    /// applied on the client side.
+    pub fn matching_extra_spec<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.extra_specs.push((key.into(), value.into()));
+        self
+    }
+
     /// Convert this query into a stream executing the request.
     ///
     /// This stream yields full `Flavor` objects.
@@ -272,9 +388,58 @@ impl DetailedFlavorQuery {
     ///
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<Flavor>> {
-        debug!("Fetching detailed flavors with {:?}", self.inner.query);
-        ResourceIterator::new(self).into_stream()
+        debug!(
+            "Fetching detailed flavors with {:?}, extra specs {:?}",
+            self.inner.query, self.extra_specs
+        );
+        let extra_specs = self.extra_specs.clone();
+        ResourceIterator::new(self.inner.session.clone(), self)
+            .into_stream()
+            .try_filter(move |flavor| {
+                let matches = extra_specs
+                    .iter()
+                    .all(|(key, value)| flavor.extra_specs.get(key) == Some(value));
+                futures::future::ready(matches)
+            })
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_stream().try_collect().await`.
+    pub async fn all(self) -> Result<Vec<Flavor>> {
+        self.into_stream().try_collect().await
     }
+
+    /// Find the smallest flavor with at least the given VCPU count and RAM.
+    ///
+    /// Flavors are compared by VCPU count, then by RAM size, then by root
+    /// disk size. Fails with `ResourceNotFound` if no flavor is suitable.
+    pub async fn smallest(mut self, vcpu_count: u32, ram_size: u64) -> Result<Flavor> {
+        self.inner.set_min_ram(ram_size);
+        self.into_stream()
+            .try_filter(|flavor| futures::future::ready(flavor.vcpu_count() >= vcpu_count))
+            .try_fold(None, |best: Option<Flavor>, flavor| {
+                futures::future::ok(match best {
+                    Some(best) if flavor_size(&best) <= flavor_size(&flavor) => Some(best),
+                    _ => Some(flavor),
+                })
+            })
+            .await?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::ResourceNotFound,
+                    format!(
+                        "No flavor with at least {} VCPUs and {} MiB of RAM",
+                        vcpu_count, ram_size
+                    ),
+                )
+            })
+    }
+}
+
+#[inline]
+fn flavor_size(flavor: &Flavor) -> (u32, u64, u64) {
+    (flavor.vcpu_count(), flavor.ram_size(), flavor.root_size())
 }
 
 #[async_trait]
@@ -291,6 +456,10 @@ impl ResourceQuery for DetailedFlavorQuery {
         resource.id().clone()
     }
 
+    fn page_size(&self) -> Option<usize> {
+        self.inner.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
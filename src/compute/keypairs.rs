@@ -17,9 +17,9 @@
 use async_trait::async_trait;
 use futures::stream::{Stream, TryStreamExt};
 
-use super::super::common::{KeyPairRef, Refresh, ResourceIterator, ResourceQuery};
+use super::super::common::{KeyPairRef, Refresh, ResourceIterator, ResourceQuery, UserRef};
 use super::super::session::Session;
-use super::super::utils::Query;
+use super::super::utils::{NamePattern, Query};
 use super::super::{Error, ErrorKind, Result};
 use super::{api, protocol};
 
@@ -28,14 +28,20 @@ use super::{api, protocol};
 pub struct KeyPair {
     session: Session,
     inner: protocol::KeyPair,
+    // Owner of the key pair if it was explicitly requested.
+    owner: Option<String>,
 }
 
-/// A query to server list.
+/// A query to key pair list.
 #[derive(Clone, Debug)]
 pub struct KeyPairQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    user: Option<UserRef>,
+    user_id: Option<String>,
+    name_pattern: Option<NamePattern>,
 }
 
 /// A request to create a key pair.
@@ -48,13 +54,17 @@ pub struct NewKeyPair {
 impl KeyPair {
     /// Load a KeyPair object.
     pub(crate) async fn new<Id: AsRef<str>>(session: Session, id: Id) -> Result<KeyPair> {
-        let inner = api::get_keypair(&session, id).await?;
-        Ok(KeyPair { session, inner })
+        let inner = api::get_keypair(&session, id, None).await?;
+        Ok(KeyPair {
+            session,
+            inner,
+            owner: None,
+        })
     }
 
     /// Delete the key pair.
     pub async fn delete(self) -> Result<()> {
-        api::delete_keypair(&self.session, &self.inner.name).await
+        api::delete_keypair(&self.session, &self.inner.name, self.owner.as_deref()).await
     }
 
     transparent_property! {
@@ -76,15 +86,28 @@ impl KeyPair {
         #[doc = "Public key."]
         public_key: ref String
     }
+
+    /// ID of the user owning the key pair, if known.
+    ///
+    /// Only returned when fetching a single key pair or when listing key
+    /// pairs of a specific user.
+    pub fn user_id(&self) -> Option<&String> {
+        self.inner.user_id.as_ref().or(self.owner.as_ref())
+    }
 }
 
 #[async_trait]
 impl Refresh for KeyPair {
     /// Refresh the keypair.
     async fn refresh(&mut self) -> Result<()> {
-        self.inner = api::get_keypair(&self.session, &self.inner.name).await?;
+        self.inner =
+            api::get_keypair(&self.session, &self.inner.name, self.owner.as_deref()).await?;
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl KeyPairQuery {
@@ -93,6 +116,10 @@ impl KeyPairQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            user: None,
+            user_id: None,
+            name_pattern: None,
         }
     }
 
@@ -114,6 +141,32 @@ impl KeyPairQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// List key pairs of the given user instead of the current one.
+    ///
+    /// Requires compute API 2.10 and usually administrator privileges.
+    pub fn set_user<U: Into<UserRef>>(&mut self, value: U) {
+        self.user = Some(value.into());
+    }
+
+    /// List key pairs of the given user instead of the current one.
+    ///
+    /// Requires compute API 2.10 and usually administrator privileges.
+    pub fn with_user<U: Into<UserRef>>(mut self, value: U) -> Self {
+        self.set_user(value);
+        self
+    }
+
+    query_name_pattern!("key pairs");
+
     /// Convert this query into a stream executing the request.
     ///
     /// Returns a `TryStream`, which is a stream with each `next`
@@ -122,7 +175,7 @@ impl KeyPairQuery {
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<KeyPair>> {
         debug!("Fetching key pairs with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -138,13 +191,15 @@ impl KeyPairQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<KeyPair> {
         debug!("Fetching one key pair with {:?}", self.query);
-        if self.can_paginate {
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yieled more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -172,6 +227,7 @@ impl NewKeyPair {
         Ok(KeyPair {
             session: self.session,
             inner: keypair,
+            owner: None,
         })
     }
 
@@ -186,6 +242,7 @@ impl NewKeyPair {
             let result = KeyPair {
                 session: self.session,
                 inner: keypair,
+                owner: None,
             };
 
             Ok((result, private_key))
@@ -227,10 +284,38 @@ impl ResourceQuery for KeyPairQuery {
         }
     }
 
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        if let Some(user) = self.user.take() {
+            if !api::supports_keypair_user(&self.session).await? {
+                return Err(Error::new(
+                    ErrorKind::IncompatibleApiVersion,
+                    "Listing key pairs of other users requires compute API 2.10",
+                ));
+            }
+            let verified = String::from(user.into_filter(&self.session).await?);
+            self.query.push_str("user_id", &verified);
+            self.user_id = Some(verified);
+        }
+        Ok(())
+    }
+
     fn extract_marker(&self, resource: &Self::Item) -> String {
         resource.name().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern
+            .as_ref()
+            .map_or(true, |pattern| pattern.matches(resource.name()))
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -243,6 +328,7 @@ impl ResourceQuery for KeyPairQuery {
             .map(|item| KeyPair {
                 session: self.session.clone(),
                 inner: item,
+                owner: self.user_id.clone(),
             })
             .collect())
     }
@@ -261,7 +347,7 @@ impl KeyPairRef {
         Ok(if self.verified {
             self
         } else {
-            KeyPairRef::new_verified(api::get_keypair(session, &self.value).await?.name)
+            KeyPairRef::new_verified(api::get_keypair(session, &self.value, None).await?.name)
         })
     }
 }
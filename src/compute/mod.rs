@@ -21,14 +21,21 @@ mod keypairs;
 mod protocol;
 mod servers;
 
+pub(crate) use self::api::create_server_external_events;
 pub use self::block_device_mapping::{BlockDevice, BlockDeviceDestinationType, BlockDeviceSource};
 pub use self::flavors::{DetailedFlavorQuery, Flavor, FlavorQuery, FlavorSummary};
 pub use self::keypairs::{KeyPair, KeyPairQuery, NewKeyPair};
 pub use self::protocol::{
-    AddressType, KeyPairType, RebootType, ServerAddress, ServerFlavor, ServerPowerState,
-    ServerSortKey, ServerStatus,
+    AddressType, CpuDiagnostics, DetailedDiagnostics, DiskDiagnostics, FlavorAccess, FlavorSortKey,
+    InstanceAction, InstanceActionEvent, KeyPairType, MemoryDiagnostics, NicDiagnostics,
+    QuotaResource, RebootType, ServerAddress, ServerDiagnostics, ServerExternalEvent,
+    ServerExternalEventName, ServerExternalEventStatus, ServerFlavor, ServerNumaNode,
+    ServerPowerState, ServerPrecheckIssue, ServerPrecheckReport, ServerSecurityGroup,
+    ServerSortKey, ServerStatus, ServerTopology, VolumeAttachment,
 };
+#[cfg(feature = "image")]
+pub use self::servers::ServerImageWaiter;
 pub use self::servers::{
-    DetailedServerQuery, NewServer, Server, ServerAction, ServerCreationWaiter, ServerNIC,
-    ServerQuery, ServerStatusWaiter, ServerSummary,
+    BulkOptions, DetailedServerQuery, NewServer, Server, ServerAction, ServerCreationWaiter,
+    ServerNIC, ServerQuery, ServerStatusWaiter, ServerSummary,
 };
@@ -18,14 +18,38 @@
 #![allow(missing_docs)]
 
 use std::collections::HashMap;
+use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use chrono::{DateTime, FixedOffset};
 use osauth::common::{empty_as_default, IdAndName, Ref};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
+use super::super::common::protocol::deserialize_optional_openstack_datetime;
 use super::BlockDevice;
 
+protocol_enum! {
+    #[doc = "Available sort keys."]
+    enum FlavorSortKey {
+        CreatedAt = "created_at",
+        Description = "description",
+        Disabled = "disabled",
+        EphemeralSize = "ephemeral_gb",
+        FlavorId = "flavorid",
+        Id = "id",
+        IsPublic = "is_public",
+        Name = "name",
+        RamSize = "memory_mb",
+        RootSize = "root_gb",
+        RxtxFactor = "rxtx_factor",
+        SwapSize = "swap",
+        UpdatedAt = "updated_at",
+        VcpuCount = "vcpus",
+        VcpuWeight = "vcpu_weight"
+    }
+}
+
 protocol_enum! {
     #[doc = "Available sort keys."]
     enum ServerSortKey {
@@ -63,7 +87,8 @@ protocol_enum! {
 
 protocol_enum! {
     #[doc = "Possible server statuses."]
-    enum ServerStatus {
+    #[derive(Default)]
+    enum ServerStatus: default {
         Active = "ACTIVE",
         Building = "BUILD",
         Deleted = "DELETED",
@@ -81,6 +106,7 @@ protocol_enum! {
         Shelved = "SHELVED",
         ShelvedOffloaded = "SHELVED_OFFLOADED",
         SoftDeleted = "SOFT_DELETED",
+        #[default]
         Unknown = "UNKNOWN",
         UpdatingPassword = "PASSWORD",
         VerifyingResize = "VERIFY_RESIZE"
@@ -89,7 +115,9 @@ protocol_enum! {
 
 protocol_enum! {
     #[doc = "Possible power states."]
+    #[derive(Default)]
     enum ServerPowerState: u8 {
+        #[default]
         NoState = 0,
         Running = 1,
         Paused = 3,
@@ -115,6 +143,15 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "A resource limited by the compute quota."]
+    enum QuotaResource {
+        Cores = "cores",
+        Instances = "instances",
+        Ram = "ram"
+    }
+}
+
 protocol_enum! {
     #[doc = "Type of a key pair."]
     enum KeyPairType {
@@ -217,6 +254,12 @@ pub struct Server {
         rename = "config_drive"
     )]
     pub has_config_drive: bool,
+    #[serde(rename = "OS-EXT-SRV-ATTR:host", default)]
+    pub host: Option<String>,
+    #[serde(rename = "OS-EXT-SRV-ATTR:hostname", default)]
+    pub hostname: Option<String>,
+    #[serde(rename = "OS-EXT-SRV-ATTR:hypervisor_hostname", default)]
+    pub hypervisor_hostname: Option<String>,
     pub id: String,
     #[serde(deserialize_with = "empty_as_default", default)]
     pub image: Option<Ref>,
@@ -224,16 +267,32 @@ pub struct Server {
     pub instance_name: Option<String>,
     #[serde(rename = "key_name", deserialize_with = "empty_as_default", default)]
     pub key_pair_name: Option<String>,
+    #[serde(rename = "OS-EXT-SRV-ATTR:launch_index", default)]
+    pub launch_index: Option<u32>,
+    #[serde(default)]
+    pub locked: Option<bool>,
+    #[serde(default)]
+    pub locked_reason: Option<String>,
     pub name: String,
     #[serde(default)]
     pub metadata: HashMap<String, String>,
     pub status: ServerStatus,
     #[serde(rename = "OS-EXT-STS:power_state", default)]
     pub power_state: ServerPowerState,
-    // pub tenant_id: String,
+    #[serde(rename = "OS-EXT-SRV-ATTR:reservation_id", default)]
+    pub reservation_id: Option<String>,
+    #[serde(rename = "OS-EXT-STS:task_state", default)]
+    pub task_state: Option<String>,
+    #[serde(rename = "tenant_id", default)]
+    pub project_id: String,
     #[serde(rename = "updated")]
     pub updated_at: DateTime<FixedOffset>,
-    // pub user_id: String,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(rename = "OS-EXT-STS:vm_state", default)]
+    pub vm_state: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -254,13 +313,47 @@ pub struct ServerRoot {
 #[derive(Clone, Debug, Serialize)]
 #[serde(untagged)]
 pub enum ServerNetwork {
-    Network { uuid: String },
-    Port { port: String },
-    FixedIp { fixed_ip: Ipv4Addr },
+    Network {
+        uuid: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+    },
+    Port {
+        port: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+    },
+    FixedIp {
+        fixed_ip: Ipv4Addr,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+    },
+}
+
+impl ServerNetwork {
+    #[inline]
+    pub fn tag(&self) -> Option<&String> {
+        match self {
+            ServerNetwork::Network { tag, .. }
+            | ServerNetwork::Port { tag, .. }
+            | ServerNetwork::FixedIp { tag, .. } => tag.as_ref(),
+        }
+    }
+
+    #[inline]
+    pub fn clear_tag(&mut self) {
+        match self {
+            ServerNetwork::Network { tag, .. }
+            | ServerNetwork::Port { tag, .. }
+            | ServerNetwork::FixedIp { tag, .. } => *tag = None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct ServerCreate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adminPass: Option<String>,
     #[serde(
         rename = "block_device_mapping_v2",
         skip_serializing_if = "Vec::is_empty"
@@ -271,8 +364,12 @@ pub struct ServerCreate {
         serialize_with = "config_drive_string_to_bool"
     )]
     pub config_drive: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     pub flavorRef: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub imageRef: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_name: Option<String>,
@@ -280,6 +377,8 @@ pub struct ServerCreate {
     pub metadata: HashMap<String, String>,
     pub name: String,
     pub networks: Vec<ServerNetwork>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_image_certificates: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_data: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -289,6 +388,17 @@ pub struct ServerCreate {
 #[derive(Clone, Debug, Serialize)]
 pub struct ServerCreateRoot {
     pub server: ServerCreate,
+    #[serde(
+        rename = "os:scheduler_hints",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub scheduler_hints: HashMap<String, Value>,
+}
+
+#[cfg(feature = "image")]
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreatedImage {
+    pub image_id: String,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -317,6 +427,33 @@ pub struct Flavor {
     #[serde(deserialize_with = "empty_as_default")]
     pub swap: u64,
     pub vcpus: u32,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Access of a project to a private flavor.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct FlavorAccess {
+    /// Flavor ID.
+    pub flavor_id: String,
+    /// ID of the project that has access to the flavor.
+    #[serde(rename = "tenant_id")]
+    pub project_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FlavorAccessRoot {
+    pub flavor_access: Vec<FlavorAccess>,
+}
+
+/// An action on a flavor.
+#[derive(Clone, Debug, Serialize)]
+pub enum FlavorAction {
+    #[serde(rename = "addTenantAccess")]
+    AddTenantAccess { tenant: String },
+    #[serde(rename = "removeTenantAccess")]
+    RemoveTenantAccess { tenant: String },
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -343,6 +480,8 @@ pub struct KeyPair {
     #[serde(default, skip_serializing)]
     pub private_key: Option<String>,
     pub public_key: String,
+    #[serde(default)]
+    pub user_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -379,18 +518,6 @@ pub struct KeyPairsRoot {
     pub keypairs: Vec<KeyPairRoot>,
 }
 
-impl Default for ServerStatus {
-    fn default() -> ServerStatus {
-        ServerStatus::Unknown
-    }
-}
-
-impl Default for ServerPowerState {
-    fn default() -> ServerPowerState {
-        ServerPowerState::NoState
-    }
-}
-
 #[inline]
 fn default_flavor_is_public() -> bool {
     true
@@ -401,3 +528,543 @@ pub struct GetConsoleOutput {
     /// Output as a string.
     pub output: String,
 }
+
+/// CPU statistics of a server.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct CpuDiagnostics {
+    /// CPU index.
+    pub id: Option<u32>,
+    /// CPU time in nanoseconds.
+    pub time: Option<u64>,
+    /// CPU utilisation in percents.
+    pub utilisation: Option<u32>,
+}
+
+/// Disk statistics of a server.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct DiskDiagnostics {
+    /// Number of disk errors.
+    pub errors_count: Option<u64>,
+    /// Number of bytes read.
+    pub read_bytes: Option<u64>,
+    /// Number of read requests.
+    pub read_requests: Option<u64>,
+    /// Number of bytes written.
+    pub write_bytes: Option<u64>,
+    /// Number of write requests.
+    pub write_requests: Option<u64>,
+}
+
+/// Memory statistics of a server.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[non_exhaustive]
+pub struct MemoryDiagnostics {
+    /// Amount of memory in MiB provisioned for the server.
+    pub maximum: Option<u64>,
+    /// Amount of memory in MiB used by the server.
+    pub used: Option<u64>,
+}
+
+/// Network interface statistics of a server.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct NicDiagnostics {
+    /// MAC address of the interface.
+    pub mac_address: Option<String>,
+    /// Number of received packets dropped.
+    pub rx_drop: Option<u64>,
+    /// Number of receive errors.
+    pub rx_errors: Option<u64>,
+    /// Number of received bytes.
+    pub rx_octets: Option<u64>,
+    /// Number of received packets.
+    pub rx_packets: Option<u64>,
+    /// Receive rate in bytes.
+    pub rx_rate: Option<u64>,
+    /// Number of transmitted packets dropped.
+    pub tx_drop: Option<u64>,
+    /// Number of transmit errors.
+    pub tx_errors: Option<u64>,
+    /// Number of transmitted bytes.
+    pub tx_octets: Option<u64>,
+    /// Number of transmitted packets.
+    pub tx_packets: Option<u64>,
+    /// Transmit rate in bytes.
+    pub tx_rate: Option<u64>,
+}
+
+/// Standardized server diagnostics (compute API 2.48 and newer).
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct DetailedDiagnostics {
+    /// Whether the server has a config drive.
+    #[serde(default)]
+    pub config_drive: bool,
+    /// Per-CPU statistics.
+    #[serde(default)]
+    pub cpu_details: Vec<CpuDiagnostics>,
+    /// Per-disk statistics.
+    #[serde(default)]
+    pub disk_details: Vec<DiskDiagnostics>,
+    /// Name of the compute driver.
+    pub driver: String,
+    /// Hypervisor type.
+    #[serde(default)]
+    pub hypervisor: Option<String>,
+    /// Operating system of the hypervisor.
+    #[serde(default)]
+    pub hypervisor_os: Option<String>,
+    /// Memory statistics.
+    #[serde(default)]
+    pub memory_details: MemoryDiagnostics,
+    /// Per-interface statistics.
+    #[serde(default)]
+    pub nic_details: Vec<NicDiagnostics>,
+    /// Number of virtual CPUs.
+    #[serde(default)]
+    pub num_cpus: Option<u32>,
+    /// Number of disks.
+    #[serde(default)]
+    pub num_disks: Option<u32>,
+    /// Number of network interfaces.
+    #[serde(default)]
+    pub num_nics: Option<u32>,
+    /// Power state of the server as reported by the driver.
+    pub state: String,
+    /// Uptime of the server in seconds.
+    #[serde(default)]
+    pub uptime: Option<u64>,
+}
+
+/// A NUMA node of a server.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct ServerNumaNode {
+    /// Mapping of guest CPUs to host CPUs (administrators only).
+    #[serde(default, deserialize_with = "empty_as_default")]
+    pub cpu_pinning: HashMap<u32, u32>,
+    /// Host NUMA node the node is placed on (administrators only).
+    #[serde(default)]
+    pub host_node: Option<u32>,
+    /// Amount of memory in MiB.
+    pub memory_mb: u64,
+    /// Thread siblings among the guest CPUs.
+    #[serde(default, deserialize_with = "empty_as_default")]
+    pub siblings: Vec<Vec<u32>>,
+    /// Guest CPUs of the node.
+    #[serde(default, deserialize_with = "empty_as_default")]
+    pub vcpu_set: Vec<u32>,
+}
+
+/// NUMA topology of a server (compute API 2.78 and newer).
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct ServerTopology {
+    /// NUMA nodes of the server.
+    #[serde(default)]
+    pub nodes: Vec<ServerNumaNode>,
+    /// Size of memory pages in KiB (if huge pages are used).
+    #[serde(default)]
+    pub pagesize_kb: Option<u64>,
+}
+
+/// A security group applied to a server.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct ServerSecurityGroup {
+    /// Security group description.
+    #[serde(default, deserialize_with = "empty_as_default")]
+    pub description: Option<String>,
+    /// Security group ID.
+    pub id: String,
+    /// Security group name.
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerSecurityGroupsRoot {
+    pub security_groups: Vec<ServerSecurityGroup>,
+}
+
+/// An event that is part of a server action.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct InstanceActionEvent {
+    /// Event name, e.g. `compute_reboot_instance`.
+    pub event: String,
+    /// Event result (`Success` or `Error`), `None` while the event is running.
+    #[serde(default)]
+    pub result: Option<String>,
+    /// Traceback of a failed event (usually only visible to administrators).
+    #[serde(default)]
+    pub traceback: Option<String>,
+}
+
+impl InstanceActionEvent {
+    /// Whether the event has failed.
+    pub fn is_error(&self) -> bool {
+        self.result.as_deref() == Some("Error")
+    }
+}
+
+/// A record of an action performed on a server.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct InstanceAction {
+    /// Action name, e.g. `reboot`.
+    pub action: String,
+    /// Events of the action, `None` if they are not visible to the caller.
+    ///
+    /// Events are visible to non-administrators since compute API 2.51.
+    #[serde(default)]
+    pub events: Option<Vec<InstanceActionEvent>>,
+    /// Error message if the action has failed.
+    #[serde(default)]
+    pub message: Option<String>,
+    /// ID of the request that started the action.
+    pub request_id: String,
+    /// When the action was started.
+    #[serde(deserialize_with = "deserialize_optional_openstack_datetime", default)]
+    pub start_time: Option<DateTime<FixedOffset>>,
+    /// When the action was last updated (compute API 2.58).
+    #[serde(deserialize_with = "deserialize_optional_openstack_datetime", default)]
+    pub updated_at: Option<DateTime<FixedOffset>>,
+}
+
+impl InstanceAction {
+    /// Whether the action has finished.
+    ///
+    /// An action with events is finished when all of its events have a
+    /// result. Some actions (e.g. `lock`) never have events, such an action
+    /// is considered finished once it has an update time. Always `false` if
+    /// events are not visible.
+    pub fn is_finished(&self) -> bool {
+        match self.events {
+            Some(ref events) if events.is_empty() => self.updated_at.is_some(),
+            Some(ref events) => events.iter().all(|event| event.result.is_some()),
+            None => false,
+        }
+    }
+
+    /// The first failed event, if any.
+    pub fn failed_event(&self) -> Option<&InstanceActionEvent> {
+        self.events
+            .as_ref()
+            .and_then(|events| events.iter().find(|event| event.is_error()))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InstanceActionRoot {
+    #[serde(rename = "instanceAction")]
+    pub instance_action: InstanceAction,
+}
+
+/// Diagnostics of a server.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ServerDiagnostics {
+    /// Standardized diagnostics (compute API 2.48 and newer).
+    Detailed(DetailedDiagnostics),
+    /// Driver-specific diagnostics returned by older compute API versions.
+    Legacy(HashMap<String, Value>),
+}
+
+protocol_enum! {
+    #[doc = "Name of an external server event."]
+    enum ServerExternalEventName: Unknown(String) {
+        #[doc = "An accelerator request has been bound (compute API 2.82)."]
+        AcceleratorRequestBound = "accelerator-request-bound",
+        #[doc = "Network information of the server has changed."]
+        NetworkChanged = "network-changed",
+        #[doc = "A virtual interface has been deleted."]
+        NetworkVifDeleted = "network-vif-deleted",
+        #[doc = "A virtual interface has been plugged."]
+        NetworkVifPlugged = "network-vif-plugged",
+        #[doc = "A virtual interface has been unplugged."]
+        NetworkVifUnplugged = "network-vif-unplugged",
+        #[doc = "The power state of a bare metal server has changed (compute API 2.76)."]
+        PowerUpdate = "power-update",
+        #[doc = "An attached volume has been extended (compute API 2.51)."]
+        VolumeExtended = "volume-extended",
+        #[doc = "A volume has been re-imaged (compute API 2.93)."]
+        VolumeReimaged = "volume-reimaged"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Status of an external server event."]
+    enum ServerExternalEventStatus: Unknown(String) {
+        Completed = "completed",
+        Failed = "failed",
+        InProgress = "in-progress"
+    }
+}
+
+/// An event sent to a server by an external service.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ServerExternalEvent {
+    /// HTTP status code of processing the event, only set in responses.
+    #[serde(default, skip_serializing)]
+    pub code: Option<u16>,
+    /// Event name.
+    pub name: ServerExternalEventName,
+    /// ID of the server.
+    #[serde(rename = "server_uuid")]
+    pub server_id: String,
+    /// Event status, `completed` if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ServerExternalEventStatus>,
+    /// Event tag, e.g. the port ID for network events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl ServerExternalEvent {
+    /// Create a new event for the given server.
+    pub fn new<S: Into<String>>(name: ServerExternalEventName, server_id: S) -> Self {
+        ServerExternalEvent {
+            code: None,
+            name,
+            server_id: server_id.into(),
+            status: None,
+            tag: None,
+        }
+    }
+
+    /// Set the event status.
+    #[inline]
+    pub fn with_status(mut self, status: ServerExternalEventStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Set the event tag.
+    #[inline]
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Whether the event was accepted by the Compute service.
+    pub fn is_accepted(&self) -> bool {
+        self.code == Some(200)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerExternalEventsRoot {
+    pub events: Vec<ServerExternalEvent>,
+}
+
+/// A volume attached to a server.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct VolumeAttachment {
+    /// Whether the volume is deleted together with the server.
+    ///
+    /// Only available since compute API 2.79.
+    #[serde(default)]
+    pub delete_on_termination: Option<bool>,
+    /// Device name inside the server, e.g. `/dev/vdb`.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// ID of the server.
+    #[serde(rename = "serverId")]
+    pub server_id: String,
+    /// Device tag, if any.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// ID of the attached volume.
+    #[serde(rename = "volumeId")]
+    pub volume_id: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VolumeAttachmentsRoot {
+    #[serde(rename = "volumeAttachments")]
+    pub volume_attachments: Vec<VolumeAttachment>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeAttachmentUpdate {
+    #[serde(rename = "volumeId")]
+    pub volume_id: String,
+    pub delete_on_termination: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeAttachmentUpdateRoot {
+    #[serde(rename = "volumeAttachment")]
+    pub volume_attachment: VolumeAttachmentUpdate,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AbsoluteLimits {
+    #[serde(rename = "maxTotalCores")]
+    pub max_cores: i64,
+    #[serde(rename = "maxTotalInstances")]
+    pub max_instances: i64,
+    #[serde(rename = "maxTotalRAMSize")]
+    pub max_ram: i64,
+    #[serde(rename = "totalCoresUsed", default)]
+    pub cores_used: i64,
+    #[serde(rename = "totalInstancesUsed", default)]
+    pub instances_used: i64,
+    #[serde(rename = "totalRAMUsed", default)]
+    pub ram_used: i64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Limits {
+    pub absolute: AbsoluteLimits,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LimitsRoot {
+    pub limits: Limits,
+}
+
+/// A problem found when checking a server creation request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ServerPrecheckIssue {
+    /// The server name is too long.
+    NameTooLong {
+        /// Length of the name in characters.
+        length: usize,
+        /// Maximum allowed length.
+        max_length: usize,
+    },
+    /// The requested flavor does not exist.
+    FlavorNotFound(String),
+    /// The requested image does not exist.
+    ImageNotFound(String),
+    /// The requested key pair does not exist.
+    KeyPairNotFound(String),
+    /// The requested network does not exist.
+    NetworkNotFound(String),
+    /// The requested port does not exist.
+    PortNotFound(String),
+    /// Creating the server would exceed the quota.
+    QuotaExceeded {
+        /// Resource that is exhausted.
+        resource: QuotaResource,
+        /// Amount requested by the new server.
+        requested: u64,
+        /// Amount still available in the quota.
+        available: u64,
+    },
+}
+
+impl fmt::Display for ServerPrecheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerPrecheckIssue::NameTooLong { length, max_length } => write!(
+                f,
+                "server name is {length} characters long, the maximum is {max_length}"
+            ),
+            ServerPrecheckIssue::FlavorNotFound(flavor) => write!(f, "flavor {flavor} not found"),
+            ServerPrecheckIssue::ImageNotFound(image) => write!(f, "image {image} not found"),
+            ServerPrecheckIssue::KeyPairNotFound(keypair) => {
+                write!(f, "key pair {keypair} not found")
+            }
+            ServerPrecheckIssue::NetworkNotFound(network) => {
+                write!(f, "network {network} not found")
+            }
+            ServerPrecheckIssue::PortNotFound(port) => write!(f, "port {port} not found"),
+            ServerPrecheckIssue::QuotaExceeded {
+                resource,
+                requested,
+                available,
+            } => write!(
+                f,
+                "{requested} {resource} requested, but only {available} left in the quota"
+            ),
+        }
+    }
+}
+
+/// Result of checking a server creation request.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct ServerPrecheckReport {
+    /// Problems that will prevent the server from being created.
+    pub issues: Vec<ServerPrecheckIssue>,
+}
+
+impl ServerPrecheckReport {
+    /// Whether no problems were found.
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn action(events: serde_json::Value, updated_at: serde_json::Value) -> InstanceAction {
+        serde_json::from_value(serde_json::json!({
+            "action": "reboot",
+            "events": events,
+            "message": null,
+            "request_id": "req-1",
+            "start_time": "2018-04-25T01:26:29.000000",
+            "updated_at": updated_at,
+        }))
+        .expect("Could not parse this JSON")
+    }
+
+    #[test]
+    fn test_instance_action_with_events() {
+        let running = action(
+            serde_json::json!([
+                {"event": "compute_stop_instance", "result": "Success"},
+                {"event": "compute_start_instance", "result": null},
+            ]),
+            serde_json::Value::Null,
+        );
+        assert!(!running.is_finished());
+        assert!(running.failed_event().is_none());
+
+        let finished = action(
+            serde_json::json!([
+                {"event": "compute_stop_instance", "result": "Success"},
+                {"event": "compute_start_instance", "result": "Error"},
+            ]),
+            serde_json::json!("2018-04-25T01:26:36.000000"),
+        );
+        assert!(finished.is_finished());
+        assert_eq!(
+            finished.failed_event().expect("No failed event").event,
+            "compute_start_instance"
+        );
+    }
+
+    #[test]
+    fn test_instance_action_without_events() {
+        let running = action(serde_json::json!([]), serde_json::Value::Null);
+        assert!(!running.is_finished());
+
+        let finished = action(
+            serde_json::json!([]),
+            serde_json::json!("2018-04-25T01:26:36.000000"),
+        );
+        assert!(finished.is_finished());
+        assert_eq!(
+            finished.updated_at.expect("No updated_at").to_rfc3339(),
+            "2018-04-25T01:26:36+00:00"
+        );
+
+        let hidden = action(
+            serde_json::Value::Null,
+            serde_json::json!("2018-04-25T01:26:36.000000"),
+        );
+        assert!(!hidden.is_finished());
+    }
+}
@@ -15,24 +15,34 @@
 //! Server management via Compute API.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use chrono::{DateTime, FixedOffset};
-use futures::stream::{Stream, TryStreamExt};
+use chrono::{DateTime, FixedOffset, TimeZone};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use futures::Future;
 use osauth::common::IdAndName;
+use reqwest::Url;
 use serde::Serialize;
+use serde_json::Value;
 
 use super::super::common::{
-    FlavorRef, ImageRef, KeyPairRef, NetworkRef, PortRef, ProjectRef, Refresh, ResourceIterator,
-    ResourceQuery, UserRef, VolumeRef,
+    ApiVersion, FlavorRef, ImageRef, KeyPairRef, NetworkRef, PortRef, ProjectRef, Refresh,
+    ResourceIterator, ResourceQuery, ServerRef, UserRef, ValidationReport, VolumeRef,
 };
 #[cfg(feature = "image")]
-use super::super::image::Image;
+use super::super::image::{Image, ImageWaiter};
+#[cfg(feature = "network")]
+use super::super::network::{Port, PortQuery};
+use super::super::ratelimit::RateLimiter;
 use super::super::session::Session;
-use super::super::utils::{unit_to_null, Query};
-use super::super::waiter::{DeletionWaiter, Waiter};
+use super::super::utils::{retry_on_auth_failure, unit_to_null, url, NamePattern, Query};
+use super::super::waiter::{
+    refresh_with_reauth, watch_status, DeletionWaiter, StatusChange, Waiter,
+};
 use super::super::{Error, ErrorKind, Result, Sort};
 use super::{api, protocol, BlockDevice, KeyPair};
 
@@ -42,16 +52,25 @@ pub struct ServerQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    project: Option<ProjectRef>,
+    user: Option<UserRef>,
+    name_pattern: Option<NamePattern>,
+    api_version: Option<ApiVersion>,
 }
 
 /// A detailed query to server list.
 ///
 /// Is constructed from a `ServerQuery`.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct DetailedServerQuery {
     inner: ServerQuery,
+    metadata: Vec<(String, String)>,
+    filters: Vec<ServerFilter>,
 }
 
+type ServerFilter = Arc<dyn Fn(&Server) -> bool + Send + Sync>;
+
 /// Structure representing a single server.
 #[derive(Clone, Debug)]
 pub struct Server {
@@ -70,7 +89,14 @@ pub struct ServerSummary {
 #[derive(Debug)]
 pub struct ServerStatusWaiter<'server> {
     server: &'server mut Server,
-    target: protocol::ServerStatus,
+    targets: Vec<protocol::ServerStatus>,
+}
+
+/// Waiter for a server action to finish.
+#[derive(Debug)]
+struct ServerActionWaiter<'server> {
+    server: &'server Server,
+    request_id: String,
 }
 
 /// A virtual NIC of a new server.
@@ -94,12 +120,34 @@ pub struct NewServer {
     metadata: HashMap<String, String>,
     name: String,
     nics: Vec<ServerNIC>,
+    nic_tags: HashMap<usize, String>,
     block_devices: Vec<BlockDevice>,
     user_data: Option<String>,
     config_drive: Option<bool>,
     availability_zone: Option<String>,
+    admin_pass: Option<String>,
+    description: Option<String>,
+    hostname: Option<String>,
+    scheduler_hints: HashMap<String, Value>,
+    strict_api_version: bool,
+    trusted_image_certificates: Vec<String>,
+}
+
+/// Options for bulk operations on servers matching a query.
+#[derive(Clone, Copy, Debug)]
+pub struct BulkOptions {
+    concurrency: usize,
+    dry_run: bool,
+    rate_limit: Option<u32>,
 }
 
+/// Waiter for a server image (snapshot) to become active.
+///
+/// The same waiter is used for all images being created, see
+/// [ImageWaiter](../image/struct.ImageWaiter.html).
+#[cfg(feature = "image")]
+pub type ServerImageWaiter = ImageWaiter;
+
 /// Waiter for server to be created.
 #[derive(Debug)]
 pub struct ServerCreationWaiter {
@@ -113,6 +161,10 @@ impl Refresh for Server {
         self.inner = api::get_server_by_id(&self.session, &self.inner.id).await?;
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl Server {
@@ -121,6 +173,12 @@ impl Server {
         Ok(Server { session, inner })
     }
 
+    /// Create a Server object from its JSON representation.
+    #[cfg(test)]
+    pub(crate) fn from_json(session: Session, value: Value) -> Server {
+        Server::new(session, serde_json::from_value(value).unwrap()).unwrap()
+    }
+
     /// Load a Server object.
     pub(crate) async fn load<Id: AsRef<str>>(session: Session, id: Id) -> Result<Server> {
         let inner = api::get_server(&session, id).await?;
@@ -204,6 +262,11 @@ impl Server {
             .next()
     }
 
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "Whether the server was created with a config drive."]
         has_config_drive: bool
@@ -222,6 +285,15 @@ impl Server {
         id: ref String
     }
 
+    /// Whether the server boots from a volume rather than from an image.
+    ///
+    /// Snapshots of such servers are volume snapshots, see
+    /// [create_image](#method.create_image).
+    #[inline]
+    pub fn is_volume_backed(&self) -> bool {
+        self.inner.image.is_none()
+    }
+
     /// Fetch the associated image.
     ///
     /// Fails with `ResourceNotFound` if the server does not have an image.
@@ -247,10 +319,30 @@ impl Server {
     }
 
     transparent_property! {
-        #[doc = "Instance name."]
+        #[doc = "Name of the compute host running the server (administrators only)."]
+        host: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Host name of the server inside the guest (administrators only)."]
+        hostname: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Host name of the hypervisor running the server (administrators only)."]
+        hypervisor_hostname: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Instance name (administrators only)."]
         instance_name: ref Option<String>
     }
 
+    transparent_property! {
+        #[doc = "Index of the server when several were created in one request (administrators only)."]
+        launch_index: Option<u32>
+    }
+
     /// Fetch the key pair used for the server.
     pub async fn key_pair(&self) -> Result<KeyPair> {
         match self.inner.key_pair_name {
@@ -267,6 +359,18 @@ impl Server {
         key_pair_name: ref Option<String>
     }
 
+    transparent_property! {
+        #[doc = "Whether the server is locked (requires compute API 2.9)."]
+        locked: Option<bool>
+    }
+
+    transparent_property! {
+        #[doc = "Reason the server was locked for."]
+        #[doc = ""]
+        #[doc = "Only available for servers listed with the lock state filter."]
+        locked_reason: ref Option<String>
+    }
+
     transparent_property! {
         #[doc = "Server name."]
         name: ref String
@@ -282,21 +386,169 @@ impl Server {
         power_state: protocol::ServerPowerState
     }
 
+    /// List Networking ports attached to the server.
+    #[cfg(feature = "network")]
+    pub async fn ports(&self) -> Result<Vec<Port>> {
+        PortQuery::new(self.session.clone())
+            .with_device_id(self.inner.id.clone())
+            .all()
+            .await
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project owning the server."]
+        project_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the request that created the server (administrators only)."]
+        reservation_id: ref Option<String>
+    }
+
     transparent_property! {
         #[doc = "Server status."]
         status: protocol::ServerStatus
     }
 
+    transparent_property! {
+        #[doc = "Task currently running on the server, if any."]
+        task_state: ref Option<String>
+    }
+
     transparent_property! {
         #[doc = "Last update date and time."]
         updated_at: DateTime<FixedOffset>
     }
 
+    transparent_property! {
+        #[doc = "ID of the user who created the server."]
+        user_id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Internal state of the server in the Compute service."]
+        vm_state: ref Option<String>
+    }
+
     /// Run an action on the server.
     pub async fn action(&mut self, action: ServerAction) -> Result<()> {
+        let _ = api::server_action(&self.session, &self.inner.id, action).await?;
+        Ok(())
+    }
+
+    /// Run an action on the server and return its request ID.
+    ///
+    /// The request ID is `None` if the cloud does not report it. Pass it
+    /// to [wait_for_action](#method.wait_for_action) to track actions that
+    /// do not change the server status.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use openstack::compute::ServerAction;
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let mut server = os.get_server("test").await?;
+    /// let request_id = server
+    ///     .action_with_request_id(ServerAction::AddSecurityGroup {
+    ///         name: "web".into(),
+    ///     })
+    ///     .await?
+    ///     .expect("No request ID returned");
+    /// let action = server
+    ///     .wait_for_action(request_id, Duration::from_secs(60))
+    ///     .await?;
+    /// println!("Action {} finished", action.action);
+    /// # Ok(()) }
+    /// ```
+    pub async fn action_with_request_id(&mut self, action: ServerAction) -> Result<Option<String>> {
         api::server_action(&self.session, &self.inner.id, action).await
     }
 
+    /// Add a fixed IP address from the given network to the server.
+    ///
+    /// Uses the compute proxy API that was removed in compute API 2.44, see
+    /// `supports_fixed_ip_actions`. Prefer attaching ports via the Network
+    /// API on modern clouds.
+    pub async fn add_fixed_ip<N: Into<NetworkRef>>(&mut self, network: N) -> Result<()> {
+        let network = network.into().into_verified(&self.session).await?;
+        api::server_legacy_action(
+            &self.session,
+            &self.inner.id,
+            ServerAction::AddFixedIp {
+                network_id: network.into(),
+            },
+        )
+        .await
+    }
+
+    /// Add a security group to the server.
+    ///
+    /// The group can be identified by its name or ID.
+    pub async fn add_security_group<S: Into<String>>(&mut self, name: S) -> Result<()> {
+        self.action(ServerAction::AddSecurityGroup { name: name.into() })
+            .await?;
+        Ok(())
+    }
+
+    /// Create an image (snapshot) of the server.
+    ///
+    /// The ID of the new image is available from the returned waiter right
+    /// away, use the waiter to wait for the image to become active.
+    ///
+    /// For a [volume-backed](#method.is_volume_backed) server the Compute
+    /// service quiesces the guest (if possible), snapshots its volumes and
+    /// creates an image without data that refers to these snapshots. Use
+    /// [Image::is_volume_snapshot](crate::image::Image::is_volume_snapshot)
+    /// to tell the two kinds of snapshots apart.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::collections::HashMap;
+    ///
+    /// use openstack::waiter::Waiter;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// let server = os.get_server("golden").await.expect("Unable to get a server");
+    /// let waiter = server
+    ///     .create_image("golden-image", HashMap::new())
+    ///     .await
+    ///     .expect("Unable to request an image");
+    /// println!("Creating image {}", waiter.image_id());
+    /// let image = waiter.wait().await.expect("Image creation failed");
+    /// # }
+    /// ```
+    #[cfg(feature = "image")]
+    pub async fn create_image<S: Into<String>>(
+        &self,
+        name: S,
+        metadata: HashMap<String, String>,
+    ) -> Result<ServerImageWaiter> {
+        let action = ServerAction::CreateImage {
+            name: name.into(),
+            metadata: if metadata.is_empty() {
+                None
+            } else {
+                Some(metadata)
+            },
+        };
+        let image_id = api::create_server_image(&self.session, &self.inner.id, action).await?;
+        Ok(ImageWaiter::new(self.session.clone(), image_id))
+    }
+
+    /// Link to the server in the dashboard (Horizon) at `base`.
+    ///
+    /// See [Cloud::dashboard_url](../struct.Cloud.html#method.dashboard_url)
+    /// for where to get the base URL from.
+    pub fn dashboard_url(&self, base: &Url) -> Result<Url> {
+        url::dashboard(base, ["project", "instances", &self.inner.id, ""])
+    }
+
     /// Delete the server.
     pub async fn delete(self) -> Result<DeletionWaiter<Server>> {
         api::delete_server(&self.session, &self.inner.id).await?;
@@ -307,6 +559,117 @@ impl Server {
         ))
     }
 
+    /// Delete the server, unlocking it if it is locked.
+    ///
+    /// The server is only unlocked if the deletion is rejected because of the
+    /// lock and `allow_unlock` is true, after which the deletion is retried.
+    /// Unlocking a server locked by an administrator requires administrator
+    /// privileges. If `allow_unlock` is false, a locked server results in
+    /// a `Conflict` error that includes the lock reason (if known).
+    pub async fn delete_force_unlock(
+        mut self,
+        allow_unlock: bool,
+    ) -> Result<DeletionWaiter<Server>> {
+        let err = match api::delete_server(&self.session, &self.inner.id).await {
+            Ok(()) => {
+                return Ok(DeletionWaiter::new(
+                    self,
+                    Duration::new(120, 0),
+                    Duration::new(1, 0),
+                ))
+            }
+            Err(err) if err.kind() == ErrorKind::Conflict => err,
+            Err(err) => return Err(err),
+        };
+
+        self.refresh().await?;
+        // The locked flag is only returned since compute API 2.9.
+        let is_locked = self
+            .inner
+            .locked
+            .unwrap_or_else(|| err.to_string().contains("locked"));
+        if !is_locked {
+            return Err(err);
+        }
+
+        let reason = self
+            .inner
+            .locked_reason
+            .as_ref()
+            .map(|reason| format!(" ({reason})"))
+            .unwrap_or_default();
+        if !allow_unlock {
+            return Err(Error::new(
+                ErrorKind::Conflict,
+                format!(
+                    "Server {} is locked{} and unlocking is not allowed",
+                    self.inner.id, reason
+                ),
+            ));
+        }
+
+        info!("Unlocking server {}{} to delete it", self.inner.id, reason);
+        self.unlock().await.map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Cannot unlock server {}: {}", self.inner.id, err),
+            )
+        })?;
+        self.delete().await
+    }
+
+    /// Watch the server status by polling it with the given interval.
+    ///
+    /// Unlike waiters, the stream does not stop at any particular status:
+    /// it yields every observed transition until the server is deleted.
+    /// Polling errors are yielded as well.
+    pub fn watch_status(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<StatusChange<protocol::ServerStatus>>> {
+        watch_status(self.clone(), interval, |server| server.status())
+    }
+
+    /// Get diagnostics of the server.
+    ///
+    /// Standardized diagnostics are returned if the cloud supports compute
+    /// API 2.48, otherwise the driver-specific legacy format is returned.
+    /// Usually requires administrator privileges.
+    pub async fn diagnostics(&self) -> Result<protocol::ServerDiagnostics> {
+        api::get_server_diagnostics(&self.session, &self.inner.id).await
+    }
+
+    /// Get NUMA topology of the server.
+    ///
+    /// Requires compute API 2.78. CPU pinning and host nodes are only
+    /// returned to administrators.
+    pub async fn topology(&self) -> Result<protocol::ServerTopology> {
+        api::get_server_topology(&self.session, &self.inner.id).await
+    }
+
+    /// List volumes attached to the server.
+    ///
+    /// The `delete_on_termination` flag is only reported with compute API 2.79 or newer.
+    pub async fn volume_attachments(&self) -> Result<Vec<protocol::VolumeAttachment>> {
+        api::list_volume_attachments(&self.session, &self.inner.id).await
+    }
+
+    /// Change whether an attached volume is deleted together with the server.
+    ///
+    /// Requires compute API 2.85 or newer.
+    pub async fn set_volume_delete_on_termination<V>(
+        &self,
+        volume: V,
+        delete_on_termination: bool,
+    ) -> Result<()>
+    where
+        V: Into<VolumeRef>,
+    {
+        let volume = volume.into().into_verified(&self.session).await?;
+        api::update_volume_attachment(&self.session, &self.inner.id, volume, delete_on_termination)
+            .await
+    }
+
     /// Get the console output as a string.
     ///
     /// Length is the number of lines to fetch from the end of console log.
@@ -318,34 +681,202 @@ impl Server {
         Ok(result.output)
     }
 
+    /// Lock the server, optionally providing a reason.
+    ///
+    /// A locked server cannot be changed by non-administrators. Providing
+    /// a reason requires compute API 2.73.
+    pub async fn lock(&mut self, reason: Option<String>) -> Result<()> {
+        let version = reason
+            .as_ref()
+            .map(|_| api::API_VERSION_SERVER_LOCKED_REASON);
+        let _ = api::server_action_with_version(
+            &self.session,
+            &self.inner.id,
+            ServerAction::Lock {
+                locked_reason: reason,
+            },
+            version,
+        )
+        .await?;
+        self.refresh().await
+    }
+
     /// Reboot the server.
     pub async fn reboot(
         &mut self,
         reboot_type: protocol::RebootType,
     ) -> Result<ServerStatusWaiter<'_>> {
-        let _ = self.action(ServerAction::Reboot { reboot_type }).await?;
-        Ok(ServerStatusWaiter {
-            server: self,
-            target: protocol::ServerStatus::Active,
+        self.action(ServerAction::Reboot { reboot_type }).await?;
+        Ok(ServerStatusWaiter::new(
+            self,
+            [protocol::ServerStatus::Active],
+        ))
+    }
+
+    /// Put the server into rescue mode, optionally wait for it to be rescued.
+    ///
+    /// The server is booted from the rescue image (or the original image if
+    /// `image` is `None`) with its original root disk attached as a secondary
+    /// disk. If `admin_pass` is `None`, the password is generated.
+    pub async fn rescue(
+        &mut self,
+        image: Option<ImageRef>,
+        admin_pass: Option<String>,
+    ) -> Result<ServerStatusWaiter<'_>> {
+        let rescue_image_ref = match image {
+            Some(image) => Some(image.into_verified(&self.session).await?.into()),
+            None => None,
+        };
+        self.action(ServerAction::Rescue {
+            admin_pass,
+            rescue_image_ref,
         })
+        .await?;
+        Ok(ServerStatusWaiter::new(
+            self,
+            [protocol::ServerStatus::Rescuing],
+        ))
+    }
+
+    /// Remove a fixed IP address from the server.
+    ///
+    /// Uses the compute proxy API that was removed in compute API 2.44, see
+    /// `supports_fixed_ip_actions`.
+    pub async fn remove_fixed_ip<A: Into<IpAddr>>(&mut self, address: A) -> Result<()> {
+        api::server_legacy_action(
+            &self.session,
+            &self.inner.id,
+            ServerAction::RemoveFixedIp {
+                address: address.into(),
+            },
+        )
+        .await
+    }
+
+    /// Remove a security group from the server.
+    pub async fn remove_security_group<S: Into<String>>(&mut self, name: S) -> Result<()> {
+        self.action(ServerAction::RemoveSecurityGroup { name: name.into() })
+            .await?;
+        Ok(())
+    }
+
+    /// List security groups applied to the server.
+    pub async fn security_groups(&self) -> Result<Vec<protocol::ServerSecurityGroup>> {
+        api::list_server_security_groups(&self.session, &self.inner.id).await
+    }
+
+    /// Whether the cloud still provides the fixed IP actions.
+    ///
+    /// They are only available when the compute service supports API versions
+    /// before 2.44.
+    pub async fn supports_fixed_ip_actions(&self) -> Result<bool> {
+        api::supports_legacy_actions(&self.session).await
+    }
+
+    /// Wait for the server to reach the given status.
+    ///
+    /// Fails if the server gets into the `ERROR` status (unless it is the
+    /// target) or the timeout expires.
+    pub async fn wait_for_status(
+        &mut self,
+        target: protocol::ServerStatus,
+        timeout: Duration,
+    ) -> Result<()> {
+        ServerStatusWaiter::new(self, [target])
+            .wait_for(timeout)
+            .await
+    }
+
+    /// Wait for the server to reach any of the given statuses.
+    ///
+    /// Returns the reached status. Fails if the server gets into the `ERROR`
+    /// status (unless it is one of the targets) or the timeout expires.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use openstack::compute::ServerStatus;
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let mut server = os.get_server("test").await?;
+    /// let status = server
+    ///     .wait_for_any_status(
+    ///         [ServerStatus::Active, ServerStatus::ShutOff],
+    ///         Duration::from_secs(600),
+    ///     )
+    ///     .await?;
+    /// println!("Server is now {}", status);
+    /// # Ok(()) }
+    /// ```
+    pub async fn wait_for_any_status<I>(
+        &mut self,
+        targets: I,
+        timeout: Duration,
+    ) -> Result<protocol::ServerStatus>
+    where
+        I: IntoIterator<Item = protocol::ServerStatus>,
+    {
+        ServerStatusWaiter::new(self, targets)
+            .wait_for(timeout)
+            .await?;
+        Ok(self.status())
+    }
+
+    /// Wait for an action with the given request ID to finish.
+    ///
+    /// The action is finished when all its events have a result or, if it
+    /// has no events, when the cloud has recorded its last update. Fails with
+    /// `ResourceNotFound` if the action is not known to the server, with
+    /// `OperationFailed` if any event has failed and with
+    /// `IncompatibleApiVersion` if action events are not visible (compute API
+    /// 2.51 or administrator privileges are required for that).
+    pub async fn wait_for_action<S: Into<String>>(
+        &self,
+        request_id: S,
+        timeout: Duration,
+    ) -> Result<protocol::InstanceAction> {
+        ServerActionWaiter {
+            server: self,
+            request_id: request_id.into(),
+        }
+        .wait_for(timeout)
+        .await
     }
 
     /// Start the server, optionally wait for it to be active.
     pub async fn start(&mut self) -> Result<ServerStatusWaiter<'_>> {
-        let _ = self.action(ServerAction::Start).await?;
-        Ok(ServerStatusWaiter {
-            server: self,
-            target: protocol::ServerStatus::Active,
-        })
+        self.action(ServerAction::Start).await?;
+        Ok(ServerStatusWaiter::new(
+            self,
+            [protocol::ServerStatus::Active],
+        ))
+    }
+
+    /// Unlock the server.
+    pub async fn unlock(&mut self) -> Result<()> {
+        self.action(ServerAction::Unlock).await?;
+        self.refresh().await
+    }
+
+    /// Leave rescue mode, optionally wait for the server to be active.
+    pub async fn unrescue(&mut self) -> Result<ServerStatusWaiter<'_>> {
+        self.action(ServerAction::Unrescue).await?;
+        Ok(ServerStatusWaiter::new(
+            self,
+            [protocol::ServerStatus::Active],
+        ))
     }
 
     /// Stop the server, optionally wait for it to be powered off.
     pub async fn stop(&mut self) -> Result<ServerStatusWaiter<'_>> {
-        let _ = self.action(ServerAction::Stop).await?;
-        Ok(ServerStatusWaiter {
-            server: self,
-            target: protocol::ServerStatus::ShutOff,
-        })
+        self.action(ServerAction::Stop).await?;
+        Ok(ServerStatusWaiter::new(
+            self,
+            [protocol::ServerStatus::ShutOff],
+        ))
     }
 }
 
@@ -353,6 +884,15 @@ impl Server {
 #[derive(Clone, Debug, Serialize)]
 #[non_exhaustive]
 pub enum ServerAction {
+    /// Adds a fixed IP address from the network to a server.
+    ///
+    /// Removed in compute API 2.44.
+    #[serde(rename = "addFixedIp")]
+    AddFixedIp {
+        /// The network to allocate the address from.
+        #[serde(rename = "networkId")]
+        network_id: String,
+    },
     /// Adds a security group to a server.
     #[serde(rename = "addSecurityGroup")]
     AddSecurityGroup {
@@ -401,6 +941,15 @@ pub enum ServerAction {
         #[serde(skip_serializing_if = "Option::is_none")]
         length: Option<u64>,
     },
+    /// Locks a server.
+    ///
+    /// Use `Server::lock` to provide a reason, it requires compute API 2.73.
+    #[serde(rename = "lock")]
+    Lock {
+        /// Reason for locking the server.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        locked_reason: Option<String>,
+    },
     /// Pauses a server. Changes its status to PAUSED.
     #[serde(rename = "pause", serialize_with = "unit_to_null")]
     Pause,
@@ -411,6 +960,14 @@ pub enum ServerAction {
         #[serde(rename = "type")]
         reboot_type: protocol::RebootType,
     },
+    /// Removes a fixed IP address from a server.
+    ///
+    /// Removed in compute API 2.44.
+    #[serde(rename = "removeFixedIp")]
+    RemoveFixedIp {
+        /// The IP address to remove.
+        address: IpAddr,
+    },
     /// Removes a security group from a server.
     #[serde(rename = "removeSecurityGroup")]
     RemoveSecurityGroup {
@@ -492,21 +1049,25 @@ impl<'server> Waiter<(), Error> for ServerStatusWaiter<'server> {
             format!(
                 "Timeout waiting for server {} to reach state {}",
                 self.server.id(),
-                self.target
+                self.targets_str()
             ),
         )
     }
 
     async fn poll(&mut self) -> Result<Option<()>> {
-        self.server.refresh().await?;
-        if self.server.status() == self.target {
-            debug!("Server {} reached state {}", self.server.id(), self.target);
+        refresh_with_reauth(self.server).await?;
+        if self.targets.contains(&self.server.status()) {
+            debug!(
+                "Server {} reached state {}",
+                self.server.id(),
+                self.server.status()
+            );
             Ok(Some(()))
         } else if self.server.status() == protocol::ServerStatus::Error {
             debug!(
                 "Failed to move server {} to {} - status is ERROR",
                 self.server.id(),
-                self.target
+                self.targets_str()
             );
             Err(Error::new(
                 ErrorKind::OperationFailed,
@@ -516,7 +1077,7 @@ impl<'server> Waiter<(), Error> for ServerStatusWaiter<'server> {
             trace!(
                 "Still waiting for server {} to get to state {}, current is {}",
                 self.server.id(),
-                self.target,
+                self.targets_str(),
                 self.server.status()
             );
             Ok(None)
@@ -524,7 +1085,118 @@ impl<'server> Waiter<(), Error> for ServerStatusWaiter<'server> {
     }
 }
 
+#[async_trait]
+impl<'server> Waiter<protocol::InstanceAction, Error> for ServerActionWaiter<'server> {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(Duration::new(600, 0))
+    }
+
+    fn default_delay(&self) -> Duration {
+        Duration::new(1, 0)
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(
+            ErrorKind::OperationTimedOut,
+            format!(
+                "Timeout waiting for action {} on server {} to finish",
+                self.request_id,
+                self.server.id()
+            ),
+        )
+    }
+
+    async fn poll(&mut self) -> Result<Option<protocol::InstanceAction>> {
+        let action = retry_on_auth_failure(&self.server.session, || {
+            api::get_instance_action(
+                &self.server.session,
+                &self.server.inner.id,
+                &self.request_id,
+            )
+        })
+        .await
+        .map_err(|err| {
+            if err.kind() == ErrorKind::ResourceNotFound {
+                Error::new(
+                    ErrorKind::ResourceNotFound,
+                    format!(
+                        "Action {} on server {} was not recorded",
+                        self.request_id,
+                        self.server.id()
+                    ),
+                )
+            } else {
+                err
+            }
+        })?;
+
+        if action.events.is_none() {
+            return Err(Error::new(
+                ErrorKind::IncompatibleApiVersion,
+                format!(
+                    "Events of action {} on server {} are not visible",
+                    self.request_id,
+                    self.server.id()
+                ),
+            ));
+        }
+
+        if let Some(event) = action.failed_event() {
+            debug!(
+                "Action {} ({}) on server {} failed in event {}",
+                action.action,
+                self.request_id,
+                self.server.id(),
+                event.event
+            );
+            let mut message = format!(
+                "Action {} on server {} failed in event {}",
+                action.action,
+                self.server.id(),
+                event.event
+            );
+            if let Some(ref details) = action.message {
+                message = format!("{message}: {details}");
+            }
+            Err(Error::new(ErrorKind::OperationFailed, message))
+        } else if action.is_finished() {
+            debug!(
+                "Action {} ({}) on server {} finished",
+                action.action,
+                self.request_id,
+                self.server.id()
+            );
+            Ok(Some(action))
+        } else {
+            trace!(
+                "Still waiting for action {} on server {} to finish",
+                self.request_id,
+                self.server.id()
+            );
+            Ok(None)
+        }
+    }
+}
+
 impl<'server> ServerStatusWaiter<'server> {
+    fn new<I>(server: &'server mut Server, targets: I) -> ServerStatusWaiter<'server>
+    where
+        I: IntoIterator<Item = protocol::ServerStatus>,
+    {
+        ServerStatusWaiter {
+            server,
+            targets: targets.into_iter().collect(),
+        }
+    }
+
+    fn targets_str(&self) -> String {
+        self.targets
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+
     /// Current state of the server.
     pub fn current_state(&self) -> &Server {
         self.server
@@ -548,9 +1220,27 @@ impl ServerSummary {
     }
 
     /// Delete the server.
-    pub async fn delete(self) -> Result<()> {
-        // TODO(dtantsur): implement wait
-        api::delete_server(&self.session, &self.inner.id).await
+    pub async fn delete(self) -> Result<DeletionWaiter<ServerSummary>> {
+        api::delete_server(&self.session, &self.inner.id).await?;
+        Ok(DeletionWaiter::new(
+            self,
+            Duration::new(120, 0),
+            Duration::new(1, 0),
+        ))
+    }
+}
+
+#[async_trait]
+impl Refresh for ServerSummary {
+    /// Refresh the server summary.
+    async fn refresh(&mut self) -> Result<()> {
+        let server = api::get_server_by_id(&self.session, &self.inner.id).await?;
+        self.inner.name = server.name;
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
     }
 }
 
@@ -560,7 +1250,48 @@ impl ServerQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            project: None,
+            user: None,
+            name_pattern: None,
+            api_version: None,
+        }
+    }
+
+    /// Make sure the cloud supports all requested filters.
+    ///
+    /// Unsupported filters are silently ignored by the Compute API.
+    async fn resolve_filters(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        if let Some(project) = self.project.take() {
+            let verified = project.into_filter(&self.session).await?;
+            self.query.push_str("project_id", verified);
+        }
+        if let Some(user) = self.user.take() {
+            let verified = user.into_filter(&self.session).await?;
+            self.query.push_str("user_id", verified);
+        }
+        if self.query.0.iter().any(|(key, _)| key == "changes-before") {
+            if !api::supports_server_changes_before_filter(&self.session).await? {
+                return Err(Error::new(
+                    ErrorKind::IncompatibleApiVersion,
+                    "Filtering servers by changes-before requires compute API 2.66",
+                ));
+            }
+            self.api_version = Some(api::API_VERSION_CHANGES_BEFORE);
+        }
+        if self.query.0.iter().any(|(key, _)| key == "locked") {
+            if !api::supports_server_locked_filter(&self.session).await? {
+                return Err(Error::new(
+                    ErrorKind::IncompatibleApiVersion,
+                    "Filtering servers by the lock state requires compute API 2.73",
+                ));
+            }
+            self.api_version = Some(api::API_VERSION_SERVER_LOCKED_REASON);
         }
+        Ok(())
     }
 
     /// Add marker to the request.
@@ -581,6 +1312,15 @@ impl ServerQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::ServerSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -595,6 +1335,54 @@ impl ServerQuery {
         self
     }
 
+    /// Only return servers changed before the given time.
+    ///
+    /// Deleted servers are included. Requires compute API 2.66.
+    pub fn set_changes_before<Tz>(&mut self, value: DateTime<Tz>)
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.query.push_str("changes-before", value.to_rfc3339());
+    }
+
+    /// Only return servers changed before the given time.
+    ///
+    /// Deleted servers are included. Requires compute API 2.66.
+    pub fn with_changes_before<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.set_changes_before(value);
+        self
+    }
+
+    /// Only return servers changed since the given time.
+    ///
+    /// Servers deleted since that time are included with the `Deleted` status,
+    /// which makes this filter suitable for incremental synchronization.
+    pub fn set_changes_since<Tz>(&mut self, value: DateTime<Tz>)
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.query.push_str("changes-since", value.to_rfc3339());
+    }
+
+    /// Only return servers changed since the given time.
+    ///
+    /// Servers deleted since that time are included with the `Deleted` status,
+    /// which makes this filter suitable for incremental synchronization.
+    pub fn with_changes_since<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.set_changes_since(value);
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by IPv4 address that should be used to access the server."]
         set_access_ip_v4, with_access_ip_v4 -> access_ip_v4: Ipv4Addr
@@ -615,6 +1403,11 @@ impl ServerQuery {
         set_flavor, with_flavor -> flavor: FlavorRef
     }
 
+    query_filter! {
+        #[doc = "Filter by the compute host running the server (administrators only)."]
+        set_host, with_host -> host: String
+    }
+
     query_filter! {
         #[doc = "Filter by host name."]
         set_hostname, with_hostname -> hostname: String
@@ -635,14 +1428,31 @@ impl ServerQuery {
         set_ip_v6, with_ip_v6 -> ip6: Ipv6Addr
     }
 
+    query_filter! {
+        #[doc = "Filter by the lock state (requires compute API 2.73)."]
+        set_locked, with_locked -> locked: bool
+    }
+
     query_filter! {
         #[doc = "Filter by name."]
         set_name, with_name -> name: String
     }
 
-    query_filter! {
-        #[doc = "Filter by project (also commonly known as tenant)."]
-        set_project, with_project -> project_id: ProjectRef
+    query_name_pattern!("servers");
+
+    /// Filter by project (also commonly known as tenant).
+    ///
+    /// The project can be given by its ID or name.
+    pub fn set_project<P: Into<ProjectRef>>(&mut self, value: P) {
+        self.project = Some(value.into());
+    }
+
+    /// Filter by project (also commonly known as tenant).
+    ///
+    /// The project can be given by its ID or name.
+    pub fn with_project<P: Into<ProjectRef>>(mut self, value: P) -> Self {
+        self.set_project(value);
+        self
     }
 
     query_filter! {
@@ -650,9 +1460,19 @@ impl ServerQuery {
         set_status, with_status -> status: protocol::ServerStatus
     }
 
-    query_filter! {
-        #[doc = "Filter by user."]
-        set_user, with_user -> user_id: UserRef
+    /// Filter by user.
+    ///
+    /// The user can be given by its ID or name.
+    pub fn set_user<U: Into<UserRef>>(&mut self, value: U) {
+        self.user = Some(value.into());
+    }
+
+    /// Filter by user.
+    ///
+    /// The user can be given by its ID or name.
+    pub fn with_user<U: Into<UserRef>>(mut self, value: U) -> Self {
+        self.set_user(value);
+        self
     }
 
     /// Convert this query into a detailed query.
@@ -660,7 +1480,11 @@ impl ServerQuery {
     /// Detailed queries return full `Server` objects instead of just `ServerSummary`.
     #[inline]
     pub fn detailed(self) -> DetailedServerQuery {
-        DetailedServerQuery { inner: self }
+        DetailedServerQuery {
+            inner: self,
+            metadata: Vec::new(),
+            filters: Vec::new(),
+        }
     }
 
     /// Convert this query into a stream executing the request.
@@ -675,7 +1499,7 @@ impl ServerQuery {
     #[inline]
     pub fn into_stream(self) -> impl Stream<Item = Result<ServerSummary>> {
         debug!("Fetching servers with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -692,13 +1516,158 @@ impl ServerQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<ServerSummary> {
         debug!("Fetching one server with {:?}", self.query);
-        if self.can_paginate {
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yieled more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
+    }
+
+    /// Delete all servers matching the query.
+    ///
+    /// Returns every matching server together with the result of its
+    /// deletion. Only listing failures are reported as an error. Deletion is
+    /// requested but not waited for.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::compute::BulkOptions;
+    ///
+    /// # async fn async_wrapper() {
+    /// let os = openstack::Cloud::from_env().await.expect("Unable to authenticate");
+    /// // The name filter is a regular expression.
+    /// let results = os
+    ///     .find_servers()
+    ///     .with_name("^ci-")
+    ///     .delete_all(BulkOptions::new().with_concurrency(5))
+    ///     .await
+    ///     .expect("Unable to list servers");
+    /// for (server, result) in results {
+    ///     if let Err(err) = result {
+    ///         println!("Failed to delete {}: {}", server.name(), err);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn delete_all(
+        self,
+        options: BulkOptions,
+    ) -> Result<Vec<(ServerSummary, Result<()>)>> {
+        self.run_bulk(options, "delete", |session, id| async move {
+            api::delete_server(&session, id).await
+        })
+        .await
+    }
+
+    /// Stop all servers matching the query.
+    ///
+    /// Returns every matching server together with the result of the stop
+    /// request. Only listing failures are reported as an error. The servers
+    /// are not waited for to reach the `SHUTOFF` state.
+    pub async fn stop_all(self, options: BulkOptions) -> Result<Vec<(ServerSummary, Result<()>)>> {
+        self.run_bulk(options, "stop", |session, id| async move {
+            api::server_action(&session, id, ServerAction::Stop)
+                .await
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn run_bulk<F, Fut>(
+        self,
+        options: BulkOptions,
+        action: &str,
+        op: F,
+    ) -> Result<Vec<(ServerSummary, Result<()>)>>
+    where
+        F: Fn(Session, String) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        // Listing first ensures that the pagination is not affected by the
+        // changes made to the servers.
+        let servers = self.all().await?;
+        if options.dry_run {
+            info!(
+                "Dry run: would {} {} server(s): {:?}",
+                action,
+                servers.len(),
+                servers.iter().map(|s| s.id()).collect::<Vec<_>>()
+            );
+            return Ok(servers.into_iter().map(|s| (s, Ok(()))).collect());
+        }
+
+        debug!("Running {} on {} server(s)", action, servers.len());
+        let limiter = options.rate_limit.map(RateLimiter::new);
+        Ok(stream::iter(servers)
+            .map(|server| {
+                let fut = op(server.session.clone(), server.inner.id.clone());
+                let limiter = limiter.as_ref();
+                async move {
+                    if let Some(limiter) = limiter {
+                        limiter.wait().await;
+                    }
+                    let result = fut.await;
+                    if let Err(ref err) = result {
+                        warn!("Failed to {} server {}: {}", action, server.id(), err);
+                    }
+                    (server, result)
+                }
+            })
+            .buffered(options.concurrency)
+            .collect()
+            .await)
+    }
+}
+
+impl BulkOptions {
+    /// Create options with the default concurrency of 10.
+    pub fn new() -> BulkOptions {
+        BulkOptions {
+            concurrency: 10,
+            dry_run: false,
+            rate_limit: None,
+        }
+    }
+
+    /// Maximum number of requests to run in parallel.
+    ///
+    /// A value of zero is treated as one.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Maximum number of requests to start per second.
+    ///
+    /// Applies in addition to the concurrency limit and to the limit of the
+    /// cloud, see [Cloud::with_rate_limit](../struct.Cloud.html#method.with_rate_limit).
+    /// A value of zero removes the limit.
+    pub fn with_rate_limit(mut self, per_second: u32) -> Self {
+        self.rate_limit = if per_second > 0 {
+            Some(per_second)
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Only list the matching servers without changing them.
+    ///
+    /// All returned results are successful in this mode.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+impl Default for BulkOptions {
+    fn default() -> BulkOptions {
+        BulkOptions::new()
     }
 }
 
@@ -712,17 +1681,31 @@ impl ResourceQuery for ServerQuery {
         Ok(self.can_paginate)
     }
 
+    async fn validate(&mut self) -> Result<()> {
+        self.resolve_filters().await
+    }
+
     fn extract_marker(&self, resource: &Self::Item) -> String {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern
+            .as_ref()
+            .map_or(true, |pattern| pattern.matches(resource.name()))
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
         marker: Option<String>,
     ) -> Result<Vec<Self::Item>> {
         let query = self.query.with_marker_and_limit(limit, marker);
-        Ok(api::list_servers(&self.session, &query)
+        Ok(api::list_servers(&self.session, &query, self.api_version)
             .await?
             .into_iter()
             .map(|srv| ServerSummary {
@@ -734,6 +1717,31 @@ impl ResourceQuery for ServerQuery {
 }
 
 impl DetailedServerQuery {
+    /// Only return servers matching the given predicate.
+    ///
+    /// The predicate is applied on the client side to each server as soon
+    /// as its page is received.
+    pub fn filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Server) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Arc::new(predicate));
+        self
+    }
+
+    /// Only return servers that have the given metadata key set to the value.
+    ///
+    /// The Compute API cannot filter on metadata, so this filter is
+    /// applied on the client side.
+    pub fn filter_metadata<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
     /// Convert this query into a stream executing the request.
     ///
     /// This stream yields full `Server` objects.
@@ -743,8 +1751,21 @@ impl DetailedServerQuery {
     ///
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<Server>> {
-        debug!("Fetching server details with {:?}", self.inner.query);
-        ResourceIterator::new(self).into_stream()
+        debug!(
+            "Fetching server details with {:?}, metadata {:?}",
+            self.inner.query, self.metadata
+        );
+        let metadata = self.metadata.clone();
+        let filters = self.filters.clone();
+        ResourceIterator::new(self.inner.session.clone(), self)
+            .into_stream()
+            .try_filter(move |server| {
+                let matches = metadata
+                    .iter()
+                    .all(|(key, value)| server.metadata().get(key) == Some(value))
+                    && filters.iter().all(|predicate| predicate(server));
+                futures::future::ready(matches)
+            })
     }
 
     /// Execute this request and return all results.
@@ -766,17 +1787,33 @@ impl ResourceQuery for DetailedServerQuery {
         Ok(self.inner.can_paginate)
     }
 
+    async fn validate(&mut self) -> Result<()> {
+        self.inner.resolve_filters().await
+    }
+
     fn extract_marker(&self, resource: &Self::Item) -> String {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.inner
+            .name_pattern
+            .as_ref()
+            .map_or(true, |pattern| pattern.matches(resource.name()))
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.inner.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
         marker: Option<String>,
     ) -> Result<Vec<Self::Item>> {
         let query = self.inner.query.with_marker_and_limit(limit, marker);
-        let servers = api::list_servers_detail(&self.inner.session, &query).await?;
+        let servers =
+            api::list_servers_detail(&self.inner.session, &query, self.inner.api_version).await?;
         let mut result = Vec::with_capacity(servers.len());
         for srv in servers {
             result.push(Server::new(self.inner.session.clone(), srv)?);
@@ -785,6 +1822,16 @@ impl ResourceQuery for DetailedServerQuery {
     }
 }
 
+impl fmt::Debug for DetailedServerQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DetailedServerQuery")
+            .field("inner", &self.inner)
+            .field("metadata", &self.metadata)
+            .field("filters", &self.filters.len())
+            .finish()
+    }
+}
+
 impl From<DetailedServerQuery> for ServerQuery {
     fn from(value: DetailedServerQuery) -> ServerQuery {
         value.inner
@@ -797,25 +1844,110 @@ impl From<ServerQuery> for DetailedServerQuery {
     }
 }
 
+impl From<Server> for ServerRef {
+    fn from(value: Server) -> ServerRef {
+        ServerRef::new_verified(value.inner.id)
+    }
+}
+
+impl From<ServerSummary> for ServerRef {
+    fn from(value: ServerSummary) -> ServerRef {
+        ServerRef::new_verified(value.inner.id)
+    }
+}
+
+#[cfg(feature = "compute")]
+impl ServerRef {
+    /// Verify this reference and convert to an ID, if possible.
+    #[allow(dead_code)] // unused without the network feature
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<ServerRef> {
+        Ok(if self.verified {
+            self
+        } else {
+            ServerRef::new_verified(api::get_server(session, &self.value).await?.id)
+        })
+    }
+}
+
+/// Maximum length of a server name accepted by the Compute API.
+const MAX_SERVER_NAME_LENGTH: usize = 255;
+const MAX_DEVICE_TAG_LENGTH: usize = 60;
+
+fn not_found_as_none<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn check_quota(
+    report: &mut protocol::ServerPrecheckReport,
+    resource: protocol::QuotaResource,
+    requested: u64,
+    limit: i64,
+    used: i64,
+) {
+    // Negative limit means unlimited
+    if limit < 0 {
+        return;
+    }
+
+    let available = u64::try_from(limit - used).unwrap_or(0);
+    if requested > available {
+        report
+            .issues
+            .push(protocol::ServerPrecheckIssue::QuotaExceeded {
+                resource,
+                requested,
+                available,
+            });
+    }
+}
+
+async fn convert_network(
+    session: &Session,
+    nic: ServerNIC,
+    tag: Option<String>,
+) -> Result<protocol::ServerNetwork> {
+    Ok(match nic {
+        ServerNIC::FromNetwork(n) => protocol::ServerNetwork::Network {
+            uuid: n.into_verified(session).await?.into(),
+            tag,
+        },
+        ServerNIC::WithPort(p) => protocol::ServerNetwork::Port {
+            port: p.into_verified(session).await?.into(),
+            tag,
+        },
+        ServerNIC::WithFixedIp(ip) => protocol::ServerNetwork::FixedIp { fixed_ip: ip, tag },
+    })
+}
+
 async fn convert_networks(
     session: &Session,
     networks: Vec<ServerNIC>,
+    mut tags: HashMap<usize, String>,
 ) -> Result<Vec<protocol::ServerNetwork>> {
     let mut result = Vec::with_capacity(networks.len());
-    for item in networks {
-        result.push(match item {
-            ServerNIC::FromNetwork(n) => protocol::ServerNetwork::Network {
-                uuid: n.into_verified(session).await?.into(),
-            },
-            ServerNIC::WithPort(p) => protocol::ServerNetwork::Port {
-                port: p.into_verified(session).await?.into(),
-            },
-            ServerNIC::WithFixedIp(ip) => protocol::ServerNetwork::FixedIp { fixed_ip: ip },
-        });
+    for (index, item) in networks.into_iter().enumerate() {
+        result.push(convert_network(session, item, tags.remove(&index)).await?);
     }
     Ok(result)
 }
 
+/// Check a device tag against the Compute API restrictions.
+fn validate_device_tag(report: &mut ValidationReport, tag: &str) {
+    if tag.is_empty() {
+        report.add("device tag is empty");
+    } else if tag.chars().count() > MAX_DEVICE_TAG_LENGTH {
+        report.add(format!(
+            "device tag {tag} is longer than {MAX_DEVICE_TAG_LENGTH} characters"
+        ));
+    } else if tag.contains(['/', ',']) {
+        report.add(format!("device tag {tag} must not contain '/' or ','"));
+    }
+}
+
 impl NewServer {
     /// Start creating a server.
     pub(crate) fn new(session: Session, name: String, flavor: FlavorRef) -> NewServer {
@@ -827,15 +1959,27 @@ impl NewServer {
             metadata: HashMap::new(),
             name,
             nics: Vec::new(),
+            nic_tags: HashMap::new(),
             block_devices: Vec::new(),
             user_data: None,
             config_drive: None,
             availability_zone: None,
+            admin_pass: None,
+            description: None,
+            hostname: None,
+            scheduler_hints: HashMap::new(),
+            strict_api_version: true,
+            trusted_image_certificates: Vec::new(),
         }
     }
 
     /// Request creation of the server.
+    ///
+    /// Fails with all problems found by [validate](#method.validate) before
+    /// sending any requests.
     pub async fn create(self) -> Result<ServerCreationWaiter> {
+        self.validate().into_result()?;
+
         let mut block_devices = Vec::with_capacity(self.block_devices.len());
         for bd in self.block_devices {
             block_devices.push(bd.into_verified(&self.session).await?);
@@ -854,24 +1998,194 @@ impl NewServer {
             },
             metadata: self.metadata,
             name: self.name,
-            networks: convert_networks(&self.session, self.nics).await?,
+            networks: convert_networks(&self.session, self.nics, self.nic_tags).await?,
             user_data: self.user_data,
             config_drive: self.config_drive,
             availability_zone: self.availability_zone,
+            adminPass: self.admin_pass,
+            description: self.description,
+            hostname: self.hostname,
+            trusted_image_certificates: self.trusted_image_certificates,
         };
 
-        let server_ref = api::create_server(&self.session, request).await?;
+        let server_ref = api::create_server(
+            &self.session,
+            request,
+            self.scheduler_hints,
+            self.strict_api_version,
+        )
+        .await?;
         Ok(ServerCreationWaiter {
             server: Server::load(self.session, server_ref.id).await?,
         })
     }
 
+    /// Check the request for problems that can be detected locally.
+    ///
+    /// Verifies that the name is valid and that there is something to boot
+    /// from: an image or a block device with the boot index of 0.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        let length = self.name.chars().count();
+        if length == 0 {
+            report.add("server name is empty");
+        } else if length > MAX_SERVER_NAME_LENGTH {
+            report.add(
+                protocol::ServerPrecheckIssue::NameTooLong {
+                    length,
+                    max_length: MAX_SERVER_NAME_LENGTH,
+                }
+                .to_string(),
+            );
+        }
+
+        if self.image.is_none() && !self.block_devices.iter().any(|bd| bd.boot_index == Some(0)) {
+            report.add("neither an image nor a block device with boot index 0 is provided");
+        }
+
+        for bd in &self.block_devices {
+            if bd.source.is_none() && bd.size_gib.is_none() {
+                report.add("a block device without a source requires a size");
+            }
+            if let Some(ref tag) = bd.tag {
+                validate_device_tag(&mut report, tag);
+            }
+        }
+
+        for (index, tag) in &self.nic_tags {
+            if *index >= self.nics.len() {
+                report.add("a device tag is set for a NIC that does not exist");
+            }
+            validate_device_tag(&mut report, tag);
+        }
+
+        report
+    }
+
+    /// Check the request for all detectable problems without creating the server.
+    ///
+    /// Combines [validate](#method.validate) with the remote checks of
+    /// [precheck](#method.precheck).
+    pub async fn verify(&self) -> Result<ValidationReport> {
+        let mut report = self.validate();
+        for issue in self.precheck().await?.issues {
+            // Already reported by validate()
+            if !matches!(issue, protocol::ServerPrecheckIssue::NameTooLong { .. }) {
+                report.add(issue.to_string());
+            }
+        }
+        Ok(report)
+    }
+
+    /// Check the request for common problems without creating the server.
+    ///
+    /// Verifies the name length, the existence of the flavor, the image,
+    /// the key pair, the networks and the ports, and that the project quota
+    /// has room for one more server of this flavor. Problems are collected
+    /// in the report, while unexpected failures are returned as errors.
+    pub async fn precheck(&self) -> Result<protocol::ServerPrecheckReport> {
+        use protocol::ServerPrecheckIssue as Issue;
+
+        let mut report = protocol::ServerPrecheckReport::default();
+
+        let length = self.name.chars().count();
+        if length > MAX_SERVER_NAME_LENGTH {
+            report.issues.push(Issue::NameTooLong {
+                length,
+                max_length: MAX_SERVER_NAME_LENGTH,
+            });
+        }
+
+        let flavor = not_found_as_none(api::get_flavor(&self.session, &self.flavor).await)?;
+        if flavor.is_none() {
+            report
+                .issues
+                .push(Issue::FlavorNotFound(self.flavor.to_string()));
+        }
+
+        if let Some(ref image) = self.image {
+            if not_found_as_none(image.clone().into_verified(&self.session).await)?.is_none() {
+                report.issues.push(Issue::ImageNotFound(image.to_string()));
+            }
+        }
+
+        if let Some(ref keypair) = self.keypair {
+            if not_found_as_none(keypair.clone().into_verified(&self.session).await)?.is_none() {
+                report
+                    .issues
+                    .push(Issue::KeyPairNotFound(keypair.to_string()));
+            }
+        }
+
+        for nic in &self.nics {
+            match nic {
+                ServerNIC::FromNetwork(network) => {
+                    if not_found_as_none(network.clone().into_verified(&self.session).await)?
+                        .is_none()
+                    {
+                        report
+                            .issues
+                            .push(Issue::NetworkNotFound(network.to_string()));
+                    }
+                }
+                ServerNIC::WithPort(port) => {
+                    if not_found_as_none(port.clone().into_verified(&self.session).await)?.is_none()
+                    {
+                        report.issues.push(Issue::PortNotFound(port.to_string()));
+                    }
+                }
+                ServerNIC::WithFixedIp(..) => {}
+            }
+        }
+
+        let limits = api::get_limits(&self.session).await?;
+        check_quota(
+            &mut report,
+            protocol::QuotaResource::Instances,
+            1,
+            limits.max_instances,
+            limits.instances_used,
+        );
+        if let Some(flavor) = flavor {
+            check_quota(
+                &mut report,
+                protocol::QuotaResource::Cores,
+                flavor.vcpus.into(),
+                limits.max_cores,
+                limits.cores_used,
+            );
+            check_quota(
+                &mut report,
+                protocol::QuotaResource::Ram,
+                flavor.ram,
+                limits.max_ram,
+                limits.ram_used,
+            );
+        }
+
+        debug!("Pre-check of server {} finished: {:?}", self.name, report);
+        Ok(report)
+    }
+
     /// Add a virtual NIC with given fixed IP to the new server.
     #[inline]
     pub fn add_fixed_ip(&mut self, fixed_ip: Ipv4Addr) {
         self.nics.push(ServerNIC::WithFixedIp(fixed_ip));
     }
 
+    /// Add a virtual NIC with a device tag to the new server.
+    ///
+    /// The tag is exposed to the guest via the metadata service.
+    /// Requires Compute API version 2.42.
+    pub fn add_tagged_nic<S>(&mut self, nic: ServerNIC, tag: S)
+    where
+        S: Into<String>,
+    {
+        let _ = self.nic_tags.insert(self.nics.len(), tag.into());
+        self.nics.push(nic);
+    }
+
     /// Add a virtual NIC from this network to the new server.
     #[inline]
     pub fn add_network<N>(&mut self, network: N)
@@ -896,7 +2210,24 @@ impl NewServer {
         &mut self.metadata
     }
 
+    /// Scheduler hints for the new server.
+    #[inline]
+    pub fn scheduler_hints(&mut self) -> &mut HashMap<String, Value> {
+        &mut self.scheduler_hints
+    }
+
+    /// Trusted image certificate IDs used to validate the image signature.
+    ///
+    /// Requires Compute API version 2.63.
+    #[inline]
+    pub fn trusted_image_certificates(&mut self) -> &mut Vec<String> {
+        &mut self.trusted_image_certificates
+    }
+
     /// NICs to attach to this server.
+    ///
+    /// Device tags are kept by the position of a NIC, removing or reordering
+    /// tagged NICs here does not move their tags.
     #[inline]
     pub fn nics(&mut self) -> &mut Vec<ServerNIC> {
         &mut self.nics
@@ -1014,6 +2345,25 @@ impl NewServer {
         self.with_block_device(BlockDevice::from_new_volume(image, size_gib, true))
     }
 
+    /// Add a virtual NIC to the new server.
+    #[inline]
+    pub fn with_nic(mut self, nic: ServerNIC) -> NewServer {
+        self.nics.push(nic);
+        self
+    }
+
+    /// Add a virtual NIC with a device tag to the new server.
+    ///
+    /// Requires Compute API version 2.42.
+    #[inline]
+    pub fn with_tagged_nic<S>(mut self, nic: ServerNIC, tag: S) -> NewServer
+    where
+        S: Into<String>,
+    {
+        self.add_tagged_nic(nic, tag);
+        self
+    }
+
     /// Add a virtual NIC with this port to the new server.
     #[inline]
     pub fn with_port<P>(mut self, port: P) -> NewServer
@@ -1024,6 +2374,52 @@ impl NewServer {
         self
     }
 
+    /// Add a scheduler hint, e.g. `group` or `different_host`.
+    pub fn with_scheduler_hint<K, V>(mut self, key: K, value: V) -> NewServer
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        let _ = self.scheduler_hints.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a trusted image certificate ID.
+    ///
+    /// Requires Compute API version 2.63.
+    pub fn with_trusted_image_certificate<S>(mut self, certificate: S) -> NewServer
+    where
+        S: Into<String>,
+    {
+        self.trusted_image_certificates.push(certificate.into());
+        self
+    }
+
+    creation_field! {
+        #[doc = "Set the administrative password for the new server."]
+        set_admin_pass, with_admin_pass -> admin_pass: optional String
+    }
+
+    creation_field! {
+        #[doc = "Set a description of the new server (requires API version 2.19)."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_field! {
+        #[doc = "Set a hostname of the new server (requires API version 2.90)."]
+        set_hostname, with_hostname -> hostname: optional String
+    }
+
+    creation_field! {
+        #[doc = "Whether to fail if a field requires an unsupported API version."]
+        #[doc = ""]
+        #[doc = "This applies to the description, the hostname, device tags and"]
+        #[doc = "trusted image certificates. By default, creation fails with"]
+        #[doc = "`IncompatibleApiVersion`. If set to `false`, such fields are"]
+        #[doc = "dropped with a warning instead."]
+        set_strict_api_version, with_strict_api_version -> strict_api_version: bool
+    }
+
     creation_field! {
         #[doc = "Use this user-data for the new server."]
         set_user_data, with_user_data -> user_data: optional String
@@ -1056,7 +2452,7 @@ impl Waiter<Server, Error> for ServerCreationWaiter {
     }
 
     async fn poll(&mut self) -> Result<Option<Server>> {
-        self.server.refresh().await?;
+        refresh_with_reauth(&mut self.server).await?;
         if self.server.status() == protocol::ServerStatus::Active {
             debug!("Server {} successfully created", self.server.id());
             // TODO(dtantsur): get rid of clone?
@@ -1126,4 +2522,97 @@ mod test {
             r#"{"createImage":{"name":"new-image","metadata":{"tag":"foo"}}}"#
         );
     }
+
+    #[tokio::test]
+    async fn test_server_ref() {
+        let session = Session::new(osauth::NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap();
+        let server = Server::from_json(
+            session.clone(),
+            serde_json::json!({
+                "OS-EXT-AZ:availability_zone": "nova",
+                "addresses": {},
+                "config_drive": "",
+                "created": "2024-01-01T00:00:00Z",
+                "flavor": {"id": "1", "links": []},
+                "id": "8f1c2a3e",
+                "name": "web",
+                "status": "ACTIVE",
+                "tenant_id": "project",
+                "updated": "2024-01-01T00:00:00Z",
+                "user_id": "user",
+            }),
+        );
+
+        let by_name = ServerRef::from("web");
+        assert!(!by_name.verified);
+        assert_eq!(by_name.as_ref(), "web");
+
+        let verified = ServerRef::from(server);
+        assert!(verified.verified);
+        assert_eq!(verified.as_ref(), "8f1c2a3e");
+        // Verified references are used as they are, without API calls.
+        let result = verified.clone().into_verified(&session).await.unwrap();
+        assert_eq!(result, verified);
+    }
+
+    #[tokio::test]
+    async fn test_new_server_tagged_nics() {
+        let session = Session::new(osauth::NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap();
+        let mut server = NewServer::new(session, "web".into(), "m1.small".into())
+            .with_image("cirros")
+            .with_fixed_ip(Ipv4Addr::LOCALHOST)
+            .with_tagged_nic(ServerNIC::WithFixedIp(Ipv4Addr::UNSPECIFIED), "data");
+        assert_eq!(server.nics.len(), 2);
+        assert_eq!(server.nic_tags.len(), 1);
+        assert_eq!(server.nic_tags[&1], "data");
+        assert!(server.validate().is_ok());
+
+        server.add_tagged_nic(ServerNIC::WithFixedIp(Ipv4Addr::BROADCAST), "a/b");
+        server.nics().truncate(1);
+        let report = server.validate();
+        assert_eq!(report.problems.len(), 3);
+    }
+
+    #[test]
+    fn test_validate_device_tag() {
+        let mut report = ValidationReport::default();
+        validate_device_tag(&mut report, "data-nic");
+        assert!(report.is_ok());
+        validate_device_tag(&mut report, "");
+        validate_device_tag(&mut report, "a/b");
+        validate_device_tag(&mut report, "a,b");
+        validate_device_tag(&mut report, &"x".repeat(61));
+        assert_eq!(report.problems.len(), 4);
+    }
+
+    #[test]
+    fn test_topology_json() {
+        let topology: protocol::ServerTopology = serde_json::from_str(
+            r#"{
+                "nodes": [
+                    {
+                        "cpu_pinning": {"0": 2, "1": 3},
+                        "host_node": 1,
+                        "memory_mb": 1024,
+                        "siblings": [[0, 1]],
+                        "vcpu_set": [0, 1]
+                    },
+                    {"memory_mb": 512, "siblings": [], "vcpu_set": [2]}
+                ],
+                "pagesize_kb": 4
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(topology.nodes.len(), 2);
+        assert_eq!(topology.nodes[0].cpu_pinning.get(&1), Some(&3));
+        assert_eq!(topology.nodes[0].host_node, Some(1));
+        assert_eq!(topology.nodes[0].siblings, vec![vec![0, 1]]);
+        assert!(topology.nodes[1].cpu_pinning.is_empty());
+        assert_eq!(topology.nodes[1].host_node, None);
+        assert_eq!(topology.pagesize_kb, Some(4));
+    }
 }
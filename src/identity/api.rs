@@ -0,0 +1,250 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Foundation bits exposing the Identity API.
+
+use osauth::identity::{Scope, Token};
+use osauth::services::{GenericService, VersionSelector};
+use osauth::{Error, ErrorKind};
+
+use super::super::session::Session;
+use super::super::utils::{self, FetchJson};
+use super::super::Result;
+use super::protocol::*;
+
+const IDENTITY: GenericService = GenericService::new("identity", VersionSelector::Major(3));
+
+/// Extract the token currently used by the session.
+async fn current_token(session: &Session) -> Result<String> {
+    let client = session.client().inner();
+    let url = session.get_endpoint(IDENTITY, &["auth", "tokens"]).await?;
+    let request = session
+        .auth_type()
+        .authenticate(client, client.get(url))
+        .await?
+        .build()
+        .map_err(|err| Error::new(ErrorKind::InvalidConfig, err.to_string()))?;
+    request
+        .headers()
+        .get("x-auth-token")
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidConfig,
+                "The authentication method does not use tokens",
+            )
+        })
+}
+
+/// Create EC2 credentials for a user in a project.
+pub async fn create_ec2_credential<U, P>(
+    session: &Session,
+    user: U,
+    project: P,
+) -> Result<Ec2Credential>
+where
+    U: AsRef<str>,
+    P: Into<String>,
+{
+    let body = Ec2CredentialCreate {
+        tenant_id: project.into(),
+    };
+    debug!(
+        "Creating EC2 credentials for user {} in project {}",
+        user.as_ref(),
+        body.tenant_id
+    );
+    let root: Ec2CredentialRoot = session
+        .post(IDENTITY, &["users", user.as_ref(), "credentials", "OS-EC2"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created EC2 credentials {}", root.credential.access);
+    Ok(root.credential)
+}
+
+/// Delete EC2 credentials of a user.
+pub async fn delete_ec2_credential<U, A>(session: &Session, user: U, access: A) -> Result<()>
+where
+    U: AsRef<str>,
+    A: AsRef<str>,
+{
+    debug!(
+        "Deleting EC2 credentials {} of user {}",
+        access.as_ref(),
+        user.as_ref()
+    );
+    let _ = session
+        .delete(
+            IDENTITY,
+            &[
+                "users",
+                user.as_ref(),
+                "credentials",
+                "OS-EC2",
+                access.as_ref(),
+            ],
+        )
+        .send()
+        .await?;
+    debug!("Successfully deleted EC2 credentials {}", access.as_ref());
+    Ok(())
+}
+
+/// Get EC2 credentials of a user by the access key.
+pub async fn get_ec2_credential<U, A>(
+    session: &Session,
+    user: U,
+    access: A,
+) -> Result<Ec2Credential>
+where
+    U: AsRef<str>,
+    A: AsRef<str>,
+{
+    trace!(
+        "Fetching EC2 credentials {} of user {}",
+        access.as_ref(),
+        user.as_ref()
+    );
+    let root: Ec2CredentialRoot = session
+        .get(
+            IDENTITY,
+            &[
+                "users",
+                user.as_ref(),
+                "credentials",
+                "OS-EC2",
+                access.as_ref(),
+            ],
+        )
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", root.credential);
+    Ok(root.credential)
+}
+
+/// Get an ID of a project by its ID or name.
+///
+/// Projects available to the current user are checked before falling back to
+/// the (usually administrator-only) project list.
+pub async fn get_project_id<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result<String> {
+    let s = id_or_name.as_ref();
+    trace!("Looking up project {}", s);
+    match session
+        .get(IDENTITY, &["projects", s])
+        .fetch_json::<ProjectRoot>()
+        .await
+    {
+        Ok(root) => return Ok(root.project.id),
+        Err(err)
+            if err.kind() == ErrorKind::ResourceNotFound
+                || err.kind() == ErrorKind::AccessDenied => {}
+        Err(err) => return Err(err),
+    }
+
+    let available: ProjectsRoot = session
+        .get(IDENTITY, &["auth", "projects"])
+        .fetch_json()
+        .await?;
+    let mut matching = available
+        .projects
+        .into_iter()
+        .filter(|project| project.name == s)
+        .collect::<Vec<_>>();
+    if matching.len() == 1 {
+        return Ok(matching.pop().unwrap().id);
+    }
+
+    let root: ProjectsRoot = session
+        .get(IDENTITY, &["projects"])
+        .query(&[("name", s)])
+        .fetch_json()
+        .await?;
+    let result = utils::one(
+        root.projects,
+        "Project with given name or ID not found",
+        "Too many projects found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result.id)
+}
+
+/// Get an ID of a user by its ID or name.
+pub async fn get_user_id<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result<String> {
+    let s = id_or_name.as_ref();
+    trace!("Looking up user {}", s);
+    match session
+        .get(IDENTITY, &["users", s])
+        .fetch_json::<UserRoot>()
+        .await
+    {
+        Ok(root) => return Ok(root.user.id),
+        Err(err)
+            if err.kind() == ErrorKind::ResourceNotFound
+                || err.kind() == ErrorKind::AccessDenied => {}
+        Err(err) => return Err(err),
+    }
+
+    let root: UsersRoot = session
+        .get(IDENTITY, &["users"])
+        .query(&[("name", s)])
+        .fetch_json()
+        .await?;
+    let result = utils::one(
+        root.users,
+        "User with given name or ID not found",
+        "Too many users found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result.id)
+}
+
+/// List EC2 credentials of a user.
+pub async fn list_ec2_credentials<U: AsRef<str>>(
+    session: &Session,
+    user: U,
+) -> Result<Vec<Ec2Credential>> {
+    trace!("Listing EC2 credentials of user {}", user.as_ref());
+    let root: Ec2CredentialsRoot = session
+        .get(IDENTITY, &["users", user.as_ref(), "credentials", "OS-EC2"])
+        .fetch_json()
+        .await?;
+    trace!("Received EC2 credentials: {:?}", root.credentials);
+    Ok(root.credentials)
+}
+
+/// Get details of the token used by the session.
+pub async fn get_token_info(session: &Session) -> Result<TokenInfo> {
+    let token = current_token(session).await?;
+    trace!("Validating the current token");
+    let root: TokenInfoRoot = session
+        .get(IDENTITY, &["auth", "tokens"])
+        .header("x-subject-token", token)
+        .fetch_json()
+        .await?;
+    trace!("Received token details {:?}", root.token);
+    Ok(root.token)
+}
+
+/// Switch the session to a token with a different scope.
+pub async fn rescope(session: &mut Session, scope: Scope) -> Result<()> {
+    let token = current_token(session).await?;
+    let auth_url = session.get_endpoint(IDENTITY, &[""]).await?;
+    debug!("Rescoping the session to {:?}", scope);
+    let auth = Token::new(auth_url.as_str(), token)?.with_scope(scope);
+    session.set_auth_type(auth);
+    // Fail early if the new scope is not accessible.
+    session.refresh().await
+}
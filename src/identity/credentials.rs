@@ -0,0 +1,96 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! EC2-style credentials management via Identity API.
+
+use super::super::common::{ProjectRef, UserRef};
+use super::super::session::Session;
+use super::super::Result;
+use super::{api, protocol};
+
+/// EC2-style credentials of a user.
+///
+/// These are commonly used to access S3-compatible object storage.
+#[derive(Clone, Debug)]
+pub struct Ec2Credential {
+    session: Session,
+    inner: protocol::Ec2Credential,
+}
+
+impl Ec2Credential {
+    /// Create new EC2 credentials.
+    pub(crate) async fn create(
+        session: Session,
+        user: UserRef,
+        project: ProjectRef,
+    ) -> Result<Ec2Credential> {
+        let user = user.into_verified(&session).await?;
+        let project = project.into_verified(&session).await?;
+        let inner = api::create_ec2_credential(&session, user, project).await?;
+        Ok(Ec2Credential { session, inner })
+    }
+
+    /// Load EC2 credentials by the access key.
+    pub(crate) async fn load<A>(session: Session, user: UserRef, access: A) -> Result<Ec2Credential>
+    where
+        A: AsRef<str>,
+    {
+        let user = user.into_verified(&session).await?;
+        let inner = api::get_ec2_credential(&session, user, access).await?;
+        Ok(Ec2Credential { session, inner })
+    }
+
+    /// List EC2 credentials of a user.
+    pub(crate) async fn list(session: Session, user: UserRef) -> Result<Vec<Ec2Credential>> {
+        let user = user.into_verified(&session).await?;
+        Ok(api::list_ec2_credentials(&session, user)
+            .await?
+            .into_iter()
+            .map(|inner| Ec2Credential {
+                session: session.clone(),
+                inner,
+            })
+            .collect())
+    }
+
+    transparent_property! {
+        #[doc = "Access key."]
+        access: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the project the credentials are valid for."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Secret key."]
+        secret: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the trust the credentials were created for."]
+        trust_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the user owning the credentials."]
+        user_id: ref String
+    }
+
+    /// Delete the credentials.
+    pub async fn delete(self) -> Result<()> {
+        api::delete_ec2_credential(&self.session, &self.inner.user_id, &self.inner.access).await
+    }
+}
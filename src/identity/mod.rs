@@ -0,0 +1,64 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Identity API implementation bits.
+
+mod api;
+mod credentials;
+mod probe;
+mod protocol;
+
+pub use self::credentials::Ec2Credential;
+pub use self::probe::ServiceProbe;
+pub use self::protocol::{CatalogEndpoint, CatalogEntry, TokenInfo, TokenProject, TokenUser};
+
+pub(crate) use self::api::{get_token_info, rescope};
+pub(crate) use self::probe::probe_services;
+
+use super::common::{ProjectRef, UserRef};
+use super::session::Session;
+use super::{ErrorKind, Result};
+
+// Looking up projects and users by name may require administrator rights.
+// If access is denied, references are passed to the services as they are.
+
+impl ProjectRef {
+    /// Verify this reference and convert to an ID, if possible.
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<ProjectRef> {
+        if self.verified {
+            return Ok(self);
+        }
+
+        match api::get_project_id(session, &self.value).await {
+            Ok(id) => Ok(ProjectRef::new_verified(id)),
+            Err(err) if err.kind() == ErrorKind::AccessDenied => Ok(self),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl UserRef {
+    /// Verify this reference and convert to an ID, if possible.
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<UserRef> {
+        if self.verified {
+            return Ok(self);
+        }
+
+        match api::get_user_id(session, &self.value).await {
+            Ok(id) => Ok(UserRef::new_verified(id)),
+            Err(err) if err.kind() == ErrorKind::AccessDenied => Ok(self),
+            Err(err) => Err(err),
+        }
+    }
+}
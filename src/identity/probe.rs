@@ -0,0 +1,196 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Probing services from the catalog.
+
+use std::time::{Duration, Instant};
+
+use futures::future;
+use reqwest::{Method, StatusCode, Url};
+use serde_json::Value;
+
+use super::super::session::Session;
+use super::super::{Error, Result};
+use super::api;
+use super::protocol::CatalogEntry;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of probing a service from the catalog.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct ServiceProbe {
+    /// Service type, e.g. `compute`.
+    pub service_type: String,
+    /// Service name.
+    pub name: Option<String>,
+    /// Endpoint URL that was probed (if any matched the endpoint filters).
+    pub url: Option<Url>,
+    /// HTTP status returned by the endpoint (if it was reached).
+    pub status: Option<StatusCode>,
+    /// Time it took to receive the response (if the endpoint was reached).
+    pub latency: Option<Duration>,
+    /// Version reported by the endpoint's version document (if any).
+    pub version: Option<String>,
+    /// Error that prevented probing the endpoint.
+    pub error: Option<Error>,
+}
+
+impl ServiceProbe {
+    fn new(entry: &CatalogEntry) -> ServiceProbe {
+        ServiceProbe {
+            service_type: entry.service_type.clone(),
+            name: entry.name.clone(),
+            url: None,
+            status: None,
+            latency: None,
+            version: None,
+            error: None,
+        }
+    }
+
+    /// Whether the endpoint responded without a server error.
+    pub fn is_healthy(&self) -> bool {
+        self.status.is_some_and(|status| !status.is_server_error())
+    }
+
+    /// Whether the endpoint responded at all.
+    #[inline]
+    pub fn is_reachable(&self) -> bool {
+        self.status.is_some()
+    }
+}
+
+/// Extract the version from a version document.
+///
+/// Understands both single version documents and version lists, in the latter
+/// case picks the current version.
+fn extract_version(document: &Value) -> Option<String> {
+    if let Some(id) = document.pointer("/version/id") {
+        return id.as_str().map(From::from);
+    }
+
+    let versions = match document.get("versions") {
+        Some(Value::Array(list)) => list,
+        // The Identity service wraps the list into "values"
+        Some(Value::Object(map)) => map.get("values")?.as_array()?,
+        _ => return None,
+    };
+    let is_current = |version: &&Value| {
+        version
+            .get("status")
+            .and_then(Value::as_str)
+            .is_some_and(|status| {
+                status.eq_ignore_ascii_case("current") || status.eq_ignore_ascii_case("stable")
+            })
+    };
+    versions
+        .iter()
+        .find(is_current)
+        .or_else(|| versions.last())
+        .and_then(|version| version.get("id"))
+        .and_then(Value::as_str)
+        .map(From::from)
+}
+
+async fn probe_service(session: &Session, entry: &CatalogEntry) -> ServiceProbe {
+    let mut result = ServiceProbe::new(entry);
+    let url = match session.endpoint_overrides().get(&entry.service_type) {
+        Some(url) => url.clone(),
+        None => match session
+            .client()
+            .get_endpoint(&entry.service_type, session.endpoint_filters())
+            .await
+        {
+            Ok(url) => url,
+            Err(err) => {
+                result.error = Some(err);
+                return result;
+            }
+        },
+    };
+    result.url = Some(url.clone());
+
+    trace!("Probing service {} at {}", entry.service_type, url);
+    let started = Instant::now();
+    let response = match session
+        .client()
+        .request(Method::GET, url)
+        .timeout(PROBE_TIMEOUT)
+        .send_unchecked()
+        .await
+    {
+        Ok(response) => response,
+        Err(err) => {
+            debug!("Service {} is not reachable: {}", entry.service_type, err);
+            result.error = Some(err);
+            return result;
+        }
+    };
+    let status = response.status();
+    let latency = started.elapsed();
+    debug!(
+        "Service {} responded with {} in {:?}",
+        entry.service_type, status, latency
+    );
+    result.status = Some(status);
+    result.latency = Some(latency);
+    if let Ok(document) = response.json::<Value>().await {
+        result.version = extract_version(&document);
+    }
+    result
+}
+
+/// Probe all services from the catalog concurrently.
+pub async fn probe_services(session: &Session) -> Result<Vec<ServiceProbe>> {
+    let catalog = api::get_token_info(session).await?.catalog;
+    Ok(future::join_all(catalog.iter().map(|entry| probe_service(session, entry))).await)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::extract_version;
+
+    #[test]
+    fn test_extract_version_single() {
+        let document = json!({"version": {"id": "v2.1", "status": "CURRENT"}});
+        assert_eq!(extract_version(&document).as_deref(), Some("v2.1"));
+    }
+
+    #[test]
+    fn test_extract_version_list() {
+        let document = json!({"versions": [
+            {"id": "v2.0", "status": "SUPPORTED"},
+            {"id": "v2.1", "status": "CURRENT"},
+            {"id": "v3.0", "status": "EXPERIMENTAL"}
+        ]});
+        assert_eq!(extract_version(&document).as_deref(), Some("v2.1"));
+    }
+
+    #[test]
+    fn test_extract_version_identity() {
+        let document = json!({"versions": {"values": [
+            {"id": "v3.14", "status": "stable"}
+        ]}});
+        assert_eq!(extract_version(&document).as_deref(), Some("v3.14"));
+    }
+
+    #[test]
+    fn test_extract_version_unknown() {
+        assert!(extract_version(&json!({"containers": []})).is_none());
+        assert!(extract_version(&json!([])).is_none());
+    }
+}
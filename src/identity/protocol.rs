@@ -0,0 +1,248 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON structures and protocol bits for the Identity API.
+
+#![allow(missing_docs)]
+
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset};
+use osauth::common::IdAndName;
+use serde::{Deserialize, Serialize};
+
+/// An endpoint of a service in the catalog.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogEndpoint {
+    /// Unique ID of the endpoint.
+    pub id: String,
+    /// Endpoint interface (`public`, `internal` or `admin`).
+    pub interface: String,
+    /// Region of the endpoint.
+    #[serde(default)]
+    pub region_id: Option<String>,
+    /// Endpoint URL.
+    pub url: String,
+}
+
+/// A service in the catalog.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogEntry {
+    /// Endpoints of the service.
+    #[serde(default)]
+    pub endpoints: Vec<CatalogEndpoint>,
+    /// Unique ID of the service.
+    pub id: String,
+    /// Service name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Service type, e.g. `compute`.
+    #[serde(rename = "type")]
+    pub service_type: String,
+}
+
+/// A project the token is scoped to.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenProject {
+    /// Domain of the project.
+    pub domain: Option<IdAndName>,
+    /// Unique ID of the project.
+    pub id: String,
+    /// Project name.
+    pub name: String,
+}
+
+/// A user the token belongs to.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenUser {
+    /// Domain of the user.
+    pub domain: Option<IdAndName>,
+    /// Unique ID of the user.
+    pub id: String,
+    /// User name.
+    pub name: String,
+}
+
+/// Details of an authentication token.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenInfo {
+    /// Service catalog.
+    #[serde(default)]
+    pub catalog: Vec<CatalogEntry>,
+    /// Domain the token is scoped to (if domain scoped).
+    #[serde(default)]
+    pub domain: Option<IdAndName>,
+    /// Token expiration date and time.
+    pub expires_at: DateTime<FixedOffset>,
+    /// Token issue date and time.
+    pub issued_at: DateTime<FixedOffset>,
+    /// Authentication methods used to obtain the token.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Project the token is scoped to (if project scoped).
+    #[serde(default)]
+    pub project: Option<TokenProject>,
+    /// Roles of the user in the scope of the token.
+    #[serde(default)]
+    pub roles: Vec<IdAndName>,
+    /// User the token belongs to.
+    pub user: TokenUser,
+}
+
+impl TokenInfo {
+    /// Whether the token carries a role with the given name.
+    pub fn has_role<S: AsRef<str>>(&self, name: S) -> bool {
+        self.roles.iter().any(|role| role.name == name.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenInfoRoot {
+    pub token: TokenInfo,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Ec2Credential {
+    pub access: String,
+    pub secret: String,
+    #[serde(default, rename = "tenant_id")]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub trust_id: Option<String>,
+    pub user_id: String,
+}
+
+impl fmt::Debug for Ec2Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ec2Credential")
+            .field("access", &self.access)
+            .field("secret", &"***")
+            .field("project_id", &self.project_id)
+            .field("trust_id", &self.trust_id)
+            .field("user_id", &self.user_id)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ec2CredentialRoot {
+    pub credential: Ec2Credential,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ec2CredentialsRoot {
+    pub credentials: Vec<Ec2Credential>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Ec2CredentialCreate {
+    pub tenant_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectRoot {
+    pub project: IdAndName,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectsRoot {
+    pub projects: Vec<IdAndName>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserRoot {
+    pub user: IdAndName,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsersRoot {
+    pub users: Vec<IdAndName>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_token_info() {
+        let root: TokenInfoRoot = serde_json::from_value(serde_json::json!({
+            "token": {
+                "methods": ["password"],
+                "user": {
+                    "domain": {"id": "default", "name": "Default"},
+                    "id": "u1",
+                    "name": "admin",
+                    "password_expires_at": null
+                },
+                "audit_ids": ["abcd"],
+                "expires_at": "2024-05-01T12:00:00.000000Z",
+                "issued_at": "2024-05-01T11:00:00.000000Z",
+                "project": {
+                    "domain": {"id": "default", "name": "Default"},
+                    "id": "p1",
+                    "name": "demo"
+                },
+                "is_domain": false,
+                "roles": [{"id": "r1", "name": "member"}, {"id": "r2", "name": "reader"}],
+                "catalog": [{
+                    "endpoints": [{
+                        "id": "e1",
+                        "interface": "public",
+                        "region_id": "RegionOne",
+                        "url": "https://cloud.example.com/compute/v2.1",
+                        "region": "RegionOne"
+                    }],
+                    "id": "s1",
+                    "type": "compute",
+                    "name": "nova"
+                }]
+            }
+        }))
+        .unwrap();
+        let token = root.token;
+        assert!(token.has_role("member"));
+        assert!(!token.has_role("admin"));
+        assert_eq!(token.project.unwrap().name, "demo");
+        assert_eq!(token.catalog[0].service_type, "compute");
+        assert_eq!(
+            token.catalog[0].endpoints[0].region_id.as_deref(),
+            Some("RegionOne")
+        );
+        assert!(token.domain.is_none());
+    }
+
+    #[test]
+    fn test_ec2_credential_hides_secret() {
+        let root: Ec2CredentialRoot = serde_json::from_value(serde_json::json!({
+            "credential": {
+                "access": "a1",
+                "secret": "s3cr3t",
+                "tenant_id": "p1",
+                "trust_id": null,
+                "user_id": "u1",
+                "links": {"self": "https://cloud.example.com/identity"}
+            }
+        }))
+        .unwrap();
+        assert_eq!(root.credential.project_id.as_deref(), Some("p1"));
+        let debug = format!("{:?}", root.credential);
+        assert!(debug.contains("a1"));
+        assert!(!debug.contains("s3cr3t"));
+    }
+}
@@ -18,13 +18,100 @@ use std::fmt::Debug;
 
 use osauth::services::IMAGE;
 use osauth::ErrorKind;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Body, Method, Response};
 use serde::Serialize;
 
 use super::super::session::Session;
-use super::super::utils;
-use super::super::Result;
+use super::super::utils::{self, FetchJson};
+use super::super::{Error, Result};
 use super::protocol::*;
 
+/// Share an image with a project.
+pub async fn add_image_member<S1, S2>(session: &Session, id: S1, member: S2) -> Result<ImageMember>
+where
+    S1: AsRef<str>,
+    S2: Into<String>,
+{
+    let body = ImageMemberCreate {
+        member: member.into(),
+    };
+    debug!("Adding member {} to image {}", body.member, id.as_ref());
+    let result: ImageMember = session
+        .post(IMAGE, &["images", id.as_ref(), "members"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created image member {:?}", result);
+    Ok(result)
+}
+
+/// Create an image record without data.
+pub async fn create_image(session: &Session, request: ImageCreate) -> Result<Image> {
+    debug!("Creating an image with {:?}", request);
+    let result: Image = session
+        .post(IMAGE, &["images"])
+        .json(&request)
+        .fetch_json()
+        .await?;
+    debug!("Created image {:?}", result);
+    Ok(result)
+}
+
+/// Delete an image.
+pub async fn delete_image<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Deleting image {}", id.as_ref());
+    let _ = session
+        .delete(IMAGE, &["images", id.as_ref()])
+        .send()
+        .await?;
+    debug!("Image {} was deleted", id.as_ref());
+    Ok(())
+}
+
+/// Stop sharing an image with a project.
+pub async fn delete_image_member<S1, S2>(session: &Session, id: S1, member: S2) -> Result<()>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    debug!(
+        "Removing member {} from image {}",
+        member.as_ref(),
+        id.as_ref()
+    );
+    let _ = session
+        .delete(IMAGE, &["images", id.as_ref(), "members", member.as_ref()])
+        .send()
+        .await?;
+    debug!(
+        "Member {} was removed from image {}",
+        member.as_ref(),
+        id.as_ref()
+    );
+    Ok(())
+}
+
+/// Remove an image from the cache or from the caching queue.
+pub async fn delete_cached_image<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Removing image {} from the cache", id.as_ref());
+    let _ = session
+        .delete(IMAGE, &["cache", id.as_ref()])
+        .send()
+        .await?;
+    debug!("Image {} was removed from the cache", id.as_ref());
+    Ok(())
+}
+
+/// Download the data of an image.
+pub async fn download_image<S: AsRef<str>>(session: &Session, id: S) -> Result<Response> {
+    trace!("Downloading image {}", id.as_ref());
+    session
+        .get(IMAGE, &["images", id.as_ref(), "file"])
+        .send()
+        .await
+}
+
 /// Get an image.
 pub async fn get_image<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result<Image> {
     let s = id_or_name.as_ref();
@@ -40,7 +127,10 @@ pub async fn get_image<S: AsRef<str>>(session: &Session, id_or_name: S) -> Resul
 /// Get an image by its ID.
 pub async fn get_image_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result<Image> {
     trace!("Fetching image {}", id.as_ref());
-    let image: Image = session.get_json(IMAGE, &["images", id.as_ref()]).await?;
+    let image: Image = session
+        .get(IMAGE, &["images", id.as_ref()])
+        .fetch_json()
+        .await?;
     trace!("Received {:?}", image);
     Ok(image)
 }
@@ -50,8 +140,8 @@ pub async fn get_image_by_name<S: AsRef<str>>(session: &Session, name: S) -> Res
     trace!("Get image by name {}", name.as_ref());
     let root: ImagesRoot = session
         .get(IMAGE, &["images"])
-        .query(&[("name", name.as_ref())])
-        .fetch()
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
         .await?;
     let result = utils::one(
         root.images,
@@ -62,13 +152,140 @@ pub async fn get_image_by_name<S: AsRef<str>>(session: &Session, name: S) -> Res
     Ok(result)
 }
 
+/// Get the state of the image cache.
+pub async fn get_image_cache(session: &Session) -> Result<ImageCache> {
+    trace!("Fetching the image cache");
+    let result: ImageCache = session.get(IMAGE, &["cache"]).fetch_json().await?;
+    trace!("Received image cache {:?}", result);
+    Ok(result)
+}
+
+/// Get a member of an image.
+pub async fn get_image_member<S1, S2>(session: &Session, id: S1, member: S2) -> Result<ImageMember>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    trace!(
+        "Fetching member {} of image {}",
+        member.as_ref(),
+        id.as_ref()
+    );
+    let result: ImageMember = session
+        .get(IMAGE, &["images", id.as_ref(), "members", member.as_ref()])
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get the image schema.
+pub async fn get_image_schema(session: &Session) -> Result<ImageSchema> {
+    trace!("Fetching the image schema");
+    let result: ImageSchema = session
+        .get(IMAGE, &["schemas", "image"])
+        .fetch_json()
+        .await?;
+    trace!("Received image schema {:?}", result);
+    Ok(result)
+}
+
+/// List members of an image.
+pub async fn list_image_members<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<Vec<ImageMember>> {
+    trace!("Listing members of image {}", id.as_ref());
+    let root: ImageMembersRoot = session
+        .get(IMAGE, &["images", id.as_ref(), "members"])
+        .fetch_json()
+        .await?;
+    trace!("Received image members: {:?}", root.members);
+    Ok(root.members)
+}
+
 /// List images.
 pub async fn list_images<Q: Serialize + Sync + Debug>(
     session: &Session,
     query: &Q,
 ) -> Result<Vec<Image>> {
     trace!("Listing images with {:?}", query);
-    let root: ImagesRoot = session.get(IMAGE, &["images"]).query(query).fetch().await?;
+    let root: ImagesRoot = session
+        .get(IMAGE, &["images"])
+        .query(query)
+        .fetch_json()
+        .await?;
     trace!("Received images: {:?}", root.images);
     Ok(root.images)
 }
+
+/// Queue an image for caching.
+pub async fn queue_cached_image<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Queueing image {} for caching", id.as_ref());
+    let _ = session.put(IMAGE, &["cache", id.as_ref()]).send().await?;
+    debug!("Image {} was queued for caching", id.as_ref());
+    Ok(())
+}
+
+/// Update an image using JSON patch operations.
+pub async fn update_image<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    update: Vec<ImagePatchOperation>,
+) -> Result<Image> {
+    debug!("Updating image {} with {:?}", id.as_ref(), update);
+    let body = serde_json::to_vec(&update).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Cannot serialize image update: {err}"),
+        )
+    })?;
+    let result: Image = session
+        .request(IMAGE, Method::PATCH, &["images", id.as_ref()])
+        .header(CONTENT_TYPE, "application/openstack-images-v2.1-json-patch")
+        .body(body)
+        .fetch_json()
+        .await?;
+    debug!("Updated image {:?}", result);
+    Ok(result)
+}
+
+/// Update the status of an image member.
+pub async fn update_image_member<S1, S2>(
+    session: &Session,
+    id: S1,
+    member: S2,
+    status: ImageMemberStatus,
+) -> Result<ImageMember>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    debug!(
+        "Setting status of member {} of image {} to {}",
+        member.as_ref(),
+        id.as_ref(),
+        status
+    );
+    let body = ImageMemberUpdate { status };
+    let result: ImageMember = session
+        .put(IMAGE, &["images", id.as_ref(), "members", member.as_ref()])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Updated image member {:?}", result);
+    Ok(result)
+}
+
+/// Upload the data of an image.
+pub async fn upload_image_data<S: AsRef<str>>(session: &Session, id: S, body: Body) -> Result<()> {
+    debug!("Uploading data for image {}", id.as_ref());
+    let _ = session
+        .put(IMAGE, &["images", id.as_ref(), "file"])
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .body(body)
+        .send()
+        .await?;
+    debug!("Data for image {} was uploaded", id.as_ref());
+    Ok(())
+}
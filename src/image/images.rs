@@ -14,23 +14,44 @@
 
 //! Image management via Image API.
 
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::pin::Pin;
+use std::time::Duration;
+
+use async_stream::try_stream;
 use async_trait::async_trait;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, TimeZone};
+use futures::io::{AsyncRead, AsyncReadExt};
 use futures::stream::{Stream, TryStreamExt};
+use md5::Md5;
+use reqwest::{Body, Response, Url};
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha384, Sha512};
 
-use super::super::common::{ImageRef, Refresh, ResourceIterator, ResourceQuery};
+use super::super::common::{ImageRef, ProjectRef, Refresh, ResourceIterator, ResourceQuery};
 use super::super::session::Session;
-use super::super::utils::Query;
-use super::super::{Result, Sort};
+use super::super::utils::{retry_on_auth_failure, url, NamePattern, Query};
+use super::super::waiter::Waiter;
+use super::super::{Error, ErrorKind, Result, Sort};
 use super::{api, protocol};
 
+/// How much data to read for format detection.
+const HEADER_SIZE: usize = 512;
+
+/// Size of a chunk of image data when uploading.
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
 /// A query to image list.
 #[derive(Clone, Debug)]
 pub struct ImageQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
     sort: Vec<String>,
+    name_pattern: Option<NamePattern>,
 }
 
 /// Structure representing a single image.
@@ -38,13 +59,52 @@ pub struct ImageQuery {
 pub struct Image {
     session: Session,
     inner: protocol::Image,
+    dirty: HashSet<String>,
+}
+
+/// A request to create an image and upload its data.
+#[derive(Debug)]
+pub struct NewImage<R> {
+    session: Session,
+    name: String,
+    body: R,
+    container_format: Option<protocol::ImageContainerFormat>,
+    disk_format: Option<protocol::ImageDiskFormat>,
+    min_disk: Option<u32>,
+    min_ram: Option<u32>,
+    visibility: Option<protocol::ImageVisibility>,
+    properties: HashMap<String, Value>,
+}
+
+/// Hash function used to verify downloaded image data.
+#[derive(Debug, Clone)]
+enum ImageHasher {
+    Md5(Md5),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+/// Waiter for an image being created to become active.
+#[derive(Debug)]
+pub struct ImageWaiter {
+    session: Session,
+    image_id: String,
 }
 
 impl Image {
     /// Create an Image object.
     pub(crate) async fn new<Id: AsRef<str>>(session: Session, id: Id) -> Result<Image> {
         let inner = api::get_image(&session, id).await?;
-        Ok(Image { session, inner })
+        Ok(Image::from_inner(session, inner))
+    }
+
+    fn from_inner(session: Session, inner: protocol::Image) -> Image {
+        Image {
+            session,
+            inner,
+            dirty: HashSet::new(),
+        }
     }
 
     transparent_property! {
@@ -52,8 +112,21 @@ impl Image {
         architecture: ref Option<String>
     }
 
+    /// Set the image architecture, e.g. `x86_64`.
+    pub fn set_architecture<S: Into<String>>(&mut self, value: S) {
+        self.inner.architecture = Some(value.into());
+        let _ = self.dirty.insert("architecture".into());
+    }
+
+    /// Set the image architecture, e.g. `x86_64`.
+    #[inline]
+    pub fn with_architecture<S: Into<String>>(mut self, value: S) -> Self {
+        self.set_architecture(value);
+        self
+    }
+
     transparent_property! {
-        #[doc = "Checksum of the image."]
+        #[doc = "MD5 checksum of the image data (legacy, see also `os_hash_value`)."]
         checksum: ref Option<String>
     }
 
@@ -72,11 +145,64 @@ impl Image {
         disk_format: Option<protocol::ImageDiskFormat>
     }
 
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "Unique ID."]
         id: ref String
     }
 
+    transparent_property! {
+        #[doc = "Algorithm used for `os_hash_value`, e.g. `sha512`."]
+        os_hash_algo: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Secure hash of the image data."]
+        os_hash_value: ref Option<String>
+    }
+
+    /// Whether the image is hidden from the default image list.
+    #[inline]
+    pub fn is_hidden(&self) -> bool {
+        self.inner.os_hidden
+    }
+
+    /// Set whether the image is hidden from the default image list.
+    pub fn set_hidden(&mut self, value: bool) {
+        self.inner.os_hidden = value;
+        let _ = self.dirty.insert("os_hidden".into());
+    }
+
+    /// Set whether the image is hidden from the default image list.
+    #[inline]
+    pub fn with_hidden(mut self, value: bool) -> Self {
+        self.set_hidden(value);
+        self
+    }
+
+    /// Whether the image is protected from deletion.
+    #[inline]
+    pub fn is_protected(&self) -> bool {
+        self.inner.protected
+    }
+
+    /// Set whether the image is protected from deletion.
+    pub fn set_protected(&mut self, value: bool) {
+        self.inner.protected = value;
+        let _ = self.dirty.insert("protected".into());
+    }
+
+    /// Set whether the image is protected from deletion.
+    #[inline]
+    pub fn with_protected(mut self, value: bool) -> Self {
+        self.set_protected(value);
+        self
+    }
+
     /// Minimum required disk size in GiB.
     ///
     /// Can be zero, if no requirements are known.
@@ -91,6 +217,201 @@ impl Image {
         self.inner.min_ram
     }
 
+    /// Set the minimum required disk size in GiB.
+    pub fn set_minimum_required_disk(&mut self, value: u32) {
+        self.inner.min_disk = value;
+        let _ = self.dirty.insert("min_disk".into());
+    }
+
+    /// Set the minimum required disk size in GiB.
+    #[inline]
+    pub fn with_minimum_required_disk(mut self, value: u32) -> Self {
+        self.set_minimum_required_disk(value);
+        self
+    }
+
+    /// Set the minimum required RAM size in MiB.
+    pub fn set_minimum_required_ram(&mut self, value: u32) {
+        self.inner.min_ram = value;
+        let _ = self.dirty.insert("min_ram".into());
+    }
+
+    /// Set the minimum required RAM size in MiB.
+    #[inline]
+    pub fn with_minimum_required_ram(mut self, value: u32) -> Self {
+        self.set_minimum_required_ram(value);
+        self
+    }
+
+    /// Disk bus to use for the image, e.g. `virtio` or `scsi`.
+    #[inline]
+    pub fn hw_disk_bus(&self) -> Option<&str> {
+        self.property("hw_disk_bus")
+    }
+
+    /// Set the disk bus to use for the image.
+    #[inline]
+    pub fn set_hw_disk_bus<S: Into<String>>(&mut self, value: S) {
+        self.set_property("hw_disk_bus", value);
+    }
+
+    /// Set the disk bus to use for the image.
+    #[inline]
+    pub fn with_hw_disk_bus<S: Into<String>>(self, value: S) -> Self {
+        self.with_property("hw_disk_bus", value)
+    }
+
+    /// Whether the QEMU guest agent is installed in the image.
+    pub fn hw_qemu_guest_agent(&self) -> Option<bool> {
+        self.property("hw_qemu_guest_agent")
+            .map(|value| matches!(value.to_lowercase().as_str(), "yes" | "true" | "1"))
+    }
+
+    /// Set whether the QEMU guest agent is installed in the image.
+    pub fn set_hw_qemu_guest_agent(&mut self, value: bool) {
+        self.set_property("hw_qemu_guest_agent", if value { "yes" } else { "no" });
+    }
+
+    /// Set whether the QEMU guest agent is installed in the image.
+    #[inline]
+    pub fn with_hw_qemu_guest_agent(mut self, value: bool) -> Self {
+        self.set_hw_qemu_guest_agent(value);
+        self
+    }
+
+    /// Operating system distribution, e.g. `ubuntu`.
+    #[inline]
+    pub fn os_distro(&self) -> Option<&str> {
+        self.property("os_distro")
+    }
+
+    /// Set the operating system distribution.
+    #[inline]
+    pub fn set_os_distro<S: Into<String>>(&mut self, value: S) {
+        self.set_property("os_distro", value);
+    }
+
+    /// Set the operating system distribution.
+    #[inline]
+    pub fn with_os_distro<S: Into<String>>(self, value: S) -> Self {
+        self.with_property("os_distro", value)
+    }
+
+    /// Operating system version, e.g. `22.04`.
+    #[inline]
+    pub fn os_version(&self) -> Option<&str> {
+        self.property("os_version")
+    }
+
+    /// Set the operating system version.
+    #[inline]
+    pub fn set_os_version<S: Into<String>>(&mut self, value: S) {
+        self.set_property("os_version", value);
+    }
+
+    /// Set the operating system version.
+    #[inline]
+    pub fn with_os_version<S: Into<String>>(self, value: S) -> Self {
+        self.with_property("os_version", value)
+    }
+
+    /// Get a custom property of the image.
+    ///
+    /// Custom properties are always strings. All of them are available via
+    /// the [extra](#method.extra) map.
+    pub fn property<K: AsRef<str>>(&self, key: K) -> Option<&str> {
+        self.inner.extra.get(key.as_ref()).and_then(Value::as_str)
+    }
+
+    /// Set a custom property of the image.
+    pub fn set_property<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let key = key.into();
+        let _ = self
+            .inner
+            .extra
+            .insert(key.clone(), Value::String(value.into()));
+        let _ = self.dirty.insert(key);
+    }
+
+    /// Set a custom property of the image.
+    #[inline]
+    pub fn with_property<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.set_property(key, value);
+        self
+    }
+
+    /// Remove a custom property of the image.
+    pub fn remove_property<K: AsRef<str>>(&mut self, key: K) {
+        let key = key.as_ref();
+        if self.inner.extra.remove(key).is_some() {
+            let _ = self.dirty.insert(key.into());
+        }
+    }
+
+    /// Whether the image is a snapshot of a volume-backed server.
+    ///
+    /// Such images contain no data: the Compute service creates snapshots of
+    /// the server volumes instead and records them in the `block_device_mapping`
+    /// property of the image.
+    pub fn is_volume_snapshot(&self) -> bool {
+        self.inner.extra.contains_key("block_device_mapping")
+    }
+
+    /// IDs of the volume snapshots referenced by this image.
+    ///
+    /// Empty unless [is_volume_snapshot](#method.is_volume_snapshot) is `true`.
+    pub fn volume_snapshot_ids(&self) -> Vec<String> {
+        let mapping = match self.inner.extra.get("block_device_mapping") {
+            Some(Value::String(value)) => serde_json::from_str(value).unwrap_or_default(),
+            Some(other) => other.clone(),
+            None => return Vec::new(),
+        };
+        match mapping {
+            Value::Array(items) => items
+                .iter()
+                .filter_map(|item| item.get("snapshot_id").and_then(Value::as_str))
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the image is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the image.
+    pub async fn save(&mut self) -> Result<()> {
+        let mut keys = self.dirty.iter().collect::<Vec<_>>();
+        keys.sort();
+        let update = keys
+            .into_iter()
+            .map(|key| {
+                let value = match key.as_str() {
+                    "architecture" => self.inner.architecture.clone().map(Value::from),
+                    "min_disk" => Some(self.inner.min_disk.into()),
+                    "min_ram" => Some(self.inner.min_ram.into()),
+                    "os_hidden" => Some(self.inner.os_hidden.into()),
+                    "protected" => Some(self.inner.protected.into()),
+                    other => self.inner.extra.get(other).cloned(),
+                };
+                protocol::ImagePatchOperation::new(key, value)
+            })
+            .collect();
+        self.inner = api::update_image(&self.session, &self.inner.id, update).await?;
+        self.dirty.clear();
+        Ok(())
+    }
+
     transparent_property! {
         #[doc = "Image name."]
         name: ref String
@@ -103,7 +424,7 @@ impl Image {
 
     transparent_property! {
         #[doc = "Image status."]
-        status: protocol::ImageStatus
+        status: clone protocol::ImageStatus
     }
 
     transparent_property! {
@@ -120,6 +441,480 @@ impl Image {
         #[doc = "Image visibility."]
         visibility: protocol::ImageVisibility
     }
+
+    /// Get the state of the image cache.
+    pub(crate) async fn cache(session: &Session) -> Result<protocol::ImageCache> {
+        api::get_image_cache(session).await
+    }
+
+    /// Download the image data.
+    ///
+    /// The data is verified against `os_hash_value` (or the legacy MD5
+    /// `checksum` if the secure hash is not available). On mismatch reading
+    /// fails with an I/O error of kind `InvalidData` wrapping an
+    /// [ImageHashMismatch](struct.ImageHashMismatch.html).
+    pub async fn download(&self) -> Result<impl AsyncRead + Send + 'static> {
+        let verifier = ImageHasher::for_image(&self.inner);
+        if verifier.is_none() {
+            warn!(
+                "Image {} has no supported hash, the download will not be verified",
+                self.inner.id
+            );
+        }
+        let resp = api::download_image(&self.session, &self.inner.id).await?;
+        Ok(download_stream(resp, self.inner.id.clone(), verifier))
+    }
+
+    /// Download the image data without verifying its hash.
+    pub async fn download_unverified(&self) -> Result<impl AsyncRead + Send + 'static> {
+        let resp = api::download_image(&self.session, &self.inner.id).await?;
+        Ok(download_stream(resp, self.inner.id.clone(), None))
+    }
+
+    /// Link to the image in the dashboard (Horizon) at `base`.
+    ///
+    /// See [Cloud::dashboard_url](../struct.Cloud.html#method.dashboard_url)
+    /// for where to get the base URL from.
+    pub fn dashboard_url(&self, base: &Url) -> Result<Url> {
+        url::dashboard(base, ["project", "images", &self.inner.id, ""])
+    }
+
+    /// Queue the image for caching by the Image service.
+    ///
+    /// Requires Image API 2.14 and usually administrator privileges.
+    pub async fn queue_for_caching(&self) -> Result<()> {
+        api::queue_cached_image(&self.session, &self.inner.id).await
+    }
+
+    /// Remove the image from the cache of the Image service.
+    ///
+    /// Also removes the image from the caching queue.
+    pub async fn remove_from_cache(&self) -> Result<()> {
+        api::delete_cached_image(&self.session, &self.inner.id).await
+    }
+
+    /// Share the image with a project.
+    ///
+    /// The image must have `shared` visibility. The new member starts in the
+    /// `pending` status until the consumer project accepts it.
+    pub async fn add_member<P>(&self, project: P) -> Result<protocol::ImageMember>
+    where
+        P: Into<ProjectRef>,
+    {
+        let project = project.into().into_verified(&self.session).await?;
+        api::add_image_member(&self.session, &self.inner.id, project).await
+    }
+
+    /// Get the membership of a project in this image.
+    pub async fn member<P>(&self, project: P) -> Result<protocol::ImageMember>
+    where
+        P: Into<ProjectRef>,
+    {
+        let project = project.into().into_verified(&self.session).await?;
+        api::get_image_member(&self.session, &self.inner.id, project).await
+    }
+
+    /// List projects this image is shared with.
+    pub async fn members(&self) -> Result<Vec<protocol::ImageMember>> {
+        api::list_image_members(&self.session, &self.inner.id).await
+    }
+
+    /// Stop sharing the image with a project.
+    pub async fn remove_member<P>(&self, project: P) -> Result<()>
+    where
+        P: Into<ProjectRef>,
+    {
+        let project = project.into().into_verified(&self.session).await?;
+        api::delete_image_member(&self.session, &self.inner.id, project).await
+    }
+
+    /// Update the membership status of a project.
+    ///
+    /// This is normally called by the consumer project to accept or reject
+    /// an image shared with it.
+    pub async fn set_member_status<P>(
+        &self,
+        project: P,
+        status: protocol::ImageMemberStatus,
+    ) -> Result<protocol::ImageMember>
+    where
+        P: Into<ProjectRef>,
+    {
+        let project = project.into().into_verified(&self.session).await?;
+        api::update_image_member(&self.session, &self.inner.id, project, status).await
+    }
+}
+
+impl<R: AsyncRead + Send + 'static> NewImage<R> {
+    /// Start creating an image.
+    pub(crate) fn new(session: Session, name: String, body: R) -> NewImage<R> {
+        NewImage {
+            session,
+            name,
+            body,
+            container_format: None,
+            disk_format: None,
+            min_disk: None,
+            min_ram: None,
+            visibility: None,
+            properties: HashMap::new(),
+        }
+    }
+
+    /// Request creation of the image and upload its data.
+    ///
+    /// The disk format is detected from the beginning of the data if not
+    /// set explicitly, and a mismatch between the declared and the detected
+    /// format is rejected. The container format defaults to `bare`. Both
+    /// formats are checked against the image schema of the cloud before
+    /// anything is created, failing with `InvalidInput` if the cloud does
+    /// not accept them.
+    ///
+    /// If the upload fails, the image record is deleted.
+    pub async fn create(self) -> Result<Image> {
+        let mut body = Box::pin(self.body);
+        let header = read_header(&mut body).await?;
+        let disk_format =
+            negotiate_disk_format(self.disk_format, protocol::ImageDiskFormat::detect(&header))?;
+        let container_format = self
+            .container_format
+            .unwrap_or(protocol::ImageContainerFormat::Bare);
+        check_format_combination(container_format, disk_format)?;
+
+        let schema = api::get_image_schema(&self.session).await?;
+        check_schema_formats(&schema, container_format, disk_format)?;
+
+        let request = protocol::ImageCreate {
+            container_format,
+            disk_format,
+            min_disk: self.min_disk,
+            min_ram: self.min_ram,
+            name: self.name,
+            visibility: self.visibility,
+            properties: self.properties,
+        };
+        let inner = api::create_image(&self.session, request).await?;
+        if let Err(err) =
+            api::upload_image_data(&self.session, &inner.id, upload_body(header, body)).await
+        {
+            warn!(
+                "Uploading data for image {} failed, deleting it: {}",
+                inner.id, err
+            );
+            if let Err(del_err) = api::delete_image(&self.session, &inner.id).await {
+                warn!("Failed to delete image {}: {}", inner.id, del_err);
+            }
+            return Err(err);
+        }
+
+        let inner = api::get_image_by_id(&self.session, &inner.id).await?;
+        Ok(Image::from_inner(self.session, inner))
+    }
+
+    creation_field! {
+        #[doc = "Set the container format (defaults to `bare`)."]
+        set_container_format, with_container_format
+            -> container_format: optional protocol::ImageContainerFormat
+    }
+
+    creation_field! {
+        #[doc = "Set the disk format (detected from the data by default)."]
+        set_disk_format, with_disk_format -> disk_format: optional protocol::ImageDiskFormat
+    }
+
+    creation_field! {
+        #[doc = "Set the minimum required disk size in GiB."]
+        set_minimum_required_disk, with_minimum_required_disk -> min_disk: optional u32
+    }
+
+    creation_field! {
+        #[doc = "Set the minimum required RAM in MiB."]
+        set_minimum_required_ram, with_minimum_required_ram -> min_ram: optional u32
+    }
+
+    /// Set a custom property of the image.
+    pub fn set_property<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let _ = self
+            .properties
+            .insert(key.into(), Value::String(value.into()));
+    }
+
+    /// Set a custom property of the image.
+    #[inline]
+    pub fn with_property<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.set_property(key, value);
+        self
+    }
+
+    creation_field! {
+        #[doc = "Set the image visibility."]
+        set_visibility, with_visibility -> visibility: optional protocol::ImageVisibility
+    }
+}
+
+impl ImageHasher {
+    /// Pick the best hash available for the image together with its expected value.
+    fn for_image(image: &protocol::Image) -> Option<(ImageHasher, String)> {
+        if let (Some(algo), Some(value)) = (&image.os_hash_algo, &image.os_hash_value) {
+            let hasher = match algo.to_lowercase().as_str() {
+                "md5" => Some(ImageHasher::Md5(Md5::new())),
+                "sha256" => Some(ImageHasher::Sha256(Sha256::new())),
+                "sha384" => Some(ImageHasher::Sha384(Sha384::new())),
+                "sha512" => Some(ImageHasher::Sha512(Sha512::new())),
+                other => {
+                    debug!(
+                        "Unsupported hash algorithm {} for image {}",
+                        other, image.id
+                    );
+                    None
+                }
+            };
+            if let Some(hasher) = hasher {
+                return Some((hasher, value.to_lowercase()));
+            }
+        }
+        image
+            .checksum
+            .as_ref()
+            .map(|value| (ImageHasher::Md5(Md5::new()), value.to_lowercase()))
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            ImageHasher::Md5(hasher) => hasher.update(data),
+            ImageHasher::Sha256(hasher) => hasher.update(data),
+            ImageHasher::Sha384(hasher) => hasher.update(data),
+            ImageHasher::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            ImageHasher::Md5(hasher) => format!("{:x}", hasher.finalize()),
+            ImageHasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            ImageHasher::Sha384(hasher) => format!("{:x}", hasher.finalize()),
+            ImageHasher::Sha512(hasher) => format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+/// Image data does not match its hash.
+///
+/// Image downloads fail with an I/O error of kind `InvalidData` wrapping this
+/// error, use `get_ref` and `downcast_ref` to get it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ImageHashMismatch {
+    /// ID of the image.
+    pub image_id: String,
+    /// Expected hash value.
+    pub expected: String,
+    /// Hash value of the downloaded data.
+    pub actual: String,
+}
+
+impl fmt::Display for ImageHashMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Hash mismatch for image {}: expected {}, got {}",
+            self.image_id, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ImageHashMismatch {}
+
+/// Convert a download response into a reader, optionally verifying the hash.
+fn download_stream(
+    resp: Response,
+    image_id: String,
+    verifier: Option<(ImageHasher, String)>,
+) -> impl AsyncRead + Send + 'static {
+    let chunks = resp.bytes_stream().map_err(|orig| {
+        let kind = if orig.is_timeout() {
+            IoErrorKind::TimedOut
+        } else {
+            IoErrorKind::Other
+        };
+        IoError::new(kind, orig)
+    });
+    Box::pin(verified_stream(chunks, image_id, verifier)).into_async_read()
+}
+
+/// Pass the data chunks through, optionally verifying the hash at the end.
+fn verified_stream<S, T>(
+    chunks: S,
+    image_id: String,
+    verifier: Option<(ImageHasher, String)>,
+) -> impl Stream<Item = std::io::Result<T>> + Send
+where
+    S: Stream<Item = std::io::Result<T>> + Send,
+    T: AsRef<[u8]> + Send,
+{
+    try_stream! {
+        let mut verifier = verifier;
+        for await chunk in chunks {
+            let chunk = chunk?;
+            if let Some((ref mut hasher, _)) = verifier {
+                hasher.update(chunk.as_ref());
+            }
+            yield chunk;
+        }
+
+        if let Some((hasher, expected)) = verifier {
+            let actual = hasher.finalize();
+            if actual != expected {
+                Err(IoError::new(
+                    IoErrorKind::InvalidData,
+                    ImageHashMismatch {
+                        image_id: image_id.clone(),
+                        expected,
+                        actual,
+                    },
+                ))?;
+            }
+            debug!("Verified hash of image {}", image_id);
+        }
+    }
+}
+
+/// Read the beginning of the image data for format detection.
+async fn read_header<R: AsyncRead + ?Sized>(body: &mut Pin<Box<R>>) -> Result<Vec<u8>> {
+    let mut header = vec![0; HEADER_SIZE];
+    let mut filled = 0;
+    while filled < HEADER_SIZE {
+        let size = body.read(&mut header[filled..]).await.map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot read image data: {err}"),
+            )
+        })?;
+        if size == 0 {
+            break;
+        }
+        filled += size;
+    }
+    header.truncate(filled);
+    Ok(header)
+}
+
+/// Pick the disk format based on the declared and the detected ones.
+fn negotiate_disk_format(
+    declared: Option<protocol::ImageDiskFormat>,
+    detected: Option<protocol::ImageDiskFormat>,
+) -> Result<protocol::ImageDiskFormat> {
+    match (declared, detected) {
+        (Some(declared), Some(detected)) if declared != detected => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Image data looks like {detected}, but disk format {declared} was requested"),
+        )),
+        (Some(protocol::ImageDiskFormat::QCOW2), None) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Disk format qcow2 was requested, but the image data has no qcow2 header",
+        )),
+        (Some(declared), _) => Ok(declared),
+        (None, Some(detected)) => {
+            debug!("Detected disk format {} from the image data", detected);
+            Ok(detected)
+        }
+        (None, None) => {
+            debug!("No known disk format detected, assuming raw");
+            Ok(protocol::ImageDiskFormat::Raw)
+        }
+    }
+}
+
+/// Check that the container and disk formats can be used together.
+fn check_format_combination(
+    container_format: protocol::ImageContainerFormat,
+    disk_format: protocol::ImageDiskFormat,
+) -> Result<()> {
+    use protocol::{ImageContainerFormat as C, ImageDiskFormat as D};
+
+    let matching = match (container_format, disk_format) {
+        (C::AMI, D::AMI) | (C::ARI, D::ARI) | (C::AKI, D::AKI) => true,
+        (C::AMI | C::ARI | C::AKI, _) | (_, D::AMI | D::ARI | D::AKI) => false,
+        _ => true,
+    };
+    if matching {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Container format {container_format} cannot be used with disk format \
+                 {disk_format}: ami, ari and aki formats must be used for both"
+            ),
+        ))
+    }
+}
+
+/// Check that the cloud accepts the container and disk formats.
+fn check_schema_formats(
+    schema: &protocol::ImageSchema,
+    container_format: protocol::ImageContainerFormat,
+    disk_format: protocol::ImageDiskFormat,
+) -> Result<()> {
+    let checks = [
+        (
+            "container",
+            container_format.to_string(),
+            schema.properties.container_format.allowed(),
+        ),
+        (
+            "disk",
+            disk_format.to_string(),
+            schema.properties.disk_format.allowed(),
+        ),
+    ];
+    for (kind, value, allowed) in checks {
+        if let Some(allowed) = allowed {
+            if !allowed.contains(&value.as_str()) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "The cloud does not accept {} format {}, supported formats are: {}",
+                        kind,
+                        value,
+                        allowed.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Convert the already read header and the rest of the data into a request body.
+fn upload_body<R: AsyncRead + Send + ?Sized + 'static>(
+    header: Vec<u8>,
+    reader: Pin<Box<R>>,
+) -> Body {
+    let stream = try_stream! {
+        let mut reader = reader;
+        if !header.is_empty() {
+            yield header;
+        }
+        loop {
+            let mut buffer = vec![0; UPLOAD_CHUNK_SIZE];
+            let size = reader.read(&mut buffer).await?;
+            if size == 0 {
+                break;
+            }
+            buffer.truncate(size);
+            yield buffer;
+        }
+    };
+    Body::wrap_stream(stream.map_err(|err: IoError| err))
 }
 
 #[async_trait]
@@ -127,8 +922,13 @@ impl Refresh for Image {
     /// Refresh the image.
     async fn refresh(&mut self) -> Result<()> {
         self.inner = api::get_image_by_id(&self.session, &self.inner.id).await?;
+        self.dirty.clear();
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl ImageQuery {
@@ -137,7 +937,9 @@ impl ImageQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
             sort: Vec::new(),
+            name_pattern: None,
         }
     }
 
@@ -166,16 +968,109 @@ impl ImageQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Only return images created at or after the given time.
+    pub fn with_created_since<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.query
+            .push_str("created_at", format!("gte:{}", value.to_rfc3339()));
+        self
+    }
+
+    /// Only return images created at or before the given time.
+    pub fn with_created_until<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.query
+            .push_str("created_at", format!("lte:{}", value.to_rfc3339()));
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by the hidden flag (hidden images are not listed by default)."]
+        with_hidden -> os_hidden: bool
+    }
+
+    /// Filter by the status of the current project as an image member.
+    ///
+    /// Shared images are only listed once accepted by default.
+    pub fn with_member_status(mut self, value: protocol::ImageMemberStatus) -> Self {
+        self.query.push("member_status", value);
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by image name."]
         with_name -> name
     }
 
+    query_name_pattern!("images");
+
+    /// Filter by a custom property of the image.
+    pub fn with_property<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.query.push_str(key, value);
+        self
+    }
+
+    query_filter! {
+        #[doc = "Filter by the protected flag."]
+        with_protected -> protected: bool
+    }
+
+    query_filter! {
+        #[doc = "Only return images of at most the given size in bytes."]
+        with_size_max -> size_max: u64
+    }
+
+    query_filter! {
+        #[doc = "Only return images of at least the given size in bytes."]
+        with_size_min -> size_min: u64
+    }
+
     query_filter! {
         #[doc = "Filter by image status."]
         with_status -> status: protocol::ImageStatus
     }
 
+    /// Only return images updated at or after the given time.
+    pub fn with_updated_since<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.query
+            .push_str("updated_at", format!("gte:{}", value.to_rfc3339()));
+        self
+    }
+
+    /// Only return images updated at or before the given time.
+    pub fn with_updated_until<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.query
+            .push_str("updated_at", format!("lte:{}", value.to_rfc3339()));
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by visibility."]
         with_visibility -> visibility: protocol::ImageVisibility
@@ -194,7 +1089,7 @@ impl ImageQuery {
             self.query.push_str("sort", self.sort.join(","));
         }
         debug!("Fetching images with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -210,13 +1105,15 @@ impl ImageQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<Image> {
         debug!("Fetching one image with {:?}", self.query);
-        if self.can_paginate {
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yieled more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -230,10 +1127,27 @@ impl ResourceQuery for ImageQuery {
         Ok(self.can_paginate)
     }
 
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
     fn extract_marker(&self, resource: &Self::Item) -> String {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern
+            .as_ref()
+            .map_or(true, |pattern| pattern.matches(resource.name()))
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -243,14 +1157,79 @@ impl ResourceQuery for ImageQuery {
         Ok(api::list_images(&self.session, &query)
             .await?
             .into_iter()
-            .map(|item| Image {
-                session: self.session.clone(),
-                inner: item,
-            })
+            .map(|item| Image::from_inner(self.session.clone(), item))
             .collect())
     }
 }
 
+#[async_trait]
+impl Waiter<Image, Error> for ImageWaiter {
+    fn default_wait_timeout(&self) -> Option<Duration> {
+        Some(Duration::new(3600, 0))
+    }
+
+    fn default_delay(&self) -> Duration {
+        Duration::new(5, 0)
+    }
+
+    fn timeout_error(&self) -> Error {
+        Error::new(
+            ErrorKind::OperationTimedOut,
+            format!(
+                "Timeout waiting for image {} to become active",
+                self.image_id
+            ),
+        )
+    }
+
+    async fn poll(&mut self) -> Result<Option<Image>> {
+        let image = retry_on_auth_failure(&self.session, || {
+            Image::new(self.session.clone(), &self.image_id)
+        })
+        .await?;
+        match image.status() {
+            protocol::ImageStatus::Active => {
+                debug!("Image {} successfully created", self.image_id);
+                Ok(Some(image))
+            }
+            protocol::ImageStatus::Killed
+            | protocol::ImageStatus::Deleted
+            | protocol::ImageStatus::PendingDelete => {
+                debug!(
+                    "Failed to create image {} - status is {}",
+                    self.image_id,
+                    image.status()
+                );
+                Err(Error::new(
+                    ErrorKind::OperationFailed,
+                    format!("Image {} got into {} state", self.image_id, image.status()),
+                ))
+            }
+            other => {
+                trace!(
+                    "Still waiting for image {} to become active, current is {}",
+                    self.image_id,
+                    other
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl ImageWaiter {
+    /// Create a waiter for an image being created.
+    #[allow(unused)]
+    pub(crate) fn new(session: Session, image_id: String) -> ImageWaiter {
+        ImageWaiter { session, image_id }
+    }
+
+    /// ID of the image being created.
+    pub fn image_id(&self) -> &String {
+        &self.image_id
+    }
+}
+
 impl From<Image> for ImageRef {
     fn from(value: Image) -> ImageRef {
         ImageRef::new_verified(value.inner.id)
@@ -260,6 +1239,7 @@ impl From<Image> for ImageRef {
 #[cfg(feature = "image")]
 impl ImageRef {
     /// Verify this reference and convert to an ID, if possible.
+    #[allow(dead_code)] // unused without the compute or block-storage features
     pub(crate) async fn into_verified(self, session: &Session) -> Result<ImageRef> {
         Ok(if self.verified {
             self
@@ -268,3 +1248,236 @@ impl ImageRef {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+
+    use futures::io::AsyncReadExt;
+    use futures::stream::{self, TryStreamExt};
+    use serde_json::json;
+
+    use super::super::protocol::{self, ImageContainerFormat, ImageDiskFormat};
+    use super::{
+        check_format_combination, check_schema_formats, negotiate_disk_format, verified_stream,
+        ImageHashMismatch, ImageHasher,
+    };
+    use crate::ErrorKind;
+
+    fn image(extra: serde_json::Value) -> protocol::Image {
+        let mut value = json!({
+            "created_at": "2024-01-01T00:00:00Z",
+            "id": "abcd",
+            "name": "test",
+            "status": "active",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "visibility": "public",
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_image_hasher_selection() {
+        assert!(ImageHasher::for_image(&image(json!({}))).is_none());
+
+        let (mut hasher, expected) = ImageHasher::for_image(&image(json!({
+            "checksum": "5EB63BBBE01EEED093CB22BB8F5ACDC3",
+            "os_hash_algo": "whirlpool",
+            "os_hash_value": "ignored",
+        })))
+        .unwrap();
+        hasher.update(b"hello world");
+        assert_eq!(hasher.finalize(), expected);
+
+        let (mut hasher, expected) = ImageHasher::for_image(&image(json!({
+            "checksum": "5eb63bbbe01eeed093cb22bb8f5acdc3",
+            "os_hash_algo": "sha256",
+            "os_hash_value": "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+        })))
+        .unwrap();
+        assert!(matches!(hasher, ImageHasher::Sha256(..)));
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert_eq!(hasher.finalize(), expected);
+    }
+
+    async fn read_verified(expected: &str) -> std::io::Result<Vec<u8>> {
+        let chunks = stream::iter([b"hello ".to_vec(), b"world".to_vec()].map(Ok::<_, IoError>));
+        let verifier = Some((ImageHasher::Md5(Default::default()), expected.to_string()));
+        let mut reader =
+            Box::pin(verified_stream(chunks, "image".into(), verifier)).into_async_read();
+        let mut result = Vec::new();
+        let _ = reader.read_to_end(&mut result).await?;
+        Ok(result)
+    }
+
+    #[tokio::test]
+    async fn test_verified_stream() {
+        let data = read_verified("5eb63bbbe01eeed093cb22bb8f5acdc3")
+            .await
+            .unwrap();
+        assert_eq!(data, b"hello world");
+
+        let err = read_verified("0123").await.unwrap_err();
+        assert_eq!(err.kind(), IoErrorKind::InvalidData);
+        let mismatch = err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<ImageHashMismatch>())
+            .expect("Not a hash mismatch");
+        assert_eq!(mismatch.image_id, "image");
+        assert_eq!(mismatch.expected, "0123");
+        assert_eq!(mismatch.actual, "5eb63bbbe01eeed093cb22bb8f5acdc3");
+    }
+
+    #[test]
+    fn test_disk_format_detection() {
+        assert_eq!(
+            ImageDiskFormat::detect(b"QFI\xfb\x00\x00\x00\x03"),
+            Some(ImageDiskFormat::QCOW2)
+        );
+        assert_eq!(
+            ImageDiskFormat::detect(b"KDMV\x01\x00\x00\x00"),
+            Some(ImageDiskFormat::VMDK)
+        );
+        assert_eq!(
+            ImageDiskFormat::detect(b"vhdxfile"),
+            Some(ImageDiskFormat::VHDX)
+        );
+        let mut vdi = vec![0; 0x48];
+        vdi[0x40..0x44].copy_from_slice(b"\x7f\x10\xda\xbe");
+        assert_eq!(ImageDiskFormat::detect(&vdi), Some(ImageDiskFormat::VDI));
+        assert_eq!(ImageDiskFormat::detect(&[0; 512]), None);
+        assert_eq!(ImageDiskFormat::detect(b""), None);
+    }
+
+    #[test]
+    fn test_negotiate_disk_format() {
+        let qcow2 = Some(ImageDiskFormat::QCOW2);
+        assert_eq!(
+            negotiate_disk_format(None, None).unwrap(),
+            ImageDiskFormat::Raw
+        );
+        assert_eq!(
+            negotiate_disk_format(None, qcow2).unwrap(),
+            ImageDiskFormat::QCOW2
+        );
+        assert_eq!(
+            negotiate_disk_format(Some(ImageDiskFormat::ISO), None).unwrap(),
+            ImageDiskFormat::ISO
+        );
+        assert_eq!(
+            negotiate_disk_format(Some(ImageDiskFormat::Raw), qcow2)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            negotiate_disk_format(qcow2, None).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_check_format_combination() {
+        check_format_combination(ImageContainerFormat::Bare, ImageDiskFormat::QCOW2).unwrap();
+        check_format_combination(ImageContainerFormat::AMI, ImageDiskFormat::AMI).unwrap();
+        check_format_combination(ImageContainerFormat::OVA, ImageDiskFormat::VMDK).unwrap();
+        assert!(
+            check_format_combination(ImageContainerFormat::Bare, ImageDiskFormat::AKI).is_err()
+        );
+        assert!(check_format_combination(ImageContainerFormat::ARI, ImageDiskFormat::Raw).is_err());
+    }
+
+    #[test]
+    fn test_check_schema_formats() {
+        let schema: protocol::ImageSchema = serde_json::from_value(json!({
+            "properties": {
+                "container_format": {
+                    "enum": [null, "bare", "ovf"],
+                    "type": ["null", "string"],
+                },
+                "disk_format": {
+                    "enum": [null, "raw", "qcow2"],
+                    "type": ["null", "string"],
+                },
+            },
+        }))
+        .unwrap();
+        check_schema_formats(&schema, ImageContainerFormat::Bare, ImageDiskFormat::QCOW2).unwrap();
+        let err = check_schema_formats(&schema, ImageContainerFormat::Bare, ImageDiskFormat::VMDK)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("raw, qcow2"));
+        assert!(
+            check_schema_formats(&schema, ImageContainerFormat::OVA, ImageDiskFormat::Raw).is_err()
+        );
+
+        let schema: protocol::ImageSchema = serde_json::from_value(json!({})).unwrap();
+        check_schema_formats(
+            &schema,
+            ImageContainerFormat::Docker,
+            ImageDiskFormat::Ploop,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_image_members_from_json() {
+        let root: protocol::ImageMembersRoot = serde_json::from_value(json!({
+            "members": [
+                {
+                    "created_at": "2013-10-07T17:58:03Z",
+                    "image_id": "dbc999e3-c52f-4200-bedd-3b18fe7f87fe",
+                    "member_id": "123456789",
+                    "schema": "/v2/schemas/member",
+                    "status": "pending",
+                    "updated_at": "2013-10-07T17:58:03Z"
+                },
+                {
+                    "created_at": "2013-10-07T17:58:55Z",
+                    "image_id": "dbc999e3-c52f-4200-bedd-3b18fe7f87fe",
+                    "member_id": "987654321",
+                    "schema": "/v2/schemas/member",
+                    "status": "accepted",
+                    "updated_at": "2013-10-08T12:08:55Z"
+                }
+            ],
+            "schema": "/v2/schemas/members"
+        }))
+        .unwrap();
+        assert_eq!(root.members.len(), 2);
+        assert_eq!(root.members[0].member_id, "123456789");
+        assert_eq!(root.members[0].status, protocol::ImageMemberStatus::Pending);
+        assert_eq!(
+            root.members[1].status,
+            protocol::ImageMemberStatus::Accepted
+        );
+        assert!(root.members[1].updated_at > root.members[1].created_at);
+    }
+
+    #[test]
+    fn test_image_member_requests_json() {
+        let create = protocol::ImageMemberCreate {
+            member: "123456789".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(create).unwrap(),
+            json!({"member": "123456789"})
+        );
+        for (status, value) in [
+            (protocol::ImageMemberStatus::Pending, "pending"),
+            (protocol::ImageMemberStatus::Accepted, "accepted"),
+            (protocol::ImageMemberStatus::Rejected, "rejected"),
+        ] {
+            let update = protocol::ImageMemberUpdate { status };
+            assert_eq!(
+                serde_json::to_value(update).unwrap(),
+                json!({"status": value})
+            );
+        }
+    }
+}
@@ -18,7 +18,8 @@ mod api;
 mod images;
 mod protocol;
 
-pub use self::images::{Image, ImageQuery};
+pub use self::images::{Image, ImageHashMismatch, ImageQuery, ImageWaiter, NewImage};
 pub use self::protocol::{
-    ImageContainerFormat, ImageDiskFormat, ImageSortKey, ImageStatus, ImageVisibility,
+    CachedImage, ImageCache, ImageContainerFormat, ImageDiskFormat, ImageMember, ImageMemberStatus,
+    ImageSortKey, ImageStatus, ImageVisibility,
 };
@@ -17,12 +17,15 @@
 #![allow(non_snake_case)]
 #![allow(missing_docs)]
 
+use std::collections::HashMap;
+
 use chrono::{DateTime, FixedOffset};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 protocol_enum! {
     #[doc = "Possible image statuses."]
-    enum ImageStatus {
+    enum ImageStatus: Unknown(String) {
         Queued = "queued",
         Saving = "saving",
         Active = "active",
@@ -75,7 +78,9 @@ protocol_enum! {
 
 protocol_enum! {
     #[doc = "Available sort keys."]
+    #[derive(Default)]
     enum ImageSortKey {
+        #[default]
         CreatedAt = "created_at",
         Id = "id",
         Name = "name",
@@ -83,9 +88,32 @@ protocol_enum! {
     }
 }
 
-impl Default for ImageSortKey {
-    fn default() -> ImageSortKey {
-        ImageSortKey::CreatedAt
+protocol_enum! {
+    #[doc = "Possible statuses of an image member."]
+    enum ImageMemberStatus: Unknown(String) {
+        Pending = "pending",
+        Accepted = "accepted",
+        Rejected = "rejected"
+    }
+}
+
+impl ImageDiskFormat {
+    /// Detect the disk format from the first bytes of the image data.
+    ///
+    /// Recognizes QCOW2, VMDK, VHDX and VDI. Returns `None` for anything
+    /// else, which usually means a raw disk image.
+    pub fn detect(header: &[u8]) -> Option<ImageDiskFormat> {
+        if header.starts_with(b"QFI\xfb") {
+            Some(ImageDiskFormat::QCOW2)
+        } else if header.starts_with(b"KDMV") {
+            Some(ImageDiskFormat::VMDK)
+        } else if header.starts_with(b"vhdxfile") {
+            Some(ImageDiskFormat::VHDX)
+        } else if header.get(0x40..0x44) == Some(b"\x7f\x10\xda\xbe") {
+            Some(ImageDiskFormat::VDI)
+        } else {
+            None
+        }
     }
 }
 
@@ -110,12 +138,60 @@ pub struct Image {
     pub min_ram: u32,
     pub name: String,
     #[serde(default)]
+    pub os_hash_algo: Option<String>,
+    #[serde(default)]
+    pub os_hash_value: Option<String>,
+    #[serde(default)]
+    pub os_hidden: bool,
+    #[serde(default)]
+    pub protected: bool,
+    #[serde(default)]
     pub size: Option<u64>,
     pub status: ImageStatus,
     pub updated_at: DateTime<FixedOffset>,
     #[serde(default)]
     pub virtual_size: Option<u64>,
     pub visibility: ImageVisibility,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// An image to create.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageCreate {
+    pub container_format: ImageContainerFormat,
+    pub disk_format: ImageDiskFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_disk: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_ram: Option<u32>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<ImageVisibility>,
+    #[serde(flatten)]
+    pub properties: HashMap<String, Value>,
+}
+
+/// A single JSON patch operation for an image update.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImagePatchOperation {
+    pub op: &'static str,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl ImagePatchOperation {
+    /// Set the property to the value or remove it if the value is `None`.
+    pub fn new(property: &str, value: Option<Value>) -> ImagePatchOperation {
+        // JSON pointer escaping
+        let path = format!("/{}", property.replace('~', "~0").replace('/', "~1"));
+        ImagePatchOperation {
+            op: if value.is_some() { "add" } else { "remove" },
+            path,
+            value,
+        }
+    }
 }
 
 /// A list of images.
@@ -123,3 +199,96 @@ pub struct Image {
 pub struct ImagesRoot {
     pub images: Vec<Image>,
 }
+
+/// Allowed values of an image schema property.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImageSchemaProperty {
+    #[serde(rename = "enum", default)]
+    pub values: Option<Vec<Option<String>>>,
+}
+
+/// Properties of the image schema that are relevant for uploads.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImageSchemaProperties {
+    #[serde(default)]
+    pub container_format: ImageSchemaProperty,
+    #[serde(default)]
+    pub disk_format: ImageSchemaProperty,
+}
+
+/// The image schema.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImageSchema {
+    #[serde(default)]
+    pub properties: ImageSchemaProperties,
+}
+
+impl ImageSchemaProperty {
+    /// Allowed values or `None` if any value is accepted.
+    pub fn allowed(&self) -> Option<Vec<&str>> {
+        self.values
+            .as_ref()
+            .map(|values| values.iter().flatten().map(String::as_str).collect())
+    }
+}
+
+/// An image present in the cache of the Image service.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct CachedImage {
+    /// Number of cache hits.
+    #[serde(default)]
+    pub hits: u64,
+    /// Image ID.
+    pub image_id: String,
+    /// Time of the last access as a UNIX timestamp.
+    #[serde(default)]
+    pub last_accessed: f64,
+    /// Time of the last modification as a UNIX timestamp.
+    #[serde(default)]
+    pub last_modified: f64,
+    /// Size of the cached image in bytes.
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// State of the image cache of the Image service.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct ImageCache {
+    /// Images that are already cached.
+    #[serde(default)]
+    pub cached_images: Vec<CachedImage>,
+    /// IDs of images queued for caching.
+    #[serde(default)]
+    pub queued_images: Vec<String>,
+}
+
+/// A project that an image is shared with.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct ImageMember {
+    pub created_at: DateTime<FixedOffset>,
+    pub image_id: String,
+    pub member_id: String,
+    pub status: ImageMemberStatus,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+/// A list of image members.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageMembersRoot {
+    pub members: Vec<ImageMember>,
+}
+
+/// A request to add an image member.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageMemberCreate {
+    pub member: String,
+}
+
+/// A request to update an image member.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageMemberUpdate {
+    pub status: ImageMemberStatus,
+}
@@ -0,0 +1,427 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Snapshot of resources across several services.
+
+use std::collections::HashMap;
+
+use futures::try_join;
+
+use super::block_storage::{Volume, VolumeQuery};
+use super::compute::{Server, ServerQuery};
+use super::image::{Image, ImageQuery};
+use super::network::{
+    FloatingIp, FloatingIpQuery, Network, NetworkQuery, Port, PortQuery, Router, RouterQuery,
+    Subnet, SubnetQuery,
+};
+use super::session::Session;
+use super::Result;
+
+/// Resources of a cloud fetched at (roughly) the same time.
+///
+/// Besides the resource lists, provides lookups between related resources
+/// (e.g. from a port to its server) that do not require further requests.
+#[derive(Clone, Debug)]
+pub struct Inventory {
+    floating_ips: Vec<FloatingIp>,
+    images: Vec<Image>,
+    networks: Vec<Network>,
+    ports: Vec<Port>,
+    routers: Vec<Router>,
+    servers: Vec<Server>,
+    subnets: Vec<Subnet>,
+    volumes: Vec<Volume>,
+    port_index: HashMap<String, usize>,
+    server_index: HashMap<String, usize>,
+}
+
+impl Inventory {
+    /// Fetch all resources concurrently.
+    pub(crate) async fn fetch(session: &Session) -> Result<Inventory> {
+        let (floating_ips, images, networks, ports, routers, servers, subnets, volumes) = try_join!(
+            FloatingIpQuery::new(session.clone()).all(),
+            ImageQuery::new(session.clone()).all(),
+            NetworkQuery::new(session.clone()).all(),
+            PortQuery::new(session.clone()).all(),
+            RouterQuery::new(session.clone()).all(),
+            ServerQuery::new(session.clone()).detailed().all(),
+            SubnetQuery::new(session.clone()).all(),
+            VolumeQuery::new(session.clone()).all(),
+        )?;
+        Ok(Inventory::new(
+            floating_ips,
+            images,
+            networks,
+            ports,
+            routers,
+            servers,
+            subnets,
+            volumes,
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        floating_ips: Vec<FloatingIp>,
+        images: Vec<Image>,
+        networks: Vec<Network>,
+        ports: Vec<Port>,
+        routers: Vec<Router>,
+        servers: Vec<Server>,
+        subnets: Vec<Subnet>,
+        volumes: Vec<Volume>,
+    ) -> Inventory {
+        let port_index = ports
+            .iter()
+            .enumerate()
+            .map(|(idx, port)| (port.id().clone(), idx))
+            .collect();
+        let server_index = servers
+            .iter()
+            .enumerate()
+            .map(|(idx, server)| (server.id().clone(), idx))
+            .collect();
+        Inventory {
+            floating_ips,
+            images,
+            networks,
+            ports,
+            routers,
+            servers,
+            subnets,
+            volumes,
+            port_index,
+            server_index,
+        }
+    }
+
+    /// Floating IPs.
+    #[inline]
+    pub fn floating_ips(&self) -> &[FloatingIp] {
+        &self.floating_ips
+    }
+
+    /// Images.
+    #[inline]
+    pub fn images(&self) -> &[Image] {
+        &self.images
+    }
+
+    /// Networks.
+    #[inline]
+    pub fn networks(&self) -> &[Network] {
+        &self.networks
+    }
+
+    /// Ports.
+    #[inline]
+    pub fn ports(&self) -> &[Port] {
+        &self.ports
+    }
+
+    /// Routers.
+    #[inline]
+    pub fn routers(&self) -> &[Router] {
+        &self.routers
+    }
+
+    /// Servers (with details).
+    #[inline]
+    pub fn servers(&self) -> &[Server] {
+        &self.servers
+    }
+
+    /// Subnets.
+    #[inline]
+    pub fn subnets(&self) -> &[Subnet] {
+        &self.subnets
+    }
+
+    /// Volumes.
+    #[inline]
+    pub fn volumes(&self) -> &[Volume] {
+        &self.volumes
+    }
+
+    /// Find a port by its ID.
+    pub fn port<S: AsRef<str>>(&self, id: S) -> Option<&Port> {
+        self.port_index
+            .get(id.as_ref())
+            .map(|&idx| &self.ports[idx])
+    }
+
+    /// Find a server by its ID.
+    pub fn server<S: AsRef<str>>(&self, id: S) -> Option<&Server> {
+        self.server_index
+            .get(id.as_ref())
+            .map(|&idx| &self.servers[idx])
+    }
+
+    /// Server the port is attached to (if any).
+    pub fn server_of_port(&self, port: &Port) -> Option<&Server> {
+        match (port.device_id(), port.device_owner()) {
+            (Some(device_id), Some(owner)) if owner.starts_with("compute:") => {
+                self.server(device_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Port the floating IP is associated with (if any).
+    pub fn port_of_floating_ip(&self, floating_ip: &FloatingIp) -> Option<&Port> {
+        floating_ip.port_id().as_ref().and_then(|id| self.port(id))
+    }
+
+    /// Server the floating IP is associated with (if any).
+    pub fn server_of_floating_ip(&self, floating_ip: &FloatingIp) -> Option<&Server> {
+        self.port_of_floating_ip(floating_ip)
+            .and_then(|port| self.server_of_port(port))
+    }
+
+    /// Ports attached to the server.
+    pub fn ports_of_server(&self, server: &Server) -> Vec<&Port> {
+        self.ports
+            .iter()
+            .filter(|port| {
+                self.server_of_port(port)
+                    .map(|s| s.id() == server.id())
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /// Floating IPs associated with the server.
+    pub fn floating_ips_of_server(&self, server: &Server) -> Vec<&FloatingIp> {
+        self.floating_ips
+            .iter()
+            .filter(|fip| {
+                self.server_of_floating_ip(fip)
+                    .map(|s| s.id() == server.id())
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /// Volumes attached to the server.
+    pub fn volumes_of_server(&self, server: &Server) -> Vec<&Volume> {
+        self.volumes
+            .iter()
+            .filter(|volume| {
+                volume
+                    .attachments()
+                    .iter()
+                    .any(|att| &att.server_id == server.id())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use osauth::NoAuth;
+    use serde_json::json;
+
+    use super::super::block_storage::Volume;
+    use super::super::compute::Server;
+    use super::super::network::{FloatingIp, Port};
+    use super::super::session::Session;
+    use super::Inventory;
+
+    fn server(session: &Session, id: &str) -> Server {
+        Server::from_json(
+            session.clone(),
+            json!({
+                "OS-EXT-AZ:availability_zone": "nova",
+                "addresses": {},
+                "config_drive": "",
+                "created": "2024-01-01T00:00:00Z",
+                "flavor": {"id": "1", "links": []},
+                "id": id,
+                "name": id,
+                "status": "ACTIVE",
+                "tenant_id": "project",
+                "updated": "2024-01-01T00:00:00Z",
+                "user_id": "user",
+            }),
+        )
+    }
+
+    fn port(session: &Session, id: &str, device_id: &str, device_owner: &str) -> Port {
+        Port::from_json(
+            session.clone(),
+            json!({
+                "admin_state_up": true,
+                "device_id": device_id,
+                "device_owner": device_owner,
+                "fixed_ips": [],
+                "id": id,
+                "mac_address": "fa:16:3e:01:02:03",
+                "name": id,
+                "network_id": "network",
+                "status": "ACTIVE",
+            }),
+        )
+    }
+
+    fn floating_ip(session: &Session, id: &str, port_id: Option<&str>) -> FloatingIp {
+        FloatingIp::from_json(
+            session.clone(),
+            json!({
+                "floating_ip_address": "172.24.4.10",
+                "floating_network_id": "public",
+                "id": id,
+                "port_id": port_id,
+                "status": "ACTIVE",
+            }),
+        )
+    }
+
+    fn volume(session: &Session, id: &str, server_id: Option<&str>) -> Volume {
+        let attachments = match server_id {
+            Some(server_id) => json!([{
+                "attachment_id": format!("{id}-attachment"),
+                "device": "/dev/vdb",
+                "id": id,
+                "server_id": server_id,
+                "volume_id": id,
+            }]),
+            None => json!([]),
+        };
+        Volume::from_json(
+            session.clone(),
+            json!({
+                "attachments": attachments,
+                "availability_zone": "nova",
+                "bootable": "false",
+                "created_at": "2024-01-01T00:00:00.000000",
+                "encrypted": false,
+                "id": id,
+                "links": [],
+                "metadata": {},
+                "multiattach": false,
+                "name": id,
+                "size": 1,
+                "status": if server_id.is_some() { "in-use" } else { "available" },
+                "updated_at": null,
+                "user_id": "user",
+                "volume_type": "lvm",
+            }),
+        )
+    }
+
+    async fn inventory() -> Inventory {
+        let session = Session::new(NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap();
+        Inventory::new(
+            vec![
+                floating_ip(&session, "fip-server1", Some("port-server1")),
+                floating_ip(&session, "fip-router", Some("port-router")),
+                floating_ip(&session, "fip-missing", Some("port-missing")),
+                floating_ip(&session, "fip-free", None),
+            ],
+            Vec::new(),
+            Vec::new(),
+            vec![
+                port(&session, "port-server1", "server1", "compute:nova"),
+                port(&session, "port-server1-2", "server1", "compute:az1"),
+                port(&session, "port-server2", "server2", "compute:nova"),
+                port(
+                    &session,
+                    "port-router",
+                    "server1",
+                    "network:router_interface",
+                ),
+                port(&session, "port-deleted", "deleted", "compute:nova"),
+            ],
+            Vec::new(),
+            vec![server(&session, "server1"), server(&session, "server2")],
+            Vec::new(),
+            vec![
+                volume(&session, "volume-server1", Some("server1")),
+                volume(&session, "volume-free", None),
+            ],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_lookups() {
+        let inv = inventory().await;
+        assert_eq!(inv.server("server2").unwrap().id(), "server2");
+        assert!(inv.server("deleted").is_none());
+        assert_eq!(inv.port("port-router").unwrap().id(), "port-router");
+        assert!(inv.port("port-missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_server_of_port() {
+        let inv = inventory().await;
+        let server_of = |id: &str| {
+            inv.server_of_port(inv.port(id).unwrap())
+                .map(|s| s.id().clone())
+        };
+        assert_eq!(server_of("port-server1").as_deref(), Some("server1"));
+        assert_eq!(server_of("port-server2").as_deref(), Some("server2"));
+        // Not owned by the compute service even though the device ID matches.
+        assert_eq!(server_of("port-router"), None);
+        // The server is not in the inventory.
+        assert_eq!(server_of("port-deleted"), None);
+    }
+
+    #[tokio::test]
+    async fn test_floating_ips() {
+        let inv = inventory().await;
+        let fips = inv.floating_ips();
+        assert_eq!(inv.server_of_floating_ip(&fips[0]).unwrap().id(), "server1");
+        assert_eq!(
+            inv.port_of_floating_ip(&fips[1]).unwrap().id(),
+            "port-router"
+        );
+        assert!(inv.server_of_floating_ip(&fips[1]).is_none());
+        assert!(inv.port_of_floating_ip(&fips[2]).is_none());
+        assert!(inv.port_of_floating_ip(&fips[3]).is_none());
+
+        let server1 = inv.server("server1").unwrap();
+        let ids: Vec<_> = inv
+            .floating_ips_of_server(server1)
+            .into_iter()
+            .map(|fip| fip.id().clone())
+            .collect();
+        assert_eq!(ids, vec!["fip-server1"]);
+        let server2 = inv.server("server2").unwrap();
+        assert!(inv.floating_ips_of_server(server2).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ports_and_volumes_of_server() {
+        let inv = inventory().await;
+        let server1 = inv.server("server1").unwrap();
+        let ports: Vec<_> = inv
+            .ports_of_server(server1)
+            .into_iter()
+            .map(|port| port.id().clone())
+            .collect();
+        assert_eq!(ports, vec!["port-server1", "port-server1-2"]);
+        let volumes: Vec<_> = inv
+            .volumes_of_server(server1)
+            .into_iter()
+            .map(|volume| volume.id().clone())
+            .collect();
+        assert_eq!(volumes, vec!["volume-server1"]);
+
+        let server2 = inv.server("server2").unwrap();
+        assert_eq!(inv.ports_of_server(server2).len(), 1);
+        assert!(inv.volumes_of_server(server2).is_empty());
+    }
+}
@@ -106,6 +106,27 @@
 //! # async fn main() { create_server().await.unwrap(); }
 //! ```
 //!
+//! # Features
+//!
+//! Each service is available behind its own Cargo feature, so that
+//! applications only compile the services they use:
+//!
+//! * `block-storage` - Block Storage API (Cinder).
+//! * `compute` - Compute API (Nova).
+//! * `identity` - Identity API (Keystone), used to resolve projects and users
+//!   by name.
+//! * `image` - Image API (Glance).
+//! * `metric` - Metric API (Gnocchi).
+//! * `network` - Networking API (Neutron).
+//! * `object-storage` - Object Storage API (Swift).
+//! * `placement` - Placement API.
+//!
+//! The `full` feature enables all services. The [inventory](inventory/index.html)
+//! module requires `block-storage`, `compute`, `image` and `network`.
+//!
+//! The TLS backend is selected by either the `native-tls` or the `rustls`
+//! feature. The default features are `full` and `native-tls`.
+//!
 //! # Requirements
 //!
 //! This crate requires Rust 2022 edition and rustc version 1.71.0 or newer.
@@ -164,6 +185,14 @@ macro_rules! transparent_property {
         }
     );
 
+    ($(#[$attr:meta])* $name:ident: clone $type:ty) => (
+        $(#[$attr])*
+        #[inline]
+        pub fn $name(&self) -> $type {
+            self.inner.$name.clone()
+        }
+    );
+
     ($(#[$attr:meta])* $name:ident: $type:ty) => (
         $(#[$attr])*
         #[inline]
@@ -220,6 +249,47 @@ macro_rules! query_filter {
     );
 }
 
+#[allow(unused_macros)]
+macro_rules! query_name_pattern {
+    ($what:literal) => {
+        #[doc = concat!("Filter ", $what, " by a name glob with `*` and `?` wildcards.")]
+        ///
+        /// The filtering happens on the client side.
+        pub fn set_name_glob<T: AsRef<str>>(&mut self, value: T) {
+            self.name_pattern = Some($crate::utils::NamePattern::glob(value.as_ref()));
+        }
+
+        #[doc = concat!("Filter ", $what, " by a name glob with `*` and `?` wildcards.")]
+        ///
+        /// The filtering happens on the client side.
+        #[inline]
+        pub fn with_name_glob<T: AsRef<str>>(mut self, value: T) -> Self {
+            self.set_name_glob(value);
+            self
+        }
+
+        #[doc = concat!("Filter ", $what, " by a regular expression on their names.")]
+        ///
+        /// The filtering happens on the client side. The expression is not
+        /// anchored, use `^` and `$` to match the whole name. An invalid
+        /// expression causes the query to fail with `InvalidInput`.
+        pub fn set_name_regex<T: AsRef<str>>(&mut self, value: T) {
+            self.name_pattern = Some($crate::utils::NamePattern::regex(value.as_ref()));
+        }
+
+        #[doc = concat!("Filter ", $what, " by a regular expression on their names.")]
+        ///
+        /// The filtering happens on the client side. The expression is not
+        /// anchored, use `^` and `$` to match the whole name. An invalid
+        /// expression causes the query to fail with `InvalidInput`.
+        #[inline]
+        pub fn with_name_regex<T: AsRef<str>>(mut self, value: T) -> Self {
+            self.set_name_regex(value);
+            self
+        }
+    };
+}
+
 #[allow(unused_macros)]
 macro_rules! creation_field {
 
@@ -534,8 +604,145 @@ macro_rules! save_fields {
     }
 }
 
+#[allow(unused_macros)]
+macro_rules! diff_fields {
+    ($self:ident -> $target:ident: $($field:ident)+) => {
+        $(if $target.inner.$field != $self.inner.$field {
+            $target.inner.$field = $self.inner.$field.clone();
+            let _ = $target.dirty.insert(stringify!($field));
+        })+
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! diff_option_fields {
+    ($self:ident -> $target:ident: $($field:ident)+) => {
+        $(if $self.inner.$field.is_some() && $target.inner.$field != $self.inner.$field {
+            $target.inner.$field = $self.inner.$field.clone();
+            let _ = $target.dirty.insert(stringify!($field));
+        })+
+    }
+}
+
+#[allow(unused_macros)]
+macro_rules! diff_vec_fields {
+    ($self:ident -> $target:ident: $($field:ident)+) => {
+        $(if !$self.inner.$field.is_empty() && $target.inner.$field != $self.inner.$field {
+            $target.inner.$field = $self.inner.$field.clone();
+            let _ = $target.dirty.insert(stringify!($field));
+        })+
+    }
+}
+
 #[allow(unused_macros)]
 macro_rules! protocol_enum {
+    {$(#[$attr:meta])* enum $name:ident: $unknown:ident(String) {
+        $($(#[$iattr:meta])* $item:ident = $val:expr),+
+    }} => (
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$iattr])* $item),+,
+            #[doc = "A value that is not known to this crate."]
+            $unknown(String),
+        }
+
+        impl $name {
+            fn as_ref(&self) -> &str {
+                match self {
+                    $($name::$item => $val),+,
+                    $name::$unknown(value) => value,
+                }
+            }
+        }
+
+        impl<'de> ::serde::de::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+                    where D: ::serde::de::Deserializer<'de> {
+                let value = String::deserialize(deserializer)?;
+                Ok(match value.as_ref() {
+                    $($val => $name::$item),+,
+                    _ => $name::$unknown(value),
+                })
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(self.as_ref())
+            }
+        }
+
+        impl ::serde::ser::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+                    where S: ::serde::ser::Serializer {
+                serializer.serialize_str(self.as_ref())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> String {
+                match value {
+                    $name::$unknown(value) => value,
+                    other => String::from(other.as_ref()),
+                }
+            }
+        }
+    );
+
+    {$(#[$attr:meta])* enum $name:ident: default {
+        $($(#[$iattr:meta])* $item:ident = $val:expr),+
+    }} => (
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$iattr])* $item),+,
+        }
+
+        impl $name {
+            fn as_ref(&self) -> &'static str {
+                match *self {
+                    $($name::$item => $val),+,
+                }
+            }
+        }
+
+        impl<'de> ::serde::de::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+                    where D: ::serde::de::Deserializer<'de> {
+                Ok(match String::deserialize(deserializer)?.as_ref() {
+                    $($val => $name::$item),+,
+                    other => {
+                        warn!("Unexpected {}: {}, using the default",
+                              stringify!($name), other);
+                        $name::default()
+                    }
+                })
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(self.as_ref())
+            }
+        }
+
+        impl ::serde::ser::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+                    where S: ::serde::ser::Serializer {
+                serializer.serialize_str(self.as_ref())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> String {
+                String::from(value.as_ref())
+            }
+        }
+    );
+
     {$(#[$attr:meta])* enum $name:ident: $carrier:ty {
         $($(#[$iattr:meta])* $item:ident = $val:expr),+
     }} => (
@@ -648,17 +855,36 @@ mod cloud;
 pub mod common;
 #[cfg(feature = "compute")]
 pub mod compute;
+#[cfg(feature = "identity")]
+pub mod identity;
 #[cfg(feature = "image")]
 pub mod image;
+#[cfg(all(
+    feature = "block-storage",
+    feature = "compute",
+    feature = "image",
+    feature = "network"
+))]
+pub mod inventory;
+#[cfg(feature = "metric")]
+pub mod metric;
 #[cfg(feature = "network")]
 pub mod network;
 #[cfg(feature = "object-storage")]
 pub mod object_storage;
+#[cfg(feature = "placement")]
+pub mod placement;
 /// Synchronous sessions based on one from [osauth](https://docs.rs/osauth/).
 pub mod session {
-    pub use osauth::services::ServiceType;
-    pub use osauth::Session;
+    pub use osauth::services::{
+        GenericService, ServiceType, VersionSelector, VersionedService, BLOCK_STORAGE, COMPUTE,
+        IMAGE, NETWORK, OBJECT_STORAGE,
+    };
+    pub use osauth::{ServiceRequestBuilder, Session};
 }
+mod ratelimit;
+mod reauth;
+mod tls;
 mod utils;
 pub mod waiter;
 
@@ -670,6 +896,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 pub use crate::cloud::Cloud;
 pub use crate::common::Refresh;
+pub use crate::tls::TlsConfig;
 
 /// Sorting request.
 #[derive(Debug, Clone)]
@@ -0,0 +1,62 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Foundation bits exposing the Metric (Gnocchi) API.
+
+use std::fmt::Debug;
+
+use osauth::services::{GenericService, VersionSelector};
+use serde::Serialize;
+
+use super::super::session::Session;
+use super::super::utils::FetchJson;
+use super::super::Result;
+use super::protocol::*;
+
+/// Metric service (Gnocchi) v1.
+const METRIC: GenericService = GenericService::new("metric", VersionSelector::Major(1));
+
+/// Get measures of a metric attached to a resource.
+pub async fn get_resource_measures<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    resource_type: &str,
+    resource_id: &str,
+    metric: &str,
+    query: &Q,
+) -> Result<Vec<Measure>> {
+    trace!(
+        "Fetching measures of metric {} of {} {} with {:?}",
+        metric,
+        resource_type,
+        resource_id,
+        query
+    );
+    let result: Vec<Measure> = session
+        .get(
+            METRIC,
+            &[
+                "resource",
+                resource_type,
+                resource_id,
+                "metric",
+                metric,
+                "measures",
+            ],
+        )
+        .query(query)
+        .fetch_json()
+        .await?;
+    trace!("Received {} measures", result.len());
+    Ok(result)
+}
@@ -0,0 +1,107 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Querying measures via Metric (Gnocchi) API.
+
+use std::fmt::Display;
+
+use chrono::{DateTime, TimeZone};
+
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::super::Result;
+use super::{api, protocol};
+
+/// A query for measures of a resource metric.
+#[derive(Clone, Debug)]
+pub struct MeasureQuery {
+    session: Session,
+    query: Query,
+    resource_type: String,
+    resource_id: String,
+    metric: String,
+}
+
+impl MeasureQuery {
+    pub(crate) fn new(session: Session, resource_id: String, metric: String) -> MeasureQuery {
+        MeasureQuery {
+            session,
+            query: Query::new(),
+            resource_type: "generic".into(),
+            resource_id,
+            metric,
+        }
+    }
+
+    /// Aggregation method to use, e.g. `mean` (the default), `max` or `rate:mean`.
+    pub fn with_aggregation<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("aggregation", value);
+        self
+    }
+
+    /// Only return measures with the given granularity (in seconds).
+    pub fn with_granularity(mut self, seconds: u64) -> Self {
+        self.query.push("granularity", seconds);
+        self
+    }
+
+    /// Type of the resource, e.g. `instance`.
+    ///
+    /// Defaults to `generic`, which matches resources of any type.
+    pub fn with_resource_type<T: Into<String>>(mut self, value: T) -> Self {
+        self.resource_type = value.into();
+        self
+    }
+
+    /// Only return measures starting from this time.
+    pub fn with_start<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        self.query.push_str("start", value.to_rfc3339());
+        self
+    }
+
+    /// Only return measures before this time.
+    pub fn with_stop<Tz>(mut self, value: DateTime<Tz>) -> Self
+    where
+        Tz: TimeZone,
+        Tz::Offset: Display,
+    {
+        self.query.push_str("stop", value.to_rfc3339());
+        self
+    }
+
+    /// Execute this request and return all measures, oldest first.
+    pub async fn all(self) -> Result<Vec<protocol::Measure>> {
+        debug!(
+            "Fetching measures of {} for {} {} with {:?}",
+            self.metric, self.resource_type, self.resource_id, self.query
+        );
+        api::get_resource_measures(
+            &self.session,
+            &self.resource_type,
+            &self.resource_id,
+            &self.metric,
+            &self.query,
+        )
+        .await
+    }
+
+    /// Execute this request and return the most recent measure, if any.
+    pub async fn latest(self) -> Result<Option<protocol::Measure>> {
+        Ok(self.all().await?.pop())
+    }
+}
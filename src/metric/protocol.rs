@@ -0,0 +1,62 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON structures and protocol bits for the Metric (Gnocchi) API.
+
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+
+/// A single aggregated measure.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(from = "(DateTime<FixedOffset>, f64, f64)")]
+#[non_exhaustive]
+pub struct Measure {
+    /// Start of the aggregation period.
+    pub timestamp: DateTime<FixedOffset>,
+    /// Length of the aggregation period in seconds.
+    pub granularity: f64,
+    /// Aggregated value.
+    pub value: f64,
+}
+
+impl From<(DateTime<FixedOffset>, f64, f64)> for Measure {
+    fn from(value: (DateTime<FixedOffset>, f64, f64)) -> Measure {
+        Measure {
+            timestamp: value.0,
+            granularity: value.1,
+            value: value.2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_measures_from_tuples() {
+        let body = r#"[
+            ["2024-05-01T12:00:00+00:00", 300.0, 12.5],
+            ["2024-05-01T12:05:00+00:00", 300.0, 47.0]
+        ]"#;
+        let result: Vec<Measure> = serde_json::from_str(body).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1].granularity, 300.0);
+        assert_eq!(result[1].value, 47.0);
+        assert_eq!(
+            result[0].timestamp.to_rfc3339(),
+            "2024-05-01T12:00:00+00:00"
+        );
+    }
+}
@@ -16,13 +16,15 @@
 
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::net;
 
 use osauth::services::NETWORK;
 use osauth::{Error, ErrorKind};
 use serde::Serialize;
+use serde_json::Value;
 
 use super::super::session::Session;
-use super::super::utils;
+use super::super::utils::{self, FetchJson};
 use super::super::Result;
 use super::protocol::*;
 
@@ -108,6 +110,86 @@ where
     Ok(())
 }
 
+/// Create a conntrack helper on a router.
+pub async fn create_conntrack_helper<S: AsRef<str>>(
+    session: &Session,
+    router_id: S,
+    request: ConntrackHelper,
+) -> Result<RouterConntrackHelper> {
+    debug!(
+        "Creating a new conntrack helper {:?} on router {}",
+        request,
+        router_id.as_ref()
+    );
+    let body = ConntrackHelperCreateRoot {
+        conntrack_helper: request,
+    };
+    let root: RouterConntrackHelperRoot = session
+        .post(
+            NETWORK,
+            &["routers", router_id.as_ref(), "conntrack_helpers"],
+        )
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created conntrack helper {:?}", root.conntrack_helper);
+    Ok(root.conntrack_helper)
+}
+
+/// Create a firewall group.
+pub async fn create_firewall_group(
+    session: &Session,
+    request: FirewallGroup,
+) -> Result<FirewallGroup> {
+    debug!("Creating a new firewall group with {:?}", request);
+    let body = FirewallGroupRoot {
+        firewall_group: request,
+    };
+    let root: FirewallGroupRoot = session
+        .post(NETWORK, &["fwaas", "firewall_groups"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created firewall group {:?}", root.firewall_group);
+    Ok(root.firewall_group)
+}
+
+/// Create a firewall policy.
+pub async fn create_firewall_policy(
+    session: &Session,
+    request: FirewallPolicy,
+) -> Result<FirewallPolicy> {
+    debug!("Creating a new firewall policy with {:?}", request);
+    let body = FirewallPolicyRoot {
+        firewall_policy: request,
+    };
+    let root: FirewallPolicyRoot = session
+        .post(NETWORK, &["fwaas", "firewall_policies"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created firewall policy {:?}", root.firewall_policy);
+    Ok(root.firewall_policy)
+}
+
+/// Create a firewall rule.
+pub async fn create_firewall_rule(
+    session: &Session,
+    request: FirewallRule,
+) -> Result<FirewallRule> {
+    debug!("Creating a new firewall rule with {:?}", request);
+    let body = FirewallRuleRoot {
+        firewall_rule: request,
+    };
+    let root: FirewallRuleRoot = session
+        .post(NETWORK, &["fwaas", "firewall_rules"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created firewall rule {:?}", root.firewall_rule);
+    Ok(root.firewall_rule)
+}
+
 /// Create a floating IP.
 pub async fn create_floating_ip(session: &Session, request: FloatingIp) -> Result<FloatingIp> {
     debug!("Creating a new floating IP with {:?}", request);
@@ -117,7 +199,7 @@ pub async fn create_floating_ip(session: &Session, request: FloatingIp) -> Resul
     let root: FloatingIpRoot = session
         .post(NETWORK, &["floatingips"])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Created floating IP {:?}", root.floatingip);
     Ok(root.floatingip)
@@ -130,12 +212,25 @@ pub async fn create_network(session: &Session, request: Network) -> Result<Netwo
     let root: NetworkRoot = session
         .post(NETWORK, &["networks"])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Created network {:?}", root.network);
     Ok(root.network)
 }
 
+/// Create several networks in one request.
+pub async fn create_networks(session: &Session, requests: Vec<Network>) -> Result<Vec<Network>> {
+    debug!("Creating new networks with {:?}", requests);
+    let body = NetworksRoot { networks: requests };
+    let root: NetworksRoot = session
+        .post(NETWORK, &["networks"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created networks {:?}", root.networks);
+    Ok(root.networks)
+}
+
 /// Create a port.
 pub async fn create_port(session: &Session, request: Port) -> Result<Port> {
     debug!("Creating a new port with {:?}", request);
@@ -143,12 +238,25 @@ pub async fn create_port(session: &Session, request: Port) -> Result<Port> {
     let root: PortRoot = session
         .post(NETWORK, &["ports"])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Created port {:?}", root.port);
     Ok(root.port)
 }
 
+/// Create several ports in one request.
+pub async fn create_ports(session: &Session, requests: Vec<Port>) -> Result<Vec<Port>> {
+    debug!("Creating new ports with {:?}", requests);
+    let body = PortsRoot { ports: requests };
+    let root: PortsRoot = session
+        .post(NETWORK, &["ports"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created ports {:?}", root.ports);
+    Ok(root.ports)
+}
+
 /// Create a router.
 pub async fn create_router(session: &Session, request: Router) -> Result<Router> {
     debug!("Creating a new router with {:?}", request);
@@ -156,7 +264,7 @@ pub async fn create_router(session: &Session, request: Router) -> Result<Router>
     let root: RouterRoot = session
         .post(NETWORK, &["routers"])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Created router {:?}", root.router);
     Ok(root.router)
@@ -169,12 +277,85 @@ pub async fn create_subnet(session: &Session, request: Subnet) -> Result<Subnet>
     let root: SubnetRoot = session
         .post(NETWORK, &["subnets"])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Created subnet {:?}", root.subnet);
     Ok(root.subnet)
 }
 
+/// Create several subnets in one request.
+pub async fn create_subnets(session: &Session, requests: Vec<Subnet>) -> Result<Vec<Subnet>> {
+    debug!("Creating new subnets with {:?}", requests);
+    let body = SubnetsRoot { subnets: requests };
+    let root: SubnetsRoot = session
+        .post(NETWORK, &["subnets"])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Created subnets {:?}", root.subnets);
+    Ok(root.subnets)
+}
+
+/// Delete a conntrack helper from a router.
+pub async fn delete_conntrack_helper<S1, S2>(session: &Session, router_id: S1, id: S2) -> Result<()>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    debug!(
+        "Deleting conntrack helper {} from router {}",
+        id.as_ref(),
+        router_id.as_ref()
+    );
+    let _ = session
+        .delete(
+            NETWORK,
+            &[
+                "routers",
+                router_id.as_ref(),
+                "conntrack_helpers",
+                id.as_ref(),
+            ],
+        )
+        .send()
+        .await?;
+    debug!("Conntrack helper {} was deleted", id.as_ref());
+    Ok(())
+}
+
+/// Delete a firewall group.
+pub async fn delete_firewall_group<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Deleting firewall group {}", id.as_ref());
+    let _ = session
+        .delete(NETWORK, &["fwaas", "firewall_groups", id.as_ref()])
+        .send()
+        .await?;
+    debug!("Firewall group {} was deleted", id.as_ref());
+    Ok(())
+}
+
+/// Delete a firewall policy.
+pub async fn delete_firewall_policy<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Deleting firewall policy {}", id.as_ref());
+    let _ = session
+        .delete(NETWORK, &["fwaas", "firewall_policies", id.as_ref()])
+        .send()
+        .await?;
+    debug!("Firewall policy {} was deleted", id.as_ref());
+    Ok(())
+}
+
+/// Delete a firewall rule.
+pub async fn delete_firewall_rule<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
+    debug!("Deleting firewall rule {}", id.as_ref());
+    let _ = session
+        .delete(NETWORK, &["fwaas", "firewall_rules", id.as_ref()])
+        .send()
+        .await?;
+    debug!("Firewall rule {} was deleted", id.as_ref());
+    Ok(())
+}
+
 /// Delete a floating IP.
 pub async fn delete_floating_ip<S: AsRef<str>>(session: &Session, id: S) -> Result<()> {
     debug!("Deleting floating IP {}", id.as_ref());
@@ -230,16 +411,219 @@ pub async fn delete_subnet<S: AsRef<str>>(session: &Session, id: S) -> Result<()
     Ok(())
 }
 
+/// Get the default security group of a project.
+pub async fn get_default_security_group<S: AsRef<str>>(
+    session: &Session,
+    project_id: S,
+) -> Result<SecurityGroup> {
+    trace!(
+        "Get the default security group of project {}",
+        project_id.as_ref()
+    );
+    let root: SecurityGroupsRoot = session
+        .get(NETWORK, &["security-groups"])
+        .query(&[("name", "default"), ("project_id", project_id.as_ref())])
+        .fetch_json()
+        .await?;
+    let result = utils::one(
+        root.security_groups,
+        "Default security group not found",
+        "Too many default security groups found",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get a firewall group.
+pub async fn get_firewall_group<S: AsRef<str>>(
+    session: &Session,
+    id_or_name: S,
+) -> Result<FirewallGroup> {
+    let s = id_or_name.as_ref();
+    match get_firewall_group_by_id(session, s).await {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            get_firewall_group_by_name(session, s).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a firewall group by its ID.
+pub async fn get_firewall_group_by_id<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<FirewallGroup> {
+    trace!("Get firewall group by ID {}", id.as_ref());
+    let root: FirewallGroupRoot = session
+        .get(NETWORK, &["fwaas", "firewall_groups", id.as_ref()])
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", root.firewall_group);
+    Ok(root.firewall_group)
+}
+
+/// Get a firewall group by its name.
+pub async fn get_firewall_group_by_name<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+) -> Result<FirewallGroup> {
+    trace!("Get firewall group by name {}", name.as_ref());
+    let root: FirewallGroupsRoot = session
+        .get(NETWORK, &["fwaas", "firewall_groups"])
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
+        .await?;
+    let result = utils::one(
+        root.firewall_groups,
+        "Firewall group with given name or ID not found",
+        "Too many firewall groups found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get a firewall policy.
+pub async fn get_firewall_policy<S: AsRef<str>>(
+    session: &Session,
+    id_or_name: S,
+) -> Result<FirewallPolicy> {
+    let s = id_or_name.as_ref();
+    match get_firewall_policy_by_id(session, s).await {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            get_firewall_policy_by_name(session, s).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a firewall policy by its ID.
+pub async fn get_firewall_policy_by_id<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<FirewallPolicy> {
+    trace!("Get firewall policy by ID {}", id.as_ref());
+    let root: FirewallPolicyRoot = session
+        .get(NETWORK, &["fwaas", "firewall_policies", id.as_ref()])
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", root.firewall_policy);
+    Ok(root.firewall_policy)
+}
+
+/// Get a firewall policy by its name.
+pub async fn get_firewall_policy_by_name<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+) -> Result<FirewallPolicy> {
+    trace!("Get firewall policy by name {}", name.as_ref());
+    let root: FirewallPoliciesRoot = session
+        .get(NETWORK, &["fwaas", "firewall_policies"])
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
+        .await?;
+    let result = utils::one(
+        root.firewall_policies,
+        "Firewall policy with given name or ID not found",
+        "Too many firewall policies found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get a firewall rule.
+pub async fn get_firewall_rule<S: AsRef<str>>(
+    session: &Session,
+    id_or_name: S,
+) -> Result<FirewallRule> {
+    let s = id_or_name.as_ref();
+    match get_firewall_rule_by_id(session, s).await {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            get_firewall_rule_by_name(session, s).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a firewall rule by its ID.
+pub async fn get_firewall_rule_by_id<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<FirewallRule> {
+    trace!("Get firewall rule by ID {}", id.as_ref());
+    let root: FirewallRuleRoot = session
+        .get(NETWORK, &["fwaas", "firewall_rules", id.as_ref()])
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", root.firewall_rule);
+    Ok(root.firewall_rule)
+}
+
+/// Get a firewall rule by its name.
+pub async fn get_firewall_rule_by_name<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+) -> Result<FirewallRule> {
+    trace!("Get firewall rule by name {}", name.as_ref());
+    let root: FirewallRulesRoot = session
+        .get(NETWORK, &["fwaas", "firewall_rules"])
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
+        .await?;
+    let result = utils::one(
+        root.firewall_rules,
+        "Firewall rule with given name or ID not found",
+        "Too many firewall rules found with given name",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
 /// Get a floating IP.
 pub async fn get_floating_ip<S: AsRef<str>>(session: &Session, id: S) -> Result<FloatingIp> {
     trace!("Get floating IP by ID {}", id.as_ref());
     let root: FloatingIpRoot = session
-        .get_json(NETWORK, &["floatingips", id.as_ref()])
+        .get(NETWORK, &["floatingips", id.as_ref()])
+        .fetch_json()
         .await?;
     trace!("Received {:?}", root.floatingip);
     Ok(root.floatingip)
 }
 
+/// Get a floating IP by its ID or address.
+pub async fn get_floating_ip_by_id_or_address<S: AsRef<str>>(
+    session: &Session,
+    id_or_address: S,
+) -> Result<FloatingIp> {
+    let s = id_or_address.as_ref();
+    match s.parse::<net::IpAddr>() {
+        Ok(address) => get_floating_ip_by_address(session, address).await,
+        Err(_) => get_floating_ip(session, s).await,
+    }
+}
+
+/// Get a floating IP by its address.
+pub async fn get_floating_ip_by_address(
+    session: &Session,
+    address: net::IpAddr,
+) -> Result<FloatingIp> {
+    trace!("Get floating IP by address {}", address);
+    let root: FloatingIpsRoot = session
+        .get(NETWORK, &["floatingips"])
+        .query(&[("floating_ip_address", address.to_string())])
+        .fetch_json()
+        .await?;
+    let result = utils::one(
+        root.floatingips,
+        "Floating IP with given address not found",
+        "Too many floating IPs found with given address",
+    )?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
 /// Get a network.
 pub async fn get_network<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result<Network> {
     let s = id_or_name.as_ref();
@@ -256,19 +640,34 @@ pub async fn get_network<S: AsRef<str>>(session: &Session, id_or_name: S) -> Res
 pub async fn get_network_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result<Network> {
     trace!("Get network by ID {}", id.as_ref());
     let root: NetworkRoot = session
-        .get_json(NETWORK, &["networks", id.as_ref()])
+        .get(NETWORK, &["networks", id.as_ref()])
+        .fetch_json()
         .await?;
     trace!("Received {:?}", root.network);
     Ok(root.network)
 }
 
+/// Get IP address availability of a network.
+pub async fn get_network_ip_availability<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<NetworkIpAvailability> {
+    trace!("Get IP availability of network {}", id.as_ref());
+    let root: NetworkIpAvailabilityRoot = session
+        .get(NETWORK, &["network-ip-availabilities", id.as_ref()])
+        .fetch_json()
+        .await?;
+    trace!("Received {:?}", root.network_ip_availability);
+    Ok(root.network_ip_availability)
+}
+
 /// Get a network by its name.
 pub async fn get_network_by_name<S: AsRef<str>>(session: &Session, name: S) -> Result<Network> {
     trace!("Get network by name {}", name.as_ref());
     let root: NetworksRoot = session
         .get(NETWORK, &["networks"])
-        .query(&[("name", name.as_ref())])
-        .fetch()
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
         .await?;
     let result = utils::one(
         root.networks,
@@ -279,6 +678,78 @@ pub async fn get_network_by_name<S: AsRef<str>>(session: &Session, name: S) -> R
     Ok(result)
 }
 
+/// Get network quotas of a project together with their usage.
+///
+/// Falls back to counting the resources if the `quota_details` extension is
+/// not available.
+pub async fn get_quota_usage<S: AsRef<str>>(
+    session: &Session,
+    project_id: S,
+) -> Result<NetworkQuotaUsage> {
+    let project_id = project_id.as_ref();
+    trace!("Get network quota usage of project {}", project_id);
+    match session
+        .get(NETWORK, &["quotas", project_id, "details"])
+        .fetch_json::<NetworkQuotaUsageRoot>()
+        .await
+    {
+        Ok(root) => {
+            trace!("Received {:?}", root.quota);
+            return Ok(root.quota);
+        }
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            debug!("Quota details are not available, counting resources instead");
+        }
+        Err(err) => return Err(err),
+    }
+
+    let root: NetworkQuotaRoot = session
+        .get(NETWORK, &["quotas", project_id])
+        .fetch_json()
+        .await?;
+    let quota = root.quota;
+    let result = NetworkQuotaUsage {
+        floating_ips: QuotaUsage::new(
+            quota.floatingip,
+            count_resources(session, "floatingips", project_id).await?,
+        ),
+        networks: QuotaUsage::new(
+            quota.network,
+            count_resources(session, "networks", project_id).await?,
+        ),
+        ports: QuotaUsage::new(
+            quota.port,
+            count_resources(session, "ports", project_id).await?,
+        ),
+        routers: QuotaUsage::new(
+            quota.router,
+            count_resources(session, "routers", project_id).await?,
+        ),
+        security_groups: QuotaUsage::new(
+            quota.security_group,
+            count_resources(session, "security-groups", project_id).await?,
+        ),
+        subnets: QuotaUsage::new(
+            quota.subnet,
+            count_resources(session, "subnets", project_id).await?,
+        ),
+    };
+    trace!("Counted {:?}", result);
+    Ok(result)
+}
+
+/// Count resources of a project, fetching only their IDs.
+async fn count_resources(session: &Session, collection: &str, project_id: &str) -> Result<i64> {
+    let root: HashMap<String, Value> = session
+        .get(NETWORK, &[collection])
+        .query(&[("project_id", project_id), ("fields", "id")])
+        .fetch_json()
+        .await?;
+    let key = collection.replace('-', "_");
+    let count = root.get(&key).and_then(Value::as_array).map_or(0, Vec::len);
+    Ok(i64::try_from(count).unwrap_or(i64::MAX))
+}
+
 /// Get a port.
 pub async fn get_port<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result<Port> {
     let s = id_or_name.as_ref();
@@ -292,7 +763,10 @@ pub async fn get_port<S: AsRef<str>>(session: &Session, id_or_name: S) -> Result
 /// Get a port by its ID.
 pub async fn get_port_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result<Port> {
     trace!("Get port by ID {}", id.as_ref());
-    let root: PortRoot = session.get_json(NETWORK, &["ports", id.as_ref()]).await?;
+    let root: PortRoot = session
+        .get(NETWORK, &["ports", id.as_ref()])
+        .fetch_json()
+        .await?;
     trace!("Received {:?}", root.port);
     Ok(root.port)
 }
@@ -302,8 +776,8 @@ pub async fn get_port_by_name<S: AsRef<str>>(session: &Session, name: S) -> Resu
     trace!("Get port by name {}", name.as_ref());
     let root: PortsRoot = session
         .get(NETWORK, &["ports"])
-        .query(&[("name", name.as_ref())])
-        .fetch()
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
         .await?;
     let result = utils::one(
         root.ports,
@@ -329,7 +803,10 @@ pub async fn get_router<S: AsRef<str>>(session: &Session, id_or_name: S) -> Resu
 /// Get a router by its ID.
 pub async fn get_router_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result<Router> {
     trace!("Get router by ID {}", id.as_ref());
-    let root: RouterRoot = session.get_json(NETWORK, &["routers", id.as_ref()]).await?;
+    let root: RouterRoot = session
+        .get(NETWORK, &["routers", id.as_ref()])
+        .fetch_json()
+        .await?;
     trace!("Received {:?}", root.router);
     Ok(root.router)
 }
@@ -339,8 +816,8 @@ pub async fn get_router_by_name<S: AsRef<str>>(session: &Session, name: S) -> Re
     trace!("Get router by name {}", name.as_ref());
     let root: RoutersRoot = session
         .get(NETWORK, &["routers"])
-        .query(&[("name", name.as_ref())])
-        .fetch()
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
         .await?;
     let result = utils::one(
         root.routers,
@@ -366,7 +843,10 @@ pub async fn get_subnet<S: AsRef<str>>(session: &Session, id_or_name: S) -> Resu
 /// Get a subnet by its ID.
 pub async fn get_subnet_by_id<S: AsRef<str>>(session: &Session, id: S) -> Result<Subnet> {
     trace!("Get subnet by ID {}", id.as_ref());
-    let root: SubnetRoot = session.get_json(NETWORK, &["subnets", id.as_ref()]).await?;
+    let root: SubnetRoot = session
+        .get(NETWORK, &["subnets", id.as_ref()])
+        .fetch_json()
+        .await?;
     trace!("Received {:?}", root.subnet);
     Ok(root.subnet)
 }
@@ -376,8 +856,8 @@ pub async fn get_subnet_by_name<S: AsRef<str>>(session: &Session, name: S) -> Re
     trace!("Get subnet by name {}", name.as_ref());
     let root: SubnetsRoot = session
         .get(NETWORK, &["subnets"])
-        .query(&[("name", name.as_ref())])
-        .fetch()
+        .query(&[("name", name.as_ref()), ("limit", "2")])
+        .fetch_json()
         .await?;
     let result = utils::one(
         root.subnets,
@@ -388,6 +868,91 @@ pub async fn get_subnet_by_name<S: AsRef<str>>(session: &Session, name: S) -> Re
     Ok(result)
 }
 
+/// Insert a rule into a firewall policy.
+pub async fn insert_firewall_rule<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    request: FirewallRuleInsertion,
+) -> Result<FirewallPolicy> {
+    debug!(
+        "Inserting {:?} into firewall policy {}",
+        request,
+        id.as_ref()
+    );
+    let policy: FirewallPolicy = session
+        .put(
+            NETWORK,
+            &["fwaas", "firewall_policies", id.as_ref(), "insert_rule"],
+        )
+        .json(&request)
+        .fetch_json()
+        .await?;
+    debug!("Updated firewall policy {:?}", policy);
+    Ok(policy)
+}
+
+/// List conntrack helpers of a router.
+pub async fn list_conntrack_helpers<S: AsRef<str>>(
+    session: &Session,
+    router_id: S,
+) -> Result<Vec<RouterConntrackHelper>> {
+    trace!("Listing conntrack helpers of router {}", router_id.as_ref());
+    let root: RouterConntrackHelpersRoot = session
+        .get(
+            NETWORK,
+            &["routers", router_id.as_ref(), "conntrack_helpers"],
+        )
+        .fetch_json()
+        .await?;
+    trace!("Received conntrack helpers: {:?}", root.conntrack_helpers);
+    Ok(root.conntrack_helpers)
+}
+
+/// List firewall groups.
+pub async fn list_firewall_groups<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    query: &Q,
+) -> Result<Vec<FirewallGroup>> {
+    trace!("Listing firewall groups with {:?}", query);
+    let root: FirewallGroupsRoot = session
+        .get(NETWORK, &["fwaas", "firewall_groups"])
+        .query(query)
+        .fetch_json()
+        .await?;
+    trace!("Received firewall groups: {:?}", root.firewall_groups);
+    Ok(root.firewall_groups)
+}
+
+/// List firewall policies.
+pub async fn list_firewall_policies<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    query: &Q,
+) -> Result<Vec<FirewallPolicy>> {
+    trace!("Listing firewall policies with {:?}", query);
+    let root: FirewallPoliciesRoot = session
+        .get(NETWORK, &["fwaas", "firewall_policies"])
+        .query(query)
+        .fetch_json()
+        .await?;
+    trace!("Received firewall policies: {:?}", root.firewall_policies);
+    Ok(root.firewall_policies)
+}
+
+/// List firewall rules.
+pub async fn list_firewall_rules<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    query: &Q,
+) -> Result<Vec<FirewallRule>> {
+    trace!("Listing firewall rules with {:?}", query);
+    let root: FirewallRulesRoot = session
+        .get(NETWORK, &["fwaas", "firewall_rules"])
+        .query(query)
+        .fetch_json()
+        .await?;
+    trace!("Received firewall rules: {:?}", root.firewall_rules);
+    Ok(root.firewall_rules)
+}
+
 /// List floating IPs.
 pub async fn list_floating_ips<Q: Serialize + Sync + Debug>(
     session: &Session,
@@ -397,7 +962,7 @@ pub async fn list_floating_ips<Q: Serialize + Sync + Debug>(
     let root: FloatingIpsRoot = session
         .get(NETWORK, &["floatingips"])
         .query(query)
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received floating IPs: {:?}", root.floatingips);
     Ok(root.floatingips)
@@ -412,7 +977,7 @@ pub async fn list_networks<Q: Serialize + Sync + Debug>(
     let root: NetworksRoot = session
         .get(NETWORK, &["networks"])
         .query(query)
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received networks: {:?}", root.networks);
     Ok(root.networks)
@@ -427,7 +992,7 @@ pub async fn list_ports<Q: Serialize + Sync + Debug>(
     let root: PortsRoot = session
         .get(NETWORK, &["ports"])
         .query(query)
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received ports: {:?}", root.ports);
     Ok(root.ports)
@@ -442,7 +1007,7 @@ pub async fn list_routers<Q: Serialize + Sync + Debug>(
     let root: RoutersRoot = session
         .get(NETWORK, &["routers"])
         .query(query)
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received routers: {:?}", root.routers);
     Ok(root.routers)
@@ -457,12 +1022,44 @@ pub async fn list_subnets<Q: Serialize + Sync + Debug>(
     let root: SubnetsRoot = session
         .get(NETWORK, &["subnets"])
         .query(query)
-        .fetch()
+        .fetch_json()
         .await?;
     trace!("Received subnets: {:?}", root.subnets);
     Ok(root.subnets)
 }
 
+/// Remove a rule from a firewall policy.
+pub async fn remove_firewall_rule<S1, S2>(
+    session: &Session,
+    id: S1,
+    rule_id: S2,
+) -> Result<FirewallPolicy>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    debug!(
+        "Removing firewall rule {} from firewall policy {}",
+        rule_id.as_ref(),
+        id.as_ref()
+    );
+    let request = FirewallRuleInsertion {
+        firewall_rule_id: rule_id.as_ref().into(),
+        insert_after: None,
+        insert_before: None,
+    };
+    let policy: FirewallPolicy = session
+        .put(
+            NETWORK,
+            &["fwaas", "firewall_policies", id.as_ref(), "remove_rule"],
+        )
+        .json(&request)
+        .fetch_json()
+        .await?;
+    debug!("Updated firewall policy {:?}", policy);
+    Ok(policy)
+}
+
 /// Remove an interface from a router.
 pub async fn remove_router_interface<S>(
     session: &Session,
@@ -513,6 +1110,100 @@ where
     Ok(())
 }
 
+/// Update a conntrack helper of a router.
+pub async fn update_conntrack_helper<S1, S2>(
+    session: &Session,
+    router_id: S1,
+    id: S2,
+    update: ConntrackHelper,
+) -> Result<RouterConntrackHelper>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    debug!(
+        "Updating conntrack helper {} of router {} with {:?}",
+        id.as_ref(),
+        router_id.as_ref(),
+        update
+    );
+    let body = ConntrackHelperCreateRoot {
+        conntrack_helper: update,
+    };
+    let root: RouterConntrackHelperRoot = session
+        .put(
+            NETWORK,
+            &[
+                "routers",
+                router_id.as_ref(),
+                "conntrack_helpers",
+                id.as_ref(),
+            ],
+        )
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Updated conntrack helper {:?}", root.conntrack_helper);
+    Ok(root.conntrack_helper)
+}
+
+/// Update a firewall group.
+pub async fn update_firewall_group<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    update: FirewallGroupUpdate,
+) -> Result<FirewallGroup> {
+    debug!("Updating firewall group {} with {:?}", id.as_ref(), update);
+    let body = FirewallGroupUpdateRoot {
+        firewall_group: update,
+    };
+    let root: FirewallGroupRoot = session
+        .put(NETWORK, &["fwaas", "firewall_groups", id.as_ref()])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Updated firewall group {:?}", root.firewall_group);
+    Ok(root.firewall_group)
+}
+
+/// Update a firewall policy.
+pub async fn update_firewall_policy<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    update: FirewallPolicyUpdate,
+) -> Result<FirewallPolicy> {
+    debug!("Updating firewall policy {} with {:?}", id.as_ref(), update);
+    let body = FirewallPolicyUpdateRoot {
+        firewall_policy: update,
+    };
+    let root: FirewallPolicyRoot = session
+        .put(NETWORK, &["fwaas", "firewall_policies", id.as_ref()])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Updated firewall policy {:?}", root.firewall_policy);
+    Ok(root.firewall_policy)
+}
+
+/// Update a firewall rule.
+pub async fn update_firewall_rule<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+    update: FirewallRuleUpdate,
+) -> Result<FirewallRule> {
+    debug!("Updating firewall rule {} with {:?}", id.as_ref(), update);
+    let body = FirewallRuleUpdateRoot {
+        firewall_rule: update,
+    };
+    let root: FirewallRuleRoot = session
+        .put(NETWORK, &["fwaas", "firewall_rules", id.as_ref()])
+        .json(&body)
+        .fetch_json()
+        .await?;
+    debug!("Updated firewall rule {:?}", root.firewall_rule);
+    Ok(root.firewall_rule)
+}
+
 /// Update a floating IP.
 pub async fn update_floating_ip<S: AsRef<str>>(
     session: &Session,
@@ -524,7 +1215,7 @@ pub async fn update_floating_ip<S: AsRef<str>>(
     let root: FloatingIpRoot = session
         .put(NETWORK, &["floatingips", id.as_ref()])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Updated floating IP {:?}", root.floatingip);
     Ok(root.floatingip)
@@ -541,7 +1232,7 @@ pub async fn update_network<S: AsRef<str>>(
     let root: NetworkRoot = session
         .put(NETWORK, &["networks", id.as_ref()])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Updated network {:?}", root.network);
     Ok(root.network)
@@ -558,7 +1249,7 @@ pub async fn update_port<S: AsRef<str>>(
     let root: PortRoot = session
         .put(NETWORK, &["ports", id.as_ref()])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Updated port {:?}", root.port);
     Ok(root.port)
@@ -575,7 +1266,7 @@ pub async fn update_router<S: AsRef<str>>(
     let root: RouterRoot = session
         .put(NETWORK, &["routers", id.as_ref()])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Updated router {:?}", root.router);
     Ok(root.router)
@@ -592,7 +1283,7 @@ pub async fn update_subnet<S: AsRef<str>>(
     let root: SubnetRoot = session
         .put(NETWORK, &["subnets", id.as_ref()])
         .json(&body)
-        .fetch()
+        .fetch_json()
         .await?;
     debug!("Updated subnet {:?}", root.subnet);
     Ok(root.subnet)
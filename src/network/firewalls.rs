@@ -0,0 +1,1632 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Firewall groups, policies and rules (FWaaS v2).
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::stream::{Stream, TryStreamExt};
+
+use super::super::common::{
+    FirewallGroupRef, FirewallPolicyRef, FirewallRuleRef, PortRef, Refresh, ResourceIterator,
+    ResourceQuery, ValidationReport,
+};
+use super::super::session::Session;
+use super::super::utils::{NamePattern, Query};
+use super::super::waiter::DeletionWaiter;
+use super::super::{Error, ErrorKind, Result};
+use super::{api, protocol, Port};
+
+/// A query to firewall group list.
+#[derive(Clone, Debug)]
+pub struct FirewallGroupQuery {
+    session: Session,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    name_pattern: Option<NamePattern>,
+}
+
+/// Structure representing a single firewall group.
+///
+/// A firewall group applies its ingress and egress policies to the ports
+/// associated with it, usually router interfaces.
+#[derive(Clone, Debug)]
+pub struct FirewallGroup {
+    session: Session,
+    inner: protocol::FirewallGroup,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a firewall group.
+#[derive(Clone, Debug)]
+pub struct NewFirewallGroup {
+    session: Session,
+    inner: protocol::FirewallGroup,
+    egress_firewall_policy: Option<FirewallPolicyRef>,
+    ingress_firewall_policy: Option<FirewallPolicyRef>,
+    ports: Vec<PortRef>,
+}
+
+/// A query to firewall policy list.
+#[derive(Clone, Debug)]
+pub struct FirewallPolicyQuery {
+    session: Session,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    name_pattern: Option<NamePattern>,
+}
+
+/// Structure representing a single firewall policy.
+///
+/// A firewall policy is an ordered list of firewall rules.
+#[derive(Clone, Debug)]
+pub struct FirewallPolicy {
+    session: Session,
+    inner: protocol::FirewallPolicy,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a firewall policy.
+#[derive(Clone, Debug)]
+pub struct NewFirewallPolicy {
+    session: Session,
+    inner: protocol::FirewallPolicy,
+    firewall_rules: Vec<FirewallRuleRef>,
+}
+
+/// A query to firewall rule list.
+#[derive(Clone, Debug)]
+pub struct FirewallRuleQuery {
+    session: Session,
+    query: Query,
+    can_paginate: bool,
+    page_size: Option<usize>,
+    name_pattern: Option<NamePattern>,
+}
+
+/// Structure representing a single firewall rule.
+#[derive(Clone, Debug)]
+pub struct FirewallRule {
+    session: Session,
+    inner: protocol::FirewallRule,
+    dirty: HashSet<&'static str>,
+}
+
+/// A request to create a firewall rule.
+#[derive(Clone, Debug)]
+pub struct NewFirewallRule {
+    session: Session,
+    inner: protocol::FirewallRule,
+}
+
+impl FirewallGroup {
+    /// Create a firewall group object.
+    fn new(session: Session, inner: protocol::FirewallGroup) -> FirewallGroup {
+        FirewallGroup {
+            session,
+            inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a FirewallGroup object.
+    pub(crate) async fn load<Id: AsRef<str>>(session: Session, id: Id) -> Result<FirewallGroup> {
+        let inner = api::get_firewall_group(&session, id).await?;
+        Ok(FirewallGroup::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "The administrative state of the firewall group."]
+        admin_state_up: bool
+    }
+
+    update_field! {
+        #[doc = "Set the administrative state of the firewall group."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    }
+
+    transparent_property! {
+        #[doc = "Firewall group description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the policy applied to the outgoing traffic (if any)."]
+        egress_firewall_policy_id: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the policy applied to the outgoing traffic (ID or name)."]
+        set_egress_firewall_policy_id, with_egress_firewall_policy_id -> egress_firewall_policy_id: optional String
+    }
+
+    /// Get the policy applied to the outgoing traffic (if any).
+    pub async fn egress_firewall_policy(&self) -> Result<Option<FirewallPolicy>> {
+        match self.inner.egress_firewall_policy_id {
+            Some(ref id) => Ok(Some(FirewallPolicy::load(self.session.clone(), id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the policy applied to the incoming traffic (if any)."]
+        ingress_firewall_policy_id: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the policy applied to the incoming traffic (ID or name)."]
+        set_ingress_firewall_policy_id, with_ingress_firewall_policy_id -> ingress_firewall_policy_id: optional String
+    }
+
+    /// Get the policy applied to the incoming traffic (if any).
+    pub async fn ingress_firewall_policy(&self) -> Result<Option<FirewallPolicy>> {
+        match self.inner.ingress_firewall_policy_id {
+            Some(ref id) => Ok(Some(FirewallPolicy::load(self.session.clone(), id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    transparent_property! {
+        #[doc = "Firewall group name."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    transparent_property! {
+        #[doc = "IDs of the ports associated with the firewall group."]
+        ports: ref Vec<String>
+    }
+
+    transparent_property! {
+        #[doc = "Project ID."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Whether the firewall group is shared with other projects."]
+        shared: bool
+    }
+
+    update_field! {
+        #[doc = "Update whether the firewall group is shared with other projects."]
+        set_shared, with_shared -> shared: bool
+    }
+
+    transparent_property! {
+        #[doc = "Status of the firewall group."]
+        status: clone protocol::FirewallGroupStatus
+    }
+
+    /// Associate a port (usually a router interface) with the firewall group.
+    ///
+    /// The change is applied immediately. Does nothing if the port is already
+    /// associated. Fails if the firewall group has unsaved changes.
+    pub async fn add_port<P: Into<PortRef>>(&mut self, port: P) -> Result<()> {
+        self.ensure_clean()?;
+        let port = port.into().into_verified(&self.session).await?;
+        if self.inner.ports.iter().any(|item| item == port.as_ref()) {
+            return Ok(());
+        }
+        let mut ports = self.inner.ports.clone();
+        ports.push(port.into());
+        self.update_ports(ports).await
+    }
+
+    /// Dissociate a port from the firewall group.
+    ///
+    /// The change is applied immediately. Does nothing if the port is not
+    /// associated. Fails if the firewall group has unsaved changes.
+    pub async fn remove_port<P: Into<PortRef>>(&mut self, port: P) -> Result<()> {
+        self.ensure_clean()?;
+        let port = port.into().into_verified(&self.session).await?;
+        if !self.inner.ports.iter().any(|item| item == port.as_ref()) {
+            return Ok(());
+        }
+        let ports = self
+            .inner
+            .ports
+            .iter()
+            .filter(|item| *item != port.as_ref())
+            .cloned()
+            .collect();
+        self.update_ports(ports).await
+    }
+
+    /// List the ports associated with the firewall group.
+    pub async fn list_ports(&self) -> Result<Vec<Port>> {
+        let mut result = Vec::with_capacity(self.inner.ports.len());
+        for id in &self.inner.ports {
+            result.push(Port::load(self.session.clone(), id).await?);
+        }
+        Ok(result)
+    }
+
+    fn ensure_clean(&self) -> Result<()> {
+        if self.is_dirty() {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The firewall group has unsaved changes",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn update_ports(&mut self, ports: Vec<String>) -> Result<()> {
+        let update = protocol::FirewallGroupUpdate {
+            ports: Some(ports),
+            ..Default::default()
+        };
+        self.inner = api::update_firewall_group(&self.session, &self.inner.id, update).await?;
+        Ok(())
+    }
+
+    /// Delete the firewall group.
+    pub async fn delete(self) -> Result<DeletionWaiter<FirewallGroup>> {
+        api::delete_firewall_group(&self.session, &self.inner.id).await?;
+        Ok(DeletionWaiter::new(
+            self,
+            Duration::new(60, 0),
+            Duration::new(1, 0),
+        ))
+    }
+
+    /// Whether the firewall group is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the firewall group.
+    pub async fn save(&mut self) -> Result<()> {
+        let mut update = protocol::FirewallGroupUpdate::default();
+        save_fields! {
+            self -> update: admin_state_up shared
+        };
+        save_option_fields! {
+            self -> update: description egress_firewall_policy_id
+                ingress_firewall_policy_id name
+        };
+        if let Some(id) = update.egress_firewall_policy_id.take() {
+            update.egress_firewall_policy_id = Some(
+                FirewallPolicyRef::from(id)
+                    .into_verified(&self.session)
+                    .await?
+                    .into(),
+            );
+        }
+        if let Some(id) = update.ingress_firewall_policy_id.take() {
+            update.ingress_firewall_policy_id = Some(
+                FirewallPolicyRef::from(id)
+                    .into_verified(&self.session)
+                    .await?
+                    .into(),
+            );
+        }
+        let inner = api::update_firewall_group(&self.session, self.id(), update).await?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Refresh for FirewallGroup {
+    /// Refresh the firewall group.
+    async fn refresh(&mut self) -> Result<()> {
+        self.inner = api::get_firewall_group_by_id(&self.session, &self.inner.id).await?;
+        self.dirty.clear();
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+}
+
+impl FirewallGroupQuery {
+    pub(crate) fn new(session: Session) -> FirewallGroupQuery {
+        FirewallGroupQuery {
+            session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            name_pattern: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Filter by firewall group name.
+    pub fn with_name<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("name", value);
+        self
+    }
+
+    query_name_pattern!("firewall groups");
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        with_project_id -> project_id
+    }
+
+    /// Convert this query into an stream executing the request.
+    ///
+    /// Returns a `TryStream`, which is a stream with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream(self) -> impl Stream<Item = Result<FirewallGroup>> {
+        debug!("Fetching firewall groups with {:?}", self.query);
+        ResourceIterator::new(self.session.clone(), self).into_stream()
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub async fn all(self) -> Result<Vec<FirewallGroup>> {
+        self.into_stream().try_collect().await
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub async fn one(mut self) -> Result<FirewallGroup> {
+        debug!("Fetching one firewall group with {:?}", self.query);
+        if self.can_paginate && self.name_pattern.is_none() {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
+    }
+}
+
+#[async_trait]
+impl ResourceQuery for FirewallGroupQuery {
+    type Item = FirewallGroup;
+
+    const DEFAULT_LIMIT: usize = 50;
+
+    async fn can_paginate(&self) -> Result<bool> {
+        Ok(self.can_paginate)
+    }
+
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
+    fn extract_marker(&self, resource: &Self::Item) -> String {
+        resource.id().clone()
+    }
+
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
+    async fn fetch_chunk(
+        &self,
+        limit: Option<usize>,
+        marker: Option<String>,
+    ) -> Result<Vec<Self::Item>> {
+        let query = self.query.with_marker_and_limit(limit, marker);
+        Ok(api::list_firewall_groups(&self.session, &query)
+            .await?
+            .into_iter()
+            .map(|item| FirewallGroup::new(self.session.clone(), item))
+            .collect())
+    }
+}
+
+impl NewFirewallGroup {
+    /// Start creating a firewall group.
+    pub(crate) fn new(session: Session) -> NewFirewallGroup {
+        NewFirewallGroup {
+            session,
+            inner: protocol::FirewallGroup::default(),
+            egress_firewall_policy: None,
+            ingress_firewall_policy: None,
+            ports: Vec::new(),
+        }
+    }
+
+    /// Request creation of a firewall group.
+    pub async fn create(mut self) -> Result<FirewallGroup> {
+        if let Some(policy) = self.egress_firewall_policy {
+            self.inner.egress_firewall_policy_id =
+                Some(policy.into_verified(&self.session).await?.into());
+        }
+        if let Some(policy) = self.ingress_firewall_policy {
+            self.inner.ingress_firewall_policy_id =
+                Some(policy.into_verified(&self.session).await?.into());
+        }
+        for port in self.ports {
+            self.inner
+                .ports
+                .push(port.into_verified(&self.session).await?.into());
+        }
+        let inner = api::create_firewall_group(&self.session, self.inner).await?;
+        Ok(FirewallGroup::new(self.session, inner))
+    }
+
+    /// Check the request for all detectable problems without creating the firewall group.
+    ///
+    /// Verifies that the policies and the ports exist.
+    pub async fn verify(&self) -> Result<ValidationReport> {
+        let mut report = ValidationReport::default();
+        for policy in self
+            .egress_firewall_policy
+            .iter()
+            .chain(self.ingress_firewall_policy.iter())
+        {
+            report.check_found(
+                policy.clone().into_verified(&self.session).await,
+                format_args!("firewall policy {policy}"),
+            )?;
+        }
+        for port in &self.ports {
+            report.check_found(
+                port.clone().into_verified(&self.session).await,
+                format_args!("port {port}"),
+            )?;
+        }
+        Ok(report)
+    }
+
+    creation_inner_field! {
+        #[doc = "Set administrative status for the firewall group."]
+        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the firewall group."]
+        set_description, with_description -> description: optional String
+    }
+
+    /// Set the policy to apply to the outgoing traffic.
+    pub fn set_egress_firewall_policy<P: Into<FirewallPolicyRef>>(&mut self, value: P) {
+        self.egress_firewall_policy = Some(value.into());
+    }
+
+    /// Set the policy to apply to the outgoing traffic.
+    #[inline]
+    pub fn with_egress_firewall_policy<P: Into<FirewallPolicyRef>>(mut self, value: P) -> Self {
+        self.set_egress_firewall_policy(value);
+        self
+    }
+
+    /// Set the policy to apply to the incoming traffic.
+    pub fn set_ingress_firewall_policy<P: Into<FirewallPolicyRef>>(&mut self, value: P) {
+        self.ingress_firewall_policy = Some(value.into());
+    }
+
+    /// Set the policy to apply to the incoming traffic.
+    #[inline]
+    pub fn with_ingress_firewall_policy<P: Into<FirewallPolicyRef>>(mut self, value: P) -> Self {
+        self.set_ingress_firewall_policy(value);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a name for the firewall group."]
+        set_name, with_name -> name: optional String
+    }
+
+    /// Associate a port (usually a router interface) with the firewall group.
+    pub fn add_port<P: Into<PortRef>>(&mut self, value: P) {
+        self.ports.push(value.into());
+    }
+
+    /// Associate a port (usually a router interface) with the firewall group.
+    #[inline]
+    pub fn with_port<P: Into<PortRef>>(mut self, value: P) -> Self {
+        self.add_port(value);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a project id for the firewall group."]
+        set_project_id, with_project_id -> project_id: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the firewall group is shared with other projects."]
+        set_shared, with_shared -> shared: bool
+    }
+}
+
+impl From<FirewallGroup> for FirewallGroupRef {
+    fn from(value: FirewallGroup) -> FirewallGroupRef {
+        FirewallGroupRef::new_verified(value.inner.id)
+    }
+}
+
+#[cfg(feature = "network")]
+impl FirewallGroupRef {
+    /// Verify this reference and convert to an ID, if possible.
+    #[allow(unused)]
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<FirewallGroupRef> {
+        Ok(if self.verified {
+            self
+        } else {
+            FirewallGroupRef::new_verified(api::get_firewall_group(session, &self.value).await?.id)
+        })
+    }
+}
+
+impl FirewallPolicy {
+    /// Create a firewall policy object.
+    fn new(session: Session, inner: protocol::FirewallPolicy) -> FirewallPolicy {
+        FirewallPolicy {
+            session,
+            inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a FirewallPolicy object.
+    pub(crate) async fn load<Id: AsRef<str>>(session: Session, id: Id) -> Result<FirewallPolicy> {
+        let inner = api::get_firewall_policy(&session, id).await?;
+        Ok(FirewallPolicy::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Whether the policy has been audited."]
+        audited: bool
+    }
+
+    update_field! {
+        #[doc = "Update whether the policy has been audited."]
+        set_audited, with_audited -> audited: bool
+    }
+
+    transparent_property! {
+        #[doc = "Firewall policy description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "IDs of the rules of the policy in the order of evaluation."]
+        firewall_rules: ref Vec<String>
+    }
+
+    update_field! {
+        #[doc = "Replace the rules of the policy (IDs in the order of evaluation)."]
+        set_firewall_rules, with_firewall_rules -> firewall_rules: Vec<String>
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "Firewall policy name."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Project ID."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Whether the policy is shared with other projects."]
+        shared: bool
+    }
+
+    update_field! {
+        #[doc = "Update whether the policy is shared with other projects."]
+        set_shared, with_shared -> shared: bool
+    }
+
+    /// List the rules of the policy in the order of evaluation.
+    pub async fn list_rules(&self) -> Result<Vec<FirewallRule>> {
+        let mut result = Vec::with_capacity(self.inner.firewall_rules.len());
+        for id in &self.inner.firewall_rules {
+            result.push(FirewallRule::load(self.session.clone(), id).await?);
+        }
+        Ok(result)
+    }
+
+    /// Insert a rule at the beginning of the policy.
+    ///
+    /// The change is applied immediately.
+    pub async fn insert_rule<R: Into<FirewallRuleRef>>(&mut self, rule: R) -> Result<()> {
+        self.insert_rule_at(rule.into(), None, None).await
+    }
+
+    /// Insert a rule after another rule of the policy.
+    ///
+    /// The change is applied immediately.
+    pub async fn insert_rule_after<R1, R2>(&mut self, rule: R1, after: R2) -> Result<()>
+    where
+        R1: Into<FirewallRuleRef>,
+        R2: Into<FirewallRuleRef>,
+    {
+        self.insert_rule_at(rule.into(), Some(after.into()), None)
+            .await
+    }
+
+    /// Insert a rule before another rule of the policy.
+    ///
+    /// The change is applied immediately.
+    pub async fn insert_rule_before<R1, R2>(&mut self, rule: R1, before: R2) -> Result<()>
+    where
+        R1: Into<FirewallRuleRef>,
+        R2: Into<FirewallRuleRef>,
+    {
+        self.insert_rule_at(rule.into(), None, Some(before.into()))
+            .await
+    }
+
+    async fn insert_rule_at(
+        &mut self,
+        rule: FirewallRuleRef,
+        after: Option<FirewallRuleRef>,
+        before: Option<FirewallRuleRef>,
+    ) -> Result<()> {
+        let request = self.rule_insertion(rule, after, before).await?;
+        let inner = api::insert_firewall_rule(&self.session, &self.inner.id, request).await?;
+        self.inner.firewall_rules = inner.firewall_rules;
+        Ok(())
+    }
+
+    async fn rule_insertion(
+        &self,
+        rule: FirewallRuleRef,
+        after: Option<FirewallRuleRef>,
+        before: Option<FirewallRuleRef>,
+    ) -> Result<protocol::FirewallRuleInsertion> {
+        Ok(protocol::FirewallRuleInsertion {
+            firewall_rule_id: rule.into_verified(&self.session).await?.into(),
+            insert_after: match after {
+                Some(value) => Some(value.into_verified(&self.session).await?.into()),
+                None => None,
+            },
+            insert_before: match before {
+                Some(value) => Some(value.into_verified(&self.session).await?.into()),
+                None => None,
+            },
+        })
+    }
+
+    /// Remove a rule from the policy.
+    ///
+    /// The change is applied immediately, the rule itself is not deleted.
+    pub async fn remove_rule<R: Into<FirewallRuleRef>>(&mut self, rule: R) -> Result<()> {
+        let rule = rule.into().into_verified(&self.session).await?;
+        let inner = api::remove_firewall_rule(&self.session, &self.inner.id, rule).await?;
+        self.inner.firewall_rules = inner.firewall_rules;
+        Ok(())
+    }
+
+    /// Delete the firewall policy.
+    pub async fn delete(self) -> Result<DeletionWaiter<FirewallPolicy>> {
+        api::delete_firewall_policy(&self.session, &self.inner.id).await?;
+        Ok(DeletionWaiter::new(
+            self,
+            Duration::new(60, 0),
+            Duration::new(1, 0),
+        ))
+    }
+
+    /// Whether the firewall policy is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the firewall policy.
+    pub async fn save(&mut self) -> Result<()> {
+        let mut update = protocol::FirewallPolicyUpdate::default();
+        save_fields! {
+            self -> update: audited firewall_rules shared
+        };
+        save_option_fields! {
+            self -> update: description name
+        };
+        let inner = api::update_firewall_policy(&self.session, self.id(), update).await?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Refresh for FirewallPolicy {
+    /// Refresh the firewall policy.
+    async fn refresh(&mut self) -> Result<()> {
+        self.inner = api::get_firewall_policy_by_id(&self.session, &self.inner.id).await?;
+        self.dirty.clear();
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+}
+
+impl FirewallPolicyQuery {
+    pub(crate) fn new(session: Session) -> FirewallPolicyQuery {
+        FirewallPolicyQuery {
+            session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            name_pattern: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Filter by firewall policy name.
+    pub fn with_name<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("name", value);
+        self
+    }
+
+    query_name_pattern!("firewall policies");
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        with_project_id -> project_id
+    }
+
+    /// Convert this query into an stream executing the request.
+    ///
+    /// Returns a `TryStream`, which is a stream with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream(self) -> impl Stream<Item = Result<FirewallPolicy>> {
+        debug!("Fetching firewall policies with {:?}", self.query);
+        ResourceIterator::new(self.session.clone(), self).into_stream()
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub async fn all(self) -> Result<Vec<FirewallPolicy>> {
+        self.into_stream().try_collect().await
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub async fn one(mut self) -> Result<FirewallPolicy> {
+        debug!("Fetching one firewall policy with {:?}", self.query);
+        if self.can_paginate && self.name_pattern.is_none() {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
+    }
+}
+
+#[async_trait]
+impl ResourceQuery for FirewallPolicyQuery {
+    type Item = FirewallPolicy;
+
+    const DEFAULT_LIMIT: usize = 50;
+
+    async fn can_paginate(&self) -> Result<bool> {
+        Ok(self.can_paginate)
+    }
+
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
+    fn extract_marker(&self, resource: &Self::Item) -> String {
+        resource.id().clone()
+    }
+
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
+    async fn fetch_chunk(
+        &self,
+        limit: Option<usize>,
+        marker: Option<String>,
+    ) -> Result<Vec<Self::Item>> {
+        let query = self.query.with_marker_and_limit(limit, marker);
+        Ok(api::list_firewall_policies(&self.session, &query)
+            .await?
+            .into_iter()
+            .map(|item| FirewallPolicy::new(self.session.clone(), item))
+            .collect())
+    }
+}
+
+impl NewFirewallPolicy {
+    /// Start creating a firewall policy.
+    pub(crate) fn new(session: Session) -> NewFirewallPolicy {
+        NewFirewallPolicy {
+            session,
+            inner: protocol::FirewallPolicy::default(),
+            firewall_rules: Vec::new(),
+        }
+    }
+
+    /// Request creation of a firewall policy.
+    pub async fn create(mut self) -> Result<FirewallPolicy> {
+        for rule in self.firewall_rules {
+            self.inner
+                .firewall_rules
+                .push(rule.into_verified(&self.session).await?.into());
+        }
+        let inner = api::create_firewall_policy(&self.session, self.inner).await?;
+        Ok(FirewallPolicy::new(self.session, inner))
+    }
+
+    /// Check the request for all detectable problems without creating the firewall policy.
+    ///
+    /// Verifies that the rules exist.
+    pub async fn verify(&self) -> Result<ValidationReport> {
+        let mut report = ValidationReport::default();
+        for rule in &self.firewall_rules {
+            report.check_found(
+                rule.clone().into_verified(&self.session).await,
+                format_args!("firewall rule {rule}"),
+            )?;
+        }
+        Ok(report)
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the policy has been audited."]
+        set_audited, with_audited -> audited: bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the firewall policy."]
+        set_description, with_description -> description: optional String
+    }
+
+    /// Append a rule to the policy.
+    ///
+    /// Rules are evaluated in the order they are added.
+    pub fn add_firewall_rule<R: Into<FirewallRuleRef>>(&mut self, value: R) {
+        self.firewall_rules.push(value.into());
+    }
+
+    /// Append a rule to the policy.
+    ///
+    /// Rules are evaluated in the order they are added.
+    #[inline]
+    pub fn with_firewall_rule<R: Into<FirewallRuleRef>>(mut self, value: R) -> Self {
+        self.add_firewall_rule(value);
+        self
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a name for the firewall policy."]
+        set_name, with_name -> name: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a project id for the firewall policy."]
+        set_project_id, with_project_id -> project_id: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the policy is shared with other projects."]
+        set_shared, with_shared -> shared: bool
+    }
+}
+
+impl From<FirewallPolicy> for FirewallPolicyRef {
+    fn from(value: FirewallPolicy) -> FirewallPolicyRef {
+        FirewallPolicyRef::new_verified(value.inner.id)
+    }
+}
+
+#[cfg(feature = "network")]
+impl FirewallPolicyRef {
+    /// Verify this reference and convert to an ID, if possible.
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<FirewallPolicyRef> {
+        Ok(if self.verified {
+            self
+        } else {
+            FirewallPolicyRef::new_verified(
+                api::get_firewall_policy(session, &self.value).await?.id,
+            )
+        })
+    }
+}
+
+impl FirewallRule {
+    /// Create a firewall rule object.
+    fn new(session: Session, inner: protocol::FirewallRule) -> FirewallRule {
+        FirewallRule {
+            session,
+            inner,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load a FirewallRule object.
+    pub(crate) async fn load<Id: AsRef<str>>(session: Session, id: Id) -> Result<FirewallRule> {
+        let inner = api::get_firewall_rule(&session, id).await?;
+        Ok(FirewallRule::new(session, inner))
+    }
+
+    transparent_property! {
+        #[doc = "Action taken on the matching traffic."]
+        action: protocol::FirewallAction
+    }
+
+    update_field! {
+        #[doc = "Update the action taken on the matching traffic."]
+        set_action, with_action -> action: protocol::FirewallAction
+    }
+
+    transparent_property! {
+        #[doc = "Firewall rule description."]
+        description: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the description."]
+        set_description, with_description -> description: optional String
+    }
+
+    transparent_property! {
+        #[doc = "ID of the destination firewall group (if any)."]
+        destination_firewall_group_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Destination IP address or CIDR (if any)."]
+        destination_ip_address: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the destination IP address or CIDR."]
+        set_destination_ip_address, with_destination_ip_address -> destination_ip_address: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Destination port or port range, e.g. `80` or `8000:8080` (if any)."]
+        destination_port: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the destination port or port range."]
+        set_destination_port, with_destination_port -> destination_port: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Whether the rule is enabled."]
+        enabled: bool
+    }
+
+    update_field! {
+        #[doc = "Enable or disable the rule."]
+        set_enabled, with_enabled -> enabled: bool
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        id: ref String
+    }
+
+    transparent_property! {
+        #[doc = "IP version of the rule."]
+        ip_version: protocol::IpVersion
+    }
+
+    transparent_property! {
+        #[doc = "Firewall rule name."]
+        name: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the name."]
+        set_name, with_name -> name: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Project ID."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "IP protocol matched by the rule (`None` for any)."]
+        protocol: Option<protocol::FirewallProtocol>
+    }
+
+    update_field! {
+        #[doc = "Update the IP protocol matched by the rule."]
+        set_protocol, with_protocol -> protocol: optional protocol::FirewallProtocol
+    }
+
+    transparent_property! {
+        #[doc = "Whether the rule is shared with other projects."]
+        shared: bool
+    }
+
+    update_field! {
+        #[doc = "Update whether the rule is shared with other projects."]
+        set_shared, with_shared -> shared: bool
+    }
+
+    transparent_property! {
+        #[doc = "ID of the source firewall group (if any)."]
+        source_firewall_group_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Source IP address or CIDR (if any)."]
+        source_ip_address: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the source IP address or CIDR."]
+        set_source_ip_address, with_source_ip_address -> source_ip_address: optional String
+    }
+
+    transparent_property! {
+        #[doc = "Source port or port range, e.g. `80` or `8000:8080` (if any)."]
+        source_port: ref Option<String>
+    }
+
+    update_field! {
+        #[doc = "Update the source port or port range."]
+        set_source_port, with_source_port -> source_port: optional String
+    }
+
+    /// Delete the firewall rule.
+    ///
+    /// A rule cannot be deleted while it is used in a policy.
+    pub async fn delete(self) -> Result<DeletionWaiter<FirewallRule>> {
+        api::delete_firewall_rule(&self.session, &self.inner.id).await?;
+        Ok(DeletionWaiter::new(
+            self,
+            Duration::new(60, 0),
+            Duration::new(1, 0),
+        ))
+    }
+
+    /// Whether the firewall rule is modified.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Save the changes to the firewall rule.
+    pub async fn save(&mut self) -> Result<()> {
+        let mut update = protocol::FirewallRuleUpdate::default();
+        save_fields! {
+            self -> update: action enabled shared
+        };
+        save_option_fields! {
+            self -> update: description destination_ip_address destination_port
+                name protocol source_ip_address source_port
+        };
+        let inner = api::update_firewall_rule(&self.session, self.id(), update).await?;
+        self.dirty.clear();
+        self.inner = inner;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Refresh for FirewallRule {
+    /// Refresh the firewall rule.
+    async fn refresh(&mut self) -> Result<()> {
+        self.inner = api::get_firewall_rule_by_id(&self.session, &self.inner.id).await?;
+        self.dirty.clear();
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+}
+
+impl FirewallRuleQuery {
+    pub(crate) fn new(session: Session) -> FirewallRuleQuery {
+        FirewallRuleQuery {
+            session,
+            query: Query::new(),
+            can_paginate: true,
+            page_size: None,
+            name_pattern: None,
+        }
+    }
+
+    /// Add marker to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_marker<T: Into<String>>(mut self, marker: T) -> Self {
+        self.can_paginate = false;
+        self.query.push_str("marker", marker);
+        self
+    }
+
+    /// Add limit to the request.
+    ///
+    /// Using this disables automatic pagination.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.can_paginate = false;
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Filter by action.
+    pub fn with_action(mut self, value: protocol::FirewallAction) -> Self {
+        self.query.push_str("action", value);
+        self
+    }
+
+    /// Filter by whether the rule is enabled.
+    pub fn with_enabled(mut self, value: bool) -> Self {
+        self.query.push("enabled", value);
+        self
+    }
+
+    /// Filter by firewall rule name.
+    pub fn with_name<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("name", value);
+        self
+    }
+
+    query_name_pattern!("firewall rules");
+
+    query_filter! {
+        #[doc = "Filter by project ID."]
+        with_project_id -> project_id
+    }
+
+    /// Convert this query into an stream executing the request.
+    ///
+    /// Returns a `TryStream`, which is a stream with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream(self) -> impl Stream<Item = Result<FirewallRule>> {
+        debug!("Fetching firewall rules with {:?}", self.query);
+        ResourceIterator::new(self.session.clone(), self).into_stream()
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_iter().collect()`.
+    pub async fn all(self) -> Result<Vec<FirewallRule>> {
+        self.into_stream().try_collect().await
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub async fn one(mut self) -> Result<FirewallRule> {
+        debug!("Fetching one firewall rule with {:?}", self.query);
+        if self.can_paginate && self.name_pattern.is_none() {
+            // We need only one result. We fetch maximum two to be able
+            // to check if the query yieled more than one result.
+            self.query.push("limit", 2);
+        }
+
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
+    }
+}
+
+#[async_trait]
+impl ResourceQuery for FirewallRuleQuery {
+    type Item = FirewallRule;
+
+    const DEFAULT_LIMIT: usize = 50;
+
+    async fn can_paginate(&self) -> Result<bool> {
+        Ok(self.can_paginate)
+    }
+
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
+    fn extract_marker(&self, resource: &Self::Item) -> String {
+        resource.id().clone()
+    }
+
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
+    async fn fetch_chunk(
+        &self,
+        limit: Option<usize>,
+        marker: Option<String>,
+    ) -> Result<Vec<Self::Item>> {
+        let query = self.query.with_marker_and_limit(limit, marker);
+        Ok(api::list_firewall_rules(&self.session, &query)
+            .await?
+            .into_iter()
+            .map(|item| FirewallRule::new(self.session.clone(), item))
+            .collect())
+    }
+}
+
+impl NewFirewallRule {
+    /// Start creating a firewall rule.
+    pub(crate) fn new(session: Session, action: protocol::FirewallAction) -> NewFirewallRule {
+        NewFirewallRule {
+            session,
+            inner: protocol::FirewallRule {
+                action,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Request creation of a firewall rule.
+    ///
+    /// Fails with all problems found by [validate](#method.validate) before
+    /// sending any requests.
+    pub async fn create(self) -> Result<FirewallRule> {
+        self.validate().into_result()?;
+        let inner = api::create_firewall_rule(&self.session, self.inner).await?;
+        Ok(FirewallRule::new(self.session, inner))
+    }
+
+    /// Check the request for problems that can be detected locally.
+    ///
+    /// Verifies that ports are only used with TCP or UDP.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let has_ports = self.inner.source_port.is_some() || self.inner.destination_port.is_some();
+        let supports_ports = matches!(
+            self.inner.protocol,
+            Some(protocol::FirewallProtocol::Tcp) | Some(protocol::FirewallProtocol::Udp)
+        );
+        if has_ports && !supports_ports {
+            report.add("source and destination ports require the TCP or UDP protocol");
+        }
+        report
+    }
+
+    creation_inner_field! {
+        #[doc = "Set description of the firewall rule."]
+        set_description, with_description -> description: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the destination IP address or CIDR."]
+        set_destination_ip_address, with_destination_ip_address -> destination_ip_address: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the destination port or port range, e.g. `80` or `8000:8080`."]
+        set_destination_port, with_destination_port -> destination_port: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the rule is enabled (the default)."]
+        set_enabled, with_enabled -> enabled: bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the IP version of the rule (IPv4 by default)."]
+        set_ip_version, with_ip_version -> ip_version: protocol::IpVersion
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a name for the firewall rule."]
+        set_name, with_name -> name: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set a project id for the firewall rule."]
+        set_project_id, with_project_id -> project_id: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the IP protocol to match (any protocol by default)."]
+        set_protocol, with_protocol -> protocol: optional protocol::FirewallProtocol
+    }
+
+    creation_inner_field! {
+        #[doc = "Set whether the rule is shared with other projects."]
+        set_shared, with_shared -> shared: bool
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the source IP address or CIDR."]
+        set_source_ip_address, with_source_ip_address -> source_ip_address: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set the source port or port range, e.g. `80` or `8000:8080`."]
+        set_source_port, with_source_port -> source_port: optional String
+    }
+}
+
+impl From<FirewallRule> for FirewallRuleRef {
+    fn from(value: FirewallRule) -> FirewallRuleRef {
+        FirewallRuleRef::new_verified(value.inner.id)
+    }
+}
+
+#[cfg(feature = "network")]
+impl FirewallRuleRef {
+    /// Verify this reference and convert to an ID, if possible.
+    pub(crate) async fn into_verified(self, session: &Session) -> Result<FirewallRuleRef> {
+        Ok(if self.verified {
+            self
+        } else {
+            FirewallRuleRef::new_verified(api::get_firewall_rule(session, &self.value).await?.id)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use osauth::NoAuth;
+
+    use super::*;
+    use crate::ErrorKind;
+
+    async fn session() -> Session {
+        Session::new(NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn policy(session: Session, rules: &[&str]) -> FirewallPolicy {
+        FirewallPolicy::new(
+            session,
+            protocol::FirewallPolicy {
+                firewall_rules: rules.iter().map(|s| s.to_string()).collect(),
+                id: "policy1".into(),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_queries_validate_name_pattern() {
+        let session = session().await;
+
+        let mut query = FirewallGroupQuery::new(session.clone()).with_name_glob("web-*");
+        assert!(query.validate().await.is_ok());
+        let mut query = FirewallGroupQuery::new(session.clone()).with_name_regex("(");
+        let err = query.validate().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let mut query = FirewallPolicyQuery::new(session.clone()).with_name_regex("^db$");
+        assert!(query.validate().await.is_ok());
+        let mut query = FirewallPolicyQuery::new(session.clone()).with_name_regex("[");
+        let err = query.validate().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let mut query = FirewallRuleQuery::new(session.clone());
+        assert!(query.validate().await.is_ok());
+        let mut query = FirewallRuleQuery::new(session).with_name_regex("a{2,1}");
+        let err = query.validate().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_query_matches_name_glob() {
+        let session = session().await;
+        let query = FirewallPolicyQuery::new(session.clone()).with_name_glob("web-*");
+        let mut web = policy(session.clone(), &[]);
+        web.inner.name = Some("web-1".into());
+        assert!(query.matches(&web));
+        let mut db = policy(session.clone(), &[]);
+        db.inner.name = Some("db-1".into());
+        assert!(!query.matches(&db));
+        // Policies without a name only match patterns accepting empty names.
+        assert!(!query.matches(&policy(session, &[])));
+    }
+
+    #[tokio::test]
+    async fn test_new_firewall_rule_validate() {
+        let session = session().await;
+        let rule = NewFirewallRule::new(session.clone(), protocol::FirewallAction::Allow);
+        assert!(rule.validate().is_ok());
+
+        for proto in [
+            protocol::FirewallProtocol::Tcp,
+            protocol::FirewallProtocol::Udp,
+        ] {
+            let rule = NewFirewallRule::new(session.clone(), protocol::FirewallAction::Allow)
+                .with_protocol(proto)
+                .with_source_port("1024:65535")
+                .with_destination_port("443");
+            assert!(rule.validate().is_ok());
+        }
+
+        let rule = NewFirewallRule::new(session.clone(), protocol::FirewallAction::Deny)
+            .with_destination_port("22");
+        assert_eq!(rule.validate().problems.len(), 1);
+
+        let rule = NewFirewallRule::new(session, protocol::FirewallAction::Reject)
+            .with_protocol(protocol::FirewallProtocol::Icmp)
+            .with_source_port("80");
+        assert_eq!(rule.validate().problems.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_new_firewall_policy_rule_order() {
+        let mut policy = NewFirewallPolicy::new(session().await)
+            .with_firewall_rule(FirewallRuleRef::new_verified("rule1".into()))
+            .with_firewall_rule(FirewallRuleRef::new_verified("rule2".into()));
+        policy.add_firewall_rule(FirewallRuleRef::new_verified("rule0".into()));
+        let rules: Vec<&str> = policy.firewall_rules.iter().map(AsRef::as_ref).collect();
+        assert_eq!(rules, vec!["rule1", "rule2", "rule0"]);
+    }
+
+    #[tokio::test]
+    async fn test_rule_insertion() {
+        let policy = policy(session().await, &["rule1", "rule2"]);
+        let rule = || FirewallRuleRef::new_verified("rule3".into());
+
+        let request = policy.rule_insertion(rule(), None, None).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            serde_json::json!({"firewall_rule_id": "rule3"})
+        );
+
+        let after = Some(FirewallRuleRef::new_verified("rule1".into()));
+        let request = policy.rule_insertion(rule(), after, None).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            serde_json::json!({"firewall_rule_id": "rule3", "insert_after": "rule1"})
+        );
+
+        let before = Some(FirewallRuleRef::new_verified("rule2".into()));
+        let request = policy.rule_insertion(rule(), None, before).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            serde_json::json!({"firewall_rule_id": "rule3", "insert_before": "rule2"})
+        );
+    }
+
+    #[test]
+    fn test_policy_rules_keep_order() {
+        let policy: protocol::FirewallPolicy = serde_json::from_value(serde_json::json!({
+            "id": "policy1",
+            "firewall_rules": ["rule2", "rule1", "rule3"],
+        }))
+        .unwrap();
+        assert_eq!(policy.firewall_rules, vec!["rule2", "rule1", "rule3"]);
+    }
+}
@@ -14,20 +14,22 @@
 
 //! Floating IP support.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::net;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use futures::stream::{Stream, TryStreamExt};
+use serde_json::Value;
 
 use super::super::common::{
-    NetworkRef, PortRef, Refresh, ResourceIterator, ResourceQuery, RouterRef, SubnetRef,
+    FloatingIpRef, NetworkRef, PortRef, ProjectRef, Refresh, ResourceIterator, ResourceQuery,
+    RouterRef, ServerRef, SubnetRef, ValidationReport,
 };
 use super::super::session::Session;
 use super::super::utils::Query;
-use super::super::waiter::DeletionWaiter;
+use super::super::waiter::{watch_status, DeletionWaiter, StatusChange};
 use super::super::{Error, ErrorKind, Result, Sort};
 use super::{api, protocol, Network, Port};
 
@@ -45,8 +47,31 @@ pub struct FloatingIpQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
     floating_network: Option<NetworkRef>,
     port: Option<PortRef>,
+    project: Option<ProjectRef>,
+}
+
+/// A detailed query to floating IP list.
+///
+/// Is constructed from a `FloatingIpQuery`.
+#[derive(Clone, Debug)]
+pub struct DetailedFloatingIpQuery {
+    inner: FloatingIpQuery,
+    concurrency: usize,
+}
+
+/// A floating IP together with its port and server.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DetailedFloatingIp {
+    /// The floating IP itself.
+    pub floating_ip: FloatingIp,
+    /// The port this IP is associated with (if any).
+    pub port: Option<Port>,
+    /// ID of the server owning the port (if the port belongs to a server).
+    pub server_id: Option<String>,
 }
 
 /// A request to create a floating IP.
@@ -69,12 +94,28 @@ impl FloatingIp {
         }
     }
 
+    /// Create a FloatingIp object from its JSON representation.
+    #[cfg(test)]
+    pub(crate) fn from_json(session: Session, value: Value) -> FloatingIp {
+        FloatingIp::new(session, serde_json::from_value(value).unwrap())
+    }
+
     /// Load a FloatingIp object.
     pub(crate) async fn load<Id: AsRef<str>>(session: Session, id: Id) -> Result<FloatingIp> {
         let inner = api::get_floating_ip(&session, id).await?;
         Ok(FloatingIp::new(session, inner))
     }
 
+    /// Load a FloatingIp object by its ID or address.
+    pub(crate) async fn load_by_ref(session: Session, value: FloatingIpRef) -> Result<FloatingIp> {
+        let inner = if value.verified {
+            api::get_floating_ip(&session, value.value).await?
+        } else {
+            api::get_floating_ip_by_id_or_address(&session, value.value).await?
+        };
+        Ok(FloatingIp::new(session, inner))
+    }
+
     transparent_property! {
         #[doc = "Creation data and time (if available)."]
         created_at: Option<DateTime<FixedOffset>>
@@ -100,6 +141,11 @@ impl FloatingIp {
         dns_name: ref Option<String>
     }
 
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "IP address of the port associated with the IP (if any)."]
         fixed_ip_address: Option<net::IpAddr>
@@ -145,6 +191,16 @@ impl FloatingIp {
         port_id: ref Option<String>
     }
 
+    transparent_property! {
+        #[doc = "ID of the project owning this floating IP."]
+        project_id: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "ID of the QoS policy of this floating IP."]
+        qos_policy_id: ref Option<String>
+    }
+
     transparent_property! {
         #[doc = "ID of the router of this floating IP."]
         router_id: ref Option<String>
@@ -165,7 +221,7 @@ impl FloatingIp {
 
     transparent_property! {
         #[doc = "Status of the floating IP."]
-        status: protocol::FloatingIpStatus
+        status: clone protocol::FloatingIpStatus
     }
 
     transparent_property! {
@@ -193,6 +249,46 @@ impl FloatingIp {
         self.update_port(new_port, fixed_ip_address).await
     }
 
+    /// Associate this floating IP with a server.
+    ///
+    /// The port is discovered automatically. The server must have exactly one
+    /// port with an IPv4 address, otherwise use [associate](#method.associate)
+    /// with an explicit port.
+    ///
+    /// # Warning
+    ///
+    /// Any changes to `fixed_ip_address` are reset on this call.
+    pub async fn associate_with_server<S>(&mut self, server: S) -> Result<()>
+    where
+        S: Into<ServerRef>,
+    {
+        let server = server.into().into_verified(&self.session).await?;
+        let mut ports = api::list_ports(&self.session, &[("device_id", server.as_ref())])
+            .await?
+            .into_iter()
+            .filter(|port| port.fixed_ips.iter().any(|ip| ip.ip_address.is_ipv4()))
+            .collect::<Vec<_>>();
+        let port = match ports.len() {
+            0 => {
+                return Err(Error::new(
+                    ErrorKind::ResourceNotFound,
+                    format!("Server {server} has no ports with an IPv4 address"),
+                ))
+            }
+            1 => ports.pop().unwrap(),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::TooManyItems,
+                    format!(
+                        "Server {server} has several ports with an IPv4 address, \
+                         provide a port explicitly"
+                    ),
+                ))
+            }
+        };
+        self.update_port(port.id.into(), None).await
+    }
+
     /// Dissociate this floating IP from a port.
     ///
     /// # Warning
@@ -202,6 +298,46 @@ impl FloatingIp {
         self.update_port(serde_json::Value::Null, None).await
     }
 
+    /// Dissociate this floating IP from the given server.
+    ///
+    /// Fails with `InvalidInput` if the floating IP is not associated with
+    /// a port of this server.
+    ///
+    /// # Warning
+    ///
+    /// Any changes to `fixed_ip_address` are reset on this call.
+    pub async fn dissociate_from_server<S>(&mut self, server: S) -> Result<()>
+    where
+        S: Into<ServerRef>,
+    {
+        let server = server.into().into_verified(&self.session).await?;
+        let device_id = match self.inner.port_id {
+            Some(ref port_id) => api::get_port_by_id(&self.session, port_id).await?.device_id,
+            None => None,
+        };
+        if device_id.as_deref() != Some(server.as_ref()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Floating IP {} is not associated with server {}",
+                    self.inner.floating_ip_address, server
+                ),
+            ));
+        }
+        self.dissociate().await
+    }
+
+    /// Watch the floating IP status by polling it with the given interval.
+    ///
+    /// Useful to detect a floating IP going `DOWN`. The stream ends once the
+    /// floating IP is deleted; failed polls are yielded as errors.
+    pub fn watch_status(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<StatusChange<protocol::FloatingIpStatus>>> {
+        watch_status(self.clone(), interval, |fip| fip.status())
+    }
+
     /// Delete the floating IP.
     pub async fn delete(self) -> Result<DeletionWaiter<FloatingIp>> {
         api::delete_floating_ip(&self.session, &self.inner.id).await?;
@@ -256,6 +392,10 @@ impl Refresh for FloatingIp {
         self.inner = api::get_floating_ip(&self.session, &self.inner.id).await?;
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl FloatingIpQuery {
@@ -264,8 +404,10 @@ impl FloatingIpQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
             floating_network: None,
             port: None,
+            project: None,
         }
     }
 
@@ -287,6 +429,15 @@ impl FloatingIpQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::FloatingIpSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -300,6 +451,16 @@ impl FloatingIpQuery {
         set_description, with_description -> description
     }
 
+    query_filter! {
+        #[doc = "Filter by DNS domain."]
+        set_dns_domain, with_dns_domain -> dns_domain
+    }
+
+    query_filter! {
+        #[doc = "Filter by DNS name."]
+        set_dns_name, with_dns_name -> dns_name
+    }
+
     query_filter! {
         #[doc = "Filter by fixed IP address."]
         set_fixed_ip_address, with_fixed_ip_address -> fixed_ip_address: net::IpAddr
@@ -351,11 +512,34 @@ impl FloatingIpQuery {
         self
     }
 
+    /// Filter by project (tenant).
+    pub fn set_project<P: Into<ProjectRef>>(&mut self, value: P) {
+        self.project = Some(value.into());
+    }
+
+    /// Filter by project (tenant).
+    pub fn with_project<P: Into<ProjectRef>>(mut self, value: P) -> Self {
+        self.set_project(value);
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by status."]
         set_status, with_status -> status: protocol::FloatingIpStatus
     }
 
+    /// Convert this query into a detailed query.
+    ///
+    /// Detailed queries also fetch the port of each floating IP and the ID
+    /// of the server owning this port.
+    #[inline]
+    pub fn detailed(self) -> DetailedFloatingIpQuery {
+        DetailedFloatingIpQuery {
+            inner: self,
+            concurrency: 10,
+        }
+    }
+
     /// Convert this query into a stream executing the request.
     ///
     /// Returns a `TryStream`, which is a stream with each `next`
@@ -364,7 +548,7 @@ impl FloatingIpQuery {
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<FloatingIp>> {
         debug!("Fetching floating_ips with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -386,7 +570,9 @@ impl FloatingIpQuery {
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -404,6 +590,10 @@ impl ResourceQuery for FloatingIpQuery {
         resource.id().clone()
     }
 
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -426,10 +616,75 @@ impl ResourceQuery for FloatingIpQuery {
             let verified = port.into_verified(&self.session).await?;
             self.query.push_str("port_id", verified);
         }
+        if let Some(project) = self.project.take() {
+            let verified = project.into_filter(&self.session).await?;
+            self.query.push_str("project_id", verified);
+        }
         Ok(())
     }
 }
 
+impl DetailedFloatingIpQuery {
+    /// Set the maximum number of ports fetched at the same time (defaults to 10).
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Convert this query into a stream executing the request.
+    ///
+    /// The order of the floating IPs is preserved.
+    ///
+    /// Returns a `TryStream`, which is a stream with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream(self) -> impl Stream<Item = Result<DetailedFloatingIp>> {
+        let concurrency = self.concurrency;
+        self.inner
+            .into_stream()
+            .map_ok(DetailedFloatingIp::load)
+            .try_buffered(concurrency)
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_stream().try_collect().await`.
+    #[inline]
+    pub async fn all(self) -> Result<Vec<DetailedFloatingIp>> {
+        self.into_stream().try_collect().await
+    }
+}
+
+impl DetailedFloatingIp {
+    async fn load(floating_ip: FloatingIp) -> Result<DetailedFloatingIp> {
+        let port = match floating_ip.port_id() {
+            // The port may be deleted while we are iterating.
+            Some(port_id) => match Port::load(floating_ip.session.clone(), port_id).await {
+                Ok(port) => Some(port),
+                Err(err) if err.kind() == ErrorKind::ResourceNotFound => None,
+                Err(err) => return Err(err),
+            },
+            None => None,
+        };
+        let server_id = port
+            .as_ref()
+            .filter(|port| port.attached_to_server())
+            .and_then(|port| port.device_id().clone());
+        trace!(
+            "Floating IP {} is associated with port {:?} and server {:?}",
+            floating_ip.id(),
+            floating_ip.port_id(),
+            server_id
+        );
+        Ok(DetailedFloatingIp {
+            floating_ip,
+            port,
+            server_id,
+        })
+    }
+}
+
 impl NewFloatingIp {
     /// Start creating a floating IP.
     pub(crate) fn new(session: Session, floating_network: NetworkRef) -> NewFloatingIp {
@@ -449,11 +704,14 @@ impl NewFloatingIp {
                 id: String::new(),
                 port_id: None,
                 port_forwardings: Vec::new(),
+                project_id: None,
+                qos_policy_id: None,
                 router_id: None,
                 // Dummy value, not used when serializing
                 status: protocol::FloatingIpStatus::Active,
                 subnet_id: None,
                 updated_at: None,
+                extra: HashMap::new(),
             },
             floating_network,
             port: None,
@@ -461,8 +719,17 @@ impl NewFloatingIp {
         }
     }
 
-    /// Request creation of the port.
+    /// Request creation of the floating IP.
+    ///
+    /// If a DNS name is set, the DNS record is created together with the
+    /// floating IP. Fails with all problems found by
+    /// [validate](#method.validate) before sending any requests.
     pub async fn create(mut self) -> Result<FloatingIp> {
+        self.validate().into_result()?;
+        if self.inner.dns_name.is_some() != self.inner.dns_domain.is_some() {
+            // The service may take the missing part from the network.
+            warn!("Only one of DNS name and domain is set for a new floating IP");
+        }
         self.inner.floating_network_id = self
             .floating_network
             .into_verified(&self.session)
@@ -479,6 +746,46 @@ impl NewFloatingIp {
         Ok(FloatingIp::new(self.session, floating_ip))
     }
 
+    /// Check the request for problems that can be detected locally.
+    ///
+    /// Verifies that a fixed IP address is only requested together with
+    /// a port.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        if self.inner.fixed_ip_address.is_some() && self.port.is_none() {
+            report.add("a fixed IP address requires a port");
+        }
+        report
+    }
+
+    /// Check the request for all detectable problems without creating the floating IP.
+    ///
+    /// In addition to [validate](#method.validate), verifies that the
+    /// floating network, the port and the subnet exist.
+    pub async fn verify(&self) -> Result<ValidationReport> {
+        let mut report = self.validate();
+        report.check_found(
+            self.floating_network
+                .clone()
+                .into_verified(&self.session)
+                .await,
+            format_args!("floating network {}", self.floating_network),
+        )?;
+        if let Some(ref port) = self.port {
+            report.check_found(
+                port.clone().into_verified(&self.session).await,
+                format_args!("port {port}"),
+            )?;
+        }
+        if let Some(ref subnet) = self.subnet {
+            report.check_found(
+                subnet.clone().into_verified(&self.session).await,
+                format_args!("subnet {subnet}"),
+            )?;
+        }
+        Ok(report)
+    }
+
     creation_inner_field! {
         #[doc = "Set description of the floating IP."]
         set_description, with_description -> description: optional String
@@ -504,6 +811,11 @@ impl NewFloatingIp {
         set_floating_ip_address, with_floating_ip_address -> floating_ip_address: net::IpAddr
     }
 
+    creation_inner_field! {
+        #[doc = "Set the QoS policy of the floating IP."]
+        set_qos_policy, with_qos_policy -> qos_policy_id: optional String
+    }
+
     /// Set the port to associate with the new IP.
     pub fn set_port<P>(&mut self, port: P)
     where
@@ -538,3 +850,9 @@ impl NewFloatingIp {
         self
     }
 }
+
+impl From<FloatingIp> for FloatingIpRef {
+    fn from(value: FloatingIp) -> FloatingIpRef {
+        FloatingIpRef::new_verified(value.inner.id)
+    }
+}
@@ -15,21 +15,37 @@
 //! Network API implementation bits.
 
 mod api;
+mod firewalls;
 mod floatingips;
 mod networks;
 mod ports;
 mod protocol;
+mod purge;
 mod routers;
 mod subnets;
 
-pub use self::floatingips::{FloatingIp, FloatingIpQuery, NewFloatingIp};
+/// Raw MAC address type that `MacAddress` wraps.
+pub use macaddr::MacAddr6;
+
+pub(crate) use self::api::{get_default_security_group, get_quota_usage};
+pub use self::firewalls::{
+    FirewallGroup, FirewallGroupQuery, FirewallPolicy, FirewallPolicyQuery, FirewallRule,
+    FirewallRuleQuery, NewFirewallGroup, NewFirewallPolicy, NewFirewallRule,
+};
+pub use self::floatingips::{
+    DetailedFloatingIp, DetailedFloatingIpQuery, FloatingIp, FloatingIpQuery, NewFloatingIp,
+};
 pub use self::networks::{Network, NetworkQuery, NewNetwork};
 pub use self::ports::{NewPort, Port, PortIpAddress, PortIpRequest, PortQuery};
 pub use self::protocol::{
-    AllocationPool, AllowedAddressPair, ConntrackHelper, ExternalGateway, FloatingIpSortKey,
-    FloatingIpStatus, Helper, HostRoute, IpVersion, Ipv6Mode, MacAddress, NetworkProtocol,
-    NetworkSortKey, NetworkStatus, PortExtraDhcpOption, PortForwarding, PortSortKey, RouterSortKey,
-    RouterStatus, SubnetSortKey,
+    AllocationPool, AllowedAddressPair, ConntrackHelper, ExternalGateway, FirewallAction,
+    FirewallGroupStatus, FirewallProtocol, FloatingIpSortKey, FloatingIpStatus, Helper, HostRoute,
+    IpVersion, Ipv6Mode, MacAddress, NetworkIpAvailability, NetworkProtocol, NetworkQuotaUsage,
+    NetworkSegment, NetworkSortKey, NetworkStatus, NetworkType, PortExtraDhcpOption,
+    PortForwarding, PortSortKey, QuotaUsage, RouterConntrackHelper, RouterSortKey, RouterStatus,
+    SecurityGroup, SubnetIpAvailability, SubnetSortKey,
 };
+pub(crate) use self::purge::purge_network_resources;
+pub use self::purge::{NetworkPurgeReport, PurgeFailure};
 pub use self::routers::{NewRouter, Router, RouterQuery};
 pub use self::subnets::{NewSubnet, Subnet, SubnetQuery};
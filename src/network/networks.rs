@@ -14,16 +14,18 @@
 
 //! Network management via Network API.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use futures::stream::{Stream, TryStreamExt};
+use reqwest::Url;
+use serde_json::Value;
 
 use super::super::common::{NetworkRef, Refresh, ResourceIterator, ResourceQuery};
 use super::super::session::Session;
-use super::super::utils::Query;
+use super::super::utils::{url, NamePattern, Query};
 use super::super::waiter::DeletionWaiter;
 use super::super::{Result, Sort};
 use super::{api, protocol};
@@ -34,6 +36,8 @@ pub struct NetworkQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    name_pattern: Option<NamePattern>,
 }
 
 /// Structure representing a single network.
@@ -49,6 +53,8 @@ pub struct Network {
 pub struct NewNetwork {
     session: Session,
     inner: protocol::Network,
+    admin_state_up_set: bool,
+    shared_set: bool,
 }
 
 impl Network {
@@ -77,6 +83,11 @@ impl Network {
         set_admin_state_up, with_admin_state_up -> admin_state_up: bool
     }
 
+    transparent_property! {
+        #[doc = "The availability zone candidates for the network (if available)."]
+        availability_zone_hints: ref Vec<String>
+    }
+
     transparent_property! {
         #[doc = "The availability zones for the network (if available)."]
         availability_zones: ref Vec<String>
@@ -117,6 +128,11 @@ impl Network {
         set_external, with_external -> external: optional bool
     }
 
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "Unique ID."]
         id: ref String
@@ -168,6 +184,26 @@ impl Network {
             -> port_security_enabled: optional bool
     }
 
+    transparent_property! {
+        #[doc = "Provider network type (admin-only, if available)."]
+        provider_network_type: clone Option<protocol::NetworkType>
+    }
+
+    transparent_property! {
+        #[doc = "Provider physical network (admin-only, if available)."]
+        provider_physical_network: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Provider segmentation ID, e.g. VLAN ID (admin-only, if available)."]
+        provider_segmentation_id: Option<u32>
+    }
+
+    transparent_property! {
+        #[doc = "Segments of a multi-segment network (admin-only, if available)."]
+        segments: ref Vec<protocol::NetworkSegment>
+    }
+
     transparent_property! {
         #[doc = "Whether the network is shared."]
         shared: bool
@@ -180,7 +216,7 @@ impl Network {
 
     transparent_property! {
         #[doc = "Status of the network."]
-        status: protocol::NetworkStatus
+        status: clone protocol::NetworkStatus
     }
 
     // TODO(dtantsur): subnets
@@ -195,6 +231,22 @@ impl Network {
         vlan_transparent: Option<bool>
     }
 
+    /// Fetch IP address usage of the network and its subnets.
+    ///
+    /// Requires the `network-ip-availability` extension and is usually only
+    /// allowed for administrators.
+    pub async fn ip_availability(&self) -> Result<protocol::NetworkIpAvailability> {
+        api::get_network_ip_availability(&self.session, &self.inner.id).await
+    }
+
+    /// Link to the network in the dashboard (Horizon) at `base`.
+    ///
+    /// See [Cloud::dashboard_url](../struct.Cloud.html#method.dashboard_url)
+    /// for where to get the base URL from.
+    pub fn dashboard_url(&self, base: &Url) -> Result<Url> {
+        url::dashboard(base, ["project", "networks", &self.inner.id, "detail"])
+    }
+
     /// Delete the network.
     pub async fn delete(self) -> Result<DeletionWaiter<Network>> {
         api::delete_network(&self.session, &self.inner.id).await?;
@@ -236,6 +288,10 @@ impl Refresh for Network {
         self.dirty.clear();
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl NetworkQuery {
@@ -244,6 +300,8 @@ impl NetworkQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            name_pattern: None,
         }
     }
 
@@ -265,6 +323,15 @@ impl NetworkQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::NetworkSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -279,6 +346,8 @@ impl NetworkQuery {
         self
     }
 
+    query_name_pattern!("networks");
+
     /// Convert this query into a stream executing the request.
     ///
     /// Returns a `TryStream`, which is a stream with each `next`
@@ -287,7 +356,7 @@ impl NetworkQuery {
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<Network>> {
         debug!("Fetching networks with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -303,13 +372,15 @@ impl NetworkQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<Network> {
         debug!("Fetching one network with {:?}", self.query);
-        if self.can_paginate {
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yieled more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -323,10 +394,27 @@ impl ResourceQuery for NetworkQuery {
         Ok(self.can_paginate)
     }
 
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
     fn extract_marker(&self, resource: &Self::Item) -> String {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -347,6 +435,8 @@ impl NewNetwork {
         NewNetwork {
             session,
             inner: protocol::Network::default(),
+            admin_state_up_set: false,
+            shared_set: false,
         }
     }
 
@@ -356,9 +446,66 @@ impl NewNetwork {
         Ok(Network::new(self.session, inner))
     }
 
+    /// Apply this request to an existing network.
+    ///
+    /// Returns a copy of the network with every updatable field that differs
+    /// from this request changed. Fields not set in the request are left
+    /// intact. Check [Network::is_dirty](struct.Network.html#method.is_dirty) to find
+    /// out whether an update is needed.
+    pub fn diff(&self, network: &Network) -> Network {
+        let mut result = network.clone();
+        if self.admin_state_up_set {
+            diff_fields! {
+                self -> result: admin_state_up
+            };
+        }
+        if self.shared_set {
+            diff_fields! {
+                self -> result: shared
+            };
+        }
+        diff_option_fields! {
+            self -> result: description external dns_domain is_default mtu name
+                port_security_enabled
+        };
+        result
+    }
+
+    /// Create several networks in one request.
+    ///
+    /// The Networking service creates either all networks or none of them.
+    pub(crate) async fn create_many(
+        session: Session,
+        networks: Vec<NewNetwork>,
+    ) -> Result<Vec<Network>> {
+        if networks.is_empty() {
+            return Ok(Vec::new());
+        }
+        let requests = networks.into_iter().map(|network| network.inner).collect();
+        Ok(api::create_networks(&session, requests)
+            .await?
+            .into_iter()
+            .map(|network| Network::new(session.clone(), network))
+            .collect())
+    }
+
+    /// Set administrative status for the network.
+    #[inline]
+    pub fn set_admin_state_up(&mut self, value: bool) {
+        self.inner.admin_state_up = value;
+        self.admin_state_up_set = true;
+    }
+
+    /// Set administrative status for the network.
+    #[inline]
+    pub fn with_admin_state_up(mut self, value: bool) -> Self {
+        self.set_admin_state_up(value);
+        self
+    }
+
     creation_inner_field! {
-        #[doc = "Set administrative status for the network."]
-        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+        #[doc = "Set the availability zone candidates for the network."]
+        set_availability_zone_hints, with_availability_zone_hints -> availability_zone_hints: Vec<String>
     }
 
     creation_inner_field! {
@@ -398,9 +545,40 @@ impl NewNetwork {
     }
 
     creation_inner_field! {
-        #[doc = "Configure whether the network is shared across all projects."]
-        set_shared, with_shared
-            -> shared: bool
+        #[doc = "Set provider network type (admin-only)."]
+        set_provider_network_type, with_provider_network_type
+            -> provider_network_type: optional protocol::NetworkType
+    }
+
+    creation_inner_field! {
+        #[doc = "Set provider physical network (admin-only)."]
+        set_provider_physical_network, with_provider_physical_network
+            -> provider_physical_network: optional String
+    }
+
+    creation_inner_field! {
+        #[doc = "Set provider segmentation ID, e.g. VLAN ID (admin-only)."]
+        set_provider_segmentation_id, with_provider_segmentation_id
+            -> provider_segmentation_id: optional u32
+    }
+
+    creation_inner_vec! {
+        #[doc = "Add a segment to a multi-segment network (admin-only)."]
+        add_segment, with_segment -> segments: protocol::NetworkSegment
+    }
+
+    /// Configure whether the network is shared across all projects.
+    #[inline]
+    pub fn set_shared(&mut self, value: bool) {
+        self.inner.shared = value;
+        self.shared_set = true;
+    }
+
+    /// Configure whether the network is shared across all projects.
+    #[inline]
+    pub fn with_shared(mut self, value: bool) -> Self {
+        self.set_shared(value);
+        self
     }
 
     creation_inner_field! {
@@ -427,3 +605,71 @@ impl NetworkRef {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use osauth::NoAuth;
+    use serde_json::json;
+
+    use super::super::super::session::Session;
+    use super::{Network, NewNetwork};
+
+    async fn session() -> Session {
+        Session::new(NoAuth::new("http://127.0.0.1:9696/").unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn network(session: &Session) -> Network {
+        Network::new(
+            session.clone(),
+            serde_json::from_value(json!({
+                "admin_state_up": false,
+                "description": "old",
+                "id": "net1",
+                "mtu": 1500,
+                "name": "net1",
+                "shared": true,
+                "status": "ACTIVE",
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_diff_unset_fields() {
+        let session = session().await;
+        let network = network(&session);
+        let result = NewNetwork::new(session).diff(&network);
+        assert!(!result.is_dirty());
+        assert!(!result.admin_state_up());
+        assert!(result.shared());
+    }
+
+    #[tokio::test]
+    async fn test_diff_changed_fields() {
+        let session = session().await;
+        let network = network(&session);
+        let result = NewNetwork::new(session)
+            .with_admin_state_up(true)
+            .with_shared(true)
+            .with_description("new")
+            .with_mtu(1500)
+            .diff(&network);
+        assert!(result.is_dirty());
+        assert!(result.admin_state_up());
+        assert_eq!(result.description().as_deref(), Some("new"));
+        let mut dirty: Vec<_> = result.dirty.iter().copied().collect();
+        dirty.sort_unstable();
+        assert_eq!(dirty, vec!["admin_state_up", "description"]);
+    }
+
+    #[tokio::test]
+    async fn test_diff_explicit_default() {
+        let session = session().await;
+        let network = network(&session);
+        let result = NewNetwork::new(session).with_shared(false).diff(&network);
+        assert!(!result.shared());
+        assert!(result.dirty.contains("shared"));
+    }
+}
@@ -14,7 +14,7 @@
 
 //! Ports management via Port API.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 use std::net;
 use std::time::Duration;
@@ -22,13 +22,20 @@ use std::time::Duration;
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use futures::stream::{Stream, TryStreamExt};
+use reqwest::Url;
+use serde_json::Value;
 
 use super::super::common::{
-    NetworkRef, PortRef, Refresh, ResourceIterator, ResourceQuery, SecurityGroupRef, SubnetRef,
+    NetworkRef, PortRef, Refresh, ResourceIterator, ResourceQuery, SecurityGroupRef, ServerRef,
+    SubnetRef, ValidationReport,
 };
+#[cfg(feature = "compute")]
+use super::super::compute::Server;
 use super::super::session::Session;
-use super::super::utils::Query;
-use super::super::waiter::DeletionWaiter;
+use super::super::utils::{try_one, url, NamePattern, Query};
+use super::super::waiter::{watch_status, DeletionWaiter, StatusChange};
+#[cfg(feature = "compute")]
+use super::super::{Error, ErrorKind};
 use super::super::{Result, Sort};
 use super::{api, protocol, MacAddress, Network, Subnet};
 
@@ -38,7 +45,12 @@ pub struct PortQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
     network: Option<NetworkRef>,
+    fixed_ip_subnets: Vec<SubnetRef>,
+    device_owner_prefix: Option<String>,
+    server: Option<ServerRef>,
+    name_pattern: Option<NamePattern>,
 }
 
 /// A fixed IP address of a port.
@@ -78,6 +90,7 @@ pub struct NewPort {
     inner: protocol::Port,
     network: NetworkRef,
     fixed_ips: Vec<PortIpRequest>,
+    admin_state_up_set: bool,
 }
 
 fn convert_fixed_ips(session: &Session, inner: &mut protocol::Port) -> Vec<PortIpAddress> {
@@ -105,6 +118,12 @@ impl Port {
         }
     }
 
+    /// Create a Port object from its JSON representation.
+    #[cfg(test)]
+    pub(crate) fn from_json(session: Session, value: Value) -> Port {
+        Port::new(session, serde_json::from_value(value).unwrap())
+    }
+
     /// Load a Port object.
     pub(crate) async fn load<Id: AsRef<str>>(session: Session, id: Id) -> Result<Port> {
         let inner = api::get_port(&session, id).await?;
@@ -121,6 +140,23 @@ impl Port {
         set_admin_state_up, with_admin_state_up -> admin_state_up: bool
     }
 
+    transparent_property! {
+        #[doc = "Additional IP and MAC addresses allowed to pass through the port."]
+        allowed_address_pairs: ref Vec<protocol::AllowedAddressPair>
+    }
+
+    /// Mutable access to allowed address pairs.
+    #[allow(unused_results)]
+    pub fn allowed_address_pairs_mut(&mut self) -> &mut Vec<protocol::AllowedAddressPair> {
+        self.dirty.insert("allowed_address_pairs");
+        &mut self.inner.allowed_address_pairs
+    }
+
+    update_field! {
+        #[doc = "Update the allowed address pairs."]
+        set_allowed_address_pairs, with_allowed_address_pairs -> allowed_address_pairs: Vec<protocol::AllowedAddressPair>
+    }
+
     /// Whether the `device_owner` is a Compute server.
     pub fn attached_to_server(&self) -> bool {
         match self.inner.device_owner {
@@ -129,6 +165,22 @@ impl Port {
         }
     }
 
+    /// Fetch the server this port is attached to.
+    ///
+    /// Fails with `ResourceNotFound` if the port is not attached to a server.
+    #[cfg(feature = "compute")]
+    pub async fn server(&self) -> Result<Server> {
+        match self.inner.device_id {
+            Some(ref device_id) if self.attached_to_server() => {
+                Server::load(self.session.clone(), device_id).await
+            }
+            _ => Err(Error::new(
+                ErrorKind::ResourceNotFound,
+                "Port is not attached to a server",
+            )),
+        }
+    }
+
     transparent_property! {
         #[doc = "Creation data and time (if available)."]
         created_at: Option<DateTime<FixedOffset>>
@@ -184,6 +236,11 @@ impl Port {
         set_dns_name, with_dns_name -> dns_name: optional String
     }
 
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "DHCP options configured for this port."]
         extra_dhcp_opts: ref Vec<protocol::PortExtraDhcpOption>
@@ -215,6 +272,8 @@ impl Port {
 
     update_field! {
         #[doc = "Update the MAC address (admin-only)."]
+        #[doc = ""]
+        #[doc = "Use `.into()` to pass a `macaddr::MacAddr6` or raw bytes."]
         set_mac_address, with_mac_address -> mac_address: MacAddress
     }
 
@@ -243,9 +302,20 @@ impl Port {
         network_id: ref String
     }
 
+    transparent_property! {
+        #[doc = "Whether port security (security groups and anti-spoofing) is enabled."]
+        port_security_enabled: Option<bool>
+    }
+
+    update_field! {
+        #[doc = "Enable or disable port security (security groups have to be removed first)."]
+        set_port_security_enabled, with_port_security_enabled
+            -> port_security_enabled: optional bool
+    }
+
     transparent_property! {
         #[doc = "Port status."]
-        status: protocol::NetworkStatus
+        status: clone protocol::NetworkStatus
     }
 
     transparent_property! {
@@ -253,6 +323,28 @@ impl Port {
         updated_at: Option<DateTime<FixedOffset>>
     }
 
+    /// Watch the port status by polling it with the given interval.
+    ///
+    /// The stream yields status transitions until the port is deleted.
+    /// Polling errors are yielded too, after which polling slows down.
+    pub fn watch_status(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<StatusChange<protocol::NetworkStatus>>> {
+        watch_status(self.clone(), interval, |port| port.status())
+    }
+
+    /// Link to the port in the dashboard (Horizon) at `base`.
+    ///
+    /// See [Cloud::dashboard_url](../struct.Cloud.html#method.dashboard_url)
+    /// for where to get the base URL from.
+    pub fn dashboard_url(&self, base: &Url) -> Result<Url> {
+        url::dashboard(
+            base,
+            ["project", "networks", "ports", &self.inner.id, "detail"],
+        )
+    }
+
     /// Delete the port.
     pub async fn delete(self) -> Result<DeletionWaiter<Port>> {
         api::delete_port(&self.session, &self.inner.id).await?;
@@ -273,11 +365,11 @@ impl Port {
     pub async fn save(&mut self) -> Result<()> {
         let mut update = protocol::PortUpdate::default();
         save_fields! {
-            self -> update: admin_state_up extra_dhcp_opts mac_address
+            self -> update: admin_state_up allowed_address_pairs extra_dhcp_opts mac_address
         };
         save_option_fields! {
             self -> update: description device_id device_owner dns_domain
-                dns_name name
+                dns_name name port_security_enabled
         };
         let mut inner = api::update_port(&self.session, self.id(), update).await?;
         self.fixed_ips = convert_fixed_ips(&self.session, &mut inner);
@@ -296,6 +388,10 @@ impl Refresh for Port {
         self.dirty.clear();
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl PortIpAddress {
@@ -311,7 +407,12 @@ impl PortQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
             network: None,
+            fixed_ip_subnets: Vec::new(),
+            device_owner_prefix: None,
+            server: None,
+            name_pattern: None,
         }
     }
 
@@ -333,6 +434,15 @@ impl PortQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::PortSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -361,6 +471,29 @@ impl PortQuery {
         set_device_owner, with_device_owner -> device_owner
     }
 
+    /// Filter by a fixed IP address.
+    pub fn set_fixed_ip_address(&mut self, value: net::IpAddr) {
+        self.query
+            .push_str("fixed_ips", format!("ip_address={value}"));
+    }
+
+    /// Filter by a fixed IP address.
+    pub fn with_fixed_ip_address(mut self, value: net::IpAddr) -> Self {
+        self.set_fixed_ip_address(value);
+        self
+    }
+
+    /// Filter by a subnet of one of the fixed IP addresses.
+    pub fn set_fixed_ip_subnet<S: Into<SubnetRef>>(&mut self, value: S) {
+        self.fixed_ip_subnets.push(value.into());
+    }
+
+    /// Filter by a subnet of one of the fixed IP addresses.
+    pub fn with_fixed_ip_subnet<S: Into<SubnetRef>>(mut self, value: S) -> Self {
+        self.set_fixed_ip_subnet(value);
+        self
+    }
+
     query_filter! {
         #[doc = "Filter by MAC address."]
         set_mac_address, with_mac_address -> mac_address
@@ -371,6 +504,23 @@ impl PortQuery {
         set_name, with_name -> name
     }
 
+    query_name_pattern!("ports");
+
+    /// Filter by the server the port is attached to.
+    ///
+    /// The server can be given by its ID or name.
+    pub fn set_server<S: Into<ServerRef>>(&mut self, value: S) {
+        self.server = Some(value.into());
+    }
+
+    /// Filter by the server the port is attached to.
+    ///
+    /// The server can be given by its ID or name.
+    pub fn with_server<S: Into<ServerRef>>(mut self, value: S) -> Self {
+        self.set_server(value);
+        self
+    }
+
     /// Filter by network.
     pub fn set_network<N: Into<NetworkRef>>(&mut self, value: N) {
         self.network = Some(value.into());
@@ -387,6 +537,35 @@ impl PortQuery {
         set_status, with_status -> status: protocol::NetworkStatus
     }
 
+    /// Only return ports of compute instances.
+    ///
+    /// Their device owner is `compute:<availability zone>`. Since Neutron can
+    /// only match the device owner exactly, this filter is applied on
+    /// the client side.
+    pub fn only_compute_ports(mut self) -> Self {
+        self.device_owner_prefix = Some("compute:".into());
+        self
+    }
+
+    /// Only return ports of DHCP agents.
+    pub fn only_dhcp_ports(self) -> Self {
+        self.with_device_owner("network:dhcp")
+    }
+
+    /// Only return router interfaces, including distributed and HA ones.
+    ///
+    /// Router gateway ports are not included.
+    pub fn only_router_interfaces(mut self) -> Self {
+        for owner in &[
+            "network:router_interface",
+            "network:router_interface_distributed",
+            "network:ha_router_replicated_interface",
+        ] {
+            self.query.push_str("device_owner", *owner);
+        }
+        self
+    }
+
     /// Convert this query into a stream executing the request.
     ///
     /// Returns a `TryStream`, which is a stream with each `next`
@@ -394,8 +573,24 @@ impl PortQuery {
     ///
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<Port>> {
-        debug!("Fetching ports with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        debug!(
+            "Fetching ports with {:?}, device owner prefix {:?}",
+            self.query, self.device_owner_prefix
+        );
+        let prefix = self.device_owner_prefix.clone();
+        ResourceIterator::new(self.session.clone(), self)
+            .into_stream()
+            .try_filter(move |port| {
+                let matches = match prefix {
+                    Some(ref prefix) => port
+                        .device_owner()
+                        .as_ref()
+                        .map(|owner| owner.starts_with(prefix))
+                        .unwrap_or(false),
+                    None => true,
+                };
+                futures::future::ready(matches)
+            })
     }
 
     /// Execute this request and return all results.
@@ -411,13 +606,20 @@ impl PortQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<Port> {
         debug!("Fetching one port with {:?}", self.query);
-        if self.can_paginate {
+        if self.device_owner_prefix.is_some() {
+            // Filtering happens on the client side, cannot limit the results.
+            return try_one(self.into_stream()).await;
+        }
+
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yieled more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -435,6 +637,16 @@ impl ResourceQuery for PortQuery {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -449,10 +661,22 @@ impl ResourceQuery for PortQuery {
     }
 
     async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
         if let Some(network) = self.network.take() {
             let verified = network.into_verified(&self.session).await?;
             self.query.push_str("network_id", verified);
         }
+        for subnet in mem::take(&mut self.fixed_ip_subnets) {
+            let verified = subnet.into_verified(&self.session).await?;
+            self.query
+                .push_str("fixed_ips", format!("subnet_id={verified}"));
+        }
+        if let Some(server) = self.server.take() {
+            let verified = server.into_verified(&self.session).await?;
+            self.query.push_str("device_id", verified);
+        }
         Ok(())
     }
 }
@@ -478,19 +702,93 @@ impl NewPort {
                 name: None,
                 // Will be replaced in create()
                 network_id: String::new(),
+                port_security_enabled: None,
                 project_id: None,
                 security_groups: Vec::new(),
                 // Dummy value, not used when serializing
                 status: protocol::NetworkStatus::Active,
                 updated_at: None,
+                extra: HashMap::new(),
             },
             network,
             fixed_ips: Vec::new(),
+            admin_state_up_set: false,
         }
     }
 
     /// Request creation of the port.
-    pub async fn create(mut self) -> Result<Port> {
+    ///
+    /// Fails with all problems found by [validate](#method.validate) before
+    /// sending any requests.
+    pub async fn create(self) -> Result<Port> {
+        let session = self.session.clone();
+        let request = self.into_request().await?;
+        let port = api::create_port(&session, request).await?;
+        Ok(Port::new(session, port))
+    }
+
+    /// Apply this request to an existing port.
+    ///
+    /// Returns a copy of the port with every updatable field that differs
+    /// from this request changed. Fields not set in the request are left
+    /// intact, the network, fixed IPs and security groups are not compared.
+    /// Check [Port::is_dirty](struct.Port.html#method.is_dirty) to find out
+    /// whether an update is needed, [Port::save](struct.Port.html#method.save)
+    /// sends only the changed fields.
+    pub fn diff(&self, port: &Port) -> Port {
+        let mut result = port.clone();
+        if self.admin_state_up_set {
+            diff_fields! {
+                self -> result: admin_state_up
+            };
+        }
+        diff_option_fields! {
+            self -> result: description device_id device_owner dns_domain
+                dns_name name port_security_enabled
+        };
+        diff_vec_fields! {
+            self -> result: allowed_address_pairs extra_dhcp_opts
+        };
+        if !self.inner.mac_address.is_nil() {
+            diff_fields! {
+                self -> result: mac_address
+            };
+        }
+        result
+    }
+
+    /// Create several ports in one request.
+    ///
+    /// The Networking service creates either all ports or none of them.
+    pub(crate) async fn create_many(session: Session, ports: Vec<NewPort>) -> Result<Vec<Port>> {
+        if ports.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Usually all ports share the same network, avoid looking it up every time.
+        let mut networks: HashMap<String, NetworkRef> = HashMap::new();
+        let mut requests = Vec::with_capacity(ports.len());
+        for mut port in ports {
+            let network = match networks.get(port.network.as_ref()) {
+                Some(verified) => verified.clone(),
+                None => {
+                    let verified = port.network.clone().into_verified(&session).await?;
+                    let _ = networks.insert(port.network.as_ref().to_string(), verified.clone());
+                    verified
+                }
+            };
+            port.network = network;
+            requests.push(port.into_request().await?);
+        }
+        Ok(api::create_ports(&session, requests)
+            .await?
+            .into_iter()
+            .map(|port| Port::new(session.clone(), port))
+            .collect())
+    }
+
+    /// Validate the port and resolve all references.
+    async fn into_request(mut self) -> Result<protocol::Port> {
+        self.validate().into_result()?;
         self.inner.network_id = self.network.into_verified(&self.session).await?.into();
         for request in self.fixed_ips {
             self.inner.fixed_ips.push(match request {
@@ -509,13 +807,68 @@ impl NewPort {
             });
         }
 
-        let port = api::create_port(&self.session, self.inner).await?;
-        Ok(Port::new(self.session, port))
+        Ok(self.inner)
     }
 
-    creation_inner_field! {
-        #[doc = "Set administrative status for the port."]
-        set_admin_state_up, with_admin_state_up -> admin_state_up: bool
+    /// Check the request for problems that can be detected locally.
+    ///
+    /// Verifies that requested fixed IP addresses are specified and unique.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut seen = HashSet::new();
+        for request in &self.fixed_ips {
+            let ip = match request {
+                PortIpRequest::IpAddress(ip) | PortIpRequest::IpFromSubnet(ip, _) => *ip,
+                PortIpRequest::AnyIpFromSubnet(..) => continue,
+            };
+            if ip.is_unspecified() {
+                report.add(format!("fixed IP address {ip} is not a valid address"));
+            } else if !seen.insert(ip) {
+                report.add(format!("fixed IP address {ip} is requested more than once"));
+            }
+        }
+        if self.inner.port_security_enabled == Some(false) && !self.inner.security_groups.is_empty()
+        {
+            report.add("security groups cannot be used with port security disabled");
+        }
+        report
+    }
+
+    /// Check the request for all detectable problems without creating the port.
+    ///
+    /// In addition to [validate](#method.validate), verifies that the network
+    /// and the subnets exist.
+    pub async fn verify(&self) -> Result<ValidationReport> {
+        let mut report = self.validate();
+        report.check_found(
+            self.network.clone().into_verified(&self.session).await,
+            format_args!("network {}", self.network),
+        )?;
+        for request in &self.fixed_ips {
+            if let PortIpRequest::AnyIpFromSubnet(subnet) | PortIpRequest::IpFromSubnet(_, subnet) =
+                request
+            {
+                report.check_found(
+                    subnet.clone().into_verified(&self.session).await,
+                    format_args!("subnet {subnet}"),
+                )?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Set administrative status for the port.
+    #[inline]
+    pub fn set_admin_state_up(&mut self, value: bool) {
+        self.inner.admin_state_up = value;
+        self.admin_state_up_set = true;
+    }
+
+    /// Set administrative status for the port.
+    #[inline]
+    pub fn with_admin_state_up(mut self, value: bool) -> Self {
+        self.set_admin_state_up(value);
+        self
     }
 
     creation_inner_vec! {
@@ -572,6 +925,8 @@ impl NewPort {
 
     creation_inner_field! {
         #[doc = "Set MAC address for the port (generated otherwise)."]
+        #[doc = ""]
+        #[doc = "Use `.into()` to pass a `macaddr::MacAddr6` or raw bytes."]
         set_mac_address, with_mac_address -> mac_address: MacAddress
     }
 
@@ -580,6 +935,12 @@ impl NewPort {
         set_name, with_name -> name: optional String
     }
 
+    creation_inner_field! {
+        #[doc = "Enable or disable port security (the network default is used otherwise)."]
+        set_port_security_enabled, with_port_security_enabled
+            -> port_security_enabled: optional bool
+    }
+
     creation_inner_vec! {
         #[doc = "Set security groups for the port."]
         add_security_group, with_security_group -> security_groups: into SecurityGroupRef
@@ -603,3 +964,68 @@ impl PortRef {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use osauth::NoAuth;
+    use serde_json::json;
+
+    use super::super::super::common::NetworkRef;
+    use super::super::super::session::Session;
+    use super::{NewPort, Port};
+
+    async fn session() -> Session {
+        Session::new(NoAuth::new("http://127.0.0.1:9696/").unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn port(session: &Session) -> Port {
+        Port::from_json(
+            session.clone(),
+            json!({
+                "admin_state_up": false,
+                "device_id": "server1",
+                "device_owner": "compute:nova",
+                "fixed_ips": [],
+                "id": "port1",
+                "mac_address": "fa:16:3e:01:02:03",
+                "name": "port1",
+                "network_id": "net1",
+                "status": "DOWN",
+            }),
+        )
+    }
+
+    fn new_port(session: Session) -> NewPort {
+        NewPort::new(session, NetworkRef::new_verified("net1".into()))
+    }
+
+    #[tokio::test]
+    async fn test_diff_unset_fields() {
+        let session = session().await;
+        let port = port(&session);
+        let result = new_port(session).diff(&port);
+        assert!(!result.is_dirty());
+        assert!(!result.admin_state_up());
+        assert_eq!(result.mac_address().to_string(), "FA:16:3E:01:02:03");
+    }
+
+    #[tokio::test]
+    async fn test_diff_changed_fields() {
+        let session = session().await;
+        let port = port(&session);
+        let result = new_port(session)
+            .with_admin_state_up(true)
+            .with_device_owner("compute:nova")
+            .with_name("port2")
+            .with_mac_address([0xfa, 0x16, 0x3e, 0x0a, 0x0b, 0x0c].into())
+            .diff(&port);
+        assert!(result.is_dirty());
+        assert!(result.admin_state_up());
+        assert_eq!(result.name().as_deref(), Some("port2"));
+        let mut dirty: Vec<_> = result.dirty.iter().copied().collect();
+        dirty.sort_unstable();
+        assert_eq!(dirty, vec!["admin_state_up", "mac_address", "name"]);
+    }
+}
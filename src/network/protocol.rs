@@ -17,6 +17,7 @@
 #![allow(non_snake_case)]
 #![allow(missing_docs)]
 
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::net;
 use std::ops::Not;
@@ -27,7 +28,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
 use super::super::common::{NetworkRef, SecurityGroupRef};
-use super::super::Result;
+use super::super::{Error, ErrorKind, Result};
 use crate::session::Session;
 
 protocol_enum! {
@@ -38,6 +39,37 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "An action of a firewall rule."]
+    enum FirewallAction {
+        Allow = "allow",
+        Deny = "deny",
+        Reject = "reject"
+    }
+}
+
+protocol_enum! {
+    #[doc = "Possible firewall group statuses."]
+    enum FirewallGroupStatus: Unknown(String) {
+        Active = "ACTIVE",
+        Down = "DOWN",
+        Inactive = "INACTIVE",
+        Error = "ERROR",
+        PendingCreate = "PENDING_CREATE",
+        PendingUpdate = "PENDING_UPDATE",
+        PendingDelete = "PENDING_DELETE"
+    }
+}
+
+protocol_enum! {
+    #[doc = "An IP protocol matched by a firewall rule."]
+    enum FirewallProtocol {
+        Icmp = "icmp",
+        Tcp = "tcp",
+        Udp = "udp"
+    }
+}
+
 protocol_enum! {
     #[doc = "Network IP protocol."]
     enum NetworkProtocol {
@@ -48,7 +80,7 @@ protocol_enum! {
 
 protocol_enum! {
     #[doc = "Possible network statuses."]
-    enum NetworkStatus {
+    enum NetworkStatus: Unknown(String) {
         Active = "ACTIVE",
         Down = "DOWN",
         Building = "BUILD",
@@ -56,9 +88,23 @@ protocol_enum! {
     }
 }
 
+protocol_enum! {
+    #[doc = "Type of a provider network segment."]
+    enum NetworkType: Unknown(String) {
+        Flat = "flat",
+        Geneve = "geneve",
+        Gre = "gre",
+        Local = "local",
+        Vlan = "vlan",
+        Vxlan = "vxlan"
+    }
+}
+
 protocol_enum! {
     #[doc = "Available sort keys."]
+    #[derive(Default)]
     enum NetworkSortKey {
+        #[default]
         CreatedAt = "created_at",
         Id = "id",
         Name = "name",
@@ -68,7 +114,7 @@ protocol_enum! {
 
 protocol_enum! {
     #[doc = "Possible floating IP statuses."]
-    enum FloatingIpStatus {
+    enum FloatingIpStatus: Unknown(String) {
         Active = "ACTIVE",
         Down = "DOWN",
         Error = "ERROR"
@@ -87,12 +133,6 @@ protocol_enum! {
     }
 }
 
-impl Default for NetworkSortKey {
-    fn default() -> NetworkSortKey {
-        NetworkSortKey::CreatedAt
-    }
-}
-
 protocol_enum! {
     #[doc = "Available sort keys."]
     enum PortSortKey {
@@ -121,7 +161,7 @@ protocol_enum! {
 
 protocol_enum! {
     #[doc = "Possible router statuses."]
-    enum RouterStatus {
+    enum RouterStatus: Unknown(String) {
         Active = "ACTIVE",
         Allocating = "ALLOCATING",
         Error = "ERROR"
@@ -156,6 +196,8 @@ protocol_enum! {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Network {
     pub admin_state_up: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub availability_zone_hints: Vec<String>,
     #[serde(default, skip_serializing)]
     pub availability_zones: Vec<String>,
     #[serde(default, skip_serializing)]
@@ -191,6 +233,26 @@ pub struct Network {
     pub port_security_enabled: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub project_id: Option<String>,
+    #[serde(
+        rename = "provider:network_type",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub provider_network_type: Option<NetworkType>,
+    #[serde(
+        rename = "provider:physical_network",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub provider_physical_network: Option<String>,
+    #[serde(
+        rename = "provider:segmentation_id",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub provider_segmentation_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<NetworkSegment>,
     #[serde(default, skip_serializing_if = "Not::not")]
     pub shared: bool,
     #[serde(skip_serializing)]
@@ -201,12 +263,15 @@ pub struct Network {
     pub updated_at: Option<DateTime<FixedOffset>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub vlan_transparent: Option<bool>,
+    #[serde(flatten, skip_serializing)]
+    pub extra: HashMap<String, Value>,
 }
 
 impl Default for Network {
     fn default() -> Network {
         Network {
             admin_state_up: true,
+            availability_zone_hints: Vec::new(),
             availability_zones: Vec::new(),
             created_at: None,
             description: None,
@@ -219,11 +284,75 @@ impl Default for Network {
             name: None,
             port_security_enabled: None,
             project_id: None,
+            provider_network_type: None,
+            provider_physical_network: None,
+            provider_segmentation_id: None,
+            segments: Vec::new(),
             shared: false,
             status: NetworkStatus::Active,
             // subnets: Vec::new(),
             updated_at: None,
             vlan_transparent: None,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+/// A segment of a multi-segment provider network.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct NetworkSegment {
+    /// Type of the segment.
+    #[serde(rename = "provider:network_type")]
+    pub network_type: NetworkType,
+    /// Physical network the segment is on (for flat and VLAN segments).
+    #[serde(
+        rename = "provider:physical_network",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub physical_network: Option<String>,
+    /// Segmentation ID, e.g. VLAN ID or VNI.
+    #[serde(
+        rename = "provider:segmentation_id",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub segmentation_id: Option<u32>,
+}
+
+impl NetworkSegment {
+    /// Create a segment of the given type.
+    pub fn new(network_type: NetworkType) -> NetworkSegment {
+        NetworkSegment {
+            network_type,
+            physical_network: None,
+            segmentation_id: None,
+        }
+    }
+
+    /// Create a flat segment on a physical network.
+    pub fn flat<S: Into<String>>(physical_network: S) -> NetworkSegment {
+        NetworkSegment {
+            physical_network: Some(physical_network.into()),
+            ..NetworkSegment::new(NetworkType::Flat)
+        }
+    }
+
+    /// Create a VLAN segment on a physical network.
+    pub fn vlan<S: Into<String>>(physical_network: S, vlan_id: u32) -> NetworkSegment {
+        NetworkSegment {
+            physical_network: Some(physical_network.into()),
+            segmentation_id: Some(vlan_id),
+            ..NetworkSegment::new(NetworkType::Vlan)
+        }
+    }
+
+    /// Create a VXLAN segment with the given VNI.
+    pub fn vxlan(vni: u32) -> NetworkSegment {
+        NetworkSegment {
+            segmentation_id: Some(vni),
+            ..NetworkSegment::new(NetworkType::Vxlan)
         }
     }
 }
@@ -264,13 +393,173 @@ pub struct NetworkUpdateRoot {
 }
 
 /// A list of networks.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NetworksRoot {
     pub networks: Vec<Network>,
 }
 
+/// IP address usage of a subnet.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SubnetIpAvailability {
+    /// CIDR of the subnet.
+    pub cidr: ipnet::IpNet,
+    /// IP protocol version.
+    pub ip_version: IpVersion,
+    /// Unique ID of the subnet.
+    pub subnet_id: String,
+    /// Name of the subnet.
+    #[serde(default)]
+    pub subnet_name: String,
+    /// Total number of IP addresses in the allocation pools.
+    pub total_ips: u128,
+    /// Number of allocated IP addresses.
+    pub used_ips: u128,
+}
+
+impl SubnetIpAvailability {
+    /// Number of IP addresses that can still be allocated.
+    pub fn free_ips(&self) -> u128 {
+        self.total_ips.saturating_sub(self.used_ips)
+    }
+}
+
+/// IP address usage of a network.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct NetworkIpAvailability {
+    /// Unique ID of the network.
+    pub network_id: String,
+    /// Name of the network.
+    #[serde(default)]
+    pub network_name: String,
+    /// ID of the project owning the network.
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// Usage of each subnet of the network.
+    #[serde(default)]
+    pub subnet_ip_availability: Vec<SubnetIpAvailability>,
+    /// Total number of IP addresses in all subnets.
+    pub total_ips: u128,
+    /// Number of allocated IP addresses in all subnets.
+    pub used_ips: u128,
+}
+
+impl NetworkIpAvailability {
+    /// Number of IP addresses that can still be allocated.
+    pub fn free_ips(&self) -> u128 {
+        self.total_ips.saturating_sub(self.used_ips)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkIpAvailabilityRoot {
+    pub network_ip_availability: NetworkIpAvailability,
+}
+
+/// Limit and usage of a single network quota.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+pub struct QuotaUsage {
+    /// Maximum allowed number of resources, negative if unlimited.
+    pub limit: i64,
+    /// Number of resources currently in use.
+    #[serde(default)]
+    pub used: i64,
+    /// Number of resources reserved by requests in progress.
+    #[serde(default)]
+    pub reserved: i64,
+}
+
+impl QuotaUsage {
+    pub(crate) fn new(limit: i64, used: i64) -> QuotaUsage {
+        QuotaUsage {
+            limit,
+            used,
+            reserved: 0,
+        }
+    }
+
+    /// Whether the quota is unlimited.
+    #[inline]
+    pub fn is_unlimited(&self) -> bool {
+        self.limit < 0
+    }
+
+    /// Number of resources that can still be created, `None` if unlimited.
+    pub fn available(&self) -> Option<u64> {
+        if self.is_unlimited() {
+            None
+        } else {
+            Some(u64::try_from(self.limit - self.used - self.reserved).unwrap_or(0))
+        }
+    }
+}
+
+impl Default for QuotaUsage {
+    fn default() -> QuotaUsage {
+        QuotaUsage::new(-1, 0)
+    }
+}
+
+/// Network quotas of a project together with their current usage.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct NetworkQuotaUsage {
+    /// Floating IP addresses.
+    #[serde(rename = "floatingip", default)]
+    pub floating_ips: QuotaUsage,
+    /// Networks.
+    #[serde(rename = "network", default)]
+    pub networks: QuotaUsage,
+    /// Ports.
+    #[serde(rename = "port", default)]
+    pub ports: QuotaUsage,
+    /// Routers.
+    #[serde(rename = "router", default)]
+    pub routers: QuotaUsage,
+    /// Security groups.
+    #[serde(rename = "security_group", default)]
+    pub security_groups: QuotaUsage,
+    /// Subnets.
+    #[serde(rename = "subnet", default)]
+    pub subnets: QuotaUsage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkQuotaUsageRoot {
+    pub quota: NetworkQuotaUsage,
+}
+
+/// Network quotas without usage.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkQuota {
+    #[serde(default = "unlimited")]
+    pub floatingip: i64,
+    #[serde(default = "unlimited")]
+    pub network: i64,
+    #[serde(default = "unlimited")]
+    pub port: i64,
+    #[serde(default = "unlimited")]
+    pub router: i64,
+    #[serde(default = "unlimited")]
+    pub security_group: i64,
+    #[serde(default = "unlimited")]
+    pub subnet: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkQuotaRoot {
+    pub quota: NetworkQuota,
+}
+
+#[inline]
+fn unlimited() -> i64 {
+    -1
+}
+
 /// An extra DHCP option.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct PortExtraDhcpOption {
     /// IP protocol version (if required).
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -329,10 +618,38 @@ pub struct FixedIp {
     pub subnet_id: String,
 }
 
+/// A MAC address of a port.
+///
+/// Can be created from its string representation using `parse`, from raw bytes
+/// or from `macaddr::MacAddr6`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Ord, PartialOrd, Hash)]
 pub struct MacAddress(macaddr::MacAddr6);
 
 impl MacAddress {
+    /// Create a MAC address from its bytes.
+    pub const fn new(bytes: [u8; 6]) -> MacAddress {
+        MacAddress(macaddr::MacAddr6::new(
+            bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5],
+        ))
+    }
+
+    /// Create a locally administered unicast MAC address from its bytes.
+    ///
+    /// Fails with `InvalidInput` if the address is not locally administered
+    /// or is a multicast address.
+    pub fn new_local(bytes: [u8; 6]) -> Result<MacAddress> {
+        let result = MacAddress::new(bytes);
+        if result.is_local() && result.is_unicast() {
+            Ok(result)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{result} is not a locally administered unicast MAC address"),
+            ))
+        }
+    }
+
+    /// Whether the address consists only of zeros.
     pub fn is_nil(&self) -> bool {
         self.0.is_nil()
     }
@@ -360,6 +677,38 @@ impl std::str::FromStr for MacAddress {
     }
 }
 
+impl TryFrom<&str> for MacAddress {
+    type Error = macaddr::ParseError;
+
+    fn try_from(value: &str) -> std::result::Result<MacAddress, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<[u8; 6]> for MacAddress {
+    fn from(value: [u8; 6]) -> MacAddress {
+        MacAddress::new(value)
+    }
+}
+
+impl From<macaddr::MacAddr6> for MacAddress {
+    fn from(value: macaddr::MacAddr6) -> MacAddress {
+        MacAddress(value)
+    }
+}
+
+impl From<MacAddress> for [u8; 6] {
+    fn from(value: MacAddress) -> [u8; 6] {
+        value.0.into_array()
+    }
+}
+
+impl From<MacAddress> for macaddr::MacAddr6 {
+    fn from(value: MacAddress) -> macaddr::MacAddr6 {
+        value.0
+    }
+}
+
 impl Serialize for MacAddress {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
@@ -380,7 +729,7 @@ impl<'de> Deserialize<'de> for MacAddress {
 }
 
 /// A port's IP address.
-#[derive(Debug, Clone, Deserialize, Serialize, Copy)]
+#[derive(Debug, Clone, Deserialize, Serialize, Copy, PartialEq)]
 pub struct AllowedAddressPair {
     pub ip_address: net::IpAddr,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -440,6 +789,8 @@ pub struct Port {
     pub name: Option<String>,
     pub network_id: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_security_enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub project_id: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub security_groups: Vec<SecurityGroupRef>,
@@ -447,6 +798,8 @@ pub struct Port {
     pub status: NetworkStatus,
     #[serde(default, skip_serializing)]
     pub updated_at: Option<DateTime<FixedOffset>>,
+    #[serde(flatten, skip_serializing)]
+    pub extra: HashMap<String, Value>,
 }
 
 /// A port.
@@ -455,6 +808,8 @@ pub struct PortUpdate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub admin_state_up: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_address_pairs: Option<Vec<AllowedAddressPair>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_id: Option<String>,
@@ -473,6 +828,8 @@ pub struct PortUpdate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_security_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub security_groups: Option<Vec<SecurityGroupRef>>,
 }
 
@@ -489,7 +846,7 @@ pub struct PortUpdateRoot {
 }
 
 /// A list of ports.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PortsRoot {
     pub ports: Vec<Port>,
 }
@@ -513,7 +870,7 @@ protocol_enum! {
 /// ConntrackHelper object.
 /// See [here](https://home.regit.org/netfilter-en/secure-use-of-helpers/) for in-depth info about
 /// conntrack helpers.
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
 pub struct ConntrackHelper {
     /// Conntrack Helper
     pub helper: Helper,
@@ -523,6 +880,52 @@ pub struct ConntrackHelper {
     pub port: u16,
 }
 
+impl ConntrackHelper {
+    /// Create a conntrack helper definition.
+    pub fn new(helper: Helper, protocol: NetworkProtocol, port: u16) -> ConntrackHelper {
+        ConntrackHelper {
+            helper,
+            protocol,
+            port,
+        }
+    }
+}
+
+/// A conntrack helper resource of a router.
+#[non_exhaustive]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct RouterConntrackHelper {
+    /// Unique ID of the conntrack helper.
+    pub id: String,
+    /// Conntrack Helper
+    pub helper: Helper,
+    /// Network IP protocol.
+    pub protocol: NetworkProtocol,
+    /// TCP or UDP port
+    pub port: u16,
+}
+
+impl From<RouterConntrackHelper> for ConntrackHelper {
+    fn from(value: RouterConntrackHelper) -> ConntrackHelper {
+        ConntrackHelper::new(value.helper, value.protocol, value.port)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConntrackHelperCreateRoot {
+    pub conntrack_helper: ConntrackHelper,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouterConntrackHelperRoot {
+    pub conntrack_helper: RouterConntrackHelper,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouterConntrackHelpersRoot {
+    pub conntrack_helpers: Vec<RouterConntrackHelper>,
+}
+
 /// External gateway information.
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -565,7 +968,7 @@ pub struct Routes {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Router {
     pub admin_state_up: bool,
-    #[serde(default, skip_serializing)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub availability_zone_hints: Vec<String>,
     #[serde(default, skip_serializing)]
     pub availability_zones: Vec<String>,
@@ -612,6 +1015,8 @@ pub struct Router {
     pub tags: Option<Vec<String>>,
     #[serde(default, skip_serializing)]
     pub updated_at: Option<DateTime<FixedOffset>>,
+    #[serde(flatten, skip_serializing)]
+    pub extra: HashMap<String, Value>,
 }
 
 impl Default for Router {
@@ -636,6 +1041,7 @@ impl Default for Router {
             status: RouterStatus::Active,
             tags: None,
             updated_at: None,
+            extra: HashMap::new(),
         }
     }
 }
@@ -712,6 +1118,47 @@ pub struct HostRoute {
     pub next_hop: net::IpAddr,
 }
 
+impl AllocationPool {
+    /// Create an allocation pool from its first and last addresses.
+    pub fn new<S, E>(start: S, end: E) -> AllocationPool
+    where
+        S: Into<net::IpAddr>,
+        E: Into<net::IpAddr>,
+    {
+        AllocationPool {
+            start: start.into(),
+            end: end.into(),
+        }
+    }
+}
+
+impl HostRoute {
+    /// Create a route to the destination network via the next hop.
+    pub fn new<D, N>(destination: D, next_hop: N) -> HostRoute
+    where
+        D: Into<ipnet::IpNet>,
+        N: Into<net::IpAddr>,
+    {
+        HostRoute {
+            destination: destination.into(),
+            next_hop: next_hop.into(),
+        }
+    }
+
+    /// Create a default route via the next hop.
+    pub fn default_via<N: Into<net::IpAddr>>(next_hop: N) -> HostRoute {
+        let next_hop = next_hop.into();
+        let destination = match next_hop {
+            net::IpAddr::V4(..) => ipnet::IpNet::V4(Default::default()),
+            net::IpAddr::V6(..) => ipnet::IpNet::V6(Default::default()),
+        };
+        HostRoute {
+            destination,
+            next_hop,
+        }
+    }
+}
+
 /// A subnet.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Subnet {
@@ -753,8 +1200,12 @@ pub struct Subnet {
     pub network_id: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_id: Option<String>,
     #[serde(default, skip_serializing)]
     pub updated_at: Option<DateTime<FixedOffset>>,
+    #[serde(flatten, skip_serializing)]
+    pub extra: HashMap<String, Value>,
 }
 
 impl Subnet {
@@ -778,7 +1229,9 @@ impl Subnet {
             name: None,
             network_id: String::new(),
             project_id: None,
+            segment_id: None,
             updated_at: None,
+            extra: HashMap::new(),
         }
     }
 }
@@ -815,7 +1268,7 @@ pub struct SubnetUpdateRoot {
 }
 
 /// A list of subnets.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SubnetsRoot {
     pub subnets: Vec<Subnet>,
 }
@@ -866,6 +1319,10 @@ pub struct FloatingIp {
     pub port_id: Option<String>,
     #[serde(default, skip_serializing)]
     pub port_forwardings: Vec<PortForwarding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos_policy_id: Option<String>,
     #[serde(default, skip_serializing)]
     pub router_id: Option<String>,
     #[serde(skip_serializing)]
@@ -874,6 +1331,8 @@ pub struct FloatingIp {
     pub subnet_id: Option<String>,
     #[serde(default, skip_serializing)]
     pub updated_at: Option<DateTime<FixedOffset>>,
+    #[serde(flatten, skip_serializing)]
+    pub extra: HashMap<String, Value>,
 }
 
 /// A port.
@@ -905,6 +1364,293 @@ pub struct FloatingIpsRoot {
     pub floatingips: Vec<FloatingIp>,
 }
 
+/// A firewall group.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FirewallGroup {
+    pub admin_state_up: bool,
+    #[serde(
+        deserialize_with = "empty_as_default",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_firewall_policy_id: Option<String>,
+    #[serde(skip_serializing)]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_firewall_policy_id: Option<String>,
+    #[serde(
+        deserialize_with = "empty_as_default",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub shared: bool,
+    #[serde(skip_serializing)]
+    pub status: FirewallGroupStatus,
+}
+
+impl Default for FirewallGroup {
+    fn default() -> FirewallGroup {
+        FirewallGroup {
+            admin_state_up: true,
+            description: None,
+            egress_firewall_policy_id: None,
+            id: String::new(),
+            ingress_firewall_policy_id: None,
+            name: None,
+            ports: Vec::new(),
+            project_id: None,
+            shared: false,
+            // Dummy value, not used when serializing
+            status: FirewallGroupStatus::Active,
+        }
+    }
+}
+
+/// A firewall group.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FirewallGroupRoot {
+    pub firewall_group: FirewallGroup,
+}
+
+/// An update to a firewall group.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirewallGroupUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_state_up: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub egress_firewall_policy_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingress_firewall_policy_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared: Option<bool>,
+}
+
+/// An update to a firewall group.
+#[derive(Debug, Clone, Serialize)]
+pub struct FirewallGroupUpdateRoot {
+    pub firewall_group: FirewallGroupUpdate,
+}
+
+/// A list of firewall groups.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallGroupsRoot {
+    pub firewall_groups: Vec<FirewallGroup>,
+}
+
+/// A firewall policy.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FirewallPolicy {
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub audited: bool,
+    #[serde(
+        deserialize_with = "empty_as_default",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub firewall_rules: Vec<String>,
+    #[serde(skip_serializing)]
+    pub id: String,
+    #[serde(
+        deserialize_with = "empty_as_default",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub shared: bool,
+}
+
+/// A firewall policy.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FirewallPolicyRoot {
+    pub firewall_policy: FirewallPolicy,
+}
+
+/// An update to a firewall policy.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirewallPolicyUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audited: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub firewall_rules: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared: Option<bool>,
+}
+
+/// An update to a firewall policy.
+#[derive(Debug, Clone, Serialize)]
+pub struct FirewallPolicyUpdateRoot {
+    pub firewall_policy: FirewallPolicyUpdate,
+}
+
+/// A list of firewall policies.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallPoliciesRoot {
+    pub firewall_policies: Vec<FirewallPolicy>,
+}
+
+/// A request to insert a rule into a firewall policy or to remove it.
+#[derive(Debug, Clone, Serialize)]
+pub struct FirewallRuleInsertion {
+    pub firewall_rule_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_before: Option<String>,
+}
+
+/// A firewall rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FirewallRule {
+    pub action: FirewallAction,
+    #[serde(
+        deserialize_with = "empty_as_default",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination_firewall_group_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination_ip_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination_port: Option<String>,
+    pub enabled: bool,
+    #[serde(skip_serializing)]
+    pub id: String,
+    pub ip_version: IpVersion,
+    #[serde(
+        deserialize_with = "empty_as_default",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<FirewallProtocol>,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub shared: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_firewall_group_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_ip_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_port: Option<String>,
+}
+
+impl Default for FirewallRule {
+    fn default() -> FirewallRule {
+        FirewallRule {
+            action: FirewallAction::Deny,
+            description: None,
+            destination_firewall_group_id: None,
+            destination_ip_address: None,
+            destination_port: None,
+            enabled: true,
+            id: String::new(),
+            ip_version: IpVersion::V4,
+            name: None,
+            project_id: None,
+            protocol: None,
+            shared: false,
+            source_firewall_group_id: None,
+            source_ip_address: None,
+            source_port: None,
+        }
+    }
+}
+
+/// A firewall rule.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FirewallRuleRoot {
+    pub firewall_rule: FirewallRule,
+}
+
+/// An update to a firewall rule.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirewallRuleUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<FirewallAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination_ip_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination_port: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<FirewallProtocol>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_ip_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_port: Option<String>,
+}
+
+/// An update to a firewall rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct FirewallRuleUpdateRoot {
+    pub firewall_rule: FirewallRuleUpdate,
+}
+
+/// A list of firewall rules.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallRulesRoot {
+    pub firewall_rules: Vec<FirewallRule>,
+}
+
+/// A security group.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct SecurityGroup {
+    /// Description of the security group.
+    #[serde(default, deserialize_with = "empty_as_default")]
+    pub description: Option<String>,
+    /// Unique ID.
+    pub id: String,
+    /// Name of the security group.
+    pub name: String,
+    /// ID of the project owning the security group.
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// Whether the security group is stateful.
+    #[serde(default)]
+    pub stateful: Option<bool>,
+}
+
+/// A list of security groups.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityGroupsRoot {
+    pub security_groups: Vec<SecurityGroup>,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -923,7 +1669,7 @@ mod test {
 
         // Test that a JSON serialisation of MAC addresses work
         assert_eq!(
-            serde_json::to_value(&a)
+            serde_json::to_value(a)
                 .expect("Could not serialize")
                 .get("mac_address")
                 .expect("No mac_address")
@@ -938,4 +1684,248 @@ mod test {
                 .expect("Cannot parse this JSON");
         assert_eq!(a.mac_address, None);
     }
+
+    #[test]
+    fn test_macaddr_constructors() {
+        let mac: MacAddress = "fa:16:3e:01:02:03".parse().expect("Cannot parse");
+        assert_eq!(mac, MacAddress::new([0xfa, 0x16, 0x3e, 1, 2, 3]));
+        assert_eq!(MacAddress::try_from("fa:16:3e:01:02:03").unwrap(), mac);
+        assert_eq!(<[u8; 6]>::from(mac), [0xfa, 0x16, 0x3e, 1, 2, 3]);
+        let _ = "not a mac"
+            .parse::<MacAddress>()
+            .expect_err("Invalid MAC address accepted");
+
+        let _ = MacAddress::new_local([0xfa, 0x16, 0x3e, 1, 2, 3]).expect("Local MAC rejected");
+        let _ =
+            MacAddress::new_local([0x00, 0x16, 0x3e, 1, 2, 3]).expect_err("Universal MAC accepted");
+        let _ =
+            MacAddress::new_local([0x03, 0x16, 0x3e, 1, 2, 3]).expect_err("Multicast MAC accepted");
+    }
+
+    #[test]
+    fn test_provider_network() {
+        let mut net = Network {
+            name: Some("vlan-net".into()),
+            provider_network_type: Some(NetworkType::Vlan),
+            provider_physical_network: Some("physnet1".into()),
+            provider_segmentation_id: Some(42),
+            availability_zone_hints: vec!["az1".into()],
+            ..Network::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&net).expect("Could not serialize"),
+            serde_json::json!({
+                "admin_state_up": true,
+                "availability_zone_hints": ["az1"],
+                "name": "vlan-net",
+                "provider:network_type": "vlan",
+                "provider:physical_network": "physnet1",
+                "provider:segmentation_id": 42
+            })
+        );
+
+        net.provider_network_type = None;
+        net.provider_physical_network = None;
+        net.provider_segmentation_id = None;
+        net.segments = vec![
+            NetworkSegment::vlan("physnet1", 42),
+            NetworkSegment::vxlan(1000),
+        ];
+        assert_eq!(
+            serde_json::to_value(&net).expect("Could not serialize")["segments"],
+            serde_json::json!([
+                {
+                    "provider:network_type": "vlan",
+                    "provider:physical_network": "physnet1",
+                    "provider:segmentation_id": 42
+                },
+                {"provider:network_type": "vxlan", "provider:segmentation_id": 1000}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_serialize_conntrack_helper() {
+        let body = ConntrackHelperCreateRoot {
+            conntrack_helper: ConntrackHelper::new(Helper::TFTP, NetworkProtocol::UDP, 69),
+        };
+        assert_eq!(
+            serde_json::to_value(body).expect("Could not serialize"),
+            serde_json::json!({
+                "conntrack_helper": {"helper": "tftp", "protocol": "udp", "port": 69}
+            })
+        );
+    }
+
+    #[test]
+    fn test_network_extra_fields() {
+        let net: Network = serde_json::from_value(serde_json::json!({
+            "admin_state_up": true,
+            "id": "abcd",
+            "name": "net",
+            "status": "ACTIVE",
+            "vendor:segment": 42
+        }))
+        .expect("Could not parse this JSON");
+        assert_eq!(
+            net.extra.get("vendor:segment"),
+            Some(&serde_json::json!(42))
+        );
+        assert!(!net.extra.contains_key("name"));
+
+        let value = serde_json::to_value(&net).expect("Could not serialize");
+        assert!(value.get("vendor:segment").is_none());
+    }
+
+    #[test]
+    fn test_unknown_status() {
+        let status: NetworkStatus =
+            serde_json::from_value(serde_json::json!("ACTIVE")).expect("Could not parse");
+        assert_eq!(status, NetworkStatus::Active);
+
+        let status: NetworkStatus =
+            serde_json::from_value(serde_json::json!("VENDOR_STATE")).expect("Could not parse");
+        assert_eq!(status, NetworkStatus::Unknown("VENDOR_STATE".into()));
+        assert_eq!(status.to_string(), "VENDOR_STATE");
+        assert_eq!(
+            serde_json::to_value(status).expect("Could not serialize"),
+            serde_json::json!("VENDOR_STATE")
+        );
+
+        // The raw value is kept, so it is not confused with other unknown values.
+        let status: NetworkStatus =
+            serde_json::from_value(serde_json::json!("UNKNOWN")).expect("Could not parse");
+        assert_eq!(status, NetworkStatus::Unknown("UNKNOWN".into()));
+        assert_ne!(status, NetworkStatus::Unknown("VENDOR_STATE".into()));
+    }
+
+    #[test]
+    fn test_parse_ip_availability() {
+        let root: NetworkIpAvailabilityRoot = serde_json::from_str(
+            r#"{"network_ip_availability": {
+                "network_id": "net1",
+                "network_name": "public",
+                "project_id": "p1",
+                "total_ips": 18446744073709551867,
+                "used_ips": 5,
+                "subnet_ip_availability": [
+                    {"cidr": "10.0.0.0/24", "ip_version": 4, "subnet_id": "s1",
+                     "subnet_name": "v4", "total_ips": 253, "used_ips": 3},
+                    {"cidr": "fd00::/64", "ip_version": 6, "subnet_id": "s2",
+                     "subnet_name": "v6", "total_ips": 18446744073709551614,
+                     "used_ips": 2}
+                ]
+            }}"#,
+        )
+        .unwrap();
+        let avail = root.network_ip_availability;
+        assert_eq!(avail.free_ips(), 18446744073709551862);
+        assert_eq!(avail.subnet_ip_availability.len(), 2);
+        assert_eq!(avail.subnet_ip_availability[0].ip_version, IpVersion::V4);
+        assert_eq!(avail.subnet_ip_availability[0].free_ips(), 250);
+    }
+
+    #[test]
+    fn test_network_quota_usage() {
+        let root: NetworkQuotaUsageRoot = serde_json::from_str(
+            r#"{"quota": {
+                "floatingip": {"limit": 10, "used": 8, "reserved": 1},
+                "network": {"limit": -1, "used": 3, "reserved": 0},
+                "port": {"limit": 50, "used": 60, "reserved": 0},
+                "rbac_policy": {"limit": 10, "used": 0, "reserved": 0}
+            }}"#,
+        )
+        .unwrap();
+        let usage = root.quota;
+        assert_eq!(usage.floating_ips.available(), Some(1));
+        assert!(usage.networks.is_unlimited());
+        assert_eq!(usage.networks.available(), None);
+        assert_eq!(usage.ports.available(), Some(0));
+        assert!(usage.routers.is_unlimited());
+    }
+
+    #[test]
+    fn test_host_route() {
+        let next_hop = net::Ipv4Addr::new(192, 168, 0, 1);
+        let route = HostRoute::default_via(next_hop);
+        assert_eq!(
+            serde_json::to_value(route).unwrap(),
+            serde_json::json!({"destination": "0.0.0.0/0", "nexthop": "192.168.0.1"})
+        );
+        let destination: ipnet::Ipv4Net = "10.0.0.0/8".parse().unwrap();
+        let route = HostRoute::new(destination, next_hop);
+        assert_eq!(route.destination.to_string(), "10.0.0.0/8");
+        assert_eq!(route.next_hop, net::IpAddr::V4(next_hop));
+    }
+
+    #[test]
+    fn test_firewall_rule() {
+        let root: FirewallRuleRoot = serde_json::from_str(
+            r#"{"firewall_rule": {
+                "action": "allow",
+                "description": "",
+                "destination_firewall_group_id": null,
+                "destination_ip_address": "10.0.0.0/24",
+                "destination_port": "80:90",
+                "enabled": true,
+                "id": "f03bd950-6c56-4f5e-a307-45967078f507",
+                "ip_version": 4,
+                "name": "http",
+                "project_id": "45977fa2dbd7482098dd68d0d8970117",
+                "protocol": "tcp",
+                "shared": false,
+                "source_firewall_group_id": null,
+                "source_ip_address": null,
+                "source_port": null
+            }}"#,
+        )
+        .unwrap();
+        let rule = root.firewall_rule;
+        assert_eq!(rule.action, FirewallAction::Allow);
+        assert_eq!(rule.description, None);
+        assert_eq!(rule.protocol, Some(FirewallProtocol::Tcp));
+        assert_eq!(rule.destination_port.as_deref(), Some("80:90"));
+        assert_eq!(rule.source_ip_address, None);
+
+        let request = FirewallRule {
+            action: FirewallAction::Reject,
+            protocol: Some(FirewallProtocol::Udp),
+            destination_port: Some("53".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            serde_json::json!({
+                "action": "reject",
+                "destination_port": "53",
+                "enabled": true,
+                "ip_version": 4,
+                "protocol": "udp"
+            })
+        );
+    }
+
+    #[test]
+    fn test_firewall_group() {
+        let root: FirewallGroupRoot = serde_json::from_str(
+            r#"{"firewall_group": {
+                "admin_state_up": true,
+                "description": "",
+                "egress_firewall_policy_id": null,
+                "id": "3af94f0e-b52d-491a-87d2-704497305948",
+                "ingress_firewall_policy_id": "c69933c1-b472-44f9-8226-30dc4ffd454c",
+                "name": "",
+                "ports": ["9d3d0d3c-d6d8-4e6b-b3b1-b3a4e2d0e3b9"],
+                "project_id": "45977fa2dbd7482098dd68d0d8970117",
+                "shared": false,
+                "status": "PENDING_CREATE"
+            }}"#,
+        )
+        .unwrap();
+        let group = root.firewall_group;
+        assert_eq!(group.name, None);
+        assert_eq!(group.egress_firewall_policy_id, None);
+        assert_eq!(group.ports.len(), 1);
+        assert_eq!(group.status, FirewallGroupStatus::PendingCreate);
+    }
 }
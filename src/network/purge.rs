@@ -0,0 +1,264 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cleaning up network resources of a project.
+
+use osauth::services::NETWORK;
+use serde::de::DeserializeOwned;
+
+use super::super::common::RawQuery;
+use super::super::session::Session;
+use super::super::{Error, Result};
+use super::{api, protocol};
+
+/// A network resource that could not be deleted.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PurgeFailure {
+    /// Type of the resource, e.g. `port`.
+    pub resource_type: &'static str,
+    /// ID of the resource.
+    pub id: String,
+    /// Error that prevented the deletion.
+    pub error: Error,
+}
+
+/// Outcome of purging network resources of a project.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct NetworkPurgeReport {
+    /// IDs of deleted floating IPs.
+    pub floating_ips: Vec<String>,
+    /// IDs of deleted networks.
+    pub networks: Vec<String>,
+    /// IDs of deleted ports.
+    pub ports: Vec<String>,
+    /// IDs of deleted routers.
+    pub routers: Vec<String>,
+    /// Resources that could not be deleted or detached.
+    pub failures: Vec<PurgeFailure>,
+}
+
+impl NetworkPurgeReport {
+    /// Whether all resources were deleted.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Whether the port is managed by the Networking service itself.
+///
+/// Such ports (DHCP, router interfaces and gateways, floating IPs) are
+/// removed together with their owners.
+fn is_service_port(port: &protocol::Port) -> bool {
+    port.device_owner
+        .as_deref()
+        .is_some_and(|owner| owner.starts_with("network:"))
+}
+
+/// Whether the port is an interface of a router.
+fn is_router_interface(port: &protocol::Port) -> bool {
+    port.device_owner.as_deref().is_some_and(|owner| {
+        owner.starts_with("network:router_interface")
+            || owner == "network:ha_router_replicated_interface"
+    })
+}
+
+/// List all items of a collection matching the filter, following pagination.
+async fn list_all<T: DeserializeOwned>(
+    session: &Session,
+    collection: &str,
+    filter: (&str, &str),
+) -> Result<Vec<T>> {
+    RawQuery::new(session.clone(), NETWORK, [collection], collection)
+        .with_query(filter.0, filter.1)
+        .all()
+        .await
+}
+
+fn record(
+    deleted: &mut Vec<String>,
+    failures: &mut Vec<PurgeFailure>,
+    resource_type: &'static str,
+    id: String,
+    result: Result<()>,
+) {
+    match result {
+        Ok(()) => deleted.push(id),
+        Err(error) => {
+            warn!("Failed to delete {} {}: {}", resource_type, id, error);
+            failures.push(PurgeFailure {
+                resource_type,
+                id,
+                error,
+            });
+        }
+    }
+}
+
+/// Delete floating IPs, routers, ports and networks of a project.
+pub(crate) async fn purge_network_resources<S: AsRef<str>>(
+    session: &Session,
+    project_id: S,
+) -> Result<NetworkPurgeReport> {
+    let project_id = project_id.as_ref();
+    debug!("Purging network resources of project {}", project_id);
+    let filter = ("project_id", project_id);
+    let mut report = NetworkPurgeReport::default();
+
+    let floating_ips: Vec<protocol::FloatingIp> = list_all(session, "floatingips", filter).await?;
+    for floating_ip in floating_ips {
+        let result = api::delete_floating_ip(session, &floating_ip.id).await;
+        record(
+            &mut report.floating_ips,
+            &mut report.failures,
+            "floating IP",
+            floating_ip.id,
+            result,
+        );
+    }
+
+    let routers: Vec<protocol::Router> = list_all(session, "routers", filter).await?;
+    for router in routers {
+        let interfaces: Vec<protocol::Port> =
+            match list_all(session, "ports", ("device_id", &router.id)).await {
+                Ok(interfaces) => interfaces,
+                Err(error) => {
+                    // The router cannot be deleted while it has interfaces.
+                    warn!("Failed to list ports of router {}: {}", router.id, error);
+                    report.failures.push(PurgeFailure {
+                        resource_type: "router",
+                        id: router.id,
+                        error,
+                    });
+                    continue;
+                }
+            };
+        for port in interfaces.iter().filter(|port| is_router_interface(port)) {
+            if let Err(error) =
+                api::remove_router_interface(session, &router.id, None, Some(&port.id)).await
+            {
+                warn!(
+                    "Failed to remove port {} from router {}: {}",
+                    port.id, router.id, error
+                );
+                report.failures.push(PurgeFailure {
+                    resource_type: "router interface",
+                    id: port.id.clone(),
+                    error,
+                });
+            }
+        }
+        let result = api::delete_router(session, &router.id).await;
+        record(
+            &mut report.routers,
+            &mut report.failures,
+            "router",
+            router.id,
+            result,
+        );
+    }
+
+    let ports: Vec<protocol::Port> = list_all(session, "ports", filter).await?;
+    for port in ports {
+        if is_service_port(&port) {
+            continue;
+        }
+        let result = api::delete_port(session, &port.id).await;
+        record(
+            &mut report.ports,
+            &mut report.failures,
+            "port",
+            port.id,
+            result,
+        );
+    }
+
+    let networks: Vec<protocol::Network> = list_all(session, "networks", filter).await?;
+    for network in networks {
+        let result = api::delete_network(session, &network.id).await;
+        record(
+            &mut report.networks,
+            &mut report.failures,
+            "network",
+            network.id,
+            result,
+        );
+    }
+
+    debug!(
+        "Purged network resources of project {} with {} failure(s)",
+        project_id,
+        report.failures.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::protocol;
+    use super::{is_router_interface, is_service_port};
+
+    fn port(device_owner: Option<&str>) -> protocol::Port {
+        let mut value = serde_json::json!({
+            "admin_state_up": true,
+            "fixed_ips": [],
+            "id": "port1",
+            "mac_address": "fa:16:3e:01:02:03",
+            "name": "port1",
+            "network_id": "net1",
+            "status": "ACTIVE",
+        });
+        if let Some(owner) = device_owner {
+            value["device_owner"] = owner.into();
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_is_service_port() {
+        for owner in [
+            "network:dhcp",
+            "network:router_interface",
+            "network:router_gateway",
+            "network:floatingip",
+        ] {
+            assert!(is_service_port(&port(Some(owner))), "{}", owner);
+        }
+        for owner in [Some("compute:nova"), Some("baremetal:none"), Some(""), None] {
+            assert!(!is_service_port(&port(owner)), "{:?}", owner);
+        }
+    }
+
+    #[test]
+    fn test_is_router_interface() {
+        for owner in [
+            "network:router_interface",
+            "network:router_interface_distributed",
+            "network:ha_router_replicated_interface",
+        ] {
+            assert!(is_router_interface(&port(Some(owner))), "{}", owner);
+        }
+        for owner in [
+            Some("network:router_gateway"),
+            Some("network:dhcp"),
+            Some("network:router_ha_interface"),
+            Some("compute:nova"),
+            None,
+        ] {
+            assert!(!is_router_interface(&port(owner)), "{:?}", owner);
+        }
+    }
+}
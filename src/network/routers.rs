@@ -12,19 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::net;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use futures::stream::{Stream, TryStreamExt};
+use reqwest::Url;
+use serde_json::Value;
 
-use super::super::common::{Refresh, ResourceIterator, ResourceQuery, RouterRef};
+use super::super::common::{
+    Refresh, ResourceIterator, ResourceQuery, RouterRef, SubnetRef, ValidationReport,
+};
 use super::super::session::Session;
-use super::super::utils::Query;
+use super::super::utils::{url, NamePattern, Query};
 use super::super::waiter::DeletionWaiter;
 use super::super::{Error, ErrorKind, Result, Sort};
-use super::{api, protocol, Network};
+use super::{api, protocol, Network, Port, PortQuery};
 
 /// A query to router list.
 #[derive(Clone, Debug)]
@@ -32,6 +37,8 @@ pub struct RouterQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
+    name_pattern: Option<NamePattern>,
 }
 
 /// Structure representing a single router.
@@ -139,6 +146,28 @@ impl Router {
         set_external_gateway, with_external_gateway -> external_gateway: optional protocol::ExternalGateway
     }
 
+    /// External IP addresses of the router's gateway.
+    ///
+    /// Empty if the router has no external gateway or the addresses are not
+    /// visible to the caller.
+    pub fn external_ips(&self) -> Vec<net::IpAddr> {
+        self.inner
+            .external_gateway
+            .as_ref()
+            .map(|gw| {
+                gw.external_fixed_ips
+                    .iter()
+                    .map(|ip| ip.ip_address)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "Flavor associated with router."]
         flavor_id:  ref Option<String>
@@ -196,7 +225,7 @@ impl Router {
 
     transparent_property! {
         #[doc = "Status of the router."]
-        status: protocol::RouterStatus
+        status: clone protocol::RouterStatus
     }
 
     transparent_property! {
@@ -209,6 +238,14 @@ impl Router {
         updated_at: Option<DateTime<FixedOffset>>
     }
 
+    /// Link to the router in the dashboard (Horizon) at `base`.
+    ///
+    /// See [Cloud::dashboard_url](../struct.Cloud.html#method.dashboard_url)
+    /// for where to get the base URL from.
+    pub fn dashboard_url(&self, base: &Url) -> Result<Url> {
+        url::dashboard(base, ["project", "routers", &self.inner.id, ""])
+    }
+
     /// Delete the router.
     pub async fn delete(self) -> Result<DeletionWaiter<Router>> {
         api::delete_router(&self.session, &self.inner.id).await?;
@@ -219,6 +256,81 @@ impl Router {
         ))
     }
 
+    /// Convert the router to a highly available (L3 HA) router.
+    ///
+    /// The Network service only allows this change on a disabled router, so
+    /// an enabled router is disabled for the duration of the update, which
+    /// interrupts its traffic. Usually requires administrator privileges.
+    pub async fn convert_to_ha(&mut self) -> Result<()> {
+        if self.inner.ha == Some(true) {
+            return Ok(());
+        }
+        let update = protocol::RouterUpdate {
+            ha: Some(true),
+            ..Default::default()
+        };
+        self.update_while_disabled(update).await
+    }
+
+    /// Convert the router to a distributed (DVR) router.
+    ///
+    /// Like [convert_to_ha](#method.convert_to_ha), temporarily disables an
+    /// enabled router.
+    pub async fn convert_to_distributed(&mut self) -> Result<()> {
+        if self.inner.distributed == Some(true) {
+            return Ok(());
+        }
+        let update = protocol::RouterUpdate {
+            distributed: Some(true),
+            ..Default::default()
+        };
+        self.update_while_disabled(update).await
+    }
+
+    async fn update_while_disabled(&mut self, update: protocol::RouterUpdate) -> Result<()> {
+        if self.is_dirty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The router has unsaved changes",
+            ));
+        }
+
+        let was_up = self.inner.admin_state_up;
+        if was_up {
+            debug!("Disabling router {} before conversion", self.inner.id);
+            self.inner = api::update_router(
+                &self.session,
+                &self.inner.id,
+                protocol::RouterUpdate {
+                    admin_state_up: Some(false),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+
+        let result = api::update_router(&self.session, &self.inner.id, update).await;
+        if let Ok(inner) = &result {
+            self.inner = inner.clone();
+        }
+
+        if was_up {
+            // Re-enable the router even if the conversion failed.
+            debug!("Enabling router {} after conversion", self.inner.id);
+            self.inner = api::update_router(
+                &self.session,
+                &self.inner.id,
+                protocol::RouterUpdate {
+                    admin_state_up: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+
+        result.map(|_| ())
+    }
+
     /// Whether the router is modified.
     pub fn is_dirty(&self) -> bool {
         !self.dirty.is_empty()
@@ -242,6 +354,19 @@ impl Router {
         Ok(())
     }
 
+    /// List internal interfaces of the router.
+    ///
+    /// Includes distributed and HA interfaces, but not the gateway port.
+    /// The subnets of the interfaces are available through their
+    /// [fixed IPs](struct.Port.html#method.fixed_ips).
+    pub async fn interfaces(&self) -> Result<Vec<Port>> {
+        PortQuery::new(self.session.clone())
+            .with_device_id(self.inner.id.clone())
+            .only_router_interfaces()
+            .all()
+            .await
+    }
+
     /// Add an interface to the router.
     pub async fn add_router_interface(
         &mut self,
@@ -269,6 +394,35 @@ impl Router {
     pub async fn remove_extra_routes(&mut self, routes: Vec<protocol::HostRoute>) -> Result<()> {
         api::remove_extra_routes(&self.session, self.id(), routes).await
     }
+
+    /// List conntrack helper resources of the router.
+    ///
+    /// Unlike `conntrack_helpers`, this call returns the helpers with their IDs.
+    pub async fn list_conntrack_helpers(&self) -> Result<Vec<protocol::RouterConntrackHelper>> {
+        api::list_conntrack_helpers(&self.session, self.id()).await
+    }
+
+    /// Add a conntrack helper to the router.
+    pub async fn add_conntrack_helper(
+        &mut self,
+        helper: protocol::ConntrackHelper,
+    ) -> Result<protocol::RouterConntrackHelper> {
+        api::create_conntrack_helper(&self.session, self.id(), helper).await
+    }
+
+    /// Update a conntrack helper of the router.
+    pub async fn update_conntrack_helper<S: AsRef<str>>(
+        &mut self,
+        id: S,
+        helper: protocol::ConntrackHelper,
+    ) -> Result<protocol::RouterConntrackHelper> {
+        api::update_conntrack_helper(&self.session, self.id(), id, helper).await
+    }
+
+    /// Remove a conntrack helper from the router.
+    pub async fn remove_conntrack_helper<S: AsRef<str>>(&mut self, id: S) -> Result<()> {
+        api::delete_conntrack_helper(&self.session, self.id(), id).await
+    }
 }
 
 #[async_trait]
@@ -279,6 +433,10 @@ impl Refresh for Router {
         self.dirty.clear();
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl RouterQuery {
@@ -287,6 +445,8 @@ impl RouterQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
+            name_pattern: None,
         }
     }
 
@@ -308,6 +468,15 @@ impl RouterQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::RouterSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -322,6 +491,8 @@ impl RouterQuery {
         self
     }
 
+    query_name_pattern!("routers");
+
     /// Convert this query into an stream executing the request.
     ///
     /// Returns a `TryStream`, which is a stream with each `next`
@@ -330,7 +501,7 @@ impl RouterQuery {
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<Router>> {
         debug!("Fetching routers with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -346,13 +517,15 @@ impl RouterQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<Router> {
         debug!("Fetching one router with {:?}", self.query);
-        if self.can_paginate {
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yieled more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -366,10 +539,27 @@ impl ResourceQuery for RouterQuery {
         Ok(self.can_paginate)
     }
 
+    async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
+        Ok(())
+    }
+
     fn extract_marker(&self, resource: &Self::Item) -> String {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -394,7 +584,11 @@ impl NewRouter {
     }
 
     /// Request creation of a router.
+    ///
+    /// Fails with all problems found by [validate](#method.validate) before
+    /// sending any requests.
     pub async fn create(self) -> Result<Router> {
+        self.validate().into_result()?;
         let inner = api::create_router(
             &self.session,
             self.inner.into_verified(&self.session).await?,
@@ -403,6 +597,50 @@ impl NewRouter {
         Ok(Router::new(self.session, inner))
     }
 
+    /// Check the request for problems that can be detected locally.
+    ///
+    /// Verifies that external fixed IPs have an address or a subnet.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        if let Some(ref gateway) = self.inner.external_gateway {
+            for fixed_ip in &gateway.external_fixed_ips {
+                if fixed_ip.ip_address.is_unspecified() && fixed_ip.subnet_id.is_empty() {
+                    report.add("an external fixed IP requires an address or a subnet");
+                }
+            }
+        }
+        report
+    }
+
+    /// Check the request for all detectable problems without creating the router.
+    ///
+    /// In addition to [validate](#method.validate), verifies that the
+    /// external network and subnets exist.
+    pub async fn verify(&self) -> Result<ValidationReport> {
+        let mut report = self.validate();
+        if let Some(ref gateway) = self.inner.external_gateway {
+            report.check_found(
+                gateway
+                    .network_id
+                    .clone()
+                    .into_verified(&self.session)
+                    .await,
+                format_args!("external network {}", gateway.network_id),
+            )?;
+            for fixed_ip in &gateway.external_fixed_ips {
+                if !fixed_ip.subnet_id.is_empty() {
+                    report.check_found(
+                        SubnetRef::from(fixed_ip.subnet_id.as_str())
+                            .into_verified(&self.session)
+                            .await,
+                        format_args!("external subnet {}", fixed_ip.subnet_id),
+                    )?;
+                }
+            }
+        }
+        Ok(report)
+    }
+
     creation_inner_field! {
         #[doc = "Set administrative status for the router."]
         set_admin_state_up, with_admin_state_up -> admin_state_up: bool
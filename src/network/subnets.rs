@@ -14,17 +14,21 @@
 
 //! Subnets management via Network API.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::net;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use futures::stream::{Stream, TryStreamExt};
+use reqwest::Url;
+use serde_json::Value;
 
-use super::super::common::{NetworkRef, Refresh, ResourceIterator, ResourceQuery, SubnetRef};
+use super::super::common::{
+    NetworkRef, Refresh, ResourceIterator, ResourceQuery, SubnetRef, ValidationReport,
+};
 use super::super::session::Session;
-use super::super::utils::Query;
+use super::super::utils::{url, NamePattern, Query};
 use super::super::waiter::DeletionWaiter;
 use super::super::{Result, Sort};
 use super::{api, protocol, Network};
@@ -35,7 +39,9 @@ pub struct SubnetQuery {
     session: Session,
     query: Query,
     can_paginate: bool,
+    page_size: Option<usize>,
     network: Option<NetworkRef>,
+    name_pattern: Option<NamePattern>,
 }
 
 /// Structure representing a subnet - a virtual NIC.
@@ -122,6 +128,11 @@ impl Subnet {
             -> dns_nameservers: Vec<String>
     }
 
+    transparent_property! {
+        #[doc = "Fields returned by the service that are not known to this crate."]
+        extra: ref HashMap<String, Value>
+    }
+
     transparent_property! {
         #[doc = "Gateway IP address (if any)."]
         gateway_ip: Option<net::IpAddr>
@@ -183,11 +194,27 @@ impl Subnet {
         network_id: ref String
     }
 
+    transparent_property! {
+        #[doc = "ID of the network segment this subnet is associated with (if any)."]
+        segment_id: ref Option<String>
+    }
+
     transparent_property! {
         #[doc = "Last update data and time (if available)."]
         updated_at: Option<DateTime<FixedOffset>>
     }
 
+    /// Link to the subnet in the dashboard (Horizon) at `base`.
+    ///
+    /// See [Cloud::dashboard_url](../struct.Cloud.html#method.dashboard_url)
+    /// for where to get the base URL from.
+    pub fn dashboard_url(&self, base: &Url) -> Result<Url> {
+        url::dashboard(
+            base,
+            ["project", "networks", "subnets", &self.inner.id, "detail"],
+        )
+    }
+
     /// Delete the subnet.
     pub async fn delete(self) -> Result<DeletionWaiter<Subnet>> {
         api::delete_subnet(&self.session, &self.inner.id).await?;
@@ -229,6 +256,10 @@ impl Refresh for Subnet {
         self.dirty.clear();
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl SubnetQuery {
@@ -237,7 +268,9 @@ impl SubnetQuery {
             session,
             query: Query::new(),
             can_paginate: true,
+            page_size: None,
             network: None,
+            name_pattern: None,
         }
     }
 
@@ -259,6 +292,15 @@ impl SubnetQuery {
         self
     }
 
+    /// Set the number of items to request per page.
+    ///
+    /// Only affects automatic pagination, the total number of items is not
+    /// limited. Reduce it if the service rejects or times out on large pages.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
     /// Add sorting to the request.
     pub fn sort_by(mut self, sort: Sort<protocol::SubnetSortKey>) -> Self {
         let (field, direction) = sort.into();
@@ -304,6 +346,8 @@ impl SubnetQuery {
         set_name, with_name -> name
     }
 
+    query_name_pattern!("subnets");
+
     /// Filter by network.
     pub fn set_network<N: Into<NetworkRef>>(&mut self, value: N) {
         self.network = Some(value.into());
@@ -323,7 +367,7 @@ impl SubnetQuery {
     /// Note that no requests are done until you start iterating.
     pub fn into_stream(self) -> impl Stream<Item = Result<Subnet>> {
         debug!("Fetching subnets with {:?}", self.query);
-        ResourceIterator::new(self).into_stream()
+        ResourceIterator::new(self.session.clone(), self).into_stream()
     }
 
     /// Execute this request and return all results.
@@ -339,13 +383,15 @@ impl SubnetQuery {
     /// with `TooManyItems` if the query produces more than one result.
     pub async fn one(mut self) -> Result<Subnet> {
         debug!("Fetching one subnet with {:?}", self.query);
-        if self.can_paginate {
+        if self.can_paginate && self.name_pattern.is_none() {
             // We need only one result. We fetch maximum two to be able
             // to check if the query yieled more than one result.
             self.query.push("limit", 2);
         }
 
-        ResourceIterator::new(self).one().await
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
     }
 }
 
@@ -363,6 +409,16 @@ impl ResourceQuery for SubnetQuery {
         resource.id().clone()
     }
 
+    fn matches(&self, resource: &Self::Item) -> bool {
+        self.name_pattern.as_ref().map_or(true, |pattern| {
+            pattern.matches(resource.name().as_deref().unwrap_or_default())
+        })
+    }
+
+    fn page_size(&self) -> Option<usize> {
+        self.page_size
+    }
+
     async fn fetch_chunk(
         &self,
         limit: Option<usize>,
@@ -377,6 +433,9 @@ impl ResourceQuery for SubnetQuery {
     }
 
     async fn validate(&mut self) -> Result<()> {
+        if let Some(ref pattern) = self.name_pattern {
+            pattern.validate()?;
+        }
         if let Some(network) = self.network.take() {
             let verified = network.into_verified(&self.session).await?;
             self.query.push_str("network_id", verified);
@@ -396,15 +455,128 @@ impl NewSubnet {
     }
 
     /// Request creation of the subnet.
-    pub async fn create(mut self) -> Result<Subnet> {
+    ///
+    /// Fails with all problems found by [validate](#method.validate) before
+    /// sending any requests.
+    pub async fn create(self) -> Result<Subnet> {
+        let session = self.session.clone();
+        let request = self.into_request().await?;
+        let subnet = api::create_subnet(&session, request).await?;
+        Ok(Subnet::new(session, subnet))
+    }
+
+    /// Create several subnets in one request.
+    ///
+    /// The Networking service creates either all subnets or none of them.
+    pub(crate) async fn create_many(
+        session: Session,
+        subnets: Vec<NewSubnet>,
+    ) -> Result<Vec<Subnet>> {
+        if subnets.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut requests = Vec::with_capacity(subnets.len());
+        for subnet in subnets {
+            requests.push(subnet.into_request().await?);
+        }
+        Ok(api::create_subnets(&session, requests)
+            .await?
+            .into_iter()
+            .map(|subnet| Subnet::new(session.clone(), subnet))
+            .collect())
+    }
+
+    /// Validate the subnet and resolve all references.
+    async fn into_request(mut self) -> Result<protocol::Subnet> {
+        self.validate().into_result()?;
         self.inner.network_id = self.network.into_verified(&self.session).await?.into();
         self.inner.ip_version = match self.inner.cidr {
             ipnet::IpNet::V4(..) => protocol::IpVersion::V4,
             ipnet::IpNet::V6(..) => protocol::IpVersion::V6,
         };
+        Ok(self.inner)
+    }
+
+    /// Check the request for problems that can be detected locally.
+    ///
+    /// Verifies that the gateway, the allocation pools and the host routes
+    /// match the CIDR, and that IPv6 modes are only used with IPv6 subnets.
+    /// Host routes must lead outside of the CIDR via a next hop inside it.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let cidr = self.inner.cidr;
+        let is_v4 = matches!(cidr, ipnet::IpNet::V4(..));
+
+        if let Some(gateway) = self.inner.gateway_ip {
+            if !cidr.contains(&gateway) {
+                report.add(format!("gateway {gateway} is outside of CIDR {cidr}"));
+            }
+        }
+
+        for pool in &self.inner.allocation_pools {
+            if !cidr.contains(&pool.start) || !cidr.contains(&pool.end) {
+                report.add(format!(
+                    "allocation pool {}-{} is outside of CIDR {}",
+                    pool.start, pool.end, cidr
+                ));
+            } else if pool.start > pool.end {
+                report.add(format!(
+                    "allocation pool {}-{} starts after its end",
+                    pool.start, pool.end
+                ));
+            } else if let Some(gateway) = self.inner.gateway_ip {
+                if pool.start <= gateway && gateway <= pool.end {
+                    report.add(format!(
+                        "gateway {} is inside allocation pool {}-{}",
+                        gateway, pool.start, pool.end
+                    ));
+                }
+            }
+        }
+
+        for route in &self.inner.host_routes {
+            let route_is_v4 = matches!(route.destination, ipnet::IpNet::V4(..));
+            if route_is_v4 != is_v4 || route.next_hop.is_ipv4() != is_v4 {
+                report.add(format!(
+                    "host route to {} via {} does not match the IP version of CIDR {}",
+                    route.destination, route.next_hop, cidr
+                ));
+                continue;
+            }
+            if cidr.contains(&route.destination) {
+                report.add(format!(
+                    "host route destination {} is inside CIDR {}",
+                    route.destination, cidr
+                ));
+            }
+            if !cidr.contains(&route.next_hop) {
+                report.add(format!(
+                    "next hop {} of host route to {} is outside of CIDR {}",
+                    route.next_hop, route.destination, cidr
+                ));
+            }
+        }
+
+        if is_v4
+            && (self.inner.ipv6_address_mode.is_some()
+                || self.inner.ipv6_router_advertisement_mode.is_some())
+        {
+            report.add(format!("IPv6 modes cannot be used with IPv4 CIDR {cidr}"));
+        }
+
+        report
+    }
 
-        let subnet = api::create_subnet(&self.session, self.inner).await?;
-        Ok(Subnet::new(self.session, subnet))
+    /// Check the request for all detectable problems without creating the subnet.
+    ///
+    /// In addition to [validate](#method.validate), verifies that the network exists.
+    pub async fn verify(&self) -> Result<ValidationReport> {
+        let mut report = self.validate();
+        report.check_found(
+            self.network.clone().into_verified(&self.session).await,
+            format_args!("network {}", self.network),
+        )?;
+        Ok(report)
     }
 
     creation_inner_vec! {
@@ -412,6 +584,12 @@ impl NewSubnet {
         add_allocation_pool, with_allocation_pool -> allocation_pools: protocol::AllocationPool
     }
 
+    creation_inner_field! {
+        #[doc = "Replace the allocation pools of the subnet."]
+        set_allocation_pools, with_allocation_pools
+            -> allocation_pools: Vec<protocol::AllocationPool>
+    }
+
     creation_inner_field! {
         #[doc = "Set CIDR of the subnet."]
         set_cidr, with_cidr -> cidr: ipnet::IpNet
@@ -437,6 +615,11 @@ impl NewSubnet {
         add_host_route, with_host_route -> host_routes: protocol::HostRoute
     }
 
+    creation_inner_field! {
+        #[doc = "Replace the host routes of the subnet."]
+        set_host_routes, with_host_routes -> host_routes: Vec<protocol::HostRoute>
+    }
+
     creation_inner_field! {
         #[doc = "Set IPv6 address assignment mode."]
         set_ipv6_address_mode, with_ipv6_address_mode
@@ -454,6 +637,11 @@ impl NewSubnet {
         set_name, with_name -> name: optional String
     }
 
+    creation_inner_field! {
+        #[doc = "Associate the subnet with a segment of a routed provider network."]
+        set_segment_id, with_segment_id -> segment_id: optional String
+    }
+
     /// Set the network of the subnet.
     pub fn set_network<N>(&mut self, value: N)
     where
@@ -489,3 +677,106 @@ impl SubnetRef {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use osauth::NoAuth;
+
+    use super::super::super::common::NetworkRef;
+    use super::super::super::session::Session;
+    use super::super::protocol::{AllocationPool, HostRoute, Ipv6Mode};
+    use super::NewSubnet;
+
+    async fn new_subnet(cidr: &str) -> NewSubnet {
+        let session = Session::new(NoAuth::new("http://127.0.0.1:9696/").unwrap())
+            .await
+            .unwrap();
+        NewSubnet::new(
+            session,
+            NetworkRef::new_verified("net1".into()),
+            cidr.parse().unwrap(),
+        )
+    }
+
+    fn pool(start: &str, end: &str) -> AllocationPool {
+        AllocationPool {
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        }
+    }
+
+    fn route(destination: &str, next_hop: &str) -> HostRoute {
+        HostRoute {
+            destination: destination.parse().unwrap(),
+            next_hop: next_hop.parse().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_valid() {
+        let mut subnet = new_subnet("192.168.1.0/24")
+            .await
+            .with_allocation_pool(pool("192.168.1.10", "192.168.1.100"))
+            .with_allocation_pool(pool("192.168.1.200", "192.168.1.200"))
+            .with_host_route(route("10.0.0.0/8", "192.168.1.254"))
+            .with_host_route(route("0.0.0.0/0", "192.168.1.1"));
+        subnet.inner.gateway_ip = Some("192.168.1.1".parse().unwrap());
+        let report = subnet.validate();
+        assert!(report.is_ok(), "{}", report);
+
+        let subnet = new_subnet("2001:db8::/64")
+            .await
+            .with_allocation_pool(pool("2001:db8::10", "2001:db8::ff"))
+            .with_host_route(route("2001:db8:1::/64", "2001:db8::1"))
+            .with_ipv6_address_mode(Ipv6Mode::DhcpStateless);
+        let report = subnet.validate();
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[tokio::test]
+    async fn test_validate_invalid_pools() {
+        let mut subnet = new_subnet("192.168.1.0/24")
+            .await
+            // Outside of the CIDR
+            .with_allocation_pool(pool("192.168.1.10", "192.168.2.10"))
+            // Reversed
+            .with_allocation_pool(pool("192.168.1.100", "192.168.1.50"))
+            // Includes the gateway
+            .with_allocation_pool(pool("192.168.1.1", "192.168.1.5"))
+            // Wrong IP version
+            .with_allocation_pool(pool("2001:db8::10", "2001:db8::ff"));
+        subnet.inner.gateway_ip = Some("192.168.1.1".parse().unwrap());
+        assert_eq!(subnet.validate().problems.len(), 4);
+
+        // The gateway itself must be inside the CIDR
+        subnet.inner.allocation_pools.clear();
+        subnet.inner.gateway_ip = Some("192.168.2.1".parse().unwrap());
+        assert_eq!(subnet.validate().problems.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_validate_invalid_routes() {
+        let subnet = new_subnet("192.168.1.0/24")
+            .await
+            // Destination inside the CIDR
+            .with_host_route(route("192.168.1.128/25", "192.168.1.1"))
+            // Next hop outside of the CIDR
+            .with_host_route(route("10.0.0.0/8", "10.0.0.1"))
+            // Wrong IP version of the destination
+            .with_host_route(route("2001:db8::/64", "192.168.1.1"))
+            // Wrong IP version of the next hop
+            .with_host_route(route("10.0.0.0/8", "2001:db8::1"));
+        let report = subnet.validate();
+        assert_eq!(report.problems.len(), 4, "{}", report);
+        assert!(report.problems[0].contains("is inside CIDR"));
+        assert!(report.problems[1].contains("is outside of CIDR"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_ipv6_modes_with_ipv4() {
+        let subnet = new_subnet("192.168.1.0/24")
+            .await
+            .with_ipv6_router_advertisement_mode(Ipv6Mode::Slaac);
+        assert_eq!(subnet.validate().problems.len(), 1);
+    }
+}
@@ -18,14 +18,16 @@ use futures::io::AsyncRead;
 use futures::stream::Stream;
 use osauth::client::NO_PATH;
 use osauth::services::OBJECT_STORAGE;
+use reqwest::header::ETAG;
 use reqwest::{Method, StatusCode};
 
+use super::super::common::protocol::get_header;
 use super::super::session::Session;
-use super::super::utils::Query;
-use super::super::Result;
+use super::super::utils::{FetchJson, Query};
+use super::super::{Error, ErrorKind, Result};
 use super::objects::ObjectHeaders;
 use super::protocol::*;
-use super::utils::{async_read_to_body, body_to_async_read};
+use super::utils::{async_read_to_body, body_to_async_read, is_object_post_header};
 
 /// Create a new container.
 ///
@@ -72,11 +74,30 @@ where
         req = req.header("X-Delete-At", delete_at);
     }
 
+    if let Some(ref etag) = headers.etag {
+        req = req.header(ETAG, etag);
+    }
+
     for (key, value) in headers.metadata {
         req = req.header(&format!("X-Object-Meta-{key}"), value);
     }
 
-    let _ = req.body(async_read_to_body(body)).send().await?;
+    let resp = req.body(async_read_to_body(body)).send().await?;
+    if let Some(expected) = headers.etag {
+        let actual = get_header(resp.headers(), &ETAG)?.map(|s| s.trim_matches('"'));
+        if actual
+            .map(|s| !s.eq_ignore_ascii_case(&expected))
+            .unwrap_or(true)
+        {
+            return Err(Error::new(
+                ErrorKind::OperationFailed,
+                format!(
+                    "Checksum mismatch for object {} in container {}: expected {}, got {:?}",
+                    o_id, c_id, expected, actual
+                ),
+            ));
+        }
+    }
     debug!("Successfully created object {} in container {}", o_id, c_id);
     // We need to retrieve the size, issue HEAD.
     get_object(session, c_id, o_id).await
@@ -142,6 +163,156 @@ where
     Ok(result)
 }
 
+/// List versions of an object.
+pub async fn list_object_versions<C, O>(
+    session: &Session,
+    container: C,
+    object: O,
+) -> Result<Vec<ObjectVersion>>
+where
+    C: AsRef<str>,
+    O: AsRef<str>,
+{
+    let c_id = container.as_ref();
+    let o_id = object.as_ref();
+    let mut query = Query::new();
+    query.push_str("format", "json");
+    query.push_str("versions", "");
+    query.push_str("prefix", o_id);
+    trace!("Listing versions of object {} in container {}", o_id, c_id);
+    let versions: Vec<ObjectVersion> = session
+        .get(OBJECT_STORAGE, &[c_id])
+        .query(&query)
+        .fetch_json()
+        .await?;
+    // Prefix matching also returns other objects starting with the same name.
+    let result: Vec<_> = versions.into_iter().filter(|v| v.name == o_id).collect();
+    trace!("Received versions {:?}", result);
+    Ok(result)
+}
+
+/// Delete a specific version of an object.
+pub async fn delete_object_version<C, O, V>(
+    session: &Session,
+    container: C,
+    object: O,
+    version_id: V,
+) -> Result<()>
+where
+    C: AsRef<str>,
+    O: AsRef<str>,
+    V: AsRef<str>,
+{
+    let c_id = container.as_ref();
+    let o_id = object.as_ref();
+    let v_id = version_id.as_ref();
+    debug!(
+        "Deleting version {} of object {} in container {}",
+        v_id, o_id, c_id
+    );
+    let _ = session
+        .delete(OBJECT_STORAGE, &[c_id, o_id])
+        .query(&[("version-id", v_id)])
+        .send()
+        .await?;
+    debug!(
+        "Successfully deleted version {} of object {} in container {}",
+        v_id, o_id, c_id
+    );
+    Ok(())
+}
+
+/// Make a specific version of an object the current one.
+pub async fn restore_object_version<C, O, V>(
+    session: &Session,
+    container: C,
+    object: O,
+    version_id: V,
+) -> Result<Object>
+where
+    C: AsRef<str>,
+    O: AsRef<str>,
+    V: AsRef<str>,
+{
+    let c_id = container.as_ref();
+    let o_id = object.as_ref();
+    let v_id = version_id.as_ref();
+    debug!(
+        "Restoring version {} of object {} in container {}",
+        v_id, o_id, c_id
+    );
+    let _ = session
+        .put(OBJECT_STORAGE, &[c_id, o_id])
+        .query(&[("version-id", v_id)])
+        .send()
+        .await?;
+    debug!(
+        "Successfully restored version {} of object {} in container {}",
+        v_id, o_id, c_id
+    );
+    get_object(session, c_id, o_id).await
+}
+
+/// Update expiration headers of an object.
+///
+/// Swift replaces all custom metadata and user-settable headers (e.g.
+/// `Content-Disposition`) of an object on POST, so their current values are
+/// fetched and sent back together with the new header. The operation is not
+/// atomic: changes made by others between these two requests are lost.
+pub async fn update_object_expiration<C, O>(
+    session: &Session,
+    container: C,
+    object: O,
+    header: (&'static str, String),
+) -> Result<Object>
+where
+    C: AsRef<str>,
+    O: AsRef<str>,
+{
+    let c_id = container.as_ref();
+    let o_id = object.as_ref();
+    debug!(
+        "Updating expiration of object {} in container {} with {:?}",
+        o_id, c_id, header
+    );
+    let current = session
+        .request(OBJECT_STORAGE, Method::HEAD, &[c_id, o_id])
+        .send()
+        .await?;
+    let mut req = session.post(OBJECT_STORAGE, &[c_id, o_id]);
+    for (key, value) in current.headers() {
+        if is_object_post_header(key.as_str()) {
+            req = req.header(key.clone(), value.clone());
+        }
+    }
+    let _ = req.header(header.0, header.1).send().await?;
+    debug!(
+        "Successfully updated expiration of object {} in container {}",
+        o_id, c_id
+    );
+    get_object(session, c_id, o_id).await
+}
+
+/// Update container metadata headers.
+pub async fn update_container<C>(
+    session: &Session,
+    container: C,
+    headers: Vec<(&'static str, String)>,
+) -> Result<()>
+where
+    C: AsRef<str>,
+{
+    let c_id = container.as_ref();
+    debug!("Updating container {} with {:?}", c_id, headers);
+    let mut req = session.post(OBJECT_STORAGE, &[c_id]);
+    for (key, value) in headers {
+        req = req.header(key, value);
+    }
+    let _ = req.send().await?;
+    debug!("Successfully updated container {}", c_id);
+    Ok(())
+}
+
 /// Download the requested object.
 pub async fn download_object<C, O>(
     session: &Session,
@@ -175,14 +346,14 @@ pub async fn list_containers(
         .await)
 }
 
-/// List objects in a given container.
+/// List objects and pseudo-directories in a given container.
 pub async fn list_objects<C>(
     session: &Session,
     container: C,
     mut query: Query,
     limit: Option<usize>,
     marker: Option<String>,
-) -> Result<impl Stream<Item = Result<Object>>>
+) -> Result<impl Stream<Item = Result<ObjectListItem>>>
 where
     C: AsRef<str> + 'static,
 {
@@ -16,14 +16,18 @@
 
 use async_trait::async_trait;
 use futures::{pin_mut, Stream, TryStreamExt};
+use osauth::services::OBJECT_STORAGE;
+use reqwest::Url;
 
 use super::super::common::{ContainerRef, Refresh};
 use super::super::session::Session;
 use super::super::utils::{try_one, Query};
-use super::super::{ErrorKind, Result};
+use super::super::{Error, ErrorKind, Result};
 use super::objects::{Object, ObjectQuery};
 use super::{api, protocol};
 
+const PUBLIC_READ_ACL: &str = ".r:*,.rlistings";
+
 /// A query to containers.
 #[derive(Clone, Debug)]
 pub struct ContainerQuery {
@@ -109,6 +113,140 @@ impl Container {
         #[doc = "Number of objects in the container."]
         object_count: u64
     }
+
+    /// Read ACL of the container, if any.
+    ///
+    /// Always `None` for containers returned from a listing. Use `refresh` to
+    /// fetch it.
+    #[inline]
+    pub fn read_acl(&self) -> Option<&String> {
+        self.inner.read_acl.as_ref()
+    }
+
+    /// Write ACL of the container, if any.
+    ///
+    /// Always `None` for containers returned from a listing. Use `refresh` to
+    /// fetch it.
+    #[inline]
+    pub fn write_acl(&self) -> Option<&String> {
+        self.inner.write_acl.as_ref()
+    }
+
+    /// Make objects in the container readable and listable anonymously.
+    ///
+    /// This is a shortcut for setting the read ACL to `.r:*,.rlistings`.
+    #[inline]
+    pub async fn make_public(&mut self) -> Result<()> {
+        self.set_read_acl(PUBLIC_READ_ACL).await
+    }
+
+    /// Remove the read ACL, making the container private.
+    pub async fn remove_read_acl(&mut self) -> Result<()> {
+        self.update_headers(vec![("X-Remove-Container-Read", "1".into())])
+            .await
+    }
+
+    /// Remove the write ACL.
+    pub async fn remove_write_acl(&mut self) -> Result<()> {
+        self.update_headers(vec![("X-Remove-Container-Write", "1".into())])
+            .await
+    }
+
+    /// Set the read ACL of the container.
+    ///
+    /// See the Object Storage documentation for the ACL syntax, e.g.
+    /// `.r:*` allows anonymous access to objects.
+    pub async fn set_read_acl<S: Into<String>>(&mut self, acl: S) -> Result<()> {
+        self.update_headers(vec![("X-Container-Read", acl.into())])
+            .await
+    }
+
+    /// Set the write ACL of the container.
+    pub async fn set_write_acl<S: Into<String>>(&mut self, acl: S) -> Result<()> {
+        self.update_headers(vec![("X-Container-Write", acl.into())])
+            .await
+    }
+
+    /// Versioning mode of the container.
+    ///
+    /// `None` if not known, which is the case for containers returned from
+    /// a listing. Use `refresh` to fetch it.
+    #[inline]
+    pub fn versioning(&self) -> Option<&protocol::ContainerVersioning> {
+        self.inner.versioning.as_ref()
+    }
+
+    /// Change the versioning mode of the container.
+    ///
+    /// The legacy modes (`Stack` and `History`) require the archive container
+    /// to exist and cannot be combined with the newer object versioning.
+    pub async fn set_versioning(
+        &mut self,
+        versioning: protocol::ContainerVersioning,
+    ) -> Result<()> {
+        self.update_headers(versioning.to_headers()).await
+    }
+
+    /// Static website configuration of the container.
+    ///
+    /// `None` if not known, which is the case for containers returned from
+    /// a listing. Use `refresh` to fetch it.
+    #[inline]
+    pub fn static_website(&self) -> Option<&protocol::StaticWebsite> {
+        self.inner.static_website.as_ref()
+    }
+
+    /// Configure the container as a static website.
+    ///
+    /// Settings missing from `website` are removed from the container. Note
+    /// that the website is only accessible anonymously if the container is
+    /// public, see [make_public](#method.make_public).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use openstack::object_storage::StaticWebsite;
+    ///
+    /// # async fn async_wrapper() -> openstack::Result<()> {
+    /// let os = openstack::Cloud::from_env().await?;
+    /// let mut container = os.get_container("docs").await?;
+    /// container.make_public().await?;
+    /// container
+    ///     .set_static_website(StaticWebsite::new("index.html").with_error("error.html"))
+    ///     .await?;
+    /// println!("Published at {}", container.website_url().await?);
+    /// # Ok(()) }
+    /// ```
+    pub async fn set_static_website(&mut self, website: protocol::StaticWebsite) -> Result<()> {
+        self.update_headers(website.to_headers()).await
+    }
+
+    /// Stop serving the container as a static website.
+    ///
+    /// The read ACL of the container is not changed.
+    pub async fn disable_static_website(&mut self) -> Result<()> {
+        self.set_static_website(protocol::StaticWebsite::default())
+            .await
+    }
+
+    /// Public URL of the container as a static website.
+    pub async fn website_url(&self) -> Result<Url> {
+        let mut url = self
+            .session
+            .get_endpoint(OBJECT_STORAGE, &[&self.inner.name])
+            .await?;
+        let _ = url
+            .path_segments_mut()
+            .map_err(|_| Error::new(ErrorKind::InvalidResponse, "Invalid endpoint URL"))?
+            .pop_if_empty()
+            .push("");
+        Ok(url)
+    }
+
+    async fn update_headers(&mut self, headers: Vec<(&'static str, String)>) -> Result<()> {
+        api::update_container(&self.session, &self.inner.name, headers).await?;
+        self.refresh().await
+    }
 }
 
 #[async_trait]
@@ -118,6 +256,10 @@ impl Refresh for Container {
         self.inner = api::get_container(&self.session, &self.inner.name).await?;
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl ContainerQuery {
@@ -22,3 +22,4 @@ mod utils;
 
 pub use containers::{Container, ContainerQuery};
 pub use objects::{NewObject, Object, ObjectQuery};
+pub use protocol::{ContainerVersioning, ObjectVersion, StaticWebsite};
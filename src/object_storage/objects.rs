@@ -15,9 +15,10 @@
 //! Stored objects.
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use async_trait::async_trait;
-use chrono::{DateTime, TimeZone};
+use chrono::{DateTime, TimeZone, Utc};
 use futures::io::AsyncRead;
 use futures::{Stream, TryStreamExt};
 use osauth::services::OBJECT_STORAGE;
@@ -27,7 +28,7 @@ use super::super::common::{ContainerRef, ObjectRef, Refresh};
 use super::super::session::Session;
 use super::super::utils::{try_one, Query};
 use super::super::Result;
-use super::{api, protocol};
+use super::{api, protocol, utils};
 
 /// A query to objects.
 #[derive(Clone, Debug)]
@@ -37,6 +38,7 @@ pub struct ObjectQuery {
     query: Query,
     limit: Option<usize>,
     marker: Option<String>,
+    delimiter: Option<String>,
 }
 
 /// A request to create an object.
@@ -54,6 +56,7 @@ pub struct NewObject<R> {
 pub struct ObjectHeaders {
     pub delete_after: Option<u32>,
     pub delete_at: Option<i64>,
+    pub etag: Option<String>,
     pub metadata: HashMap<String, String>,
 }
 
@@ -115,6 +118,14 @@ impl Object {
         api::delete_object(&self.session, &self.c_name, self.inner.name).await
     }
 
+    /// Delete a specific version of the object.
+    ///
+    /// Requires object versioning to be enabled on the container.
+    #[inline]
+    pub async fn delete_version<V: AsRef<str>>(&self, version_id: V) -> Result<()> {
+        api::delete_object_version(&self.session, &self.c_name, &self.inner.name, version_id).await
+    }
+
     /// Download the object.
     ///
     /// The object can be read from the resulting reader.
@@ -134,6 +145,47 @@ impl Object {
         &self.c_name
     }
 
+    /// Date and time when the object is going to be deleted (if set).
+    pub fn delete_at(&self) -> Option<DateTime<Utc>> {
+        self.inner
+            .delete_at
+            .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+    }
+
+    /// Set the date and time when the object must be deleted.
+    ///
+    /// Other metadata of the object is re-sent with its current values, so
+    /// concurrent metadata updates may be lost.
+    pub async fn set_delete_at<T: TimeZone>(&mut self, datetime: DateTime<T>) -> Result<()> {
+        self.update_expiration(("X-Delete-At", datetime.timestamp().to_string()))
+            .await
+    }
+
+    /// Set TTL in seconds for the object.
+    ///
+    /// Other metadata of the object is re-sent with its current values, so
+    /// concurrent metadata updates may be lost.
+    pub async fn set_delete_after(&mut self, ttl: u32) -> Result<()> {
+        self.update_expiration(("X-Delete-After", ttl.to_string()))
+            .await
+    }
+
+    /// Remove the expiration of the object.
+    ///
+    /// Other metadata of the object is re-sent with its current values, so
+    /// concurrent metadata updates may be lost.
+    pub async fn remove_expiration(&mut self) -> Result<()> {
+        self.update_expiration(("X-Remove-Delete-At", "1".into()))
+            .await
+    }
+
+    async fn update_expiration(&mut self, header: (&'static str, String)) -> Result<()> {
+        self.inner =
+            api::update_object_expiration(&self.session, &self.c_name, &self.inner.name, header)
+                .await?;
+        Ok(())
+    }
+
     transparent_property! {
         #[doc = "Object content type (if set)."]
         content_type: ref Option<String>
@@ -149,6 +201,16 @@ impl Object {
         name: ref String
     }
 
+    /// Make a previous version of the object the current one.
+    ///
+    /// Requires object versioning to be enabled on the container.
+    pub async fn restore_version<V: AsRef<str>>(&mut self, version_id: V) -> Result<()> {
+        self.inner =
+            api::restore_object_version(&self.session, &self.c_name, &self.inner.name, version_id)
+                .await?;
+        Ok(())
+    }
+
     /// Object url.
     #[inline]
     pub async fn url(&self) -> Result<Url> {
@@ -156,6 +218,14 @@ impl Object {
             .get_endpoint(OBJECT_STORAGE, &[self.container_name(), self.name()])
             .await
     }
+
+    /// List all versions of the object.
+    ///
+    /// Requires object versioning to be enabled on the container.
+    #[inline]
+    pub async fn versions(&self) -> Result<Vec<protocol::ObjectVersion>> {
+        api::list_object_versions(&self.session, &self.c_name, &self.inner.name).await
+    }
 }
 
 #[async_trait]
@@ -165,6 +235,10 @@ impl Refresh for Object {
         self.inner = api::get_object(&self.session, &self.c_name, &self.inner.name).await?;
         Ok(())
     }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
 }
 
 impl ObjectQuery {
@@ -175,6 +249,7 @@ impl ObjectQuery {
             query: Query::new(),
             limit: None,
             marker: None,
+            delimiter: None,
         }
     }
 
@@ -190,27 +265,78 @@ impl ObjectQuery {
         self
     }
 
-    /// Convert this query into a stream of objects.
-    pub async fn into_stream(self) -> Result<impl Stream<Item = Result<Object>>> {
+    query_filter! {
+        #[doc = "Only return objects with names starting with this prefix."]
+        with_prefix -> prefix
+    }
+
+    /// Group object names by a delimiter, usually `/`.
+    ///
+    /// Objects with the delimiter in their names after the prefix are not
+    /// returned. Use [list_pseudo_directories](#method.list_pseudo_directories)
+    /// to list the pseudo-directories they form.
+    pub fn with_delimiter<T: Into<String>>(mut self, delimiter: T) -> Self {
+        self.delimiter = Some(delimiter.into());
+        self
+    }
+
+    async fn into_item_stream(
+        mut self,
+    ) -> Result<impl Stream<Item = Result<protocol::ObjectListItem>>> {
+        if let Some(delimiter) = self.delimiter {
+            self.query.push_str("delimiter", delimiter);
+        }
         debug!(
             "Fetching objects in container {} with {:?}",
             self.c_name, self.query
         );
-        Ok(api::list_objects(
+        api::list_objects(
             &self.session,
-            self.c_name.clone(),
+            self.c_name,
             self.query,
             self.limit,
             self.marker,
         )
-        .await?
-        .map_ok({
-            let session = self.session;
-            let c_name = self.c_name;
-            move |obj| Object::new(session.clone(), obj, c_name.clone())
+        .await
+    }
+
+    /// Convert this query into a stream of objects.
+    ///
+    /// Pseudo-directories are skipped.
+    pub async fn into_stream(self) -> Result<impl Stream<Item = Result<Object>>> {
+        let session = self.session.clone();
+        let c_name = self.c_name.clone();
+        Ok(self.into_item_stream().await?.try_filter_map(move |item| {
+            futures::future::ok(match item {
+                protocol::ObjectListItem::Object(obj) => {
+                    Some(Object::new(session.clone(), obj, c_name.clone()))
+                }
+                protocol::ObjectListItem::PseudoDirectory { .. } => None,
+            })
         }))
     }
 
+    /// List pseudo-directories matching this query.
+    ///
+    /// Returns their full names including the prefix and the trailing
+    /// delimiter, e.g. `photos/2024/`. Uses `/` as a delimiter unless
+    /// [with_delimiter](#method.with_delimiter) is called.
+    pub async fn list_pseudo_directories(mut self) -> Result<Vec<String>> {
+        if self.delimiter.is_none() {
+            self.delimiter = Some("/".into());
+        }
+        self.into_item_stream()
+            .await?
+            .try_filter_map(|item| {
+                futures::future::ok(match item {
+                    protocol::ObjectListItem::PseudoDirectory { subdir } => Some(subdir),
+                    protocol::ObjectListItem::Object(..) => None,
+                })
+            })
+            .try_collect()
+            .await
+    }
+
     /// Execute this request and return all results.
     ///
     /// A convenience shortcut for `self.into_iter().collect()`.
@@ -252,6 +378,9 @@ impl<R: AsyncRead + Sync + Send + 'static> NewObject<R> {
     }
 
     /// Request creation of the object.
+    ///
+    /// If an ETag is set, the upload fails when the checksum of the stored
+    /// object does not match it.
     pub async fn create(self) -> Result<Object> {
         let c_name = self.c_name.clone();
 
@@ -274,6 +403,9 @@ impl<R: AsyncRead + Sync + Send + 'static> NewObject<R> {
     }
 
     /// Set TTL in seconds for the object.
+    ///
+    /// Other metadata of the object is re-sent with its current values, so
+    /// concurrent metadata updates may be lost.
     #[inline]
     pub fn with_delete_after(mut self, ttl: u32) -> NewObject<R> {
         self.headers.delete_after = Some(ttl);
@@ -281,12 +413,25 @@ impl<R: AsyncRead + Sync + Send + 'static> NewObject<R> {
     }
 
     /// Set the date and time when the object must be deleted.
+    ///
+    /// Other metadata of the object is re-sent with its current values, so
+    /// concurrent metadata updates may be lost.
     #[inline]
     pub fn with_delete_at<T: TimeZone>(mut self, datetime: DateTime<T>) -> NewObject<R> {
         self.headers.delete_at = Some(datetime.timestamp());
         self
     }
 
+    /// Set the expected MD5 checksum (ETag) of the object contents.
+    ///
+    /// The object storage rejects the upload if the received data does not
+    /// match the checksum.
+    #[inline]
+    pub fn with_etag<S: Into<String>>(mut self, etag: S) -> NewObject<R> {
+        self.headers.etag = Some(etag.into());
+        self
+    }
+
     /// Insert a new metadata item.
     #[inline]
     pub fn with_metadata<K, V>(mut self, key: K, item: V) -> NewObject<R>
@@ -299,6 +444,28 @@ impl<R: AsyncRead + Sync + Send + 'static> NewObject<R> {
     }
 }
 
+impl NewObject<()> {
+    /// Start creating an object from a local file.
+    ///
+    /// The MD5 checksum of the file is calculated in advance and used as
+    /// the ETag of the upload.
+    pub(crate) async fn from_path(
+        session: Session,
+        c_name: ContainerRef,
+        name: String,
+        path: &Path,
+    ) -> Result<NewObject<impl AsyncRead + Send + Sync + 'static>> {
+        let etag = utils::file_md5(path).await?;
+        trace!(
+            "Calculated MD5 checksum {} of file {}",
+            etag,
+            path.display()
+        );
+        let body = utils::open_file(path).await?;
+        Ok(NewObject::new(session, c_name, name, body).with_etag(etag))
+    }
+}
+
 impl From<Object> for ObjectRef {
     fn from(value: Object) -> ObjectRef {
         ObjectRef::new_verified(value.inner.name)
@@ -23,12 +23,55 @@ use serde::Deserialize;
 use super::super::common::protocol;
 use super::super::{Error, ErrorKind};
 
+/// Versioning mode of a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ContainerVersioning {
+    /// Versioning is disabled.
+    Disabled,
+    /// Object versioning via the `X-Versions-Enabled` API.
+    Enabled,
+    /// Legacy versioning: previous versions are pushed to the given container
+    /// and restored from it when the current version is deleted.
+    Stack(String),
+    /// Legacy versioning: previous versions and deletion markers are
+    /// stored in the given container.
+    History(String),
+}
+
+/// Static website configuration of a container.
+///
+/// Requires the `staticweb` middleware on the server side. Objects of the
+/// website must be publicly readable, see `Container::make_public`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StaticWebsite {
+    /// Object to serve for requests to the container or its pseudo-directories,
+    /// e.g. `index.html`.
+    pub index: Option<String>,
+    /// Suffix of error pages, e.g. `error.html` to serve `404error.html`
+    /// when an object is not found.
+    pub error: Option<String>,
+    /// Whether to generate listings of pseudo-directories without an index.
+    pub listings: bool,
+    /// Stylesheet to use for generated listings.
+    pub listings_css: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Container {
     pub bytes: u64,
     pub name: String,
     #[serde(rename = "count")]
     pub object_count: u64,
+    #[serde(skip)]
+    pub versioning: Option<ContainerVersioning>,
+    #[serde(skip)]
+    pub read_acl: Option<String>,
+    #[serde(skip)]
+    pub write_acl: Option<String>,
+    #[serde(skip)]
+    pub static_website: Option<StaticWebsite>,
 }
 
 impl PaginatedResource for Container {
@@ -47,6 +90,28 @@ pub struct Object {
     pub content_type: Option<String>,
     pub name: String,
     pub hash: Option<String>,
+    // Only returned in headers, not in the list of objects.
+    #[serde(skip)]
+    pub delete_at: Option<i64>,
+}
+
+/// A version of an object.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct ObjectVersion {
+    /// Size of this version.
+    pub bytes: u64,
+    /// Content type of this version (if set).
+    pub content_type: Option<String>,
+    /// Hash (ETag) of this version.
+    pub hash: Option<String>,
+    /// Whether this version is the current one.
+    #[serde(default)]
+    pub is_latest: bool,
+    /// Object name.
+    pub name: String,
+    /// Version ID to use with other version calls.
+    pub version_id: String,
 }
 
 static CONTENT_LENGTH: HeaderName = header::CONTENT_LENGTH;
@@ -61,6 +126,25 @@ impl PaginatedResource for Object {
     }
 }
 
+/// An item of an object listing with a delimiter.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ObjectListItem {
+    PseudoDirectory { subdir: String },
+    Object(Object),
+}
+
+impl PaginatedResource for ObjectListItem {
+    type Id = String;
+    type Root = Vec<Self>;
+    fn resource_id(&self) -> Self::Id {
+        match self {
+            ObjectListItem::PseudoDirectory { subdir } => subdir.clone(),
+            ObjectListItem::Object(object) => object.name.clone(),
+        }
+    }
+}
+
 impl Container {
     pub fn from_headers(name: &str, value: &HeaderMap) -> Result<Container, Error> {
         let bytes_header = HeaderName::from_static("x-container-bytes-used");
@@ -81,12 +165,156 @@ impl Container {
                     format!("Container-Object-Count is not an integer: {e}"),
                 )
             })?;
+        let read_header = HeaderName::from_static("x-container-read");
+        let write_header = HeaderName::from_static("x-container-write");
+        let versioning = ContainerVersioning::from_headers(value)?;
+        let static_website = StaticWebsite::from_headers(value)?;
+        let read_acl = protocol::get_header(value, &read_header)?.map(From::from);
+        let write_acl = protocol::get_header(value, &write_header)?.map(From::from);
         Ok(Container {
             bytes,
             name: name.into(),
             object_count: count,
+            versioning: Some(versioning),
+            read_acl,
+            write_acl,
+            static_website: Some(static_website),
+        })
+    }
+}
+
+impl ContainerVersioning {
+    fn from_headers(value: &HeaderMap) -> Result<ContainerVersioning, Error> {
+        let enabled_header = HeaderName::from_static("x-versions-enabled");
+        let versions_header = HeaderName::from_static("x-versions-location");
+        let history_header = HeaderName::from_static("x-history-location");
+        Ok(
+            if let Some(location) = protocol::get_header(value, &versions_header)? {
+                ContainerVersioning::Stack(location.into())
+            } else if let Some(location) = protocol::get_header(value, &history_header)? {
+                ContainerVersioning::History(location.into())
+            } else if protocol::get_header(value, &enabled_header)?
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false)
+            {
+                ContainerVersioning::Enabled
+            } else {
+                ContainerVersioning::Disabled
+            },
+        )
+    }
+
+    /// Headers to set to switch a container to this versioning mode.
+    pub(crate) fn to_headers(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ContainerVersioning::Disabled => vec![
+                ("X-Versions-Enabled", "false".into()),
+                ("X-Remove-Versions-Location", "1".into()),
+                ("X-Remove-History-Location", "1".into()),
+            ],
+            ContainerVersioning::Enabled => vec![("X-Versions-Enabled", "true".into())],
+            ContainerVersioning::Stack(location) => {
+                vec![("X-Versions-Location", location.clone())]
+            }
+            ContainerVersioning::History(location) => {
+                vec![("X-History-Location", location.clone())]
+            }
+        }
+    }
+}
+
+impl StaticWebsite {
+    /// Create a website configuration with the given index object.
+    pub fn new<S: Into<String>>(index: S) -> StaticWebsite {
+        StaticWebsite {
+            index: Some(index.into()),
+            ..StaticWebsite::default()
+        }
+    }
+
+    /// Set the suffix of error pages.
+    pub fn with_error<S: Into<String>>(mut self, suffix: S) -> StaticWebsite {
+        self.error = Some(suffix.into());
+        self
+    }
+
+    /// Enable or disable listings of pseudo-directories.
+    pub fn with_listings(mut self, listings: bool) -> StaticWebsite {
+        self.listings = listings;
+        self
+    }
+
+    /// Set the stylesheet for listings (and enable them).
+    pub fn with_listings_css<S: Into<String>>(mut self, css: S) -> StaticWebsite {
+        self.listings = true;
+        self.listings_css = Some(css.into());
+        self
+    }
+
+    /// Whether the container is configured to serve anything.
+    pub fn is_enabled(&self) -> bool {
+        self.index.is_some() || self.listings
+    }
+
+    fn from_headers(value: &HeaderMap) -> Result<StaticWebsite, Error> {
+        let index_header = HeaderName::from_static("x-container-meta-web-index");
+        let error_header = HeaderName::from_static("x-container-meta-web-error");
+        let listings_header = HeaderName::from_static("x-container-meta-web-listings");
+        let css_header = HeaderName::from_static("x-container-meta-web-listings-css");
+        Ok(StaticWebsite {
+            index: protocol::get_header(value, &index_header)?.map(From::from),
+            error: protocol::get_header(value, &error_header)?.map(From::from),
+            listings: protocol::get_header(value, &listings_header)?
+                .map(|v| {
+                    ["true", "yes", "on", "1"]
+                        .iter()
+                        .any(|t| v.eq_ignore_ascii_case(t))
+                })
+                .unwrap_or(false),
+            listings_css: protocol::get_header(value, &css_header)?.map(From::from),
         })
     }
+
+    /// Headers to set to apply this configuration.
+    pub(crate) fn to_headers(&self) -> Vec<(&'static str, String)> {
+        fn set_or_remove(
+            headers: &mut Vec<(&'static str, String)>,
+            name: &'static str,
+            remove_name: &'static str,
+            value: &Option<String>,
+        ) {
+            match value {
+                Some(value) => headers.push((name, value.clone())),
+                None => headers.push((remove_name, "1".into())),
+            }
+        }
+
+        let mut headers = Vec::new();
+        set_or_remove(
+            &mut headers,
+            "X-Container-Meta-Web-Index",
+            "X-Remove-Container-Meta-Web-Index",
+            &self.index,
+        );
+        set_or_remove(
+            &mut headers,
+            "X-Container-Meta-Web-Error",
+            "X-Remove-Container-Meta-Web-Error",
+            &self.error,
+        );
+        if self.listings {
+            headers.push(("X-Container-Meta-Web-Listings", "true".into()));
+        } else {
+            headers.push(("X-Remove-Container-Meta-Web-Listings", "1".into()));
+        }
+        set_or_remove(
+            &mut headers,
+            "X-Container-Meta-Web-Listings-CSS",
+            "X-Remove-Container-Meta-Web-Listings-CSS",
+            &self.listings_css,
+        );
+        headers
+    }
 }
 
 impl Object {
@@ -101,11 +329,23 @@ impl Object {
             })?;
         let ct = protocol::get_header(value, &CONTENT_TYPE)?.map(From::from);
         let hash = protocol::get_header(value, &ETAG)?.map(From::from);
+        let delete_at_header = HeaderName::from_static("x-delete-at");
+        let delete_at = protocol::get_header(value, &delete_at_header)?
+            .map(|v| {
+                v.parse().map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidResponse,
+                        format!("X-Delete-At is not an integer: {e}"),
+                    )
+                })
+            })
+            .transpose()?;
         Ok(Object {
             bytes: size,
             content_type: ct,
             name: name.into(),
             hash,
+            delete_at,
         })
     }
 }
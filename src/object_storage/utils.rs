@@ -14,11 +14,38 @@
 
 //! Utilities for Object Storage API, mainly around inter-library compatibility.
 
-use futures::io::{AsyncRead, Error as IoError, ErrorKind as IoErrorKind};
+use std::path::Path;
+
+use futures::io::{AsyncRead, AsyncReadExt, Error as IoError, ErrorKind as IoErrorKind};
 use futures::stream::TryStreamExt;
+use md5::{Digest, Md5};
 use reqwest::{Body, Response};
+use tokio::fs::File;
 use tokio_util::codec;
-use tokio_util::compat::FuturesAsyncReadCompatExt;
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+
+use super::super::{Error, ErrorKind, Result};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// User-settable object headers (other than metadata) that Swift replaces on POST.
+///
+/// Expiration headers are not included since they are always set explicitly.
+const OBJECT_POST_HEADERS: &[&str] = &[
+    "cache-control",
+    "content-disposition",
+    "content-encoding",
+    "content-language",
+    "content-type",
+    "expires",
+    "x-object-manifest",
+    "x-robots-tag",
+];
+
+/// Whether the (lower-case) header has to be sent back to keep it on POST.
+pub fn is_object_post_header(name: &str) -> bool {
+    name.starts_with("x-object-meta-") || OBJECT_POST_HEADERS.contains(&name)
+}
 
 /// Convert an object implementing AsyncRead to a reqwest Body.
 #[inline]
@@ -42,3 +69,73 @@ pub fn body_to_async_read(resp: Response) -> impl AsyncRead + Send + Sync + 'sta
         })
         .into_async_read()
 }
+
+fn file_error(path: &Path, err: IoError) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("Cannot read file {}: {}", path.display(), err),
+    )
+}
+
+/// Open a local file for streaming as an object body.
+pub async fn open_file(path: &Path) -> Result<impl AsyncRead + Send + Sync + 'static> {
+    let file = File::open(path).await.map_err(|e| file_error(path, e))?;
+    Ok(file.compat())
+}
+
+/// Calculate the MD5 hash of a local file in the format of an ETag.
+pub async fn file_md5(path: &Path) -> Result<String> {
+    let mut file = open_file(path).await?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+    loop {
+        let size = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| file_error(path, e))?;
+        if size == 0 {
+            break;
+        }
+        hasher.update(&buffer[..size]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{file_md5, is_object_post_header};
+
+    #[tokio::test]
+    async fn test_file_md5() {
+        let path = std::env::temp_dir().join(format!("openstack-md5-{}", std::process::id()));
+        fs::write(&path, b"The quick brown fox jumps over the lazy dog").unwrap();
+        let result = file_md5(&path).await;
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), "9e107d9d372bb6826bd81d3542a419d6");
+    }
+
+    #[test]
+    fn test_is_object_post_header() {
+        for header in [
+            "x-object-meta-color",
+            "content-disposition",
+            "content-encoding",
+            "content-type",
+            "x-object-manifest",
+        ] {
+            assert!(is_object_post_header(header), "{header}");
+        }
+        for header in [
+            "content-length",
+            "etag",
+            "last-modified",
+            "x-delete-at",
+            "x-timestamp",
+            "x-static-large-object",
+        ] {
+            assert!(!is_object_post_header(header), "{header}");
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Foundation bits exposing the Placement API.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use osauth::services::{ServiceType, VersionedService};
+use osauth::ErrorKind;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::Serialize;
+
+use super::super::common::ApiVersion;
+use super::super::session::Session;
+use super::super::utils::FetchJson;
+use super::super::{Error, Result};
+use super::protocol::*;
+
+/// Placement service.
+#[derive(Copy, Clone, Debug)]
+pub struct PlacementService;
+
+/// Placement service type.
+pub const PLACEMENT: PlacementService = PlacementService;
+
+impl ServiceType for PlacementService {
+    fn catalog_type(&self) -> &'static str {
+        "placement"
+    }
+}
+
+impl VersionedService for PlacementService {
+    fn get_version_header(&self, version: ApiVersion) -> (HeaderName, HeaderValue) {
+        let value = format!("placement {version}")
+            .parse()
+            .expect("API version is always a valid header value");
+        (HeaderName::from_static("openstack-api-version"), value)
+    }
+}
+
+/// Filtering by resources.
+const API_VERSION_RESOURCES_FILTER: ApiVersion = ApiVersion(1, 4);
+/// Allocation candidates.
+const API_VERSION_ALLOCATION_CANDIDATES: ApiVersion = ApiVersion(1, 10);
+/// Nested providers.
+const API_VERSION_PROVIDER_TREES: ApiVersion = ApiVersion(1, 14);
+/// Traits in provider summaries.
+const API_VERSION_SUMMARY_TRAITS: ApiVersion = ApiVersion(1, 17);
+/// Filtering by required traits.
+const API_VERSION_REQUIRED_TRAITS: ApiVersion = ApiVersion(1, 18);
+/// Parent and root providers in provider summaries.
+const API_VERSION_SUMMARY_TREES: ApiVersion = ApiVersion(1, 29);
+
+async fn pick_api_version(
+    session: &Session,
+    minimum: Option<ApiVersion>,
+    versions: &[ApiVersion],
+) -> Result<Option<ApiVersion>> {
+    let version = session
+        .pick_api_version(PLACEMENT, versions.iter().copied())
+        .await?;
+    match minimum {
+        Some(minimum) if version.map(|v| v < minimum).unwrap_or(true) => Err(Error::new(
+            ErrorKind::IncompatibleApiVersion,
+            format!("Placement API version {minimum} is required for this request"),
+        )),
+        _ => Ok(version),
+    }
+}
+
+/// Get allocation candidates.
+pub async fn get_allocation_candidates<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    query: &Q,
+    requires_traits: bool,
+) -> Result<AllocationCandidates> {
+    trace!("Fetching allocation candidates with {:?}", query);
+    let minimum = if requires_traits {
+        API_VERSION_REQUIRED_TRAITS
+    } else {
+        API_VERSION_ALLOCATION_CANDIDATES
+    };
+    let version = pick_api_version(
+        session,
+        Some(minimum),
+        &[
+            API_VERSION_ALLOCATION_CANDIDATES,
+            API_VERSION_SUMMARY_TRAITS,
+            API_VERSION_REQUIRED_TRAITS,
+            API_VERSION_SUMMARY_TREES,
+        ],
+    )
+    .await?;
+    let mut builder = session
+        .get(PLACEMENT, &["allocation_candidates"])
+        .query(query);
+    if let Some(version) = version {
+        builder.set_api_version(version);
+    }
+    let result: AllocationCandidates = builder.fetch_json().await?;
+    trace!(
+        "Received {} allocation candidates",
+        result.allocation_requests.len()
+    );
+    Ok(result)
+}
+
+/// Get a resource provider.
+pub async fn get_resource_provider<S: AsRef<str>>(
+    session: &Session,
+    id_or_name: S,
+) -> Result<ResourceProvider> {
+    let s = id_or_name.as_ref();
+    match get_resource_provider_by_id(session, s).await {
+        Ok(value) => Ok(value),
+        Err(err) if err.kind() == ErrorKind::ResourceNotFound => {
+            get_resource_provider_by_name(session, s).await
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Get a resource provider by its UUID.
+pub async fn get_resource_provider_by_id<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<ResourceProvider> {
+    trace!("Fetching resource provider {}", id.as_ref());
+    let version = pick_api_version(session, None, &[API_VERSION_PROVIDER_TREES]).await?;
+    let mut builder = session.get(PLACEMENT, &["resource_providers", id.as_ref()]);
+    if let Some(version) = version {
+        builder.set_api_version(version);
+    }
+    let result: ResourceProvider = builder.fetch_json().await?;
+    trace!("Received {:?}", result);
+    Ok(result)
+}
+
+/// Get a resource provider by its name.
+pub async fn get_resource_provider_by_name<S: AsRef<str>>(
+    session: &Session,
+    name: S,
+) -> Result<ResourceProvider> {
+    trace!("Get resource provider by name {}", name.as_ref());
+    let mut items = list_resource_providers(session, &[("name", name.as_ref())], false).await?;
+    match items.len() {
+        0 => Err(Error::new(
+            ErrorKind::ResourceNotFound,
+            format!("No resource provider with name {}", name.as_ref()),
+        )),
+        1 => Ok(items.pop().unwrap()),
+        _ => Err(Error::new(
+            ErrorKind::TooManyItems,
+            format!("Too many resource providers with name {}", name.as_ref()),
+        )),
+    }
+}
+
+/// List inventories of a resource provider.
+pub async fn list_inventories<S: AsRef<str>>(
+    session: &Session,
+    id: S,
+) -> Result<HashMap<String, ResourceInventory>> {
+    trace!("Listing inventories of resource provider {}", id.as_ref());
+    let root: InventoriesRoot = session
+        .get(
+            PLACEMENT,
+            &["resource_providers", id.as_ref(), "inventories"],
+        )
+        .fetch_json()
+        .await?;
+    trace!("Received inventories: {:?}", root.inventories);
+    Ok(root.inventories)
+}
+
+/// List resource providers.
+pub async fn list_resource_providers<Q: Serialize + Sync + Debug>(
+    session: &Session,
+    query: &Q,
+    requires_traits: bool,
+) -> Result<Vec<ResourceProvider>> {
+    trace!("Listing resource providers with {:?}", query);
+    let minimum = if requires_traits {
+        Some(API_VERSION_REQUIRED_TRAITS)
+    } else {
+        None
+    };
+    let version = pick_api_version(
+        session,
+        minimum,
+        &[
+            API_VERSION_RESOURCES_FILTER,
+            API_VERSION_PROVIDER_TREES,
+            API_VERSION_REQUIRED_TRAITS,
+        ],
+    )
+    .await?;
+    let mut builder = session.get(PLACEMENT, &["resource_providers"]).query(query);
+    if let Some(version) = version {
+        builder.set_api_version(version);
+    }
+    let root: ResourceProvidersRoot = builder.fetch_json().await?;
+    trace!("Received resource providers: {:?}", root.resource_providers);
+    Ok(root.resource_providers)
+}
+
+/// List usages of a resource provider.
+pub async fn list_usages<S: AsRef<str>>(session: &Session, id: S) -> Result<HashMap<String, u64>> {
+    trace!("Listing usages of resource provider {}", id.as_ref());
+    let root: UsagesRoot = session
+        .get(PLACEMENT, &["resource_providers", id.as_ref(), "usages"])
+        .fetch_json()
+        .await?;
+    trace!("Received usages: {:?}", root.usages);
+    Ok(root.usages)
+}
@@ -0,0 +1,174 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON structures and protocol bits for the Placement API.
+
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer};
+
+/// A resource provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceProvider {
+    pub generation: u64,
+    pub name: String,
+    #[serde(default)]
+    pub parent_provider_uuid: Option<String>,
+    #[serde(default)]
+    pub root_provider_uuid: Option<String>,
+    pub uuid: String,
+}
+
+/// A list of resource providers.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceProvidersRoot {
+    pub resource_providers: Vec<ResourceProvider>,
+}
+
+/// Inventory of one resource class on a resource provider.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct ResourceInventory {
+    /// Overcommit ratio applied to the total amount.
+    pub allocation_ratio: f64,
+    /// Maximum amount that a single allocation may request.
+    pub max_unit: u64,
+    /// Minimum amount that a single allocation may request.
+    pub min_unit: u64,
+    /// Amount reserved for use outside of the Placement service.
+    pub reserved: u64,
+    /// Granularity of allocations.
+    pub step_size: u64,
+    /// Total amount of the resource.
+    pub total: u64,
+}
+
+impl ResourceInventory {
+    /// Capacity available for allocations.
+    ///
+    /// Calculated as `(total - reserved) * allocation_ratio`, the same way
+    /// the Placement service does it.
+    pub fn capacity(&self) -> u64 {
+        (self.total.saturating_sub(self.reserved) as f64 * self.allocation_ratio) as u64
+    }
+}
+
+/// Inventories of a resource provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InventoriesRoot {
+    pub inventories: HashMap<String, ResourceInventory>,
+}
+
+/// Usages of a resource provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsagesRoot {
+    pub usages: HashMap<String, u64>,
+}
+
+/// Resources requested from one resource provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllocationResources {
+    pub resources: HashMap<String, u64>,
+}
+
+/// A possible allocation that satisfies the request.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct AllocationRequest {
+    /// Resources to allocate, grouped by resource provider UUID.
+    #[serde(deserialize_with = "flatten_allocations")]
+    pub allocations: HashMap<String, HashMap<String, u64>>,
+}
+
+/// Capacity and usage of one resource class in a provider summary.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[non_exhaustive]
+pub struct ResourceCapacity {
+    /// Capacity available for allocations (already includes the allocation ratio).
+    pub capacity: u64,
+    /// Amount already allocated.
+    pub used: u64,
+}
+
+/// Summary of a resource provider involved in allocation candidates.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct ProviderSummary {
+    /// Capacity and usage for each resource class.
+    pub resources: HashMap<String, ResourceCapacity>,
+    /// Traits of the resource provider.
+    #[serde(default)]
+    pub traits: Vec<String>,
+    /// UUID of the parent provider, if any.
+    #[serde(default)]
+    pub parent_provider_uuid: Option<String>,
+    /// UUID of the root provider of the tree.
+    #[serde(default)]
+    pub root_provider_uuid: Option<String>,
+}
+
+/// Allocation candidates for a resource request.
+#[derive(Debug, Clone, Deserialize)]
+#[non_exhaustive]
+pub struct AllocationCandidates {
+    /// Possible allocations.
+    pub allocation_requests: Vec<AllocationRequest>,
+    /// Summaries of all resource providers involved, keyed by UUID.
+    pub provider_summaries: HashMap<String, ProviderSummary>,
+}
+
+fn flatten_allocations<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, HashMap<String, u64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: HashMap<String, AllocationResources> = Deserialize::deserialize(deserializer)?;
+    Ok(value
+        .into_iter()
+        .map(|(uuid, item)| (uuid, item.resources))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_allocation_candidates() {
+        let body = r#"{
+            "allocation_requests": [
+                {"allocations": {"a1": {"resources": {"VCPU": 2, "MEMORY_MB": 1024}}}}
+            ],
+            "provider_summaries": {
+                "a1": {
+                    "resources": {
+                        "VCPU": {"capacity": 16, "used": 4},
+                        "MEMORY_MB": {"capacity": 8192, "used": 0}
+                    },
+                    "traits": ["HW_CPU_X86_AVX2"]
+                }
+            }
+        }"#;
+        let result: AllocationCandidates = serde_json::from_str(body).unwrap();
+        assert_eq!(result.allocation_requests.len(), 1);
+        assert_eq!(result.allocation_requests[0].allocations["a1"]["VCPU"], 2);
+        let summary = &result.provider_summaries["a1"];
+        assert_eq!(summary.resources["VCPU"].capacity, 16);
+        assert_eq!(summary.traits, vec!["HW_CPU_X86_AVX2".to_string()]);
+        assert!(summary.root_provider_uuid.is_none());
+    }
+}
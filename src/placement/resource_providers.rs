@@ -0,0 +1,283 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resource providers and allocation candidates via Placement API.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use futures::stream::{Stream, TryStreamExt};
+
+use super::super::common::{Refresh, ResourceIterator, ResourceQuery};
+use super::super::session::Session;
+use super::super::utils::Query;
+use super::super::{Error, ErrorKind, Result};
+use super::{api, protocol};
+
+/// Structure representing a resource provider.
+#[derive(Clone, Debug)]
+pub struct ResourceProvider {
+    session: Session,
+    inner: protocol::ResourceProvider,
+}
+
+/// A query to resource provider list.
+#[derive(Clone, Debug)]
+pub struct ResourceProviderQuery {
+    session: Session,
+    query: Query,
+    resources: Vec<String>,
+    required: Vec<String>,
+}
+
+/// A query for allocation candidates.
+#[derive(Clone, Debug)]
+pub struct AllocationCandidateQuery {
+    session: Session,
+    query: Query,
+    resources: Vec<String>,
+    required: Vec<String>,
+}
+
+/// Build the final query with resources and required traits.
+fn build_query(query: &Query, resources: &[String], required: &[String]) -> Query {
+    let mut query = query.clone();
+    if !resources.is_empty() {
+        query.push_str("resources", resources.join(","));
+    }
+    if !required.is_empty() {
+        query.push_str("required", required.join(","));
+    }
+    query
+}
+
+impl ResourceProvider {
+    /// Load a ResourceProvider object.
+    pub(crate) async fn load<Id: AsRef<str>>(
+        session: Session,
+        id_or_name: Id,
+    ) -> Result<ResourceProvider> {
+        let inner = api::get_resource_provider(&session, id_or_name).await?;
+        Ok(ResourceProvider { session, inner })
+    }
+
+    transparent_property! {
+        #[doc = "Generation of the resource provider, changes on every update."]
+        generation: u64
+    }
+
+    /// Unique ID (an alias for `uuid`).
+    #[inline]
+    pub fn id(&self) -> &String {
+        &self.inner.uuid
+    }
+
+    transparent_property! {
+        #[doc = "Resource provider name."]
+        name: ref String
+    }
+
+    transparent_property! {
+        #[doc = "UUID of the parent provider (requires API version 1.14)."]
+        parent_provider_uuid: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "UUID of the root provider of the tree (requires API version 1.14)."]
+        root_provider_uuid: ref Option<String>
+    }
+
+    transparent_property! {
+        #[doc = "Unique ID."]
+        uuid: ref String
+    }
+
+    /// Fetch inventories of this provider, keyed by resource class.
+    pub async fn inventories(&self) -> Result<HashMap<String, protocol::ResourceInventory>> {
+        api::list_inventories(&self.session, &self.inner.uuid).await
+    }
+
+    /// Fetch the amounts of resources consumed on this provider, keyed by resource class.
+    pub async fn usages(&self) -> Result<HashMap<String, u64>> {
+        api::list_usages(&self.session, &self.inner.uuid).await
+    }
+}
+
+#[async_trait]
+impl Refresh for ResourceProvider {
+    /// Refresh the resource provider.
+    async fn refresh(&mut self) -> Result<()> {
+        self.inner = api::get_resource_provider_by_id(&self.session, &self.inner.uuid).await?;
+        Ok(())
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+}
+
+impl ResourceProviderQuery {
+    pub(crate) fn new(session: Session) -> ResourceProviderQuery {
+        ResourceProviderQuery {
+            session,
+            query: Query::new(),
+            resources: Vec::new(),
+            required: Vec::new(),
+        }
+    }
+
+    /// Filter by resource provider name.
+    pub fn with_name<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("name", value);
+        self
+    }
+
+    /// Filter by providers that have capacity for the given amount of a resource class.
+    ///
+    /// Can be called several times to request several resource classes.
+    pub fn with_resource<T: AsRef<str>>(mut self, resource_class: T, amount: u64) -> Self {
+        self.resources
+            .push(format!("{}:{}", resource_class.as_ref(), amount));
+        self
+    }
+
+    /// Filter by providers that have the given trait (requires API version 1.18).
+    ///
+    /// Prefix the trait with `!` to require its absence.
+    pub fn with_required_trait<T: Into<String>>(mut self, value: T) -> Self {
+        self.required.push(value.into());
+        self
+    }
+
+    /// Filter by providers in the same tree as the given provider.
+    pub fn with_tree<T: Into<String>>(mut self, value: T) -> Self {
+        self.query.push_str("in_tree", value);
+        self
+    }
+
+    /// Convert this query into a stream executing the request.
+    ///
+    /// Returns a `TryStream`, which is a stream with each `next`
+    /// call returning a `Result`.
+    ///
+    /// Note that no requests are done until you start iterating.
+    pub fn into_stream(self) -> impl Stream<Item = Result<ResourceProvider>> {
+        debug!(
+            "Fetching resource providers with {:?}, resources {:?}, required {:?}",
+            self.query, self.resources, self.required
+        );
+        ResourceIterator::new(self.session.clone(), self).into_stream()
+    }
+
+    /// Execute this request and return all results.
+    ///
+    /// A convenience shortcut for `self.into_stream().try_collect().await`.
+    pub async fn all(self) -> Result<Vec<ResourceProvider>> {
+        self.into_stream().try_collect().await
+    }
+
+    /// Return one and exactly one result.
+    ///
+    /// Fails with `ResourceNotFound` if the query produces no results and
+    /// with `TooManyItems` if the query produces more than one result.
+    pub async fn one(self) -> Result<ResourceProvider> {
+        debug!("Fetching one resource provider with {:?}", self.query);
+        ResourceIterator::new(self.session.clone(), self)
+            .one()
+            .await
+    }
+}
+
+#[async_trait]
+impl ResourceQuery for ResourceProviderQuery {
+    type Item = ResourceProvider;
+
+    const DEFAULT_LIMIT: usize = 50;
+
+    async fn can_paginate(&self) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn extract_marker(&self, resource: &Self::Item) -> String {
+        resource.uuid().clone()
+    }
+
+    async fn fetch_chunk(
+        &self,
+        _limit: Option<usize>,
+        _marker: Option<String>,
+    ) -> Result<Vec<Self::Item>> {
+        let query = build_query(&self.query, &self.resources, &self.required);
+        Ok(
+            api::list_resource_providers(&self.session, &query, !self.required.is_empty())
+                .await?
+                .into_iter()
+                .map(|inner| ResourceProvider {
+                    session: self.session.clone(),
+                    inner,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl AllocationCandidateQuery {
+    pub(crate) fn new(session: Session) -> AllocationCandidateQuery {
+        AllocationCandidateQuery {
+            session,
+            query: Query::new(),
+            resources: Vec::new(),
+            required: Vec::new(),
+        }
+    }
+
+    /// Request the given amount of a resource class.
+    ///
+    /// Can be called several times to request several resource classes.
+    pub fn with_resource<T: AsRef<str>>(mut self, resource_class: T, amount: u64) -> Self {
+        self.resources
+            .push(format!("{}:{}", resource_class.as_ref(), amount));
+        self
+    }
+
+    /// Only consider providers that have the given trait (requires API version 1.18).
+    ///
+    /// Prefix the trait with `!` to require its absence.
+    pub fn with_required_trait<T: Into<String>>(mut self, value: T) -> Self {
+        self.required.push(value.into());
+        self
+    }
+
+    /// Limit the number of returned allocation requests (requires API version 1.16).
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.query.push("limit", limit);
+        self
+    }
+
+    /// Execute this request.
+    ///
+    /// Fails with `InvalidInput` if no resources were requested.
+    pub async fn fetch(self) -> Result<protocol::AllocationCandidates> {
+        if self.resources.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "At least one resource must be requested",
+            ));
+        }
+
+        let query = build_query(&self.query, &self.resources, &self.required);
+        debug!("Fetching allocation candidates with {:?}", query);
+        api::get_allocation_candidates(&self.session, &query, !self.required.is_empty()).await
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client-side rate limiting of requests.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use osauth::client::AuthenticatedClient;
+use osauth::{AuthType, EndpointFilters, Error};
+use reqwest::{Client, RequestBuilder, Url};
+use tokio::time::{sleep_until, Instant};
+
+/// Limiter spreading events evenly over time.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing the given number of events per second.
+    ///
+    /// The number must not be zero.
+    pub fn new(per_second: u32) -> RateLimiter {
+        RateLimiter {
+            interval: Duration::from_secs(1) / per_second,
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Wait until the next event is allowed.
+    pub async fn wait(&self) {
+        let now = Instant::now();
+        let slot = {
+            let mut next = self.next.lock().expect("Rate limiter lock is poisoned");
+            let slot = next.map_or(now, |next| next.max(now));
+            *next = slot.checked_add(self.interval);
+            slot
+        };
+        if slot > now {
+            trace!("Delaying a request by {:?}", slot - now);
+            sleep_until(slot).await;
+        }
+    }
+}
+
+/// Authentication wrapper that limits the rate of all requests.
+///
+/// Every request made through a session passes authentication, so this is
+/// the only place that sees all of them.
+#[derive(Debug)]
+pub struct RateLimitedAuth {
+    inner: AuthenticatedClient,
+    limiter: RateLimiter,
+}
+
+impl RateLimitedAuth {
+    /// Wrap the authentication of the client.
+    pub fn new(inner: AuthenticatedClient, per_second: u32) -> RateLimitedAuth {
+        RateLimitedAuth {
+            inner,
+            limiter: RateLimiter::new(per_second),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthType for RateLimitedAuth {
+    async fn authenticate(
+        &self,
+        client: &Client,
+        request: RequestBuilder,
+    ) -> Result<RequestBuilder, Error> {
+        self.limiter.wait().await;
+        self.inner.auth_type().authenticate(client, request).await
+    }
+
+    async fn get_endpoint(
+        &self,
+        client: &Client,
+        service_type: &str,
+        filters: &EndpointFilters,
+    ) -> Result<Url, Error> {
+        self.inner
+            .auth_type()
+            .get_endpoint(client, service_type, filters)
+            .await
+    }
+
+    async fn refresh(&self, client: &Client) -> Result<(), Error> {
+        self.inner.auth_type().refresh(client).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use osauth::NoAuth;
+    use tokio::time::Instant;
+
+    use super::super::session::Session;
+    use super::{RateLimitedAuth, RateLimiter};
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        let limiter = RateLimiter::new(20);
+        let started = Instant::now();
+        for _ in 0..3 {
+            limiter.wait().await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_auth() {
+        let mut session = Session::new(NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap();
+        let auth = RateLimitedAuth::new(session.client().clone(), 10);
+        session.set_auth_type(auth);
+        let endpoint = session
+            .client()
+            .get_endpoint("compute", &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(endpoint.as_str(), "http://127.0.0.1:5000/");
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Re-authentication with a new token.
+
+use std::fmt;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use osauth::client::AuthenticatedClient;
+use osauth::{AuthType, EndpointFilters, Error};
+use reqwest::{Client, RequestBuilder, Url};
+
+type Renew = dyn Fn() -> BoxFuture<'static, Result<AuthenticatedClient, Error>> + Send + Sync;
+
+/// Authentication wrapper that can replace its token.
+///
+/// Refreshing `osauth` authentication keeps the cached token while it has
+/// not expired, even if a service has already rejected it. Refreshing this
+/// wrapper creates the authentication from scratch instead.
+pub struct RenewableAuth {
+    current: RwLock<AuthenticatedClient>,
+    renew: Box<Renew>,
+}
+
+impl RenewableAuth {
+    /// Wrap the authentication of the client.
+    ///
+    /// The `renew` callback must return a freshly authenticated client.
+    pub fn new<F>(inner: AuthenticatedClient, renew: F) -> RenewableAuth
+    where
+        F: Fn() -> BoxFuture<'static, Result<AuthenticatedClient, Error>> + Send + Sync + 'static,
+    {
+        RenewableAuth {
+            current: RwLock::new(inner),
+            renew: Box::new(renew),
+        }
+    }
+
+    fn current(&self) -> AuthenticatedClient {
+        self.current
+            .read()
+            .expect("Authentication lock is poisoned")
+            .clone()
+    }
+}
+
+impl fmt::Debug for RenewableAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenewableAuth")
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl AuthType for RenewableAuth {
+    async fn authenticate(
+        &self,
+        client: &Client,
+        request: RequestBuilder,
+    ) -> Result<RequestBuilder, Error> {
+        self.current()
+            .auth_type()
+            .authenticate(client, request)
+            .await
+    }
+
+    async fn get_endpoint(
+        &self,
+        client: &Client,
+        service_type: &str,
+        filters: &EndpointFilters,
+    ) -> Result<Url, Error> {
+        self.current()
+            .auth_type()
+            .get_endpoint(client, service_type, filters)
+            .await
+    }
+
+    async fn refresh(&self, _client: &Client) -> Result<(), Error> {
+        let fresh = (self.renew)().await?;
+        *self
+            .current
+            .write()
+            .expect("Authentication lock is poisoned") = fresh;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use futures::FutureExt;
+    use osauth::{ErrorKind, NoAuth};
+
+    use super::super::session::Session;
+    use super::super::utils;
+    use super::super::Error;
+    use super::RenewableAuth;
+
+    #[tokio::test]
+    async fn test_renewable_auth_replaces_token() {
+        let mut session = Session::new(NoAuth::new("http://127.0.0.1:5000/").unwrap())
+            .await
+            .unwrap();
+        let renewals = Arc::new(AtomicUsize::new(0));
+        let counter = renewals.clone();
+        let auth = RenewableAuth::new(session.client().clone(), move || {
+            let _ = counter.fetch_add(1, Ordering::SeqCst);
+            async {
+                let session = Session::new(NoAuth::new("http://127.0.0.1:5001/").unwrap()).await?;
+                Ok(session.client().clone())
+            }
+            .boxed()
+        });
+        session.set_auth_type(auth);
+
+        let endpoint = session
+            .client()
+            .get_endpoint("compute", &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(endpoint.as_str(), "http://127.0.0.1:5000/");
+
+        let err = Error::new(ErrorKind::AuthenticationFailed, "token expired");
+        assert!(utils::reauthenticate(Some(&session), &err).await);
+        assert_eq!(renewals.load(Ordering::SeqCst), 1);
+        // Clones share the authentication, so the original session is updated.
+        let endpoint = session
+            .client()
+            .get_endpoint("compute", &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(endpoint.as_str(), "http://127.0.0.1:5001/");
+
+        let err = Error::new(ErrorKind::InvalidInput, "bad request");
+        assert!(!utils::reauthenticate(Some(&session), &err).await);
+        assert_eq!(renewals.load(Ordering::SeqCst), 1);
+    }
+}
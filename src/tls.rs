@@ -0,0 +1,365 @@
+// Copyright 2024 Dmitry Tantsur <divius.inside@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TLS configuration and sessions using it.
+
+use std::collections::HashMap;
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+use std::fs;
+use std::path::PathBuf;
+
+use osauth::CloudConfig;
+use reqwest::Client;
+
+use super::utils;
+use super::{Error, ErrorKind, Result};
+
+/// TLS options used to connect to a cloud.
+///
+/// Useful for private clouds with internal certificate authorities or
+/// clouds requiring client certificates.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # async fn async_wrapper() -> openstack::Result<()> {
+/// let auth = openstack::auth::Password::new(
+///         "https://cloud.example.com",
+///         "user1", "pa$$word", "Default")?;
+/// let tls = openstack::TlsConfig::new()
+///     .with_ca_certificate("/etc/pki/internal-ca.pem")
+///     .with_client_certificate("/etc/pki/client.pem", "/etc/pki/client.key");
+/// let os = openstack::Cloud::new_with_tls(auth, &tls).await?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    ca_certificates: Vec<PathBuf>,
+    client_certificate: Option<(PathBuf, PathBuf)>,
+    insecure: bool,
+}
+
+impl TlsConfig {
+    /// Create TLS options using the system defaults.
+    pub fn new() -> TlsConfig {
+        TlsConfig::default()
+    }
+
+    /// Load TLS options from environment variables.
+    ///
+    /// Understands `OS_CACERT`, `OS_CERT`, `OS_KEY` and `OS_INSECURE`.
+    pub fn from_env() -> TlsConfig {
+        TlsConfig::default().with_vars(utils::env_vars())
+    }
+
+    /// Trust the CA certificate (bundle) from the given PEM file.
+    pub fn add_ca_certificate<P: Into<PathBuf>>(&mut self, path: P) {
+        self.ca_certificates.push(path.into());
+    }
+
+    /// Trust the CA certificate (bundle) from the given PEM file.
+    #[inline]
+    pub fn with_ca_certificate<P: Into<PathBuf>>(mut self, path: P) -> TlsConfig {
+        self.add_ca_certificate(path);
+        self
+    }
+
+    /// CA certificate files to trust in addition to the system ones.
+    #[inline]
+    pub fn ca_certificates(&self) -> &[PathBuf] {
+        &self.ca_certificates
+    }
+
+    /// Use a client certificate and its private key from the given PEM files.
+    pub fn set_client_certificate<P1, P2>(&mut self, cert: P1, key: P2)
+    where
+        P1: Into<PathBuf>,
+        P2: Into<PathBuf>,
+    {
+        self.client_certificate = Some((cert.into(), key.into()));
+    }
+
+    /// Use a client certificate and its private key from the given PEM files.
+    #[inline]
+    pub fn with_client_certificate<P1, P2>(mut self, cert: P1, key: P2) -> TlsConfig
+    where
+        P1: Into<PathBuf>,
+        P2: Into<PathBuf>,
+    {
+        self.set_client_certificate(cert, key);
+        self
+    }
+
+    /// Client certificate and private key files (if any).
+    #[inline]
+    pub fn client_certificate(&self) -> Option<(&PathBuf, &PathBuf)> {
+        self.client_certificate
+            .as_ref()
+            .map(|(cert, key)| (cert, key))
+    }
+
+    /// Whether to skip verification of server certificates.
+    ///
+    /// # Warning
+    ///
+    /// This makes connections vulnerable to man-in-the-middle attacks.
+    /// Only use it for testing.
+    pub fn set_insecure(&mut self, insecure: bool) {
+        self.insecure = insecure;
+    }
+
+    /// Whether to skip verification of server certificates.
+    ///
+    /// # Warning
+    ///
+    /// This makes connections vulnerable to man-in-the-middle attacks.
+    /// Only use it for testing.
+    #[inline]
+    pub fn with_insecure(mut self, insecure: bool) -> TlsConfig {
+        self.set_insecure(insecure);
+        self
+    }
+
+    /// Whether verification of server certificates is disabled.
+    #[inline]
+    pub fn is_insecure(&self) -> bool {
+        self.insecure
+    }
+
+    /// Update the options from `OS_*` variables.
+    pub(crate) fn with_vars<I>(mut self, vars: I) -> TlsConfig
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let vars: HashMap<String, String> = vars.into_iter().collect();
+        if let Some(cacert) = vars.get("OS_CACERT") {
+            // Already loaded from the configuration by osauth
+            if !self.ca_certificates.contains(&PathBuf::from(cacert)) {
+                self.add_ca_certificate(cacert);
+            }
+        }
+        if let (Some(cert), Some(key)) = (vars.get("OS_CERT"), vars.get("OS_KEY")) {
+            self.set_client_certificate(cert, key);
+        }
+        if let Some(insecure) = vars.get("OS_INSECURE") {
+            self.insecure = is_true(insecure);
+        }
+        self
+    }
+
+    /// Load the options from a `clouds.yaml` entry.
+    ///
+    /// Understands `cacert`, `cert`, `key` and `verify`.
+    pub(crate) fn from_config(config: &serde_yaml::Value) -> Result<TlsConfig> {
+        let mut result = TlsConfig::default();
+        if let Some(cacert) = config_string(config, "cacert")? {
+            result.add_ca_certificate(cacert);
+        }
+        match (
+            config_string(config, "cert")?,
+            config_string(config, "key")?,
+        ) {
+            (Some(cert), Some(key)) => result.set_client_certificate(cert, key),
+            (None, None) => {}
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidConfig,
+                    "Both cert and key are required for a client certificate",
+                ))
+            }
+        }
+        match config.get("verify") {
+            Some(serde_yaml::Value::Bool(verify)) => result.insecure = !verify,
+            Some(other) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidConfig,
+                    format!("Invalid verify {:?}: expected a boolean", other),
+                ))
+            }
+            None => {}
+        }
+        Ok(result)
+    }
+
+    /// Build an HTTP client with these options.
+    #[allow(unused_mut)] // mut builder unused with --no-default-features
+    pub(crate) fn build_client(&self) -> Result<Client> {
+        let mut builder = Client::builder();
+
+        #[cfg(any(feature = "native-tls", feature = "rustls"))]
+        {
+            for path in &self.ca_certificates {
+                let content = read_pem(path)?;
+                let certs = reqwest::Certificate::from_pem_bundle(&content).map_err(|err| {
+                    Error::new(
+                        ErrorKind::InvalidConfig,
+                        format!("Cannot parse {} as PEM: {}", path.display(), err),
+                    )
+                })?;
+                for cert in certs {
+                    builder = builder.add_root_certificate(cert);
+                }
+            }
+
+            if let Some((ref cert, ref key)) = self.client_certificate {
+                builder = builder.identity(load_identity(cert, key)?);
+            }
+
+            if self.insecure {
+                warn!("TLS certificate verification is disabled");
+                builder = builder.danger_accept_invalid_certs(true);
+            }
+        }
+
+        #[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+        if *self != TlsConfig::default() {
+            return Err(Error::new(
+                ErrorKind::InvalidConfig,
+                "TLS support is disabled",
+            ));
+        }
+
+        builder.build().map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidConfig,
+                format!("Cannot initialize HTTP client: {}", err),
+            )
+        })
+    }
+}
+
+#[inline]
+fn is_true(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+fn config_string(config: &serde_yaml::Value, key: &str) -> Result<Option<String>> {
+    match config.get(key) {
+        Some(serde_yaml::Value::String(value)) => Ok(Some(value.clone())),
+        Some(other) => Err(Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Invalid {} {:?}: expected a string", key, other),
+        )),
+        None => Ok(None),
+    }
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+fn read_pem(path: &PathBuf) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Cannot open {}: {}", path.display(), err),
+        )
+    })
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+fn load_identity(cert: &PathBuf, key: &PathBuf) -> Result<reqwest::Identity> {
+    let cert_content = read_pem(cert)?;
+    let key_content = read_pem(key)?;
+
+    #[cfg(feature = "native-tls")]
+    let result = reqwest::Identity::from_pkcs8_pem(&cert_content, &key_content);
+    #[cfg(not(feature = "native-tls"))]
+    let result = reqwest::Identity::from_pem(&[cert_content, key_content].join(&b'\n'));
+
+    result.map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidConfig,
+            format!(
+                "Cannot load client certificate {} with key {}: {}",
+                cert.display(),
+                key.display(),
+                err
+            ),
+        )
+    })
+}
+
+/// Serialize the cloud configuration for inspecting arbitrary keys.
+pub(crate) fn config_to_value(config: &CloudConfig) -> Result<serde_yaml::Value> {
+    serde_yaml::to_value(config).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidConfig,
+            format!("Cannot serialize the cloud configuration: {}", err),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::TlsConfig;
+
+    fn vars(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tls_config_from_vars() {
+        let tls = TlsConfig::new().with_vars(vars(&[
+            ("OS_CACERT", "/ca.pem"),
+            ("OS_CERT", "/client.pem"),
+            ("OS_KEY", "/client.key"),
+            ("OS_INSECURE", "True"),
+        ]));
+        assert_eq!(tls.ca_certificates(), &[PathBuf::from("/ca.pem")]);
+        assert_eq!(
+            tls.client_certificate(),
+            Some((&PathBuf::from("/client.pem"), &PathBuf::from("/client.key")))
+        );
+        assert!(tls.is_insecure());
+    }
+
+    #[test]
+    fn test_tls_config_from_vars_cacert_only() {
+        let tls =
+            TlsConfig::new().with_vars(vars(&[("OS_CACERT", "/ca.pem"), ("OS_INSECURE", "0")]));
+        assert!(!tls.is_insecure());
+    }
+
+    #[test]
+    fn test_tls_config_from_config() {
+        let config: serde_yaml::Value =
+            serde_yaml::from_str("cacert: /ca.pem\ncert: /c.pem\nkey: /c.key\nverify: false")
+                .unwrap();
+        let tls = TlsConfig::from_config(&config).unwrap();
+        assert_eq!(
+            tls,
+            TlsConfig::new()
+                .with_ca_certificate("/ca.pem")
+                .with_client_certificate("/c.pem", "/c.key")
+                .with_insecure(true)
+        );
+    }
+
+    #[test]
+    fn test_tls_config_from_config_invalid() {
+        for text in ["cert: /c.pem", "verify: maybe", "cacert: 42"] {
+            let config: serde_yaml::Value = serde_yaml::from_str(text).unwrap();
+            assert!(TlsConfig::from_config(&config).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_tls_config_missing_file() {
+        let tls = TlsConfig::new().with_ca_certificate("/I/do/not/exist");
+        assert!(tls.build_client().is_err());
+    }
+}
@@ -19,17 +19,30 @@
 use std::cell::{Ref, RefCell};
 use std::collections::HashMap;
 use std::fmt;
+use std::future::Future;
 use std::hash::Hash;
 
+use async_trait::async_trait;
 use futures::{pin_mut, Stream, TryStreamExt};
+use osauth::services::ServiceType;
+use osauth::{ServiceRequestBuilder, Session};
+use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::{Serialize, Serializer};
 
 use super::{Error, ErrorKind, Result};
 
+/// Maximum number of bytes of a parsing error to include into error messages.
+const MAX_ERROR_TEXT_SIZE: usize = 2048;
+
 /// Type of query parameters.
 #[derive(Clone)]
 pub struct Query(pub Vec<(String, String)>);
 
+/// Client-side pattern for resource names.
+#[derive(Debug, Clone)]
+pub struct NamePattern(std::result::Result<Regex, String>);
+
 /// Cached clone-able value.
 #[derive(Debug, Clone)]
 pub struct ValueCache<T: Clone>(RefCell<Option<T>>);
@@ -91,6 +104,50 @@ impl Serialize for Query {
     }
 }
 
+impl NamePattern {
+    /// Pattern from a shell-style glob with `*` and `?` wildcards.
+    pub fn glob(pattern: &str) -> NamePattern {
+        let mut expr = String::with_capacity(pattern.len() + 2);
+        expr.push('^');
+        let mut buf = [0; 4];
+        for c in pattern.chars() {
+            match c {
+                '*' => expr.push_str(".*"),
+                '?' => expr.push('.'),
+                other => expr.push_str(&regex::escape(other.encode_utf8(&mut buf))),
+            }
+        }
+        expr.push('$');
+        NamePattern::regex(&expr)
+    }
+
+    /// Pattern from a regular expression.
+    pub fn regex(pattern: &str) -> NamePattern {
+        NamePattern(Regex::new(pattern).map_err(|err| err.to_string()))
+    }
+
+    /// Check that the pattern is valid.
+    pub fn validate(&self) -> Result<()> {
+        match self.0 {
+            Ok(..) => Ok(()),
+            Err(ref msg) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid name pattern: {}", msg),
+            )),
+        }
+    }
+
+    /// Whether the name matches the pattern.
+    ///
+    /// An invalid pattern matches nothing.
+    pub fn matches(&self, name: &str) -> bool {
+        match self.0 {
+            Ok(ref regex) => regex.is_match(name),
+            Err(..) => false,
+        }
+    }
+}
+
 impl<T: Clone> ValueCache<T> {
     /// Create a cache.
     pub fn new(value: Option<T>) -> ValueCache<T> {
@@ -175,7 +232,7 @@ impl<K: Hash + Eq, V: Clone> MapCache<K, V> {
     /// Get a reference to the value.
     ///
     /// Borrows the inner RefCell.
-    pub fn get_ref(&self, key: &K) -> Option<Ref<V>> {
+    pub fn get_ref(&self, key: &K) -> Option<Ref<'_, V>> {
         let map = self.0.borrow();
         if map.contains_key(key) {
             Some(Ref::map(map, |m| m.get(key).unwrap()))
@@ -233,6 +290,51 @@ where
     }
 }
 
+/// Re-authenticate after a service has rejected the token.
+///
+/// Returns `true` if the error was an authentication failure and the session
+/// has been refreshed, so that an idempotent request can be retried. Sessions
+/// created from configuration receive a new token, other authentication types
+/// are refreshed by `osauth` as usual.
+pub async fn reauthenticate(session: Option<&Session>, err: &Error) -> bool {
+    if err.kind() != ErrorKind::AuthenticationFailed {
+        return false;
+    }
+
+    let session = match session {
+        Some(session) => session,
+        None => return false,
+    };
+
+    warn!("Token was rejected ({}), re-authenticating", err);
+    // Clones share the authentication, so refreshing a clone is enough.
+    match session.clone().refresh().await {
+        Ok(()) => true,
+        Err(refresh_err) => {
+            warn!("Re-authentication failed: {}", refresh_err);
+            false
+        }
+    }
+}
+
+/// Run an idempotent request, retrying it once if the token was rejected.
+pub async fn retry_on_auth_failure<T, F, Fut>(session: &Session, mut request: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    match request().await {
+        Err(err) => {
+            if reauthenticate(Some(session), &err).await {
+                request().await
+            } else {
+                Err(err)
+            }
+        }
+        ok => ok,
+    }
+}
+
 /// Serialize an enum unit variant into a None
 /// This is used to turn [ServerAction::Start] into
 /// `"os-start": null` instead of just `"os-start"`
@@ -240,11 +342,74 @@ pub fn unit_to_null<S: Serializer>(s: S) -> std::result::Result<S::Ok, S::Error>
     s.serialize_none()
 }
 
+/// Environment variables with valid UTF-8 names and values.
+///
+/// Unlike `std::env::vars`, does not panic on invalid entries, skips them instead.
+pub fn env_vars() -> impl Iterator<Item = (String, String)> {
+    std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+}
+
+/// Truncate a text to at most `MAX_ERROR_TEXT_SIZE` bytes on a character boundary.
+fn truncate_for_error(text: &str) -> String {
+    if text.len() <= MAX_ERROR_TEXT_SIZE {
+        return text.to_string();
+    }
+
+    let mut index = MAX_ERROR_TEXT_SIZE;
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    format!("{}...", &text[..index])
+}
+
+/// Parse a JSON response body.
+///
+/// On failure, the error includes the path to the offending field. The body
+/// itself is only logged at the trace level since it may contain secrets.
+pub fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    let mut de = serde_json::Deserializer::from_slice(body);
+    serde_path_to_error::deserialize(&mut de).map_err(|err| {
+        trace!(
+            "Cannot parse response body {}",
+            String::from_utf8_lossy(body)
+        );
+        let path = err.path().to_string();
+        Error::new(
+            ErrorKind::InvalidResponse,
+            format!(
+                "Cannot parse response at `{}`: {}",
+                path,
+                truncate_for_error(&err.into_inner().to_string())
+            ),
+        )
+    })
+}
+
+/// Extension for requests returning JSON.
+#[async_trait]
+pub trait FetchJson {
+    /// Send the request and parse JSON in the response.
+    ///
+    /// Unlike `fetch`, provides detailed errors on parsing failures.
+    async fn fetch_json<T: DeserializeOwned + Send>(self) -> Result<T>;
+}
+
+#[async_trait]
+impl<S: ServiceType + Send> FetchJson for ServiceRequestBuilder<S> {
+    async fn fetch_json<T: DeserializeOwned + Send>(self) -> Result<T> {
+        let body = self.send().await?.bytes().await?;
+        parse_json(&body)
+    }
+}
+
 pub mod url {
     //! Handy primitives for working with URLs.
 
     use reqwest::Url;
 
+    use crate::{Error, ErrorKind, Result};
+
     #[inline]
     #[allow(unused_results)]
     pub fn is_root(url: &Url) -> bool {
@@ -272,6 +437,21 @@ pub mod url {
         url
     }
 
+    /// Build a dashboard link from its base URL and path segments.
+    pub fn dashboard<I>(base: &Url, segments: I) -> Result<Url>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        if base.cannot_be_a_base() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Dashboard URL {} cannot be used as a base", base),
+            ));
+        }
+        Ok(extend(base.clone(), segments))
+    }
+
     #[inline]
     #[allow(unused_results)]
     pub fn pop(mut url: Url, keep_slash: bool) -> Url {
@@ -282,3 +462,97 @@ pub mod url {
         url
     }
 }
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use reqwest::Url;
+
+    use super::super::ErrorKind;
+    use super::{parse_json, truncate_for_error, url, NamePattern, MAX_ERROR_TEXT_SIZE};
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        #[allow(dead_code)]
+        id: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Root {
+        #[allow(dead_code)]
+        items: Vec<Item>,
+    }
+
+    #[test]
+    fn test_parse_json_error_details() {
+        let body = br#"{"secret": "hunter2", "items": [{"id": 1}, {"id": 2.5}]}"#;
+        let err = parse_json::<Root>(body).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidResponse);
+        let msg = err.to_string();
+        assert!(msg.contains("`items[1].id`"), "{}", msg);
+        assert!(!msg.contains("hunter2"), "{}", msg);
+    }
+
+    #[test]
+    fn test_parse_json_truncates_error() {
+        let body = format!(r#"{{"items": "{}"}}"#, "x".repeat(MAX_ERROR_TEXT_SIZE * 2));
+        let msg = parse_json::<Root>(body.as_bytes()).unwrap_err().to_string();
+        assert!(msg.len() < MAX_ERROR_TEXT_SIZE + 200, "{}", msg.len());
+        assert!(msg.ends_with("..."));
+    }
+
+    #[test]
+    fn test_truncate_for_error() {
+        assert_eq!(truncate_for_error("short"), "short");
+
+        let text = "x".repeat(MAX_ERROR_TEXT_SIZE);
+        assert_eq!(truncate_for_error(&text), text);
+
+        // Two-byte characters, the limit falls in the middle of one.
+        let text = format!("x{}", "é".repeat(MAX_ERROR_TEXT_SIZE));
+        let result = truncate_for_error(&text);
+        assert_eq!(result.len(), MAX_ERROR_TEXT_SIZE - 1 + 3);
+        assert!(result.ends_with("é..."));
+    }
+
+    #[test]
+    fn test_name_pattern_glob() {
+        let pattern = NamePattern::glob("ci-*.test?");
+        assert!(pattern.validate().is_ok());
+        assert!(pattern.matches("ci-.test1"));
+        assert!(pattern.matches("ci-job-42.test1"));
+        assert!(!pattern.matches("ci-job-42-test1"));
+        assert!(!pattern.matches("ci-job.test"));
+        assert!(!pattern.matches("my-ci-job.test1"));
+    }
+
+    #[test]
+    fn test_name_pattern_regex() {
+        let pattern = NamePattern::regex("^ci-[0-9]+");
+        assert!(pattern.matches("ci-42-job"));
+        assert!(!pattern.matches("ci-job"));
+
+        let pattern = NamePattern::regex("ci-(");
+        assert_eq!(
+            pattern.validate().unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert!(!pattern.matches("ci-("));
+    }
+
+    #[test]
+    fn test_url_dashboard() {
+        let base = Url::parse("https://horizon/dashboard/").unwrap();
+        assert_eq!(
+            url::dashboard(&base, ["project", "instances", "abcd", ""])
+                .unwrap()
+                .as_str(),
+            "https://horizon/dashboard/project/instances/abcd/"
+        );
+
+        let base = Url::parse("mailto:admin@example.com").unwrap();
+        let err = url::dashboard(&base, ["project"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -14,14 +14,33 @@
 
 //! Framework for waiting for asynchronous events.
 
+use std::collections::hash_map::RandomState;
 use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
 use std::time::Duration;
 
+use async_stream::stream;
 use async_trait::async_trait;
+use futures::future::join_all;
+use futures::stream::Stream;
+use tokio::time::sleep;
 pub use waiter::Waiter;
 
+use crate::utils::reauthenticate;
 use crate::{Error, ErrorKind, Refresh, Result};
 
+/// Maximum factor by which the polling interval grows after failures.
+const MAX_BACKOFF_FACTOR: u32 = 16;
+
+/// A status transition observed while watching a resource.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusChange<S> {
+    /// Status before the change.
+    pub old: S,
+    /// Status after the change.
+    pub new: S,
+}
+
 /// Wait for resource deletion.
 #[derive(Debug)]
 pub struct DeletionWaiter<T> {
@@ -67,7 +86,7 @@ impl<T: Refresh + Debug + Send> Waiter<(), Error> for DeletionWaiter<T> {
     }
 
     async fn poll(&mut self) -> Result<Option<()>> {
-        let result = self.inner.refresh().await;
+        let result = refresh_with_reauth(&mut self.inner).await;
         match result {
             Ok(..) => {
                 trace!("Still waiting for resource {:?} to be deleted", self.inner);
@@ -84,3 +103,236 @@ impl<T: Refresh + Debug + Send> Waiter<(), Error> for DeletionWaiter<T> {
         }
     }
 }
+
+/// Wait for several resources to be deleted concurrently.
+///
+/// All resources are polled at the same time and share one deadline: the
+/// longest of the default timeouts of the waiters. Returns the outcome for
+/// each waiter in the original order.
+pub async fn wait_all<T, I>(waiters: I) -> Vec<Result<()>>
+where
+    T: Refresh + Debug + Send,
+    I: IntoIterator<Item = DeletionWaiter<T>>,
+{
+    let waiters: Vec<_> = waiters.into_iter().collect();
+    let timeout = waiters
+        .iter()
+        .map(|waiter| waiter.wait_timeout)
+        .max()
+        .unwrap_or_default();
+    wait_all_for(waiters, timeout).await
+}
+
+/// Wait for several resources to be deleted concurrently with a timeout.
+///
+/// All resources are polled at the same time and share the deadline.
+/// Resources that are not deleted in time get an `OperationTimedOut` error.
+/// Returns the outcome for each waiter in the original order.
+pub async fn wait_all_for<T, I>(waiters: I, timeout: Duration) -> Vec<Result<()>>
+where
+    T: Refresh + Debug + Send,
+    I: IntoIterator<Item = DeletionWaiter<T>>,
+{
+    join_all(waiters.into_iter().map(|waiter| waiter.wait_for(timeout))).await
+}
+
+/// Refresh the resource, re-authenticating once if the token was rejected.
+///
+/// Waiters may run longer than the token validity, so refreshing is retried
+/// after getting a new token.
+pub(crate) async fn refresh_with_reauth<T: Refresh + Send>(resource: &mut T) -> Result<()> {
+    match resource.refresh().await {
+        Err(err) => {
+            if reauthenticate(resource.session(), &err).await {
+                resource.refresh().await
+            } else {
+                Err(err)
+            }
+        }
+        ok => ok,
+    }
+}
+
+/// Add up to 10% of random jitter to the delay.
+fn jittered(delay: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    let max_jitter = delay.as_millis() as u64 / 10;
+    if max_jitter == 0 {
+        delay
+    } else {
+        delay.saturating_add(Duration::from_millis(random % (max_jitter + 1)))
+    }
+}
+
+/// Poll the resource and yield its status changes.
+///
+/// Failures are yielded and polling continues with an exponential back-off.
+/// The stream ends after yielding a `ResourceNotFound` error.
+#[allow(dead_code)] // unused with --no-default-features
+pub(crate) fn watch_status<T, S, F>(
+    mut resource: T,
+    interval: Duration,
+    status: F,
+) -> impl Stream<Item = Result<StatusChange<S>>>
+where
+    T: Refresh + Debug + Send,
+    S: Clone + PartialEq + Debug,
+    F: Fn(&T) -> S,
+{
+    stream! {
+        let mut current = status(&resource);
+        let mut failures = 0;
+        loop {
+            let factor = 2u32.saturating_pow(failures).min(MAX_BACKOFF_FACTOR);
+            sleep(jittered(interval.saturating_mul(factor))).await;
+            match refresh_with_reauth(&mut resource).await {
+                Ok(()) => {
+                    failures = 0;
+                    let new = status(&resource);
+                    if new != current {
+                        debug!("Status of {:?} changed from {:?} to {:?}", resource, current, new);
+                        let old = std::mem::replace(&mut current, new.clone());
+                        yield Ok(StatusChange { old, new });
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::ResourceNotFound => {
+                    debug!("Resource {:?} disappeared while watching", resource);
+                    yield Err(e);
+                    break;
+                }
+                Err(e) => {
+                    failures += 1;
+                    warn!("Failed to refresh {:?} ({} times): {}", resource, failures, e);
+                    yield Err(e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use futures::stream::StreamExt;
+
+    use super::{jittered, wait_all, wait_all_for, watch_status, DeletionWaiter, StatusChange};
+    use crate::{Error, ErrorKind, Refresh, Result};
+
+    #[derive(Debug)]
+    struct Fake {
+        steps: Vec<Result<&'static str>>,
+        status: &'static str,
+    }
+
+    #[async_trait]
+    impl Refresh for Fake {
+        async fn refresh(&mut self) -> Result<()> {
+            self.status = self.steps.remove(0)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_jittered() {
+        for _ in 0..100 {
+            let delay = jittered(Duration::from_millis(100));
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(110));
+        }
+        assert_eq!(jittered(Duration::from_millis(5)), Duration::from_millis(5));
+        assert_eq!(jittered(Duration::MAX), Duration::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_watch_status() {
+        let fake = Fake {
+            steps: vec![
+                Ok("BUILD"),
+                Ok("ACTIVE"),
+                Err(Error::new(ErrorKind::InternalServerError, "boom")),
+                Ok("ACTIVE"),
+                Ok("DOWN"),
+                Err(Error::new(ErrorKind::ResourceNotFound, "gone")),
+            ],
+            status: "BUILD",
+        };
+        let items: Vec<_> = watch_status(fake, Duration::from_millis(1), |f| f.status)
+            .collect()
+            .await;
+        assert_eq!(items.len(), 4);
+        assert_eq!(
+            items[0].as_ref().unwrap(),
+            &StatusChange {
+                old: "BUILD",
+                new: "ACTIVE"
+            }
+        );
+        assert_eq!(
+            items[1].as_ref().unwrap_err().kind(),
+            ErrorKind::InternalServerError
+        );
+        assert_eq!(
+            items[2].as_ref().unwrap(),
+            &StatusChange {
+                old: "ACTIVE",
+                new: "DOWN"
+            }
+        );
+        assert_eq!(
+            items[3].as_ref().unwrap_err().kind(),
+            ErrorKind::ResourceNotFound
+        );
+    }
+
+    fn deleted_after(count: usize) -> DeletionWaiter<Fake> {
+        let mut steps: Vec<_> = (0..count).map(|_| Ok("DELETING")).collect();
+        steps.push(Err(Error::new(ErrorKind::ResourceNotFound, "gone")));
+        DeletionWaiter::new(
+            Fake {
+                steps,
+                status: "DELETING",
+            },
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_wait_all() {
+        let failing = DeletionWaiter::new(
+            Fake {
+                steps: vec![
+                    Ok("DELETING"),
+                    Err(Error::new(ErrorKind::InternalServerError, "boom")),
+                ],
+                status: "DELETING",
+            },
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+        );
+        let results = wait_all(vec![deleted_after(3), failing, deleted_after(0)]).await;
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1].as_ref().unwrap_err().kind(),
+            ErrorKind::InternalServerError
+        );
+        assert!(results[2].is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_wait_all_for_timeout() {
+        let results = wait_all_for(
+            vec![deleted_after(0), deleted_after(100_000)],
+            Duration::from_millis(20),
+        )
+        .await;
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1].as_ref().unwrap_err().kind(),
+            ErrorKind::OperationTimedOut
+        );
+    }
+}
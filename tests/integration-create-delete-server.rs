@@ -43,7 +43,7 @@ fn validate_port(port: &openstack::network::Port, server: &openstack::compute::S
         .unwrap()
         .starts_with("compute:"));
     assert!(port.attached_to_server());
-    assert!(port.fixed_ips().len() > 0);
+    assert!(!port.fixed_ips().is_empty());
 }
 
 async fn power_on_off_server(server: &mut openstack::compute::Server) {
@@ -93,7 +93,7 @@ async fn validate_server(os: &openstack::Cloud, server: &mut openstack::compute:
         .one()
         .await
         .expect("Cannot find the port attached to the server");
-    validate_port(&port, &server);
+    validate_port(&port, server);
 
     let image = server.image().await.expect("Cannot fetch Server image");
     assert_eq!(image.id(), server.image_id().unwrap());
@@ -143,7 +143,7 @@ async fn test_basic_server_ops() {
         .all()
         .await
         .expect("Cannot find active ports for network");
-    assert!(ports.len() > 0);
+    assert!(!ports.is_empty());
 
     let server_port = os
         .find_ports()
@@ -175,7 +175,7 @@ async fn test_basic_server_ops() {
         .get_console_output(Some(10))
         .await
         .expect("Failed to get console output");
-    assert!(console.len() > 0);
+    assert!(!console.is_empty());
 
     server
         .delete()
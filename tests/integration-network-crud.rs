@@ -107,14 +107,12 @@ async fn test_port_create_update_delete() {
 
     os.get_port("rust-openstack-integration-2")
         .await
-        .err()
-        .expect("Port is still present");
+        .expect_err("Port is still present");
 
     port_found
         .refresh()
         .await
-        .err()
-        .expect("Refresh succeeds on deleted port");
+        .expect_err("Refresh succeeds on deleted port");
 }
 
 #[tokio::test]
@@ -463,7 +461,13 @@ async fn test_router_create_delete_simple() {
 
     subnet.refresh().await.expect("Cannot refresh subnet");
 
-    let _ = router.delete();
+    router
+        .delete()
+        .await
+        .expect("Cannot request router deletion")
+        .wait()
+        .await
+        .expect("Router was not deleted");
 
     subnet
         .delete()